@@ -1,5 +1,5 @@
 use crate::Error;
-use std::{env, fs};
+use std::{env, fs, mem};
 
 /// Which action should be executed?
 ///
@@ -11,12 +11,28 @@ pub enum Action {
 	Help,
 	/// Print the current version.
 	Version,
-	/// Enter the REPL.
-	Repl,
-	/// Evaluate the arguments.
-	Eval { exprs: Vec<String> },
+	/// Enter the REPL, or evaluate the lines piped into stdin, optionally
+	/// showing the conversion steps, using Reverse Polish Notation or
+	/// printing each result as JSON.
+	Repl {
+		show_work: bool,
+		rpn: bool,
+		json: bool,
+	},
+	/// Evaluate the arguments, optionally showing the conversion steps,
+	/// using Reverse Polish Notation or printing the result as JSON.
+	Eval {
+		exprs: Vec<String>,
+		show_work: bool,
+		rpn: bool,
+		json: bool,
+	},
 	/// Show the default config file
 	DefaultConfig,
+	/// Show past calculations, optionally only those matching the query
+	History { query: Option<String> },
+	/// Download the latest exchange rates and store them in the cache
+	UpdateCurrencies,
 }
 
 impl Action {
@@ -24,11 +40,22 @@ impl Action {
 		let mut print_help = false;
 		let mut print_version = false;
 		let mut print_default_config = false;
+		let mut update_currencies = false;
+		let mut show_work = false;
+		let mut rpn = false;
+		let mut json = false;
 		let mut before_double_dash = true;
 		let mut exprs = vec![];
 		let mut expr = String::new();
 		let mut idx = 0;
 
+		if args.first().is_some_and(|arg| arg == "history") {
+			let query = args[1..].join(" ");
+			return Ok(Self::History {
+				query: crate::calculation_log::parse_query(&query).map(ToString::to_string),
+			});
+		}
+
 		while idx < args.len() {
 			let arg = &args[idx];
 			match (before_double_dash, arg.as_str()) {
@@ -38,13 +65,16 @@ impl Action {
 				(true, "--default-config" | "--print-default-config") => {
 					print_default_config = true;
 				}
+				(true, "--update-currencies") => update_currencies = true,
+				(true, "--show-work") => show_work = true,
+				(true, "--rpn") => rpn = true,
+				(true, "--json") => json = true,
 				(true, "-f" | "--file") => {
 					idx += 1;
 					let filename = args.get(idx).ok_or("expected a filename")?;
 					let contents = fs::read_to_string(filename)?;
 					if !expr.is_empty() {
-						exprs.push(expr);
-						expr = String::new();
+						exprs.push(mem::take(&mut expr));
 					}
 					exprs.push(contents);
 				}
@@ -52,19 +82,24 @@ impl Action {
 					idx += 1;
 					let e = args.get(idx).ok_or("expected an expression")?;
 					if !expr.is_empty() {
-						exprs.push(expr);
-						expr = String::new();
+						exprs.push(mem::take(&mut expr));
+					}
+					exprs.push(e.clone());
+				}
+				// `--` ends the list of options, and also separates
+				// multiple expressions
+				(_, "--") => {
+					before_double_dash = false;
+					if !expr.is_empty() {
+						exprs.push(mem::take(&mut expr));
 					}
-					exprs.push(e.to_string());
 				}
-				(true, "--") => before_double_dash = false,
 				(_, arg) => {
 					let mut read_file = false;
 					if before_double_dash {
 						if let Ok(contents) = fs::read_to_string(arg) {
 							if !expr.is_empty() {
-								exprs.push(expr);
-								expr = String::new();
+								exprs.push(mem::take(&mut expr));
 							}
 							exprs.push(contents);
 							read_file = true;
@@ -89,26 +124,63 @@ impl Action {
 			Self::Version
 		} else if print_default_config {
 			Self::DefaultConfig
+		} else if update_currencies {
+			Self::UpdateCurrencies
+		} else if json && rpn {
+			return Err("--json cannot be combined with --rpn".into());
 		} else if exprs.is_empty() && expr.is_empty() {
-			Self::Repl
+			Self::Repl {
+				show_work,
+				rpn,
+				json,
+			}
 		} else {
 			// If neither help nor version is requested, evaluate the arguments
 			if !expr.is_empty() {
 				exprs.push(expr);
 			}
-			Self::Eval { exprs }
+			Self::Eval {
+				exprs,
+				show_work,
+				rpn,
+				json,
+			}
 		})
 	}
 
-	pub fn get() -> Result<Self, Error> {
+	/// Returns the action along with any `--set key=value` config overrides
+	pub fn get() -> Result<(Self, Vec<String>), Error> {
 		let args: Vec<_> = env::args().skip(1).collect();
-		Self::from_args(args.as_slice())
+		let (args, overrides) = split_config_overrides(&args)?;
+		Ok((Self::from_args(args.as_slice())?, overrides))
 	}
 }
 
+/// Removes all `--set key=value` options (before any `--`) from the
+/// arguments, returning the remaining arguments and the overrides.
+pub fn split_config_overrides(args: &[String]) -> Result<(Vec<String>, Vec<String>), Error> {
+	let mut remaining = vec![];
+	let mut overrides = vec![];
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		if arg == "--" {
+			remaining.push(arg.clone());
+			remaining.extend(args.cloned());
+			break;
+		}
+		if arg == "--set" {
+			let setting = args.next().ok_or("expected a setting like `key=value`")?;
+			overrides.push(setting.clone());
+		} else {
+			remaining.push(arg.clone());
+		}
+	}
+	Ok((remaining, overrides))
+}
+
 #[cfg(test)]
 mod tests {
-	use super::Action;
+	use super::{split_config_overrides, Action};
 
 	macro_rules! action {
 		($( $arg:literal ),*) => {
@@ -119,6 +191,17 @@ mod tests {
 	fn eval(expr: &str) -> Action {
 		Action::Eval {
 			exprs: vec![expr.to_string()],
+			show_work: false,
+			rpn: false,
+			json: false,
+		}
+	}
+
+	fn repl() -> Action {
+		Action::Repl {
+			show_work: false,
+			rpn: false,
+			json: false,
 		}
 	}
 
@@ -155,10 +238,138 @@ mod tests {
 
 	#[test]
 	fn empty_arguments() {
-		assert_eq!(Action::Repl, action![]);
-		assert_eq!(Action::Repl, action![""]);
-		assert_eq!(Action::Repl, action!["", ""]);
-		assert_eq!(Action::Repl, action!["\t", " "]);
+		assert_eq!(repl(), action![]);
+		assert_eq!(repl(), action![""]);
+		assert_eq!(repl(), action!["", ""]);
+		assert_eq!(repl(), action!["\t", " "]);
 		assert_eq!(eval("1"), action!["\t", " ", "1"]);
 	}
+
+	#[test]
+	fn show_work_argument() {
+		assert_eq!(
+			Action::Eval {
+				exprs: vec!["5 miles to km".to_string()],
+				show_work: true,
+				rpn: false,
+				json: false,
+			},
+			action!["--show-work", "5 miles to km"]
+		);
+		assert_eq!(eval("--show-work"), action!["--", "--show-work"]);
+	}
+
+	#[test]
+	fn flags_without_expressions_apply_to_stdin() {
+		assert_eq!(
+			Action::Repl {
+				show_work: true,
+				rpn: false,
+				json: true,
+			},
+			action!["--json", "--show-work"]
+		);
+	}
+
+	#[test]
+	fn double_dash_separates_expressions() {
+		let exprs = |exprs: &[&str]| Action::Eval {
+			exprs: exprs.iter().map(ToString::to_string).collect(),
+			show_work: false,
+			rpn: false,
+			json: false,
+		};
+		assert_eq!(
+			exprs(&["1 + 1", "2 + 2"]),
+			action!["1", "+", "1", "--", "2 + 2"]
+		);
+		assert_eq!(
+			exprs(&["a = 5", "2a", "-a"]),
+			action!["--", "a = 5", "--", "2a", "--", "-a"]
+		);
+		assert_eq!(eval("1"), action!["1", "--", "--"]);
+	}
+
+	#[test]
+	fn update_currencies_argument() {
+		assert_eq!(Action::UpdateCurrencies, action!["--update-currencies"]);
+		assert_eq!(Action::Help, action!["--update-currencies", "--help"]);
+		assert_eq!(
+			eval("--update-currencies"),
+			action!["--", "--update-currencies"]
+		);
+	}
+
+	#[test]
+	fn rpn_argument() {
+		assert_eq!(
+			Action::Repl {
+				show_work: false,
+				rpn: true,
+				json: false,
+			},
+			action!["--rpn"]
+		);
+		assert_eq!(
+			Action::Eval {
+				exprs: vec!["3 4 +".to_string()],
+				show_work: false,
+				rpn: true,
+				json: false,
+			},
+			action!["--rpn", "3", "4", "+"]
+		);
+	}
+
+	#[test]
+	fn history_subcommand() {
+		assert_eq!(Action::History { query: None }, action!["history"]);
+		assert_eq!(
+			Action::History {
+				query: Some("5 miles".to_string())
+			},
+			action!["history", "5", "miles"]
+		);
+		assert_eq!(
+			Action::History {
+				query: Some("miles".to_string())
+			},
+			action!["history", "'miles'"]
+		);
+		assert_eq!(eval("1 + history"), action!["1", "+", "history"]);
+	}
+
+	#[test]
+	fn config_overrides_are_separated() {
+		let args =
+			["--set", "locale=de", "1", "+", "1", "--set", "rpn=false"].map(ToString::to_string);
+		let (remaining, overrides) = split_config_overrides(&args).unwrap();
+		assert_eq!(remaining, ["1", "+", "1"]);
+		assert_eq!(overrides, ["locale=de", "rpn=false"]);
+
+		let args = ["1", "--", "--set", "a"].map(ToString::to_string);
+		let (remaining, overrides) = split_config_overrides(&args).unwrap();
+		assert_eq!(remaining, ["1", "--", "--set", "a"]);
+		assert!(overrides.is_empty());
+
+		assert!(split_config_overrides(&["--set".to_string()]).is_err());
+	}
+
+	#[test]
+	fn json_argument() {
+		assert_eq!(
+			Action::Eval {
+				exprs: vec!["1 + 1".to_string()],
+				show_work: false,
+				rpn: false,
+				json: true,
+			},
+			action!["--json", "1 + 1"]
+		);
+		assert_eq!(eval("--json"), action!["--", "--json"]);
+		assert!(
+			Action::from_args(&["--json".to_string(), "--rpn".to_string(), "1".to_string()])
+				.is_err()
+		);
+	}
 }
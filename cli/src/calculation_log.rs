@@ -0,0 +1,251 @@
+use crate::{file_paths, Error};
+use std::{fmt, fs, io::Write, path, time};
+
+// A plain text log of the calculations entered in the REPL, stored in
+// `calculations.tsv` in the state directory. Every calculation is on its
+// own line as `<unix timestamp>\t<input>\t<result>`, with tabs, newlines
+// and backslashes escaped. New entries are appended, and searches read the
+// whole file. Once the log has more than `max-calculation-log-size`
+// entries, the oldest ones are removed.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+	pub timestamp: u64,
+	pub input: String,
+	pub result: String,
+}
+
+impl Entry {
+	fn matches(&self, query: &str) -> bool {
+		let query = query.to_lowercase();
+		self.input.to_lowercase().contains(&query) || self.result.to_lowercase().contains(&query)
+	}
+
+	fn to_line(&self) -> String {
+		format!(
+			"{}\t{}\t{}\n",
+			self.timestamp,
+			escape(&self.input),
+			escape(&self.result)
+		)
+	}
+
+	fn from_line(line: &str) -> Option<Self> {
+		let mut fields = line.split('\t');
+		let timestamp = fields.next()?.parse().ok()?;
+		let input = unescape(fields.next()?);
+		let result = unescape(fields.next()?);
+		if fields.next().is_some() {
+			return None;
+		}
+		Some(Self {
+			timestamp,
+			input,
+			result,
+		})
+	}
+}
+
+impl fmt::Display for Entry {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} = {}", self.input, self.result)
+	}
+}
+
+fn escape(s: &str) -> String {
+	let mut result = String::with_capacity(s.len());
+	for ch in s.chars() {
+		match ch {
+			'\\' => result.push_str("\\\\"),
+			'\t' => result.push_str("\\t"),
+			'\n' => result.push_str("\\n"),
+			'\r' => result.push_str("\\r"),
+			ch => result.push(ch),
+		}
+	}
+	result
+}
+
+fn unescape(s: &str) -> String {
+	let mut result = String::with_capacity(s.len());
+	let mut chars = s.chars();
+	while let Some(ch) = chars.next() {
+		if ch != '\\' {
+			result.push(ch);
+			continue;
+		}
+		match chars.next() {
+			Some('t') => result.push('\t'),
+			Some('n') => result.push('\n'),
+			Some('r') => result.push('\r'),
+			Some(ch) => result.push(ch),
+			None => result.push('\\'),
+		}
+	}
+	result
+}
+
+pub fn get_location(mode: file_paths::DirMode) -> Result<path::PathBuf, Error> {
+	let mut path = file_paths::get_state_dir(mode)?;
+	path.push("calculations.tsv");
+	Ok(path)
+}
+
+fn get_current_timestamp() -> Result<u64, Error> {
+	Ok(time::SystemTime::now()
+		.duration_since(time::SystemTime::UNIX_EPOCH)?
+		.as_secs())
+}
+
+/// Removes the oldest lines so that at most `max_entries` are left, or
+/// returns `None` if there are no more than that
+fn truncate(contents: &str, max_entries: usize) -> Option<&str> {
+	let excess = contents.lines().count().checked_sub(max_entries)?;
+	if excess == 0 {
+		return None;
+	}
+	Some(match contents.match_indices('\n').nth(excess - 1) {
+		Some((idx, _)) => &contents[idx + 1..],
+		None => "",
+	})
+}
+
+/// Appends a calculation to the log, keeping at most `max_entries`
+pub fn record(input: &str, result: &str, max_entries: usize) -> Result<(), Error> {
+	let entry = Entry {
+		timestamp: get_current_timestamp()?,
+		input: input.trim().to_string(),
+		result: result.trim_end().to_string(),
+	};
+	let path = get_location(file_paths::DirMode::Create)?;
+	fs::OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(&path)?
+		.write_all(entry.to_line().as_bytes())?;
+	let contents = fs::read_to_string(&path)?;
+	if let Some(kept) = truncate(&contents, max_entries) {
+		fs::write(&path, kept)?;
+	}
+	Ok(())
+}
+
+fn search_in(contents: &str, query: Option<&str>) -> Vec<Entry> {
+	contents
+		.lines()
+		.filter_map(Entry::from_line)
+		.filter(|entry| query.is_none_or(|query| entry.matches(query)))
+		.collect()
+}
+
+/// Returns all past calculations whose input or result contains `query`
+/// (ignoring case), from oldest to newest. If `query` is `None`, every
+/// calculation is returned.
+pub fn search(query: Option<&str>) -> Result<Vec<Entry>, Error> {
+	let path = get_location(file_paths::DirMode::DontCreate)?;
+	let contents = match fs::read_to_string(path) {
+		Ok(contents) => contents,
+		// nothing has been recorded yet
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+		Err(e) => return Err(e.into()),
+	};
+	Ok(search_in(&contents, query))
+}
+
+/// Parses the argument of the `:search` REPL command, which may optionally
+/// be surrounded by quotes
+pub fn parse_query(query: &str) -> Option<&str> {
+	let query = query.trim();
+	let query = ['\'', '"']
+		.into_iter()
+		.find_map(|quote| query.strip_prefix(quote)?.strip_suffix(quote))
+		.unwrap_or(query);
+	if query.is_empty() {
+		None
+	} else {
+		Some(query)
+	}
+}
+
+pub fn print_search_results(query: Option<&str>) -> Result<(), Error> {
+	let entries = search(query)?;
+	if entries.is_empty() {
+		match query {
+			Some(query) => println!("No calculations found matching '{query}'"),
+			None => println!("No calculations found"),
+		}
+	}
+	for entry in entries {
+		println!("{entry}");
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entry(input: &str, result: &str) -> Entry {
+		Entry {
+			timestamp: 1_700_000_000,
+			input: input.to_string(),
+			result: result.to_string(),
+		}
+	}
+
+	#[test]
+	fn entries_roundtrip() {
+		for e in [
+			entry("5 miles to km", "8.04672 km"),
+			entry("a = 1\tb\\c", "1\nx"),
+			entry("", ""),
+		] {
+			let line = e.to_line();
+			assert_eq!(line.matches('\n').count(), 1);
+			assert_eq!(Entry::from_line(line.trim_end_matches('\n')), Some(e));
+		}
+	}
+
+	#[test]
+	fn search_is_case_insensitive() {
+		let contents = [
+			entry("5 miles to km", "8.04672 km"),
+			entry("1 + 1", "2"),
+			entry("3 km to mi", "approx. 1.8641135767 Miles"),
+		]
+		.iter()
+		.map(Entry::to_line)
+		.collect::<String>();
+		let inputs = |query| {
+			search_in(&contents, query)
+				.into_iter()
+				.map(|e| e.input)
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(inputs(Some("MILES")), ["5 miles to km", "3 km to mi"]);
+		assert_eq!(inputs(Some("2")), ["5 miles to km", "1 + 1"]);
+		assert_eq!(inputs(None).len(), 3);
+	}
+
+	#[test]
+	fn invalid_lines_are_skipped() {
+		assert!(search_in("abc\tdef\tghi\n1\t2\n\n", None).is_empty());
+	}
+
+	#[test]
+	fn oldest_entries_are_removed() {
+		assert_eq!(truncate("a\nb\nc\n", 3), None);
+		assert_eq!(truncate("a\nb\nc\n", 5), None);
+		assert_eq!(truncate("a\nb\nc\n", 2), Some("b\nc\n"));
+		assert_eq!(truncate("a\nb\nc\n", 0), Some(""));
+	}
+
+	#[test]
+	fn search_queries() {
+		assert_eq!(parse_query(" 'miles' "), Some("miles"));
+		assert_eq!(parse_query("\"5 km\""), Some("5 km"));
+		assert_eq!(parse_query("miles"), Some("miles"));
+		assert_eq!(parse_query("''"), None);
+		assert_eq!(parse_query(""), None);
+	}
+}
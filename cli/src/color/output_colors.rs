@@ -22,6 +22,10 @@ impl PartialEq for OutputColors {
 			&& self.get_style("keyword") == other.get_style("keyword")
 			&& self.get_style("built-in-function") == other.get_style("built-in-function")
 			&& self.get_style("date") == other.get_style("date")
+			&& self.get_style("operator") == other.get_style("operator")
+			&& self.get_style("unit") == other.get_style("unit")
+			&& self.get_style("error") == other.get_style("error")
+			&& self.get_style("negative-amount") == other.get_style("negative-amount")
 			&& self.get_style("other") == other.get_style("other")
 	}
 }
@@ -32,9 +36,10 @@ impl OutputColors {
 	fn get_style(&self, name: &str) -> Color {
 		self.styles.get(name).cloned().unwrap_or_else(|| {
 			match name {
-				"number" | "date" | "string" | "other" => Color::default(),
+				"number" | "date" | "string" | "operator" | "unit" | "other" => Color::default(),
 				"identifier" => Color::new(Base::White),
 				"keyword" | "built-in-function" => Color::bold(Base::Blue),
+				"error" | "negative-amount" => Color::new(Base::Red),
 				_ => {
 					// this should never happen
 					Color::default()
@@ -50,7 +55,10 @@ impl OutputColors {
 				"number"
 					| "string" | "identifier"
 					| "keyword" | "built-in-function"
-					| "date" | "other"
+					| "date" | "operator"
+					| "unit" | "error"
+					| "negative-amount"
+					| "other"
 			) {
 				eprintln!("Warning: ignoring unknown configuration setting `colors.{key}`");
 			}
@@ -68,6 +76,10 @@ impl OutputColors {
 			SpanKind::Keyword => self.get_style("keyword").to_ansi(),
 			SpanKind::BuiltInFunction => self.get_style("built_in_function").to_ansi(),
 			SpanKind::Date => self.get_style("date").to_ansi(),
+			SpanKind::Operator => self.get_style("operator").to_ansi(),
+			SpanKind::Unit => self.get_style("unit").to_ansi(),
+			SpanKind::Error => self.get_style("error").to_ansi(),
+			SpanKind::NegativeAmount => self.get_style("negative-amount").to_ansi(),
 			_ => self.get_style("other").to_ansi(),
 		}
 	}
@@ -1,15 +1,43 @@
-use crate::{color, custom_units::CustomUnitDefinition};
+use crate::{color, custom_units::CustomUnitDefinition, table::TableFormat};
 use std::{env, fmt, fs, io};
 
 #[derive(Debug, Eq, PartialEq)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
 	pub prompt: String,
 	pub enable_colors: bool,
 	pub coulomb_and_farad: bool,
+	pub scientific_notation_upper: Option<i32>,
+	pub scientific_notation_lower: Option<i32>,
+	pub keep_trailing_zeros: bool,
+	pub integer_decimal_point: bool,
+	pub prefer_fractions: bool,
+	pub table_format: TableFormat,
+	pub unit_exponent_notation: fend_core::UnitExponentNotation,
+	pub exact_and_approximate: bool,
+	pub binary_digit_grouping: usize,
+	pub hex_digit_grouping: usize,
+	pub digit_separators: fend_core::DigitSeparators,
+	pub parse_digit_separators: bool,
+	pub recurring_digits: fend_core::RecurringDigitsNotation,
+	pub accounting_negatives: bool,
+	pub derived_units: bool,
+	pub unit_system: fend_core::UnitSystem,
+	pub multiplication_sign: fend_core::MultiplicationSign,
+	pub division_sign: fend_core::DivisionSign,
+	pub implicit_multiplication: fend_core::ImplicitMultiplication,
+	pub show_conversion_steps: bool,
+	pub allow_shadowing_constants: bool,
+	pub symbolic_variables: bool,
+	pub locale: fend_core::Locale,
 	pub colors: color::OutputColors,
 	pub max_history_size: usize,
+	pub enable_calculation_log: bool,
+	pub max_calculation_log_size: usize,
+	pub persist_memory: bool,
 	pub enable_internet_access: bool,
 	pub exchange_rate_source: ExchangeRateSource,
+	pub exchange_rate_max_age: u64,
 	pub custom_units: Vec<CustomUnitDefinition>,
 	unknown_settings: UnknownSettings,
 	unknown_keys: Vec<String>,
@@ -24,7 +52,7 @@ pub enum ExchangeRateSource {
 
 struct ExchangeRateSourceVisitor;
 
-impl<'de> serde::de::Visitor<'de> for ExchangeRateSourceVisitor {
+impl serde::de::Visitor<'_> for ExchangeRateSourceVisitor {
 	type Value = ExchangeRateSource;
 
 	fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -58,6 +86,179 @@ enum UnknownSettings {
 	Warn,
 }
 
+// a scientific notation threshold is either an exponent or 'never'
+fn parse_threshold<E: serde::de::Error>(value: toml::Value) -> Result<Option<i32>, E> {
+	match value {
+		toml::Value::String(s) if s == "never" => Ok(None),
+		toml::Value::Integer(i) => i32::try_from(i).map(Some).map_err(|_| {
+			serde::de::Error::invalid_value(
+				serde::de::Unexpected::Signed(i),
+				&"an exponent or `never`",
+			)
+		}),
+		_ => Err(serde::de::Error::invalid_type(
+			serde::de::Unexpected::Other(value.type_str()),
+			&"an exponent or `never`",
+		)),
+	}
+}
+
+fn parse_unit_exponent_notation<E: serde::de::Error>(
+	value: &str,
+) -> Result<fend_core::UnitExponentNotation, E> {
+	Ok(match value {
+		"mixed" => fend_core::UnitExponentNotation::Mixed,
+		"slash" => fend_core::UnitExponentNotation::Slash,
+		"negative-exponents" => fend_core::UnitExponentNotation::NegativeExponents,
+		"superscripts" => fend_core::UnitExponentNotation::Superscripts,
+		_ => {
+			return Err(serde::de::Error::unknown_variant(
+				value,
+				&["mixed", "slash", "negative-exponents", "superscripts"],
+			))
+		}
+	})
+}
+
+fn parse_recurring_digits_notation<E: serde::de::Error>(
+	value: &str,
+) -> Result<fend_core::RecurringDigitsNotation, E> {
+	Ok(match value {
+		"parentheses" => fend_core::RecurringDigitsNotation::Parentheses,
+		"overline" => fend_core::RecurringDigitsNotation::Overline,
+		_ => {
+			return Err(serde::de::Error::unknown_variant(
+				value,
+				&["parentheses", "overline"],
+			))
+		}
+	})
+}
+
+fn parse_multiplication_sign<E: serde::de::Error>(
+	value: &str,
+) -> Result<fend_core::MultiplicationSign, E> {
+	Ok(match value {
+		"space" => fend_core::MultiplicationSign::Space,
+		"asterisk" => fend_core::MultiplicationSign::Asterisk,
+		"dot" => fend_core::MultiplicationSign::Dot,
+		"cross" => fend_core::MultiplicationSign::Cross,
+		_ => {
+			return Err(serde::de::Error::unknown_variant(
+				value,
+				&["space", "asterisk", "dot", "cross"],
+			))
+		}
+	})
+}
+
+fn parse_digit_separators<E: serde::de::Error>(
+	value: &str,
+) -> Result<fend_core::DigitSeparators, E> {
+	Ok(match value {
+		"none" => fend_core::DigitSeparators::None,
+		"comma" => fend_core::DigitSeparators::Comma,
+		"space" => fend_core::DigitSeparators::Space,
+		"period-decimal-comma" => fend_core::DigitSeparators::PeriodDecimalComma,
+		"space-decimal-comma" => fend_core::DigitSeparators::SpaceDecimalComma,
+		"decimal-comma" => fend_core::DigitSeparators::DecimalComma,
+		_ => {
+			return Err(serde::de::Error::unknown_variant(
+				value,
+				&[
+					"none",
+					"comma",
+					"space",
+					"period-decimal-comma",
+					"space-decimal-comma",
+					"decimal-comma",
+				],
+			))
+		}
+	})
+}
+
+// `decimal-separator` is a shorthand for the `digit-separators` values
+// without a thousands separator
+fn parse_decimal_separator<E: serde::de::Error>(
+	value: &str,
+) -> Result<fend_core::DigitSeparators, E> {
+	Ok(match value {
+		"point" | "period" => fend_core::DigitSeparators::None,
+		"comma" => fend_core::DigitSeparators::DecimalComma,
+		_ => {
+			return Err(serde::de::Error::unknown_variant(
+				value,
+				&["point", "period", "comma"],
+			))
+		}
+	})
+}
+
+fn parse_division_sign<E: serde::de::Error>(value: &str) -> Result<fend_core::DivisionSign, E> {
+	Ok(match value {
+		"slash" => fend_core::DivisionSign::Slash,
+		"division-slash" => fend_core::DivisionSign::DivisionSlash,
+		_ => {
+			return Err(serde::de::Error::unknown_variant(
+				value,
+				&["slash", "division-slash"],
+			))
+		}
+	})
+}
+
+fn parse_implicit_multiplication<E: serde::de::Error>(
+	value: &str,
+) -> Result<fend_core::ImplicitMultiplication, E> {
+	Ok(match value {
+		"left-to-right" => fend_core::ImplicitMultiplication::LeftToRight,
+		"before-division" => fend_core::ImplicitMultiplication::BeforeDivision,
+		_ => {
+			return Err(serde::de::Error::unknown_variant(
+				value,
+				&["left-to-right", "before-division"],
+			))
+		}
+	})
+}
+
+fn parse_unit_system<E: serde::de::Error>(value: &str) -> Result<fend_core::UnitSystem, E> {
+	Ok(match value {
+		"metric" => fend_core::UnitSystem::Metric,
+		"imperial" => fend_core::UnitSystem::Imperial,
+		_ => {
+			return Err(serde::de::Error::unknown_variant(
+				value,
+				&["metric", "imperial"],
+			))
+		}
+	})
+}
+
+fn parse_locale<E: serde::de::Error>(value: &str) -> Result<fend_core::Locale, E> {
+	if value == "auto" {
+		return Ok(locale_from_env());
+	}
+	fend_core::Locale::from_language_tag(value).ok_or_else(|| {
+		serde::de::Error::invalid_value(
+			serde::de::Unexpected::Str(value),
+			&"`auto` or a language like `en` or `de`",
+		)
+	})
+}
+
+// if the locale setting is set to 'auto', use the language from the
+// standard environment variables
+fn locale_from_env() -> fend_core::Locale {
+	["LC_ALL", "LC_MESSAGES", "LANG"]
+		.into_iter()
+		.filter_map(env::var_os)
+		.find(|value| !value.is_empty())
+		.and_then(|value| fend_core::Locale::from_language_tag(&value.to_string_lossy()))
+		.unwrap_or_default()
+}
+
 struct ConfigVisitor;
 
 impl<'de> serde::de::Visitor<'de> for ConfigVisitor {
@@ -67,15 +268,43 @@ impl<'de> serde::de::Visitor<'de> for ConfigVisitor {
 		formatter.write_str("a fend configuration struct")
 	}
 
+	#[allow(clippy::too_many_lines)]
 	fn visit_map<V: serde::de::MapAccess<'de>>(self, mut map: V) -> Result<Config, V::Error> {
 		let mut result = Config::default();
 		let mut seen_prompt = false;
 		let mut seen_enable_colors = false;
 		let mut seen_coulomb_farad = false;
+		let mut seen_sci_upper = false;
+		let mut seen_sci_lower = false;
+		let mut seen_keep_trailing_zeros = false;
+		let mut seen_integer_decimal_point = false;
+		let mut seen_prefer_fractions = false;
+		let mut seen_table_format = false;
+		let mut seen_unit_exponent_notation = false;
+		let mut seen_exact_and_approximate = false;
+		let mut seen_binary_digit_grouping = false;
+		let mut seen_hex_digit_grouping = false;
+		let mut seen_digit_separators = false;
+		let mut seen_parse_digit_separators = false;
+		let mut seen_recurring_digits = false;
+		let mut seen_accounting_negatives = false;
+		let mut seen_derived_units = false;
+		let mut seen_unit_system = false;
+		let mut seen_multiplication_sign = false;
+		let mut seen_division_sign = false;
+		let mut seen_implicit_multiplication = false;
+		let mut seen_show_conversion_steps = false;
+		let mut seen_allow_shadowing_constants = false;
+		let mut seen_symbolic_variables = false;
+		let mut seen_locale = false;
 		let mut seen_colors = false;
 		let mut seen_max_hist_size = false;
+		let mut seen_enable_calculation_log = false;
+		let mut seen_max_calculation_log_size = false;
+		let mut seen_persist_memory = false;
 		let mut seen_enable_internet_access = false;
 		let mut seen_exchange_rate_source = false;
+		let mut seen_exchange_rate_max_age = false;
 		let mut seen_custom_units = false;
 		while let Some(key) = map.next_key::<String>()? {
 			match key.as_str() {
@@ -109,6 +338,185 @@ impl<'de> serde::de::Visitor<'de> for ConfigVisitor {
 					result.coulomb_and_farad = map.next_value()?;
 					seen_coulomb_farad = true;
 				}
+				"scientific-notation-upper" => {
+					if seen_sci_upper {
+						return Err(serde::de::Error::duplicate_field(
+							"scientific-notation-upper",
+						));
+					}
+					result.scientific_notation_upper = parse_threshold(map.next_value()?)?;
+					seen_sci_upper = true;
+				}
+				"scientific-notation-lower" => {
+					if seen_sci_lower {
+						return Err(serde::de::Error::duplicate_field(
+							"scientific-notation-lower",
+						));
+					}
+					result.scientific_notation_lower = parse_threshold(map.next_value()?)?;
+					seen_sci_lower = true;
+				}
+				"keep-trailing-zeros" => {
+					if seen_keep_trailing_zeros {
+						return Err(serde::de::Error::duplicate_field("keep-trailing-zeros"));
+					}
+					result.keep_trailing_zeros = map.next_value()?;
+					seen_keep_trailing_zeros = true;
+				}
+				"integer-decimal-point" => {
+					if seen_integer_decimal_point {
+						return Err(serde::de::Error::duplicate_field("integer-decimal-point"));
+					}
+					result.integer_decimal_point = map.next_value()?;
+					seen_integer_decimal_point = true;
+				}
+				"prefer-fractions" => {
+					if seen_prefer_fractions {
+						return Err(serde::de::Error::duplicate_field("prefer-fractions"));
+					}
+					result.prefer_fractions = map.next_value()?;
+					seen_prefer_fractions = true;
+				}
+				"table-format" => {
+					if seen_table_format {
+						return Err(serde::de::Error::duplicate_field("table-format"));
+					}
+					result.table_format = map.next_value()?;
+					seen_table_format = true;
+				}
+				"unit-exponent-notation" => {
+					if seen_unit_exponent_notation {
+						return Err(serde::de::Error::duplicate_field("unit-exponent-notation"));
+					}
+					result.unit_exponent_notation =
+						parse_unit_exponent_notation(&map.next_value::<String>()?)?;
+					seen_unit_exponent_notation = true;
+				}
+				"exact-and-approximate" => {
+					if seen_exact_and_approximate {
+						return Err(serde::de::Error::duplicate_field("exact-and-approximate"));
+					}
+					result.exact_and_approximate = map.next_value()?;
+					seen_exact_and_approximate = true;
+				}
+				"binary-digit-grouping" => {
+					if seen_binary_digit_grouping {
+						return Err(serde::de::Error::duplicate_field("binary-digit-grouping"));
+					}
+					result.binary_digit_grouping = map.next_value()?;
+					seen_binary_digit_grouping = true;
+				}
+				"hex-digit-grouping" => {
+					if seen_hex_digit_grouping {
+						return Err(serde::de::Error::duplicate_field("hex-digit-grouping"));
+					}
+					result.hex_digit_grouping = map.next_value()?;
+					seen_hex_digit_grouping = true;
+				}
+				"digit-separators" => {
+					if seen_digit_separators {
+						return Err(serde::de::Error::duplicate_field("digit-separators"));
+					}
+					result.digit_separators = parse_digit_separators(&map.next_value::<String>()?)?;
+					seen_digit_separators = true;
+				}
+				"decimal-separator" => {
+					if seen_digit_separators {
+						return Err(serde::de::Error::duplicate_field("digit-separators"));
+					}
+					result.digit_separators =
+						parse_decimal_separator(&map.next_value::<String>()?)?;
+					seen_digit_separators = true;
+				}
+				"parse-digit-separators" => {
+					if seen_parse_digit_separators {
+						return Err(serde::de::Error::duplicate_field("parse-digit-separators"));
+					}
+					result.parse_digit_separators = map.next_value()?;
+					seen_parse_digit_separators = true;
+				}
+				"recurring-digits" => {
+					if seen_recurring_digits {
+						return Err(serde::de::Error::duplicate_field("recurring-digits"));
+					}
+					result.recurring_digits =
+						parse_recurring_digits_notation(&map.next_value::<String>()?)?;
+					seen_recurring_digits = true;
+				}
+				"accounting-negatives" => {
+					if seen_accounting_negatives {
+						return Err(serde::de::Error::duplicate_field("accounting-negatives"));
+					}
+					result.accounting_negatives = map.next_value()?;
+					seen_accounting_negatives = true;
+				}
+				"derived-units" => {
+					if seen_derived_units {
+						return Err(serde::de::Error::duplicate_field("derived-units"));
+					}
+					result.derived_units = map.next_value()?;
+					seen_derived_units = true;
+				}
+				"unit-system" => {
+					if seen_unit_system {
+						return Err(serde::de::Error::duplicate_field("unit-system"));
+					}
+					result.unit_system = parse_unit_system(&map.next_value::<String>()?)?;
+					seen_unit_system = true;
+				}
+				"multiplication-sign" => {
+					if seen_multiplication_sign {
+						return Err(serde::de::Error::duplicate_field("multiplication-sign"));
+					}
+					result.multiplication_sign =
+						parse_multiplication_sign(&map.next_value::<String>()?)?;
+					seen_multiplication_sign = true;
+				}
+				"division-sign" => {
+					if seen_division_sign {
+						return Err(serde::de::Error::duplicate_field("division-sign"));
+					}
+					result.division_sign = parse_division_sign(&map.next_value::<String>()?)?;
+					seen_division_sign = true;
+				}
+				"implicit-multiplication" => {
+					if seen_implicit_multiplication {
+						return Err(serde::de::Error::duplicate_field("implicit-multiplication"));
+					}
+					result.implicit_multiplication =
+						parse_implicit_multiplication(&map.next_value::<String>()?)?;
+					seen_implicit_multiplication = true;
+				}
+				"show-conversion-steps" => {
+					if seen_show_conversion_steps {
+						return Err(serde::de::Error::duplicate_field("show-conversion-steps"));
+					}
+					result.show_conversion_steps = map.next_value()?;
+					seen_show_conversion_steps = true;
+				}
+				"allow-shadowing-constants" => {
+					if seen_allow_shadowing_constants {
+						return Err(serde::de::Error::duplicate_field(
+							"allow-shadowing-constants",
+						));
+					}
+					result.allow_shadowing_constants = map.next_value()?;
+					seen_allow_shadowing_constants = true;
+				}
+				"symbolic-variables" => {
+					if seen_symbolic_variables {
+						return Err(serde::de::Error::duplicate_field("symbolic-variables"));
+					}
+					result.symbolic_variables = map.next_value()?;
+					seen_symbolic_variables = true;
+				}
+				"locale" => {
+					if seen_locale {
+						return Err(serde::de::Error::duplicate_field("locale"));
+					}
+					result.locale = parse_locale(&map.next_value::<String>()?)?;
+					seen_locale = true;
+				}
 				"exchange-rate-source" => {
 					if seen_exchange_rate_source {
 						return Err(serde::de::Error::duplicate_field("exchange-rate-source"));
@@ -116,6 +524,13 @@ impl<'de> serde::de::Visitor<'de> for ConfigVisitor {
 					result.exchange_rate_source = map.next_value()?;
 					seen_exchange_rate_source = true;
 				}
+				"exchange-rate-max-age" => {
+					if seen_exchange_rate_max_age {
+						return Err(serde::de::Error::duplicate_field("exchange-rate-max-age"));
+					}
+					result.exchange_rate_max_age = map.next_value()?;
+					seen_exchange_rate_max_age = true;
+				}
 				"colors" => {
 					if seen_colors {
 						return Err(serde::de::Error::duplicate_field("colors"));
@@ -130,6 +545,29 @@ impl<'de> serde::de::Visitor<'de> for ConfigVisitor {
 					result.max_history_size = map.next_value()?;
 					seen_max_hist_size = true;
 				}
+				"enable-calculation-log" => {
+					if seen_enable_calculation_log {
+						return Err(serde::de::Error::duplicate_field("enable-calculation-log"));
+					}
+					result.enable_calculation_log = map.next_value()?;
+					seen_enable_calculation_log = true;
+				}
+				"max-calculation-log-size" => {
+					if seen_max_calculation_log_size {
+						return Err(serde::de::Error::duplicate_field(
+							"max-calculation-log-size",
+						));
+					}
+					result.max_calculation_log_size = map.next_value()?;
+					seen_max_calculation_log_size = true;
+				}
+				"persist-memory" => {
+					if seen_persist_memory {
+						return Err(serde::de::Error::duplicate_field("persist-memory"));
+					}
+					result.persist_memory = map.next_value()?;
+					seen_persist_memory = true;
+				}
 				"enable-internet-access" => {
 					if seen_enable_internet_access {
 						return Err(serde::de::Error::duplicate_field("enable-internet-access"));
@@ -174,10 +612,38 @@ impl<'de> serde::Deserialize<'de> for Config {
 			"prompt",
 			"enable-colors",
 			"coulomb-and-farad",
+			"scientific-notation-upper",
+			"scientific-notation-lower",
+			"keep-trailing-zeros",
+			"integer-decimal-point",
+			"prefer-fractions",
+			"table-format",
+			"unit-exponent-notation",
+			"exact-and-approximate",
+			"binary-digit-grouping",
+			"hex-digit-grouping",
+			"digit-separators",
+			"decimal-separator",
+			"parse-digit-separators",
+			"recurring-digits",
+			"accounting-negatives",
+			"derived-units",
+			"unit-system",
+			"multiplication-sign",
+			"division-sign",
+			"implicit-multiplication",
+			"show-conversion-steps",
+			"allow-shadowing-constants",
+			"symbolic-variables",
+			"locale",
 			"colors",
 			"max-history-size",
+			"enable-calculation-log",
+			"max-calculation-log-size",
+			"persist-memory",
 			"unknown-settings",
 			"enable-internet-access",
+			"exchange-rate-max-age",
 		];
 		deserializer.deserialize_struct("Config", FIELDS, ConfigVisitor)
 	}
@@ -189,11 +655,38 @@ impl Default for Config {
 			prompt: "> ".to_string(),
 			enable_colors: use_colors_if_auto(),
 			coulomb_and_farad: false,
+			scientific_notation_upper: None,
+			scientific_notation_lower: None,
+			keep_trailing_zeros: false,
+			integer_decimal_point: false,
+			prefer_fractions: false,
+			table_format: TableFormat::Aligned,
+			unit_exponent_notation: fend_core::UnitExponentNotation::Mixed,
+			exact_and_approximate: false,
+			binary_digit_grouping: 0,
+			hex_digit_grouping: 0,
+			digit_separators: fend_core::DigitSeparators::None,
+			parse_digit_separators: false,
+			recurring_digits: fend_core::RecurringDigitsNotation::Parentheses,
+			accounting_negatives: false,
+			derived_units: true,
+			unit_system: fend_core::UnitSystem::Metric,
+			multiplication_sign: fend_core::MultiplicationSign::Space,
+			division_sign: fend_core::DivisionSign::Slash,
+			implicit_multiplication: fend_core::ImplicitMultiplication::LeftToRight,
+			show_conversion_steps: false,
+			allow_shadowing_constants: false,
+			symbolic_variables: false,
+			locale: locale_from_env(),
 			colors: color::OutputColors::default(),
 			max_history_size: 1000,
+			enable_calculation_log: true,
+			max_calculation_log_size: 10000,
+			persist_memory: false,
 			enable_internet_access: true,
 			unknown_settings: UnknownSettings::Warn,
 			exchange_rate_source: ExchangeRateSource::UnitedNations,
+			exchange_rate_max_age: 72,
 			custom_units: vec![],
 			unknown_keys: vec![],
 		}
@@ -202,32 +695,85 @@ impl Default for Config {
 
 pub static DEFAULT_CONFIG_FILE: &str = include_str!("default_config.toml");
 
-fn read_config_file() -> Config {
+fn read_config_file() -> toml::Table {
 	let Ok(path) = crate::file_paths::get_config_file_location() else {
-		return Config::default();
+		return toml::Table::new();
 	};
 	let Ok(mut file) = fs::File::open(&path) else {
-		return Config::default();
+		return toml::Table::new();
 	};
 	let mut bytes = vec![];
 	let Ok(_) = <fs::File as io::Read>::read_to_end(&mut file, &mut bytes) else {
-		return Config::default();
+		return toml::Table::new();
 	};
 	let Ok(config_string) = String::from_utf8(bytes) else {
 		eprintln!("Error: config file is not UTF-8 encoded");
-		return Config::default();
+		return toml::Table::new();
 	};
-	let config = match toml::from_str(&config_string) {
-		Ok(config) => config,
+	match config_string.parse() {
+		Ok(table) => table,
 		Err(e) => {
-			eprintln!("Error: invalid config file in {path:?}:\n{e}");
+			eprintln!("Error: invalid config file in {}:\n{e}", path.display());
 			eprint!("Using the default config file instead, you can view it ");
 			eprintln!("by running `fend --default-config`");
-			Config::default()
+			toml::Table::new()
 		}
+	}
+}
+
+/// Applies a setting like `digit-separators=comma` from the command line.
+/// Values that aren't valid TOML are treated as strings, so they don't need
+/// to be quoted.
+// keys that change the same setting, of which only one may be used
+const SAME_SETTING: &[&[&str]] = &[
+	&["enable-colors", "color"],
+	&["digit-separators", "decimal-separator"],
+];
+
+fn apply_override(table: &mut toml::Table, setting: &str) -> Result<(), String> {
+	let Some((key, value)) = setting.split_once('=') else {
+		return Err(format!(
+			"expected a setting like `key=value`, found `{setting}`"
+		));
 	};
-	print_warnings_about_unknown_keys(&config);
-	config
+	let key = key.trim();
+	let value = value.trim();
+	// e.g. `--set decimal-separator=comma` replaces `digit-separators`
+	// from the config file
+	if let Some(keys) = SAME_SETTING.iter().find(|keys| keys.contains(&key)) {
+		for key in *keys {
+			table.remove(*key);
+		}
+	}
+	let value = format!("value = {value}")
+		.parse::<toml::Table>()
+		.ok()
+		.and_then(|mut parsed| parsed.remove("value"))
+		.unwrap_or_else(|| toml::Value::String(value.to_string()));
+	table.insert(key.to_string(), value);
+	Ok(())
+}
+
+// applies each override that leaves the config valid, and returns
+// an error message for every override that was skipped
+fn apply_overrides(table: &mut toml::Table, overrides: &[String]) -> Vec<String> {
+	let mut errors = vec![];
+	for setting in overrides {
+		let mut updated = table.clone();
+		let result = apply_override(&mut updated, setting)
+			.and_then(|()| parse_config(updated.clone()).map(|_| ()));
+		match result {
+			Ok(()) => *table = updated,
+			Err(e) => errors.push(format!("ignoring `--set {setting}`: {}", e.trim_end())),
+		}
+	}
+	errors
+}
+
+fn parse_config(table: toml::Table) -> Result<Config, String> {
+	toml::Value::Table(table)
+		.try_into()
+		.map_err(|e: toml::de::Error| e.to_string())
 }
 
 fn print_warnings_about_unknown_keys(config: &Config) {
@@ -267,8 +813,22 @@ fn use_colors_if_auto() -> bool {
 	false
 }
 
-pub fn read() -> Config {
-	read_config_file()
+/// Reads the config file, with settings from the command line (e.g.
+/// `--set locale=de`) taking precedence
+pub fn read(overrides: &[String]) -> Config {
+	let mut table = read_config_file();
+	if let Err(e) = parse_config(table.clone()) {
+		eprintln!("Error: invalid configuration:\n{e}");
+		eprint!("Using the default config file instead, you can view it ");
+		eprintln!("by running `fend --default-config`");
+		table = toml::Table::new();
+	}
+	for error in apply_overrides(&mut table, overrides) {
+		eprintln!("Warning: {error}");
+	}
+	let config = parse_config(table).unwrap_or_default();
+	print_warnings_about_unknown_keys(&config);
+	config
 }
 
 #[cfg(test)]
@@ -280,4 +840,120 @@ mod tests {
 		let deserialized: Config = toml::from_str(DEFAULT_CONFIG_FILE).unwrap();
 		assert_eq!(deserialized, Config::default());
 	}
+
+	#[test]
+	fn test_scientific_notation_thresholds() {
+		let config: Config =
+			toml::from_str("scientific-notation-upper = 12\nscientific-notation-lower = -6")
+				.unwrap();
+		assert_eq!(config.scientific_notation_upper, Some(12));
+		assert_eq!(config.scientific_notation_lower, Some(-6));
+		assert!(toml::from_str::<Config>("scientific-notation-upper = 'always'").is_err());
+	}
+
+	#[test]
+	fn test_locale() {
+		let config: Config = toml::from_str("locale = 'de'").unwrap();
+		assert_eq!(config.locale, fend_core::Locale::German);
+		let config: Config = toml::from_str("locale = 'auto'").unwrap();
+		assert_eq!(config.locale, locale_from_env());
+		assert!(toml::from_str::<Config>("locale = 'xx'").is_err());
+	}
+
+	#[test]
+	fn test_unit_system() {
+		let config: Config = toml::from_str("unit-system = 'imperial'").unwrap();
+		assert_eq!(config.unit_system, fend_core::UnitSystem::Imperial);
+		assert!(toml::from_str::<Config>("unit-system = 'customary'").is_err());
+	}
+
+	#[test]
+	fn test_implicit_multiplication() {
+		let config: Config = toml::from_str("implicit-multiplication = 'before-division'").unwrap();
+		assert_eq!(
+			config.implicit_multiplication,
+			fend_core::ImplicitMultiplication::BeforeDivision
+		);
+		assert!(toml::from_str::<Config>("implicit-multiplication = 'right-to-left'").is_err());
+	}
+
+	#[test]
+	fn test_digit_separators() {
+		let config: Config = toml::from_str(
+			"digit-separators = 'period-decimal-comma'\nparse-digit-separators = true",
+		)
+		.unwrap();
+		assert_eq!(
+			config.digit_separators,
+			fend_core::DigitSeparators::PeriodDecimalComma
+		);
+		assert!(config.parse_digit_separators);
+		assert!(toml::from_str::<Config>("digit-separators = 'apostrophe'").is_err());
+	}
+
+	#[test]
+	fn test_decimal_separator() {
+		let config: Config = toml::from_str("decimal-separator = 'comma'").unwrap();
+		assert_eq!(
+			config.digit_separators,
+			fend_core::DigitSeparators::DecimalComma
+		);
+		let config: Config = toml::from_str("decimal-separator = 'point'").unwrap();
+		assert_eq!(config.digit_separators, fend_core::DigitSeparators::None);
+		assert!(toml::from_str::<Config>(
+			"digit-separators = 'comma'\ndecimal-separator = 'comma'"
+		)
+		.is_err());
+
+		let mut table: toml::Table = "digit-separators = 'space'".parse().unwrap();
+		apply_override(&mut table, "decimal-separator=comma").unwrap();
+		let config = parse_config(table).unwrap();
+		assert_eq!(
+			config.digit_separators,
+			fend_core::DigitSeparators::DecimalComma
+		);
+	}
+
+	#[test]
+	fn test_exchange_rate_max_age() {
+		let config: Config = toml::from_str("exchange-rate-max-age = 168").unwrap();
+		assert_eq!(config.exchange_rate_max_age, 168);
+		assert!(toml::from_str::<Config>("exchange-rate-max-age = -1").is_err());
+	}
+
+	#[test]
+	fn test_overrides() {
+		let mut table: toml::Table = "locale = 'de'\nmax-history-size = 10".parse().unwrap();
+		apply_override(&mut table, "digit-separators=comma").unwrap();
+		apply_override(&mut table, "max-history-size = 20").unwrap();
+		apply_override(&mut table, "locale='en'").unwrap();
+		let config = parse_config(table).unwrap();
+		assert_eq!(config.digit_separators, fend_core::DigitSeparators::Comma);
+		assert_eq!(config.max_history_size, 20);
+		assert_eq!(config.locale, fend_core::Locale::English);
+
+		let mut table = toml::Table::new();
+		assert!(apply_override(&mut table, "locale").is_err());
+		apply_override(&mut table, "max-history-size=lots").unwrap();
+		assert!(parse_config(table).is_err());
+	}
+
+	#[test]
+	fn test_invalid_overrides_are_skipped() {
+		let mut table: toml::Table = "max-history-size = 10".parse().unwrap();
+		let overrides = [
+			"locale".to_string(),
+			"digit-separators=comma".to_string(),
+			"max-history-size=lots".to_string(),
+			"unit-system=imperial".to_string(),
+		];
+		let errors = apply_overrides(&mut table, &overrides);
+		assert_eq!(errors.len(), 2);
+		assert!(errors[0].starts_with("ignoring `--set locale`"));
+		assert!(errors[1].starts_with("ignoring `--set max-history-size=lots`"));
+		let config = parse_config(table).unwrap();
+		assert_eq!(config.max_history_size, 10);
+		assert_eq!(config.digit_separators, fend_core::DigitSeparators::Comma);
+		assert_eq!(config.unit_system, fend_core::UnitSystem::Imperial);
+	}
 }
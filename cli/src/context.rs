@@ -1,6 +1,52 @@
-use std::{cell::RefCell, time};
+use std::sync::{Arc, Mutex};
+use std::{cell::RefCell, fs, mem, time};
 
-use crate::{config, exchange_rates};
+use crate::{config, exchange_rates, file_paths, Error};
+
+// returns how many seconds the local time zone is behind UTC at the given
+// unix time (the same sign as JavaScript's `Date.getTimezoneOffset()`), or 0
+// if it can't be determined
+#[cfg(unix)]
+#[allow(unsafe_code, clippy::useless_conversion)]
+fn local_timezone_offset_secs(unix_secs: i64) -> i64 {
+	let Some(time) = libc::time_t::try_from(unix_secs).ok() else {
+		return 0;
+	};
+	let mut tm = mem::MaybeUninit::<libc::tm>::uninit();
+	// https://man7.org/linux/man-pages/man3/localtime_r.3p.html
+	let result = unsafe { libc::localtime_r(&raw const time, tm.as_mut_ptr()) };
+	if result.is_null() {
+		return 0;
+	}
+	let tm = unsafe { tm.assume_init() };
+	// `tm_gmtoff` is the number of seconds the time zone is ahead of UTC
+	-i64::from(tm.tm_gmtoff)
+}
+
+#[cfg(windows)]
+#[allow(unsafe_code)]
+fn local_timezone_offset_secs(_unix_secs: i64) -> i64 {
+	use windows_sys::Win32::System::Time::{GetTimeZoneInformation, TIME_ZONE_INFORMATION};
+
+	const TIME_ZONE_ID_STANDARD: u32 = 1;
+	const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+
+	// https://learn.microsoft.com/en-us/windows/win32/api/timezoneapi/nf-timezoneapi-gettimezoneinformation
+	let mut info = unsafe { mem::zeroed::<TIME_ZONE_INFORMATION>() };
+	let bias_minutes = match unsafe { GetTimeZoneInformation(&raw mut info) } {
+		TIME_ZONE_ID_STANDARD => info.Bias + info.StandardBias,
+		TIME_ZONE_ID_DAYLIGHT => info.Bias + info.DaylightBias,
+		0 => info.Bias,
+		_ => return 0,
+	};
+	// the bias is UTC minus local time, in minutes
+	i64::from(bias_minutes) * 60
+}
+
+#[cfg(not(any(unix, windows)))]
+fn local_timezone_offset_secs(_unix_secs: i64) -> i64 {
+	0
+}
 
 pub struct HintInterrupt {
 	start: time::Instant,
@@ -27,6 +73,12 @@ pub struct InnerCtx {
 
 	// true if the user typed some partial input, false otherwise
 	input_typed: bool,
+
+	// true if input is in Reverse Polish Notation, which can't be previewed
+	rpn_mode: bool,
+
+	// timestamp of the exchange rates used by the last calculation, if any
+	exchange_rate_timestamp: Option<u64>,
 }
 
 impl InnerCtx {
@@ -34,10 +86,50 @@ impl InnerCtx {
 		let mut res = Self {
 			core_ctx: fend_core::Context::new(),
 			input_typed: false,
+			rpn_mode: false,
+			exchange_rate_timestamp: None,
 		};
 		if config.coulomb_and_farad {
 			res.core_ctx.use_coulomb_and_farad();
 		}
+		res.core_ctx.set_scientific_notation_thresholds(
+			config.scientific_notation_upper,
+			config.scientific_notation_lower,
+		);
+		res.core_ctx
+			.set_keep_trailing_zeros(config.keep_trailing_zeros);
+		res.core_ctx
+			.set_integer_decimal_point(config.integer_decimal_point);
+		res.core_ctx.set_prefer_fractions(config.prefer_fractions);
+		res.core_ctx
+			.set_unit_exponent_notation(config.unit_exponent_notation);
+		res.core_ctx
+			.set_exact_and_approximate(config.exact_and_approximate);
+		res.core_ctx
+			.set_binary_digit_grouping(Some(config.binary_digit_grouping));
+		res.core_ctx
+			.set_hex_digit_grouping(Some(config.hex_digit_grouping));
+		res.core_ctx.set_digit_separators(config.digit_separators);
+		res.core_ctx
+			.set_parse_digit_separators(config.parse_digit_separators);
+		res.core_ctx
+			.set_recurring_digits_notation(config.recurring_digits);
+		res.core_ctx
+			.set_accounting_negatives(config.accounting_negatives);
+		res.core_ctx.set_derived_units(config.derived_units);
+		res.core_ctx.set_unit_system(config.unit_system);
+		res.core_ctx
+			.set_multiplication_sign(config.multiplication_sign);
+		res.core_ctx.set_division_sign(config.division_sign);
+		res.core_ctx
+			.set_implicit_multiplication(config.implicit_multiplication);
+		res.core_ctx
+			.set_show_conversion_steps(config.show_conversion_steps);
+		res.core_ctx
+			.set_allow_shadowing_constants(config.allow_shadowing_constants);
+		res.core_ctx
+			.set_symbolic_variables(config.symbolic_variables);
+		res.core_ctx.set_locale(config.locale);
 		for custom_unit in &config.custom_units {
 			res.core_ctx.define_custom_unit_v1(
 				&custom_unit.singular,
@@ -46,8 +138,36 @@ impl InnerCtx {
 				&custom_unit.attribute.to_fend_core(),
 			);
 		}
+		if config.persist_memory {
+			// ignore errors if e.g. no memory has been saved yet
+			mem::drop(res.load_memory());
+		}
 		res
 	}
+
+	fn load_memory(&mut self) -> Result<(), Error> {
+		let path = file_paths::get_user_units_file_location(file_paths::DirMode::DontCreate)?;
+		if let Ok(bytes) = fs::read(path) {
+			self.core_ctx
+				.deserialize_user_units(&mut bytes.as_slice())?;
+		}
+		let path = file_paths::get_memory_file_location(file_paths::DirMode::DontCreate)?;
+		let bytes = fs::read(path)?;
+		self.core_ctx.deserialize_memory(&mut bytes.as_slice())?;
+		Ok(())
+	}
+
+	fn save_memory(&self) -> Result<(), Error> {
+		let mut bytes = vec![];
+		self.core_ctx.serialize_memory(&mut bytes)?;
+		let path = file_paths::get_memory_file_location(file_paths::DirMode::Create)?;
+		fs::write(path, bytes)?;
+		let mut bytes = vec![];
+		self.core_ctx.serialize_user_units(&mut bytes)?;
+		let path = file_paths::get_user_units_file_location(file_paths::DirMode::Create)?;
+		fs::write(path, bytes)?;
+		Ok(())
+	}
 }
 
 #[derive(Clone)]
@@ -65,27 +185,79 @@ impl<'a> Context<'a> {
 		line: &str,
 		int: &impl fend_core::Interrupt,
 		config: &config::Config,
-	) -> Result<fend_core::FendResult, String> {
+	) -> Result<fend_core::FendResult, fend_core::EvaluationError> {
+		self.eval_with(config, |core_ctx| {
+			fend_core::evaluate_with_error_details(line, core_ctx, int)
+		})
+	}
+
+	/// Evaluates `line` with the given names bound to earlier values, see
+	/// [`fend_core::evaluate_with_values`]. This is used for the Reverse
+	/// Polish Notation stack, and doesn't change `_` or `ans`.
+	pub fn eval_with_values(
+		&self,
+		line: &str,
+		values: &[(&str, &fend_core::StoredValue)],
+		int: &impl fend_core::Interrupt,
+		config: &config::Config,
+	) -> Result<(fend_core::FendResult, fend_core::StoredValue), fend_core::EvaluationError> {
+		self.eval_with(config, |core_ctx| {
+			fend_core::evaluate_with_values(line, values, core_ctx, int)
+		})
+	}
+
+	fn eval_with<T>(
+		&self,
+		config: &config::Config,
+		eval: impl FnOnce(&mut fend_core::Context) -> T,
+	) -> T {
 		let mut ctx_borrow = self.ctx.borrow_mut();
 		ctx_borrow.core_ctx.set_random_u32_fn(random_u32);
 		ctx_borrow.core_ctx.set_output_mode_terminal();
+		if let Ok(now) = time::SystemTime::now().duration_since(time::UNIX_EPOCH) {
+			let ms = u64::try_from(now.as_millis()).unwrap_or(u64::MAX);
+			let secs = i64::try_from(now.as_secs()).unwrap_or(i64::MAX);
+			ctx_borrow
+				.core_ctx
+				.set_current_time_v1(ms, local_timezone_offset_secs(secs));
+		}
+		let rate_timestamp = Arc::new(Mutex::new(None));
 		let exchange_rate_handler = exchange_rates::ExchangeRateHandler {
 			enable_internet_access: config.enable_internet_access,
 			source: config.exchange_rate_source,
+			max_age: config.exchange_rate_max_age.saturating_mul(3600),
+			rate_timestamp: Arc::clone(&rate_timestamp),
 		};
 		ctx_borrow
 			.core_ctx
 			.set_exchange_rate_handler_v1(exchange_rate_handler);
 		ctx_borrow.input_typed = false;
-		fend_core::evaluate_with_interrupt(line, &mut ctx_borrow.core_ctx, int)
+		let res = eval(&mut ctx_borrow.core_ctx);
+		ctx_borrow.exchange_rate_timestamp = rate_timestamp.lock().ok().and_then(|t| *t);
+		if config.persist_memory {
+			// ignore errors when saving memory
+			mem::drop(ctx_borrow.save_memory());
+		}
+		res
 	}
 
-	pub fn eval_hint(&self, line: &str) -> fend_core::FendResult {
+	pub fn eval_hint(&self, line: &str) -> Option<fend_core::FendResult> {
 		let mut ctx_borrow = self.ctx.borrow_mut();
 		ctx_borrow.core_ctx.set_output_mode_terminal();
 		ctx_borrow.input_typed = !line.is_empty();
+		if ctx_borrow.rpn_mode {
+			return None;
+		}
 		let int = HintInterrupt::default();
-		fend_core::evaluate_preview_with_interrupt(line, &mut ctx_borrow.core_ctx, &int)
+		Some(fend_core::evaluate_preview_with_interrupt(
+			line,
+			&mut ctx_borrow.core_ctx,
+			&int,
+		))
+	}
+
+	pub fn get_completions(&self, line: &str) -> (usize, Vec<fend_core::Completion>) {
+		fend_core::get_completions(line, &self.ctx.borrow().core_ctx)
 	}
 
 	pub fn serialize(&self) -> Result<Vec<u8>, String> {
@@ -97,6 +269,19 @@ impl<'a> Context<'a> {
 		Ok(result)
 	}
 
+	pub fn set_rpn_mode(&self, rpn_mode: bool) {
+		self.ctx.borrow_mut().rpn_mode = rpn_mode;
+	}
+
+	/// The date of the exchange rates used by the last call to `eval`, if it
+	/// involved any currencies
+	pub fn get_exchange_rate_date(&self) -> Option<String> {
+		self.ctx
+			.borrow()
+			.exchange_rate_timestamp
+			.map(exchange_rates::format_date)
+	}
+
 	pub fn get_input_typed(&self) -> bool {
 		self.ctx.borrow().input_typed
 	}
@@ -1,10 +1,9 @@
 use crate::config::{self, ExchangeRateSource};
 use crate::file_paths;
 use crate::Error;
+use std::sync::{Arc, Mutex};
 use std::{error, fmt, fs, io::Write, time};
 
-const MAX_AGE: u64 = 86400 * 3;
-
 fn get_current_timestamp() -> Result<u64, Error> {
 	Ok(time::SystemTime::now()
 		.duration_since(time::SystemTime::UNIX_EPOCH)?
@@ -19,28 +18,36 @@ fn get_cache_filename(source: config::ExchangeRateSource) -> Result<&'static str
 	})
 }
 
-fn load_cached_data(source: config::ExchangeRateSource) -> Result<String, Error> {
+/// Exchange rate data together with the time (as a Unix timestamp) at which
+/// it was downloaded
+struct RateData {
+	xml: String,
+	timestamp: u64,
+}
+
+fn read_cached_data(source: config::ExchangeRateSource) -> Result<RateData, Error> {
 	let mut cache_file = file_paths::get_cache_dir(file_paths::DirMode::DontCreate)?;
 	cache_file.push(get_cache_filename(source)?);
 	let cache_contents = fs::read_to_string(cache_file)?;
-	let (timestamp, cache_xml) =
-		cache_contents.split_at(cache_contents.find(';').ok_or("invalid cache file")?);
-	let timestamp = timestamp.parse::<u64>()?;
-	let current_timestamp = get_current_timestamp()?;
-	let age = current_timestamp
-		.checked_sub(timestamp)
+	let (timestamp, cache_xml) = cache_contents.split_once(';').ok_or("invalid cache file")?;
+	Ok(RateData {
+		xml: cache_xml.to_string(),
+		timestamp: timestamp.parse::<u64>()?,
+	})
+}
+
+fn is_expired(data: &RateData, max_age: u64) -> Result<bool, Error> {
+	let age = get_current_timestamp()?
+		.checked_sub(data.timestamp)
 		.ok_or("invalid cache timestamp")?;
-	if age > MAX_AGE {
-		return Err("cache expired".into());
-	}
-	Ok(cache_xml.to_string())
+	Ok(age > max_age)
 }
 
-fn store_cached_data(source: config::ExchangeRateSource, xml: &str) -> Result<(), Error> {
+fn store_cached_data(source: config::ExchangeRateSource, data: &RateData) -> Result<(), Error> {
 	let mut cache_file = file_paths::get_cache_dir(file_paths::DirMode::Create)?;
 	cache_file.push(get_cache_filename(source)?);
 	let mut file = fs::File::create(cache_file)?;
-	write!(file, "{};{xml}", get_current_timestamp()?)?;
+	write!(file, "{};{}", data.timestamp, data.xml)?;
 	Ok(())
 }
 
@@ -55,13 +62,7 @@ fn http_get(_url: &str) -> Result<String, Error> {
 	Err("internet access has been disabled in this build of fend".into())
 }
 
-fn load_exchange_rate_xml(source: config::ExchangeRateSource) -> Result<(String, bool), Error> {
-	match load_cached_data(source) {
-		Ok(xml) => return Ok((xml, true)),
-		Err(_e) => {
-			// failed to load cached data
-		}
-	}
+fn download_exchange_rate_xml(source: config::ExchangeRateSource) -> Result<RateData, Error> {
 	let url = match source {
 		ExchangeRateSource::Disabled => return Err(ExchangeRateSourceDisabledError.into()),
 		ExchangeRateSource::EuropeanUnion => {
@@ -71,8 +72,64 @@ fn load_exchange_rate_xml(source: config::ExchangeRateSource) -> Result<(String,
 			"https://treasury.un.org/operationalrates/xsql2XML.php"
 		}
 	};
-	let xml = http_get(url)?;
-	Ok((xml, false))
+	let timestamp = get_current_timestamp()?;
+	Ok(RateData {
+		xml: http_get(url)?,
+		timestamp,
+	})
+}
+
+/// Uses the cached exchange rates if they are younger than `max_age` seconds,
+/// and otherwise downloads (and caches) new ones if internet access is enabled
+fn load_exchange_rates(
+	source: config::ExchangeRateSource,
+	max_age: u64,
+	enable_internet_access: bool,
+) -> Result<(Vec<(String, f64)>, u64), Error> {
+	let cached = read_cached_data(source).ok();
+	if let Some(data) = &cached {
+		if !is_expired(data, max_age)? {
+			if let Ok(parsed_data) = parse_exchange_rates(source, &data.xml) {
+				return Ok((parsed_data, data.timestamp));
+			}
+		}
+	}
+	if !enable_internet_access {
+		return Err(match cached {
+			Some(data) => CacheExpiredError(data.timestamp).into(),
+			None => InternetAccessDisabledError.into(),
+		});
+	}
+	let data = download_exchange_rate_xml(source)?;
+	let parsed_data = parse_exchange_rates(source, &data.xml)?;
+	store_cached_data(source, &data)?;
+	Ok((parsed_data, data.timestamp))
+}
+
+/// Downloads the latest exchange rates and stores them in the cache,
+/// regardless of how old the cached rates are. Returns the number of
+/// currencies that exchange rates were downloaded for.
+pub fn update_cached_exchange_rates(source: config::ExchangeRateSource) -> Result<usize, Error> {
+	let data = download_exchange_rate_xml(source)?;
+	let parsed_data = parse_exchange_rates(source, &data.xml)?;
+	store_cached_data(source, &data)?;
+	Ok(parsed_data.len())
+}
+
+/// Formats a Unix timestamp as a UTC date like `2024-06-30`
+pub fn format_date(timestamp: u64) -> String {
+	// see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+	let days = timestamp / 86400 + 719_468;
+	let era = days / 146_097;
+	let day_of_era = days % 146_097;
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let mp = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = year_of_era + era * 400 + u64::from(month <= 2);
+	format!("{year}-{month:02}-{day:02}")
 }
 
 fn parse_exchange_rates(
@@ -152,15 +209,6 @@ fn parse_exchange_rates_un(exchange_rates: &str) -> Result<Vec<(String, f64)>, E
 	Ok(result)
 }
 
-fn get_exchange_rates(source: config::ExchangeRateSource) -> Result<Vec<(String, f64)>, Error> {
-	let (xml, cached) = load_exchange_rate_xml(source)?;
-	let parsed_data = parse_exchange_rates(source, &xml)?;
-	if !cached {
-		store_cached_data(source, &xml)?;
-	}
-	Ok(parsed_data)
-}
-
 #[derive(Debug, Clone)]
 struct UnknownExchangeRate(String);
 
@@ -192,9 +240,29 @@ impl fmt::Display for ExchangeRateSourceDisabledError {
 
 impl error::Error for ExchangeRateSourceDisabledError {}
 
+#[derive(Copy, Clone, Debug)]
+pub struct CacheExpiredError(u64);
+impl fmt::Display for CacheExpiredError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"the cached exchange rates from {} have expired and internet access is disabled \
+			by fend configuration; run `fend --update-currencies` to download new rates",
+			format_date(self.0)
+		)
+	}
+}
+
+impl error::Error for CacheExpiredError {}
+
 pub struct ExchangeRateHandler {
 	pub enable_internet_access: bool,
 	pub source: ExchangeRateSource,
+	/// Maximum age of cached exchange rates, in seconds
+	pub max_age: u64,
+	/// Set to the timestamp of the exchange rates whenever they are used, so
+	/// that currency results can be annotated with the date of the rates
+	pub rate_timestamp: Arc<Mutex<Option<u64>>>,
 }
 
 impl fend_core::ExchangeRateFn for ExchangeRateHandler {
@@ -202,10 +270,11 @@ impl fend_core::ExchangeRateFn for ExchangeRateHandler {
 		&self,
 		currency: &str,
 	) -> Result<f64, Box<dyn std::error::Error + Send + Sync + 'static>> {
-		if !self.enable_internet_access {
-			return Err(InternetAccessDisabledError.into());
+		let (exchange_rates, timestamp) =
+			load_exchange_rates(self.source, self.max_age, self.enable_internet_access)?;
+		if let Ok(mut rate_timestamp) = self.rate_timestamp.lock() {
+			*rate_timestamp = Some(timestamp);
 		}
-		let exchange_rates = get_exchange_rates(self.source)?;
 		for (c, rate) in exchange_rates {
 			if currency == c {
 				return Ok(rate);
@@ -214,3 +283,16 @@ impl fend_core::ExchangeRateFn for ExchangeRateHandler {
 		Err(UnknownExchangeRate(currency.to_string()).into())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::format_date;
+
+	#[test]
+	fn test_format_date() {
+		assert_eq!(format_date(0), "1970-01-01");
+		assert_eq!(format_date(951_782_400), "2000-02-29");
+		assert_eq!(format_date(1_719_791_999), "2024-06-30");
+		assert_eq!(format_date(1_735_689_600), "2025-01-01");
+	}
+}
@@ -19,7 +19,7 @@ impl error::Error for HomeDirError {}
 
 impl From<HomeDirError> for io::Error {
 	fn from(e: HomeDirError) -> Self {
-		Self::new(io::ErrorKind::Other, e)
+		Self::other(e)
 	}
 }
 
@@ -101,6 +101,18 @@ pub fn get_history_file_location(mode: DirMode) -> Result<path::PathBuf, io::Err
 	Ok(history_path)
 }
 
+pub fn get_memory_file_location(mode: DirMode) -> Result<path::PathBuf, io::Error> {
+	let mut memory_path = get_state_dir(mode)?;
+	memory_path.push("memory");
+	Ok(memory_path)
+}
+
+pub fn get_user_units_file_location(mode: DirMode) -> Result<path::PathBuf, io::Error> {
+	let mut units_path = get_state_dir(mode)?;
+	units_path.push("units");
+	Ok(units_path)
+}
+
 pub fn get_cache_dir(mode: DirMode) -> Result<path::PathBuf, io::Error> {
 	// first try $FEND_CACHE_DIR
 	if let Some(env_var_cache_dir) = env::var_os("FEND_CACHE_DIR") {
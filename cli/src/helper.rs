@@ -1,3 +1,6 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
 use crate::{config, context::Context};
 
 pub struct Hint(String);
@@ -27,7 +30,7 @@ impl rustyline::hint::Hinter for Helper<'_> {
 	type Hint = Hint;
 
 	fn hint(&self, line: &str, _pos: usize, _ctx: &rustyline::Context<'_>) -> Option<Hint> {
-		let result = self.ctx.eval_hint(line);
+		let result = self.ctx.eval_hint(line)?;
 		let s = result.get_main_result();
 		Some(if s.is_empty() {
 			return None;
@@ -42,9 +45,65 @@ impl rustyline::hint::Hinter for Helper<'_> {
 	}
 }
 
-impl rustyline::highlight::Highlighter for Helper<'_> {}
+impl rustyline::highlight::Highlighter for Helper<'_> {
+	fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+		if !self.config.enable_colors {
+			return Cow::Borrowed(line);
+		}
+		let mut result = String::new();
+		for span in fend_core::highlight(line) {
+			let style = self.config.colors.get_color(span.kind());
+			write!(result, "{style}{}\x1b[0m", &line[span.range()]).unwrap();
+		}
+		Cow::Owned(result)
+	}
 
-impl rustyline::validate::Validator for Helper<'_> {}
+	fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+		self.config.enable_colors
+	}
+}
+
+// Returns true if the input has unclosed parentheses or brackets, in which
+// case pressing enter starts a new line instead of evaluating it. Brackets
+// inside double-quoted strings and comments are ignored.
+pub fn has_unclosed_brackets(input: &str) -> bool {
+	let mut depth: usize = 0;
+	let mut in_string = false;
+	let mut chars = input.chars().peekable();
+	while let Some(ch) = chars.next() {
+		match ch {
+			'"' => in_string = !in_string,
+			'\\' if in_string => {
+				chars.next();
+			}
+			'#' if !in_string && matches!(chars.peek(), Some(' ' | '!')) => {
+				// skip the rest of the line
+				for ch in chars.by_ref() {
+					if ch == '\n' {
+						break;
+					}
+				}
+			}
+			'(' | '[' | '{' if !in_string => depth += 1,
+			')' | ']' | '}' if !in_string => depth = depth.saturating_sub(1),
+			_ => (),
+		}
+	}
+	depth > 0
+}
+
+impl rustyline::validate::Validator for Helper<'_> {
+	fn validate(
+		&self,
+		ctx: &mut rustyline::validate::ValidationContext<'_>,
+	) -> rustyline::Result<rustyline::validate::ValidationResult> {
+		Ok(if has_unclosed_brackets(ctx.input()) {
+			rustyline::validate::ValidationResult::Incomplete
+		} else {
+			rustyline::validate::ValidationResult::Valid(None)
+		})
+	}
+}
 
 #[derive(Debug)]
 pub struct FendCandidate {
@@ -68,7 +127,7 @@ impl rustyline::completion::Completer for Helper<'_> {
 		pos: usize,
 		_ctx: &rustyline::Context<'_>,
 	) -> rustyline::Result<(usize, Vec<Self::Candidate>)> {
-		let (pos, completions) = fend_core::get_completions_for_prefix(&line[..pos]);
+		let (pos, completions) = self.ctx.get_completions(&line[..pos]);
 		let v: Vec<_> = completions
 			.into_iter()
 			.map(|c| FendCandidate { completion: c })
@@ -78,3 +137,20 @@ impl rustyline::completion::Completer for Helper<'_> {
 }
 
 impl rustyline::Helper for Helper<'_> {}
+
+#[cfg(test)]
+mod tests {
+	use super::has_unclosed_brackets;
+
+	#[test]
+	fn unclosed_brackets() {
+		assert!(!has_unclosed_brackets("1 + 2"));
+		assert!(has_unclosed_brackets("(1 +"));
+		assert!(has_unclosed_brackets("[1, 2,\n3"));
+		assert!(!has_unclosed_brackets("(1 +\n2)"));
+		assert!(!has_unclosed_brackets("\"(\" + 1"));
+		assert!(!has_unclosed_brackets("\"\\\"(\""));
+		assert!(!has_unclosed_brackets("1 # (comment"));
+		assert!(!has_unclosed_brackets("1)"));
+	}
+}
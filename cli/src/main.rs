@@ -4,9 +4,10 @@
 #![deny(elided_lifetimes_in_paths)]
 
 use std::fmt::Write;
-use std::{error, io, process};
+use std::{error, io, mem, process};
 
 mod args;
+mod calculation_log;
 mod color;
 mod config;
 mod context;
@@ -15,6 +16,8 @@ mod exchange_rates;
 mod file_paths;
 mod helper;
 mod interrupt;
+mod rpn;
+mod table;
 mod terminal;
 
 use args::Action as ArgsAction;
@@ -26,9 +29,21 @@ type Error = Box<dyn error::Error + Send + Sync + 'static>;
 enum EvalResult {
 	Ok,
 	Err,
+	// the input could not be parsed
+	SyntaxErr,
 	NoInput,
 }
 
+impl EvalResult {
+	fn exit_code(&self) -> ExitCode {
+		match self {
+			Self::Ok | Self::NoInput => ExitCode::SUCCESS,
+			Self::Err => ExitCode::FAILURE,
+			Self::SyntaxErr => ExitCode::from(2),
+		}
+	}
+}
+
 fn print_spans(spans: Vec<fend_core::SpanRef<'_>>, config: &config::Config) -> String {
 	let mut result = String::new();
 	for span in spans {
@@ -51,7 +66,27 @@ fn eval_and_print_res(
 			if result.is_empty() || res.is_unit_type() {
 				return EvalResult::NoInput;
 			}
+			if config.enable_calculation_log {
+				// ignore errors when logging the calculation
+				mem::drop(calculation_log::record(
+					line,
+					res.get_main_result(),
+					config.max_calculation_log_size,
+				));
+			}
 			if print_res {
+				if config.show_conversion_steps {
+					for step in res.conversion_steps() {
+						println!("{step}");
+					}
+				}
+				if let Some(table) = res
+					.table()
+					.and_then(|table| table::format(table, config.table_format.for_stdout()))
+				{
+					print!("{table}");
+					return EvalResult::Ok;
+				}
 				let string_result = if config.enable_colors {
 					print_spans(result, config)
 				} else {
@@ -62,9 +97,87 @@ fn eval_and_print_res(
 				} else {
 					print!("{string_result}");
 				}
+				if let Some(date) = context.get_exchange_rate_date() {
+					// printed to stderr so that scripts can still use the result as-is
+					eprintln!("(using exchange rates from {date})");
+				}
 			}
 			EvalResult::Ok
 		}
+		Err(e) => {
+			eprintln!("Error: {e}");
+			if e.kind() == fend_core::ErrorKind::Syntax {
+				EvalResult::SyntaxErr
+			} else {
+				EvalResult::Err
+			}
+		}
+	}
+}
+
+/// Like `eval_and_print_res`, but prints the result or error as JSON to stdout
+fn eval_and_print_json(
+	line: &str,
+	context: &mut Context<'_>,
+	print_res: bool,
+	int: &impl fend_core::Interrupt,
+	config: &config::Config,
+) -> EvalResult {
+	let (json, result) = match context.eval(line, int, config) {
+		Ok(res) => {
+			let json = res.get_result_in_format(fend_core::ResultFormat::Json);
+			(json.to_string(), EvalResult::Ok)
+		}
+		Err(e) if e.kind() == fend_core::ErrorKind::Syntax => (e.to_json(), EvalResult::SyntaxErr),
+		Err(e) => (e.to_json(), EvalResult::Err),
+	};
+	if print_res || !matches!(result, EvalResult::Ok) {
+		let warnings: Vec<_> = context
+			.get_exchange_rate_date()
+			.map(|date| format!("using exchange rates from {date}"))
+			.into_iter()
+			.collect();
+		println!("{}", add_json_warnings(json, &warnings));
+	}
+	result
+}
+
+/// Adds a `warnings` array to a JSON object, so that notices like the
+/// exchange rate date don't need to be printed separately
+fn add_json_warnings(mut json: String, warnings: &[String]) -> String {
+	let closing_brace = json.pop();
+	debug_assert_eq!(closing_brace, Some('}'));
+	json.push_str(",\"warnings\":[");
+	for (i, warning) in warnings.iter().enumerate() {
+		if i > 0 {
+			json.push(',');
+		}
+		json.push('"');
+		fend_core::json::escape_string(warning, &mut json);
+		json.push('"');
+	}
+	json.push_str("]}");
+	json
+}
+
+fn eval_rpn(
+	line: &str,
+	stack: &mut rpn::Stack,
+	context: &Context<'_>,
+	int: &impl fend_core::Interrupt,
+	config: &config::Config,
+) -> EvalResult {
+	let res = stack.eval_line(line, |expr, values| {
+		let (res, value) = context
+			.eval_with_values(expr, values, int, config)
+			.map_err(|e| e.to_string())?;
+		if res.is_unit_type() || res.get_main_result().is_empty() {
+			return Err(format!("'{expr}' does not have a value"));
+		}
+		Ok((res.get_main_result().to_string(), value))
+	});
+	match res {
+		Ok(()) => EvalResult::Ok,
 		Err(msg) => {
 			eprintln!("Error: {msg}");
 			EvalResult::Err
@@ -88,6 +201,11 @@ fn print_help(explain_quitting: bool) {
 	} else {
 		println!("Failed to get history file location");
 	}
+	if let Ok(log_path) = calculation_log::get_location(file_paths::DirMode::DontCreate) {
+		println!("Calculation log: {}", log_path.to_string_lossy());
+	} else {
+		println!("Failed to get calculation log location");
+	}
 	if let Ok(cache_path) = file_paths::get_cache_dir(file_paths::DirMode::DontCreate) {
 		println!("Cache directory: {}", cache_path.to_string_lossy());
 	} else {
@@ -98,7 +216,7 @@ fn print_help(explain_quitting: bool) {
 	}
 }
 
-fn repl_loop(config: &config::Config) -> ExitCode {
+fn repl_loop(config: &config::Config, rpn: bool) -> ExitCode {
 	let core_context = std::cell::RefCell::new(context::InnerCtx::new(config));
 	let mut context = Context::new(&core_context);
 	let mut prompt_state = match terminal::init_prompt(config, &context) {
@@ -111,6 +229,8 @@ fn repl_loop(config: &config::Config) -> ExitCode {
 	let mut initial_run = true; // set to false after first successful command
 	let mut last_command_success = true;
 	let interrupt = interrupt::register_handler();
+	let mut rpn_stack = rpn.then(rpn::Stack::default);
+	context.set_rpn_mode(rpn);
 	loop {
 		match prompt_state.read_line() {
 			Ok(line) => match line.as_str() {
@@ -119,13 +239,34 @@ fn repl_loop(config: &config::Config) -> ExitCode {
 				"help" | "?" => {
 					print_help(true);
 				}
+				line if line == ":search" || line.starts_with(":search ") => {
+					let query = calculation_log::parse_query(&line[":search".len()..]);
+					if let Err(e) = calculation_log::print_search_results(query) {
+						eprintln!("Error: {e}");
+					}
+				}
+				"set input rpn" => {
+					rpn_stack.get_or_insert_with(rpn::Stack::default);
+					context.set_rpn_mode(true);
+				}
+				"set input infix" => {
+					rpn_stack = None;
+					context.set_rpn_mode(false);
+				}
 				"!serialize" => match context.serialize() {
 					Ok(res) => println!("{res:?}"),
 					Err(e) => eprintln!("{e}"),
 				},
 				line => {
 					interrupt.reset();
-					match eval_and_print_res(line, &mut context, true, &interrupt, config) {
+					let res = if let Some(stack) = &mut rpn_stack {
+						let res = eval_rpn(line, stack, &context, &interrupt, config);
+						print!("{stack}");
+						res
+					} else {
+						eval_and_print_res(line, &mut context, true, &interrupt, config)
+					};
+					match res {
 						EvalResult::Ok => {
 							last_command_success = true;
 							initial_run = false;
@@ -133,7 +274,7 @@ fn repl_loop(config: &config::Config) -> ExitCode {
 						EvalResult::NoInput => {
 							last_command_success = true;
 						}
-						EvalResult::Err => {
+						EvalResult::Err | EvalResult::SyntaxErr => {
 							last_command_success = false;
 						}
 					}
@@ -168,29 +309,118 @@ fn repl_loop(config: &config::Config) -> ExitCode {
 	}
 }
 
-fn eval_exprs(exprs: &[String]) -> ExitCode {
-	let config = config::read();
+/// Evaluates the expressions one after the other. Only the last result is
+/// printed, and evaluation stops at the first error.
+fn eval_exprs(
+	exprs: &[String],
+	overrides: &[String],
+	show_work: bool,
+	rpn: bool,
+	json: bool,
+) -> ExitCode {
+	let mut config = config::read(overrides);
+	config.show_conversion_steps |= show_work;
+	// only calculations entered in the REPL are logged
+	config.enable_calculation_log = false;
 	let core_context = std::cell::RefCell::new(context::InnerCtx::new(&config));
+	if rpn {
+		let mut stack = rpn::Stack::default();
+		for expr in exprs {
+			let context = Context::new(&core_context);
+			let int = interrupt::Never::default();
+			if let EvalResult::Err = eval_rpn(expr, &mut stack, &context, &int, &config) {
+				return ExitCode::FAILURE;
+			}
+		}
+		for result in stack.results() {
+			println!("{result}");
+		}
+		return ExitCode::SUCCESS;
+	}
 	for (i, expr) in exprs.iter().enumerate() {
 		let print_res = i == exprs.len() - 1;
-		match eval_and_print_res(
+		let eval_fn = if json {
+			eval_and_print_json
+		} else {
+			eval_and_print_res
+		};
+		let res = eval_fn(
 			expr.as_str(),
 			&mut Context::new(&core_context),
 			print_res,
 			&interrupt::Never::default(),
 			&config,
-		) {
-			EvalResult::Ok | EvalResult::NoInput => (),
-			EvalResult::Err => return ExitCode::FAILURE,
+		);
+		if let EvalResult::Err | EvalResult::SyntaxErr = res {
+			return res.exit_code();
 		}
 	}
 	ExitCode::SUCCESS
 }
 
+/// Evaluates input piped into stdin one line at a time, printing every
+/// result (as one JSON object per line if `json` is set). Lines with
+/// unclosed parentheses or brackets are continued on the next line.
+/// Evaluation continues after errors, but the exit code is that of the
+/// first error.
+fn eval_stdin_lines(
+	input: &str,
+	overrides: &[String],
+	show_work: bool,
+	rpn: bool,
+	json: bool,
+) -> ExitCode {
+	let mut exprs = vec![];
+	let mut expr = String::new();
+	for line in input.lines() {
+		if !expr.is_empty() {
+			expr.push('\n');
+		}
+		expr.push_str(line);
+		if helper::has_unclosed_brackets(&expr) {
+			continue;
+		}
+		if expr.trim().is_empty() {
+			expr.clear();
+		} else {
+			exprs.push(mem::take(&mut expr));
+		}
+	}
+	if !expr.trim().is_empty() {
+		exprs.push(expr);
+	}
+	if rpn {
+		return eval_exprs(&exprs, overrides, show_work, rpn, false);
+	}
+	let mut config = config::read(overrides);
+	config.show_conversion_steps |= show_work;
+	config.enable_calculation_log = false;
+	let core_context = std::cell::RefCell::new(context::InnerCtx::new(&config));
+	let eval_fn = if json {
+		eval_and_print_json
+	} else {
+		eval_and_print_res
+	};
+	let mut exit_code = ExitCode::SUCCESS;
+	for expr in &exprs {
+		let res = eval_fn(
+			expr,
+			&mut Context::new(&core_context),
+			true,
+			&interrupt::Never::default(),
+			&config,
+		);
+		if exit_code == ExitCode::SUCCESS {
+			exit_code = res.exit_code();
+		}
+	}
+	exit_code
+}
+
 fn real_main() -> ExitCode {
 	// Assemble the action from all but the first argument.
-	let action = match ArgsAction::get() {
-		Ok(action) => action,
+	let (action, overrides) = match ArgsAction::get() {
+		Ok(res) => res,
 		Err(e) => {
 			eprintln!("Error: {e}");
 			return ExitCode::FAILURE;
@@ -206,13 +436,43 @@ fn real_main() -> ExitCode {
 		ArgsAction::DefaultConfig => {
 			println!("{}", config::DEFAULT_CONFIG_FILE);
 		}
-		ArgsAction::Eval { exprs } => {
-			return eval_exprs(&exprs);
+		ArgsAction::UpdateCurrencies => {
+			let config = config::read(&overrides);
+			match exchange_rates::update_cached_exchange_rates(config.exchange_rate_source) {
+				Ok(count) => println!("Downloaded exchange rates for {count} currencies"),
+				Err(e) => {
+					eprintln!("Error: failed to update exchange rates: {e}");
+					return ExitCode::FAILURE;
+				}
+			}
 		}
-		ArgsAction::Repl => {
+		ArgsAction::History { query } => {
+			if let Err(e) = calculation_log::print_search_results(query.as_deref()) {
+				eprintln!("Error: {e}");
+				return ExitCode::FAILURE;
+			}
+		}
+		ArgsAction::Eval {
+			exprs,
+			show_work,
+			rpn,
+			json,
+		} => {
+			return eval_exprs(&exprs, &overrides, show_work, rpn, json);
+		}
+		ArgsAction::Repl {
+			show_work,
+			rpn,
+			json,
+		} => {
 			if terminal::is_terminal_stdin() {
-				let config = config::read();
-				return repl_loop(&config);
+				if json {
+					eprintln!("Error: --json requires an expression or piped input");
+					return ExitCode::FAILURE;
+				}
+				let mut config = config::read(&overrides);
+				config.show_conversion_steps |= show_work;
+				return repl_loop(&config, rpn);
 			}
 			let mut input = String::new();
 			match io::Read::read_to_string(&mut io::stdin(), &mut input) {
@@ -222,7 +482,7 @@ fn real_main() -> ExitCode {
 					return ExitCode::FAILURE;
 				}
 			}
-			return eval_exprs(&[input]);
+			return eval_stdin_lines(&input, &overrides, show_work, rpn, json);
 		}
 	}
 	ExitCode::SUCCESS
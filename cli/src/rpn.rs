@@ -0,0 +1,206 @@
+use fend_core::StoredValue;
+use std::fmt;
+
+// Reverse Polish Notation input, e.g. `3 4 + 2 *`. Every stack entry keeps
+// its evaluated value, so that results stay exact (e.g. `1 3 /` is stored as
+// 1/3 rather than as `0.3333333333`). Operators are applied to those values
+// by evaluating e.g. `a + b` with `a` and `b` bound to the operands.
+
+const BINARY_OPERATORS: &[&str] = &["+", "-", "*", "/", "^", "mod"];
+const UNARY_FUNCTIONS: &[&str] = &[
+	"sqrt", "cbrt", "abs", "sin", "cos", "tan", "asin", "acos", "atan", "ln", "log", "log10",
+	"exp", "floor", "ceil", "round",
+];
+
+#[derive(Clone, Debug)]
+struct Entry {
+	value: StoredValue,
+	result: String,
+}
+
+/// Evaluates an expression, with the given names bound to the values of
+/// stack entries, returning the formatted result and its value
+pub trait Eval:
+	FnMut(&str, &[(&str, &StoredValue)]) -> Result<(String, StoredValue), String>
+{
+}
+
+impl<F: FnMut(&str, &[(&str, &StoredValue)]) -> Result<(String, StoredValue), String>> Eval for F {}
+
+#[derive(Default, Debug)]
+pub struct Stack {
+	entries: Vec<Entry>,
+}
+
+impl Stack {
+	/// Applies each whitespace-separated token in `line` to the stack. If any
+	/// token fails, the stack is left unchanged.
+	pub fn eval_line(&mut self, line: &str, mut eval: impl Eval) -> Result<(), String> {
+		let mut entries = self.entries.clone();
+		for token in line.split_whitespace() {
+			apply_token(&mut entries, token, &mut eval)?;
+		}
+		self.entries = entries;
+		Ok(())
+	}
+
+	/// The results on the stack, from the bottom to the top
+	pub fn results(&self) -> impl Iterator<Item = &str> {
+		self.entries.iter().map(|entry| entry.result.as_str())
+	}
+}
+
+impl fmt::Display for Stack {
+	// shows each level of the stack, with the top of the stack (level 1)
+	// at the bottom
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let len = self.entries.len();
+		for (i, entry) in self.entries.iter().enumerate() {
+			writeln!(f, "{}: {}", len - i, entry.result)?;
+		}
+		Ok(())
+	}
+}
+
+fn pop(entries: &mut Vec<Entry>, token: &str, count: usize) -> Result<Vec<Entry>, String> {
+	if entries.len() < count {
+		let values = if count == 1 { "value" } else { "values" };
+		return Err(format!(
+			"stack underflow: `{token}` needs {count} {values}, but the stack has {}",
+			entries.len()
+		));
+	}
+	Ok(entries.split_off(entries.len() - count))
+}
+
+/// Evaluates `expr` with the operands bound to `a` and `b`, and pushes the
+/// result
+fn push(
+	entries: &mut Vec<Entry>,
+	expr: &str,
+	operands: &[Entry],
+	eval: &mut impl Eval,
+) -> Result<(), String> {
+	let values: Vec<_> = ["a", "b"]
+		.into_iter()
+		.zip(operands.iter().map(|entry| &entry.value))
+		.collect();
+	let (result, value) = eval(expr, &values)?;
+	entries.push(Entry { value, result });
+	Ok(())
+}
+
+fn apply_token(entries: &mut Vec<Entry>, token: &str, eval: &mut impl Eval) -> Result<(), String> {
+	match token {
+		"dup" => {
+			let top = pop(entries, token, 1)?;
+			entries.extend(top.clone());
+			entries.extend(top);
+		}
+		"swap" => {
+			let mut top = pop(entries, token, 2)?;
+			top.reverse();
+			entries.extend(top);
+		}
+		"drop" => {
+			pop(entries, token, 1)?;
+		}
+		"clear" => entries.clear(),
+		"neg" | "inv" | "!" => {
+			let operand = pop(entries, token, 1)?;
+			let expr = match token {
+				"neg" => "-a",
+				"inv" => "1 / a",
+				_ => "a!",
+			};
+			push(entries, expr, &operand, eval)?;
+		}
+		op if BINARY_OPERATORS.contains(&op) => {
+			let operands = pop(entries, token, 2)?;
+			push(entries, &format!("a {op} b"), &operands, eval)?;
+		}
+		func if UNARY_FUNCTIONS.contains(&func) => {
+			let operand = pop(entries, token, 1)?;
+			push(entries, &format!("{func}(a)"), &operand, eval)?;
+		}
+		value => push(entries, value, &[], eval)?,
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Stack;
+
+	fn eval_rpn(stack: &mut Stack, line: &str) -> Result<(), String> {
+		let mut context = fend_core::Context::new();
+		stack.eval_line(line, |expr, values| {
+			let int = crate::interrupt::Never::default();
+			let (res, value) = fend_core::evaluate_with_values(expr, values, &mut context, &int)
+				.map_err(|e| e.to_string())?;
+			if res.is_unit_type() {
+				return Err("expected a value".to_string());
+			}
+			Ok((res.get_main_result().to_string(), value))
+		})
+	}
+
+	fn results(stack: &Stack) -> Vec<&str> {
+		stack.results().collect()
+	}
+
+	#[test]
+	fn arithmetic() {
+		let mut stack = Stack::default();
+		eval_rpn(&mut stack, "3 4 + 2 *").unwrap();
+		assert_eq!(results(&stack), ["14"]);
+		eval_rpn(&mut stack, "5 - 2 ^").unwrap();
+		assert_eq!(results(&stack), ["81"]);
+		eval_rpn(&mut stack, "clear 1 3 / 3 *").unwrap();
+		assert_eq!(results(&stack), ["1"]);
+		eval_rpn(&mut stack, "clear 16 sqrt neg 5 !").unwrap();
+		assert_eq!(results(&stack), ["-4", "120"]);
+		eval_rpn(&mut stack, "clear 5 miles * km /").unwrap();
+		assert_eq!(results(&stack), ["8.04672"]);
+	}
+
+	#[test]
+	fn operators_apply_to_stored_values() {
+		let mut stack = Stack::default();
+		// each `dup +` would double the length of a re-evaluated expression
+		let line = format!("1{}", " dup +".repeat(100));
+		eval_rpn(&mut stack, &line).unwrap();
+		assert_eq!(results(&stack), ["1267650600228229401496703205376"]);
+		eval_rpn(&mut stack, "clear 1 3 / dup dup + +").unwrap();
+		assert_eq!(results(&stack), ["1"]);
+	}
+
+	#[test]
+	fn stack_commands() {
+		let mut stack = Stack::default();
+		eval_rpn(&mut stack, "1 2 3").unwrap();
+		assert_eq!(stack.to_string(), "3: 1\n2: 2\n1: 3\n");
+		eval_rpn(&mut stack, "swap").unwrap();
+		assert_eq!(results(&stack), ["1", "3", "2"]);
+		eval_rpn(&mut stack, "drop dup").unwrap();
+		assert_eq!(results(&stack), ["1", "3", "3"]);
+		eval_rpn(&mut stack, "clear").unwrap();
+		assert_eq!(stack.to_string(), "");
+	}
+
+	#[test]
+	fn errors_leave_the_stack_unchanged() {
+		let mut stack = Stack::default();
+		eval_rpn(&mut stack, "1 2").unwrap();
+		assert_eq!(
+			eval_rpn(&mut stack, "+ +"),
+			Err("stack underflow: `+` needs 2 values, but the stack has 1".to_string())
+		);
+		assert_eq!(results(&stack), ["1", "2"]);
+		assert_eq!(
+			eval_rpn(&mut stack, "0 /"),
+			Err("division by zero".to_string())
+		);
+		assert_eq!(results(&stack), ["1", "2"]);
+	}
+}
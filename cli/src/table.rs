@@ -0,0 +1,162 @@
+use std::fmt::Write;
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum TableFormat {
+	/// Print tables as plain results
+	Disabled,
+	/// Print tables with aligned columns
+	Aligned,
+	/// Print tables as comma-separated values
+	Csv,
+}
+
+impl TableFormat {
+	/// Aligned tables are only useful in a terminal, so results that are
+	/// piped into other programs are printed as usual
+	pub fn for_stdout(self) -> Self {
+		if self == Self::Aligned && !crate::terminal::is_terminal_stdout() {
+			Self::Disabled
+		} else {
+			self
+		}
+	}
+}
+
+struct TableFormatVisitor;
+
+impl serde::de::Visitor<'_> for TableFormatVisitor {
+	type Value = TableFormat;
+
+	fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		formatter.write_str("`aligned`, `csv`, or `disabled`")
+	}
+
+	fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+		Ok(match v {
+			"aligned" => TableFormat::Aligned,
+			"csv" => TableFormat::Csv,
+			"disabled" => TableFormat::Disabled,
+			_ => {
+				return Err(serde::de::Error::unknown_variant(
+					v,
+					&["aligned", "csv", "disabled"],
+				))
+			}
+		})
+	}
+}
+
+impl<'de> serde::Deserialize<'de> for TableFormat {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_str(TableFormatVisitor)
+	}
+}
+
+/// Formats the table with a header line, and pads every column to the width
+/// of its widest cell
+fn format_aligned(table: &fend_core::Table) -> String {
+	let mut widths: Vec<usize> = table.headers().iter().map(|h| h.chars().count()).collect();
+	for row in table.rows() {
+		for (width, cell) in widths.iter_mut().zip(row) {
+			*width = (*width).max(cell.chars().count());
+		}
+	}
+	let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+	let mut result = String::new();
+	for row in [table.headers(), separator.as_slice()]
+		.into_iter()
+		.chain(table.rows().iter().map(Vec::as_slice))
+	{
+		let mut line = String::new();
+		for (i, (cell, width)) in row.iter().zip(&widths).enumerate() {
+			if i != 0 {
+				line.push_str("  ");
+			}
+			write!(line, "{cell:width$}").unwrap();
+		}
+		result.push_str(line.trim_end());
+		result.push('\n');
+	}
+	result
+}
+
+fn format_csv_cell(cell: &str) -> String {
+	if cell.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", cell.replace('"', "\"\""))
+	} else {
+		cell.to_string()
+	}
+}
+
+fn format_csv(table: &fend_core::Table) -> String {
+	let mut result = String::new();
+	for row in std::iter::once(table.headers()).chain(table.rows().iter().map(Vec::as_slice)) {
+		let cells: Vec<String> = row.iter().map(|cell| format_csv_cell(cell)).collect();
+		result.push_str(&cells.join(","));
+		result.push('\n');
+	}
+	result
+}
+
+/// Returns the table formatted according to `format`, or `None` if tables
+/// are disabled
+pub fn format(table: &fend_core::Table, format: TableFormat) -> Option<String> {
+	match format {
+		TableFormat::Disabled => None,
+		TableFormat::Aligned => Some(format_aligned(table)),
+		TableFormat::Csv => Some(format_csv(table)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn earth_table() -> fend_core::Table {
+		let mut ctx = fend_core::Context::new();
+		let res = fend_core::evaluate("earth", &mut ctx).unwrap();
+		res.table().unwrap().clone()
+	}
+
+	#[test]
+	fn aligned_table() {
+		let table = earth_table();
+		let output = format(&table, TableFormat::Aligned).unwrap();
+		let lines: Vec<&str> = output.lines().collect();
+		assert_eq!(lines.len(), table.rows().len() + 2);
+		assert_eq!(lines[0], "name             value");
+		assert!(lines[1].starts_with("---------------  -----"));
+		assert_eq!(lines[2], "axial_tilt       23.4392811 degrees");
+	}
+
+	#[test]
+	fn csv_table() {
+		let table = earth_table();
+		let output = format(&table, TableFormat::Csv).unwrap();
+		assert!(output.starts_with("name,value\naxial_tilt,23.4392811 degrees\n"));
+		assert_eq!(format_csv_cell("a,\"b\""), "\"a,\"\"b\"\"\"");
+		assert!(format(&table, TableFormat::Disabled).is_none());
+	}
+
+	fn table_of(input: &str) -> fend_core::Table {
+		let mut ctx = fend_core::Context::new();
+		let res = fend_core::evaluate(input, &mut ctx).unwrap();
+		res.table().unwrap().clone()
+	}
+
+	#[test]
+	fn csv_table_of_rows() {
+		let output = format(&table_of("amortize(1%, 3, 1000)"), TableFormat::Csv).unwrap();
+		assert_eq!(
+			output,
+			"period,payment,interest,principal,balance\n\
+			1,340.02,10,330.02,669.98\n\
+			2,340.02,6.7,333.32,336.66\n\
+			3,340.03,3.37,336.66,0\n"
+		);
+		let output = format(&table_of("[[1, 2], [3, 4]]"), TableFormat::Csv).unwrap();
+		assert_eq!(output, "#,1,2\n1,1,2\n2,3,4\n");
+		let output = format(&table_of("[[1 m], [2 m]]"), TableFormat::Aligned).unwrap();
+		assert_eq!(output, "#  1\n-  ---\n1  1 m\n2  2 m\n");
+	}
+}
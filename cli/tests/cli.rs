@@ -0,0 +1,240 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::{env, fs, time};
+
+/// Runs fend with the given arguments and stdin, using an empty
+/// configuration and state directory so that the tests don't depend on (or
+/// modify) the user's files. Returns stdout, whether fend succeeded and
+/// whether a calculation was logged.
+fn run(name: &str, args: &[&str], stdin: &str) -> (String, bool, bool) {
+	let output = run_with(name, args, &[], &[], stdin);
+	(output.stdout, output.success, output.logged)
+}
+
+struct Output {
+	stdout: String,
+	stderr: String,
+	success: bool,
+	exit_code: Option<i32>,
+	logged: bool,
+}
+
+/// Like `run`, but also sets the given environment variables and creates
+/// the given files in the cache directory before running fend
+fn run_with(
+	name: &str,
+	args: &[&str],
+	vars: &[(&str, &str)],
+	cache_files: &[(&str, &str)],
+	stdin: &str,
+) -> Output {
+	let dir: PathBuf = env::temp_dir().join(format!("fend-test-{name}-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+	for (filename, contents) in cache_files {
+		fs::write(dir.join(filename), contents).unwrap();
+	}
+	let mut child = Command::new(env!("CARGO_BIN_EXE_fend"))
+		.args(args)
+		.env("FEND_CONFIG_DIR", &dir)
+		.env("FEND_STATE_DIR", &dir)
+		.env("FEND_CACHE_DIR", &dir)
+		.envs(vars.iter().copied())
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	child
+		.stdin
+		.take()
+		.unwrap()
+		.write_all(stdin.as_bytes())
+		.unwrap();
+	let output = child.wait_with_output().unwrap();
+	let logged = dir.join("calculations.tsv").exists();
+	fs::remove_dir_all(&dir).unwrap();
+	Output {
+		stdout: String::from_utf8(output.stdout).unwrap(),
+		stderr: String::from_utf8(output.stderr).unwrap(),
+		success: output.status.success(),
+		exit_code: output.status.code(),
+		logged,
+	}
+}
+
+#[test]
+fn stdin_lines() {
+	let (stdout, success, _) = run("plain", &[], "1 + 1\n2 * 3\n");
+	assert_eq!(stdout, "2\n6\n");
+	assert!(success);
+}
+
+#[test]
+fn stdin_lines_continue_unclosed_brackets() {
+	let (stdout, success, _) = run("brackets", &[], "a = 5\n2a\n(a +\n1)\n\n");
+	assert_eq!(stdout, "5\n10\n6\n");
+	assert!(success);
+}
+
+#[test]
+fn expressions_separated_by_double_dash() {
+	let (stdout, success, _) = run("double-dash", &["a = 5", "--", "2a", "--", "-a"], "");
+	assert_eq!(stdout, "-5\n");
+	assert!(success);
+}
+
+#[test]
+fn syntax_errors_have_their_own_exit_code() {
+	let exit_code = |name, args: &[&str], stdin| run_with(name, args, &[], &[], stdin).exit_code;
+	assert_eq!(exit_code("code-ok", &["1 + 1"], ""), Some(0));
+	assert_eq!(exit_code("code-eval", &["1 / 0"], ""), Some(1));
+	assert_eq!(exit_code("code-syntax", &["1 +"], ""), Some(2));
+	// piped input uses the exit code of the first error
+	assert_eq!(exit_code("code-stdin", &[], "1 +\n1 / 0\n2\n"), Some(2));
+	assert_eq!(exit_code("code-stdin-eval", &[], "1 / 0\n1 +\n"), Some(1));
+}
+
+#[test]
+fn stdin_lines_as_json() {
+	let (stdout, success, _) = run("json", &["--json"], "1 + 1\nxyzzyq\n");
+	let lines: Vec<_> = stdout.lines().collect();
+	assert_eq!(lines.len(), 2);
+	assert!(lines[0].starts_with('{') && lines[0].contains(r#""plain":"2""#));
+	assert!(lines[1].starts_with('{') && lines[1].contains(r#""type":"error""#));
+	assert!(lines
+		.iter()
+		.all(|line| line.ends_with(r#","warnings":[]}"#)));
+	assert!(!success);
+}
+
+#[test]
+fn show_work_as_json() {
+	let (stdout, success, _) = run(
+		"show-work-json",
+		&["--json", "--show-work", "1 mile to m"],
+		"",
+	);
+	assert!(stdout.contains(r#""steps":["1 mile = 1609.344 meters"]"#));
+	assert!(success);
+}
+
+#[test]
+fn stdin_lines_show_work() {
+	let (stdout, success, _) = run("show-work", &["--show-work"], "1 mile to m\n");
+	assert_eq!(stdout, "1 mile = 1609.344 meters\n1609.344 m\n");
+	assert!(success);
+}
+
+#[test]
+fn tables_are_only_aligned_in_a_terminal() {
+	let (stdout, success, _) = run("table-piped", &["amortize(1%, 3, 1000)"], "");
+	assert!(
+		stdout.starts_with("{\n    period: [1, 2, 3],\n"),
+		"{stdout}"
+	);
+	assert!(success);
+	let (stdout, success, _) = run(
+		"table-csv",
+		&["--set", "table-format=csv", "amortize(1%, 3, 1000)"],
+		"",
+	);
+	assert!(stdout.starts_with("period,payment,interest,principal,balance\n"));
+	assert!(success);
+}
+
+#[test]
+fn only_repl_calculations_are_logged() {
+	let (stdout, success, logged) = run("log-args", &["1 + 1"], "");
+	assert_eq!(stdout, "2\n");
+	assert!(success && !logged);
+	let (stdout, success, logged) = run("log-stdin", &[], "1 + 1\n");
+	assert_eq!(stdout, "2\n");
+	assert!(success && !logged);
+}
+
+#[test]
+#[cfg(unix)]
+fn current_time_uses_local_time_zone() {
+	// a POSIX time zone string for UTC+5:30, which doesn't need tzdata
+	let output = run_with("tz", &["now"], &[("TZ", "IST-5:30")], &[], "");
+	assert!(
+		output.stdout.trim_end().ends_with("UTC+5:30"),
+		"{}",
+		output.stdout
+	);
+	assert!(output.success);
+}
+
+#[test]
+fn cached_exchange_rates() {
+	const OFFLINE: &[&str] = &[
+		"--set",
+		"exchange-rate-source=EU",
+		"--set",
+		"enable-internet-access=false",
+		"10 EUR to USD",
+	];
+	let mut xml = String::new();
+	for (currency, rate) in [
+		("USD", "1.25"),
+		("JPY", "160"),
+		("GBP", "0.85"),
+		("CHF", "0.95"),
+		("AUD", "1.6"),
+		("CAD", "1.5"),
+		("NZD", "1.8"),
+		("SEK", "11.5"),
+		("NOK", "11.7"),
+	] {
+		xml.push_str(&format!("<Cube currency='{currency}' rate='{rate}'/>\n"));
+	}
+	let now = time::SystemTime::now()
+		.duration_since(time::UNIX_EPOCH)
+		.unwrap()
+		.as_secs();
+
+	let cache = format!("{now};{xml}");
+	let output = run_with(
+		"rates-cached",
+		OFFLINE,
+		&[],
+		&[("eurofxref-daily.xml.cache", &cache)],
+		"",
+	);
+	assert_eq!(output.stdout, "12.5 USD\n");
+	assert!(output.stderr.contains("(using exchange rates from "));
+	assert!(output.success);
+
+	// in JSON mode the exchange rate date is reported as a warning
+	let output = run_with(
+		"rates-cached-json",
+		&[&["--json"], OFFLINE].concat(),
+		&[],
+		&[("eurofxref-daily.xml.cache", &cache)],
+		"",
+	);
+	assert!(output
+		.stdout
+		.contains(r#""warnings":["using exchange rates from "#));
+	assert!(output.stderr.is_empty());
+	assert!(output.success);
+
+	// rates older than `exchange-rate-max-age` are reported as expired
+	let cache = format!("0;{xml}");
+	let output = run_with(
+		"rates-expired",
+		OFFLINE,
+		&[],
+		&[("eurofxref-daily.xml.cache", &cache)],
+		"",
+	);
+	assert!(output
+		.stderr
+		.contains("cached exchange rates from 1970-01-01 have expired"));
+	assert!(!output.success);
+
+	let output = run_with("rates-missing", OFFLINE, &[], &[], "");
+	assert!(output.stderr.contains("internet access is disabled"));
+	assert!(!output.success);
+}
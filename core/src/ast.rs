@@ -7,6 +7,7 @@ use crate::result::FResult;
 use crate::scope::Scope;
 use crate::serialize::{Deserialize, Serialize};
 use crate::value::{built_in_function::BuiltInFunction, ApplyMulHandling, Value};
+use crate::vector::CoordinateSystem;
 use crate::Attrs;
 use std::borrow::Cow;
 use std::sync::Arc;
@@ -28,11 +29,16 @@ pub(crate) enum Bop {
 	Minus,
 	Mul,
 	Div,
+	FloorDiv,
 	Mod,
 	Pow,
 	Bitwise(BitwiseBop),
 	Combination,
 	Permutation,
+	Dot,
+	Cross,
+	PlusMinus,
+	Range,
 }
 
 impl Bop {
@@ -52,6 +58,11 @@ impl Bop {
 			Self::Bitwise(BitwiseBop::RightShift) => 11,
 			Self::Combination => 12,
 			Self::Permutation => 13,
+			Self::Dot => 14,
+			Self::Cross => 15,
+			Self::FloorDiv => 16,
+			Self::PlusMinus => 17,
+			Self::Range => 18,
 		};
 		n.serialize(write)?;
 		Ok(())
@@ -73,6 +84,11 @@ impl Bop {
 			11 => Self::Bitwise(BitwiseBop::RightShift),
 			12 => Self::Combination,
 			13 => Self::Permutation,
+			14 => Self::Dot,
+			15 => Self::Cross,
+			16 => Self::FloorDiv,
+			17 => Self::PlusMinus,
+			18 => Self::Range,
 			_ => return Err(FendError::DeserializationError),
 		})
 	}
@@ -86,6 +102,7 @@ impl fmt::Display for Bop {
 			Self::Minus => "-",
 			Self::Mul => "*",
 			Self::Div => "/",
+			Self::FloorDiv => "//",
 			Self::Mod => " mod ",
 			Self::Pow => "^",
 			Self::Bitwise(BitwiseBop::And) => "&",
@@ -95,6 +112,10 @@ impl fmt::Display for Bop {
 			Self::Bitwise(BitwiseBop::RightShift) => ">>",
 			Self::Combination => "nCr",
 			Self::Permutation => "nPr",
+			Self::Dot => " dot ",
+			Self::Cross => " cross ",
+			Self::PlusMinus => " \u{b1} ",
+			Self::Range => "..",
 		};
 		write!(f, "{s}")
 	}
@@ -104,30 +125,44 @@ impl fmt::Display for Bop {
 pub(crate) enum Expr {
 	Literal(Value),
 	Ident(Ident),
-	Parens(Box<Expr>),
-	UnaryMinus(Box<Expr>),
-	UnaryPlus(Box<Expr>),
-	UnaryDiv(Box<Expr>),
-	Factorial(Box<Expr>),
-	Bop(Bop, Box<Expr>, Box<Expr>),
+	Parens(Box<Self>),
+	UnaryMinus(Box<Self>),
+	UnaryPlus(Box<Self>),
+	UnaryDiv(Box<Self>),
+	Factorial(Box<Self>),
+	Bop(Bop, Box<Self>, Box<Self>),
 	// Call a function or multiply the expressions
-	Apply(Box<Expr>, Box<Expr>),
+	Apply(Box<Self>, Box<Self>),
 	// Call a function, or throw an error if lhs is not a function
-	ApplyFunctionCall(Box<Expr>, Box<Expr>),
+	ApplyFunctionCall(Box<Self>, Box<Self>),
 	// Multiply the expressions
-	ApplyMul(Box<Expr>, Box<Expr>),
+	ApplyMul(Box<Self>, Box<Self>),
 
-	As(Box<Expr>, Box<Expr>),
-	Fn(Ident, Box<Expr>),
+	As(Box<Self>, Box<Self>),
+	Fn(Ident, Box<Self>),
 
-	Of(Ident, Box<Expr>),
+	Of(Ident, Box<Self>),
 
-	Assign(Ident, Box<Expr>),
-	Equality(bool, Box<Expr>, Box<Expr>),
-	Statements(Box<Expr>, Box<Expr>),
+	Assign(Ident, Box<Self>),
+	// e.g. `unit bottle = 0.75 liters`
+	DefineUnit(Ident, Box<Self>),
+	Equality(bool, Box<Self>, Box<Self>),
+	// e.g. `a ~= b`
+	ApproxEquality(Box<Self>, Box<Self>),
+	Statements(Box<Self>, Box<Self>),
+	// e.g. `[1, 2, 3]`
+	Vector(Vec<Self>),
+	// e.g. `2x + 3 = 11` in `solve(2x + 3 = 11, x)`
+	Equation(Box<Self>, Box<Self>),
+	// e.g. `formula area: A = w h where A in m^2, w in m, h in m`
+	DefineFormula(Ident, Box<Self>, Vec<(Ident, Self)>),
+	// e.g. `solve area for h given A = 6 m^2, w = 2 m`, where the formula is
+	// either a name or an equation
+	SolveFormula(Box<Self>, Ident, Vec<(Ident, Self)>),
 }
 
 impl Expr {
+	#[allow(clippy::match_same_arms)]
 	pub(crate) fn compare<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
 		Ok(match (self, other) {
 			(Self::Literal(a), Self::Literal(b)) => {
@@ -154,17 +189,38 @@ impl Expr {
 			(Self::As(a1, a2), Self::As(b1, b2)) => a1.compare(b1, int)? && a2.compare(b2, int)?,
 			(Self::Fn(a1, a2), Self::Fn(b1, b2)) => a1 == b1 && a2.compare(b2, int)?,
 			(Self::Of(a1, a2), Self::Of(b1, b2)) => a1 == b1 && a2.compare(b2, int)?,
-			(Self::Assign(a1, a2), Self::Assign(b1, b2)) => a1 == b1 && a2.compare(b2, int)?,
+			(Self::Assign(a1, a2), Self::Assign(b1, b2))
+			| (Self::DefineUnit(a1, a2), Self::DefineUnit(b1, b2)) => a1 == b1 && a2.compare(b2, int)?,
 			(Self::Equality(a1, a2, a3), Self::Equality(b1, b2, b3)) => {
 				a1 == b1 && a2.compare(b2, int)? && a3.compare(b3, int)?
 			}
+			(Self::ApproxEquality(a1, a2), Self::ApproxEquality(b1, b2))
+			| (Self::Equation(a1, a2), Self::Equation(b1, b2)) => {
+				a1.compare(b1, int)? && a2.compare(b2, int)?
+			}
 			(Self::Statements(a1, a2), Self::Statements(b1, b2)) => {
 				a1.compare(b1, int)? && a2.compare(b2, int)?
 			}
+			(Self::DefineFormula(a1, a2, a3), Self::DefineFormula(b1, b2, b3))
+			| (Self::SolveFormula(a2, a1, a3), Self::SolveFormula(b2, b1, b3)) => {
+				a1 == b1 && a2.compare(b2, int)? && compare_list(a3, b3, int)?
+			}
+			(Self::Vector(a), Self::Vector(b)) => {
+				if a.len() != b.len() {
+					return Ok(false);
+				}
+				for (a, b) in a.iter().zip(b) {
+					if !a.compare(b, int)? {
+						return Ok(false);
+					}
+				}
+				true
+			}
 			_ => false,
 		})
 	}
 
+	#[allow(clippy::too_many_lines)]
 	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
 		match self {
 			Self::Literal(x) => {
@@ -247,6 +303,40 @@ impl Expr {
 				a.serialize(write)?;
 				b.serialize(write)?;
 			}
+			Self::Vector(components) => {
+				17u8.serialize(write)?;
+				components.len().serialize(write)?;
+				for c in components {
+					c.serialize(write)?;
+				}
+			}
+			Self::ApproxEquality(a, b) => {
+				18u8.serialize(write)?;
+				a.serialize(write)?;
+				b.serialize(write)?;
+			}
+			Self::DefineUnit(a, b) => {
+				19u8.serialize(write)?;
+				a.serialize(write)?;
+				b.serialize(write)?;
+			}
+			Self::Equation(a, b) => {
+				20u8.serialize(write)?;
+				a.serialize(write)?;
+				b.serialize(write)?;
+			}
+			Self::DefineFormula(name, equation, units) => {
+				21u8.serialize(write)?;
+				name.serialize(write)?;
+				equation.serialize(write)?;
+				serialize_list(units, write)?;
+			}
+			Self::SolveFormula(formula, var, givens) => {
+				22u8.serialize(write)?;
+				formula.serialize(write)?;
+				var.serialize(write)?;
+				serialize_list(givens, write)?;
+			}
 		}
 		Ok(())
 	}
@@ -302,6 +392,36 @@ impl Expr {
 				Box::new(Self::deserialize(read)?),
 				Box::new(Self::deserialize(read)?),
 			),
+			17 => Self::Vector({
+				let len = usize::deserialize(read)?;
+				let mut components = Vec::with_capacity(len);
+				for _ in 0..len {
+					components.push(Self::deserialize(read)?);
+				}
+				components
+			}),
+			18 => Self::ApproxEquality(
+				Box::new(Self::deserialize(read)?),
+				Box::new(Self::deserialize(read)?),
+			),
+			19 => Self::DefineUnit(
+				Ident::deserialize(read)?,
+				Box::new(Self::deserialize(read)?),
+			),
+			20 => Self::Equation(
+				Box::new(Self::deserialize(read)?),
+				Box::new(Self::deserialize(read)?),
+			),
+			21 => Self::DefineFormula(
+				Ident::deserialize(read)?,
+				Box::new(Self::deserialize(read)?),
+				deserialize_list(read)?,
+			),
+			22 => Self::SolveFormula(
+				Box::new(Self::deserialize(read)?),
+				Ident::deserialize(read)?,
+				deserialize_list(read)?,
+			),
 			_ => return Err(FendError::DeserializationError),
 		})
 	}
@@ -354,6 +474,7 @@ impl Expr {
 			}
 			Self::Of(a, b) => format!("{a} of {}", b.format(attrs, ctx, int)?),
 			Self::Assign(a, b) => format!("{a} = {}", b.format(attrs, ctx, int)?),
+			Self::DefineUnit(a, b) => format!("unit {a} = {}", b.format(attrs, ctx, int)?),
 			Self::Statements(a, b) => format!(
 				"{}; {}",
 				a.format(attrs, ctx, int)?,
@@ -365,10 +486,86 @@ impl Expr {
 				if *is_equals { "==" } else { "!=" },
 				b.format(attrs, ctx, int)?
 			),
+			Self::ApproxEquality(a, b) => format!(
+				"{} ~= {}",
+				a.format(attrs, ctx, int)?,
+				b.format(attrs, ctx, int)?
+			),
+			Self::Equation(a, b) => format!(
+				"{} = {}",
+				a.format(attrs, ctx, int)?,
+				b.format(attrs, ctx, int)?
+			),
+			Self::DefineFormula(name, equation, units) => {
+				let equation = equation.format(attrs, ctx, int)?;
+				let units = units
+					.iter()
+					.map(|(var, unit)| Ok(format!("{var} in {}", unit.format(attrs, ctx, int)?)))
+					.collect::<FResult<Vec<_>>>()?;
+				if units.is_empty() {
+					format!("formula {name}: {equation}")
+				} else {
+					format!("formula {name}: {equation} where {}", units.join(", "))
+				}
+			}
+			Self::SolveFormula(formula, var, givens) => {
+				let formula = formula.format(attrs, ctx, int)?;
+				let givens = givens
+					.iter()
+					.map(|(name, value)| Ok(format!("{name} = {}", value.format(attrs, ctx, int)?)))
+					.collect::<FResult<Vec<_>>>()?;
+				if givens.is_empty() {
+					format!("solve {formula} for {var}")
+				} else {
+					format!("solve {formula} for {var} given {}", givens.join(", "))
+				}
+			}
+			Self::Vector(components) => {
+				let mut result = "[".to_string();
+				for (i, c) in components.iter().enumerate() {
+					if i > 0 {
+						result.push_str(", ");
+					}
+					result.push_str(&c.format(attrs, ctx, int)?);
+				}
+				result.push(']');
+				result
+			}
 		})
 	}
 }
 
+/// Compares lists like `V = 12 V, I = 0.5 A` in formulas
+fn compare_list<I: Interrupt>(a: &[(Ident, Expr)], b: &[(Ident, Expr)], int: &I) -> FResult<bool> {
+	if a.len() != b.len() {
+		return Ok(false);
+	}
+	for ((a1, a2), (b1, b2)) in a.iter().zip(b) {
+		if a1 != b1 || !a2.compare(b2, int)? {
+			return Ok(false);
+		}
+	}
+	Ok(true)
+}
+
+fn serialize_list(list: &[(Ident, Expr)], write: &mut impl io::Write) -> FResult<()> {
+	list.len().serialize(write)?;
+	for (name, expr) in list {
+		name.serialize(write)?;
+		expr.serialize(write)?;
+	}
+	Ok(())
+}
+
+fn deserialize_list(read: &mut impl io::Read) -> FResult<Vec<(Ident, Expr)>> {
+	let len = usize::deserialize(read)?;
+	let mut list = Vec::with_capacity(len);
+	for _ in 0..len {
+		list.push((Ident::deserialize(read)?, Expr::deserialize(read)?));
+	}
+	Ok(list)
+}
+
 /// returns true if rhs is '-1' or '(-1)'
 fn should_compute_inverse<I: Interrupt>(rhs: &Expr, int: &I) -> FResult<bool> {
 	if let Expr::UnaryMinus(inner) = rhs {
@@ -407,7 +604,13 @@ pub(crate) fn evaluate<I: Interrupt>(
 		Expr::Literal(v) => v,
 		Expr::Ident(ident) => resolve_identifier(&ident, scope, attrs, context, int)?,
 		Expr::Parens(x) => eval!(*x)?,
-		Expr::UnaryMinus(x) => eval!(*x)?.handle_num(|x| Ok(-x), Expr::UnaryMinus, scope)?,
+		Expr::UnaryMinus(x) => match eval!(*x)? {
+			v @ (Value::Vector(_) | Value::Quaternion(_)) => crate::vector::negate(v)?,
+			v @ Value::Matrix(_) => crate::matrix::negate(v)?,
+			v @ Value::Symbolic(_) => crate::symbolic::negate(v, int)?,
+			v @ Value::Interval(_) => crate::interval::negate(v, int)?,
+			v => v.handle_num(|x| Ok(-x), Expr::UnaryMinus, scope)?,
+		},
 		Expr::UnaryPlus(x) => eval!(*x)?.handle_num(Ok, Expr::UnaryPlus, scope)?,
 		Expr::UnaryDiv(x) => {
 			eval!(*x)?.handle_num(|x| Number::from(1).div(x, int), Expr::UnaryDiv, scope)?
@@ -415,12 +618,81 @@ pub(crate) fn evaluate<I: Interrupt>(
 		Expr::Factorial(x) => {
 			eval!(*x)?.handle_num(|x| x.factorial(int), Expr::Factorial, scope)?
 		}
-		Expr::Bop(Bop::Plus, a, b) => evaluate_add(eval!(*a)?, eval!(*b)?, scope, int)?,
+		Expr::Bop(Bop::Plus, a, b) => {
+			let percentage = is_percentage(&b);
+			match (eval!(*a)?, eval!(*b)?) {
+				// e.g. `50 + 10%` is 55
+				(Value::Num(a), Value::Num(b)) if percentage && !is_percentage_unit(&a, int)? => {
+					Value::Num(Box::new(a.mul(Number::from(1).add(*b, int)?, int)?))
+				}
+				(a, b) => evaluate_add(a, b, scope, int)?,
+			}
+		}
 		Expr::Bop(Bop::Minus, a, b) => {
+			let percentage = is_percentage(&b);
 			let a = eval!(*a)?;
 			match a {
-				Value::Num(a) => Value::Num(Box::new(a.sub(eval!(*b)?.expect_num()?, int)?)),
-				Value::Date(a) => a.sub(eval!(*b)?, int)?,
+				Value::Num(a) => match eval!(*b)? {
+					// e.g. `200 - 25%` is 150
+					Value::Num(b) if percentage && !is_percentage_unit(&a, int)? => {
+						Value::Num(Box::new(a.mul(Number::from(1).sub(*b, int)?, int)?))
+					}
+					b @ (Value::Vector(_) | Value::Quaternion(_)) => {
+						crate::vector::evaluate_bop(Bop::Minus, Value::Num(a), b, scope, int)?
+					}
+					b @ Value::Matrix(_) => {
+						crate::matrix::evaluate_bop(Bop::Minus, Value::Num(a), b, scope, int)?
+					}
+					b @ Value::Symbolic(_) => {
+						crate::symbolic::evaluate_bop(Bop::Minus, Value::Num(a), b, scope, int)?
+					}
+					b @ Value::Interval(_) => {
+						crate::interval::evaluate_bop(Bop::Minus, Value::Num(a), b, scope, int)?
+					}
+					b => Value::Num(Box::new(a.sub(b.expect_num()?, int)?)),
+				},
+				Value::Date(a) => match eval!(*b)? {
+					// the number of days between two dates
+					Value::Date(b) => {
+						let days = a.julian_day_number() - b.julian_day_number();
+						let result = Number::from(days.unsigned_abs()).mul(
+							crate::units::query_unit("days", attrs, context, int)?.expect_num()?,
+							int,
+						)?;
+						Value::Num(Box::new(if days < 0 { -result } else { result }))
+					}
+					b => a.sub(b, int)?,
+				},
+				Value::TimeOfDay(a) => match eval!(*b)? {
+					// the duration between two times of day
+					Value::TimeOfDay(b) => {
+						let seconds = a.seconds_since(b);
+						let result = Number::from(seconds.unsigned_abs())
+							.mul(
+								crate::units::query_unit("s", attrs, context, int)?.expect_num()?,
+								int,
+							)?
+							.convert_to(
+								crate::units::query_unit("min", attrs, context, int)?
+									.expect_num()?,
+								int,
+							)?;
+						Value::Num(Box::new(if seconds < 0 { -result } else { result }))
+					}
+					b => a.sub(b, int)?,
+				},
+				a @ (Value::Vector(_) | Value::Quaternion(_)) => {
+					crate::vector::evaluate_bop(Bop::Minus, a, eval!(*b)?, scope, int)?
+				}
+				a @ Value::Matrix(_) => {
+					crate::matrix::evaluate_bop(Bop::Minus, a, eval!(*b)?, scope, int)?
+				}
+				a @ Value::Symbolic(_) => {
+					crate::symbolic::evaluate_bop(Bop::Minus, a, eval!(*b)?, scope, int)?
+				}
+				a @ Value::Interval(_) => {
+					crate::interval::evaluate_bop(Bop::Minus, a, eval!(*b)?, scope, int)?
+				}
 				f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _)) => f.apply(
 					Expr::UnaryMinus(b),
 					ApplyMulHandling::OnlyApply,
@@ -444,8 +716,18 @@ pub(crate) fn evaluate<I: Interrupt>(
 					return Ok(res);
 				}
 			}
+			let rhs = eval!(*b)?;
+			if lhs.is_matrix() {
+				return crate::matrix::evaluate_bop(Bop::Pow, lhs, rhs, scope, int);
+			}
+			if lhs.is_symbolic() || rhs.is_symbolic() {
+				return crate::symbolic::evaluate_bop(Bop::Pow, lhs, rhs, scope, int);
+			}
+			if lhs.is_interval() || rhs.is_interval() {
+				return crate::interval::evaluate_bop(Bop::Pow, lhs, rhs, scope, int);
+			}
 			lhs.handle_two_nums(
-				eval!(*b)?,
+				rhs,
 				|a, b| a.pow(b, int),
 				|a| {
 					|f| {
@@ -468,13 +750,36 @@ pub(crate) fn evaluate<I: Interrupt>(
 				scope,
 			)?
 		}
-		Expr::Bop(bop, a, b) => eval!(*a)?.handle_two_nums(
-			eval!(*b)?,
-			|a, b| a.bop(bop, b, attrs, context, int),
-			|a| |f| Expr::Bop(bop, f, Box::new(Expr::Literal(Value::Num(Box::new(a))))),
-			|a| |f| Expr::Bop(bop, Box::new(Expr::Literal(Value::Num(Box::new(a)))), f),
-			scope,
-		)?,
+		Expr::Bop(bop @ (Bop::PlusMinus | Bop::Range), a, b) => {
+			crate::interval::create(bop, eval!(*a)?, eval!(*b)?, int)?
+		}
+		Expr::Bop(bop, a, b) => {
+			if bop == Bop::Bitwise(BitwiseBop::And) {
+				if let Some(value) =
+					evaluate_mixed_units(&a, &b, scope.clone(), attrs, context, int)?
+				{
+					return Ok(value);
+				}
+			}
+			let (a, b) = (eval!(*a)?, eval!(*b)?);
+			if a.is_matrix() || b.is_matrix() {
+				crate::matrix::evaluate_bop(bop, a, b, scope, int)?
+			} else if a.is_vector_or_quaternion() || b.is_vector_or_quaternion() {
+				crate::vector::evaluate_bop(bop, a, b, scope, int)?
+			} else if a.is_symbolic() || b.is_symbolic() {
+				crate::symbolic::evaluate_bop(bop, a, b, scope, int)?
+			} else if a.is_interval() || b.is_interval() {
+				crate::interval::evaluate_bop(bop, a, b, scope, int)?
+			} else {
+				a.handle_two_nums(
+					b,
+					|a, b| a.bop(bop, b, attrs, context, int),
+					|a| |f| Expr::Bop(bop, f, Box::new(Expr::Literal(Value::Num(Box::new(a))))),
+					|a| |f| Expr::Bop(bop, Box::new(Expr::Literal(Value::Num(Box::new(a)))), f),
+					scope,
+				)?
+			}
+		}
 		Expr::Apply(a, b) | Expr::ApplyMul(a, b) => {
 			if let (Expr::Ident(a), Expr::Ident(b)) = (&*a, &*b) {
 				let ident = format!("{a}_{b}");
@@ -482,6 +787,19 @@ pub(crate) fn evaluate<I: Interrupt>(
 					return Ok(val);
 				}
 			}
+			if let Some((f, args)) = min_max_call(&a, &b, scope.as_ref(), context) {
+				return Value::BuiltInFunction(f).apply(
+					Expr::Vector(args),
+					ApplyMulHandling::OnlyApply,
+					scope,
+					attrs,
+					context,
+					int,
+				);
+			}
+			if is_min_function(&a, scope.as_ref(), context) {
+				return apply_min(*b, ApplyMulHandling::Both, scope, attrs, context, int);
+			}
 			match (*a, *b) {
 				(a, Expr::Of(x, expr)) if x.as_str() == "%" => eval!(a)?
 					.handle_num(
@@ -494,16 +812,29 @@ pub(crate) fn evaluate<I: Interrupt>(
 			}
 		}
 		Expr::ApplyFunctionCall(a, b) => {
+			if is_min_function(&a, scope.as_ref(), context) {
+				return apply_min(*b, ApplyMulHandling::OnlyApply, scope, attrs, context, int);
+			}
 			eval!(*a)?.apply(*b, ApplyMulHandling::OnlyApply, scope, attrs, context, int)?
 		}
 		Expr::As(a, b) => evaluate_as(*a, *b, scope, attrs, context, int)?,
 		Expr::Fn(a, b) => Value::Fn(a, b, scope),
-		Expr::Of(a, b) => eval!(*b)?.get_object_member(&a)?,
+		Expr::Of(a, b) => eval!(*b)?.get_object_member(&a, int)?,
 		Expr::Assign(a, b) => {
+			if !context.allow_shadowing_constants && BUILT_IN_CONSTANTS.contains(&a.as_str()) {
+				return Err(FendError::CannotShadowConstant(a.to_string()));
+			}
 			let rhs = evaluate(*b, scope, attrs, context, int)?;
 			context.variables.insert(a.to_string(), rhs.clone());
 			rhs
 		}
+		Expr::DefineUnit(name, definition) => {
+			let value =
+				evaluate((*definition).clone(), scope, attrs, context, int)?.expect_num()?;
+			let definition = definition.format(attrs, context, int)?;
+			context.define_unit(name.as_str(), format!("l@{definition}"));
+			Value::Num(Box::new(value))
+		}
 		Expr::Statements(a, b) => {
 			let _lhs = evaluate(*a, scope.clone(), attrs, context, int)?;
 			evaluate(*b, scope, attrs, context, int)?
@@ -516,9 +847,121 @@ pub(crate) fn evaluate<I: Interrupt>(
 				Some(cmp::Ordering::Greater | cmp::Ordering::Less) | None => !is_equals,
 			})
 		}
+		Expr::Equation(_, _) => return Err(FendError::UnsolvedEquation),
+		Expr::DefineFormula(name, equation, units) => {
+			crate::formula::define_formula(&name, &equation, units, attrs, context, int)?;
+			Value::Unit
+		}
+		Expr::SolveFormula(formula, var, givens) => {
+			crate::formula::solve(*formula, &var, givens, scope, attrs, context, int)?
+		}
+		Expr::ApproxEquality(a, b) => {
+			let lhs = evaluate(*a, scope.clone(), attrs, context, int)?.expect_num()?;
+			let rhs = evaluate(*b, scope, attrs, context, int)?.expect_num()?;
+			Value::Bool(lhs.approx_eq(rhs, None, int)?)
+		}
+		Expr::Vector(components) => {
+			let mut numbers = Vec::with_capacity(components.len());
+			let mut rows = vec![];
+			for c in components {
+				match eval!(c)? {
+					// a list of vectors is a matrix, e.g. `[[1, 2], [3, 4]]`
+					Value::Vector(row) if numbers.is_empty() => rows.push(row),
+					n if rows.is_empty() => numbers.push(n.expect_num()?),
+					_ => return Err(FendError::InconsistentMatrixRows),
+				}
+			}
+			if rows.is_empty() {
+				Value::Vector(crate::vector::Vector::new(numbers))
+			} else {
+				Value::Matrix(Box::new(crate::matrix::Matrix::from_rows(rows)?))
+			}
+		}
 	})
 }
 
+/// Whether `expr` refers to `name`, which hasn't been redefined
+fn is_unshadowed(
+	expr: &Expr,
+	name: &str,
+	scope: Option<&Arc<Scope>>,
+	context: &crate::Context,
+) -> bool {
+	let Expr::Ident(ident) = expr else {
+		return false;
+	};
+	ident.as_str() == name
+		&& scope.is_none_or(|scope| scope.get_expr(ident).is_none())
+		&& !context.variables.contains_key(name)
+}
+
+/// Whether `expr` refers to `min`, which hasn't been redefined
+fn is_min_function(expr: &Expr, scope: Option<&Arc<Scope>>, context: &crate::Context) -> bool {
+	is_unshadowed(expr, "min", scope, context)
+}
+
+/// `min(a, b)` is parsed as `min (a) (b)`, which would otherwise be
+/// `a` minutes multiplied by `b`. This returns the function and its
+/// arguments if `f (last_arg)` is such a call to `min` or `max` with
+/// at least two arguments.
+fn min_max_call(
+	f: &Expr,
+	last_arg: &Expr,
+	scope: Option<&Arc<Scope>>,
+	context: &crate::Context,
+) -> Option<(BuiltInFunction, Vec<Expr>)> {
+	if !matches!(last_arg, Expr::Parens(_)) {
+		return None;
+	}
+	let mut args = vec![last_arg.clone()];
+	let mut f = f;
+	while let Expr::Apply(g, arg) = f {
+		if !matches!(**arg, Expr::Parens(_)) {
+			return None;
+		}
+		args.push((**arg).clone());
+		f = g;
+	}
+	let function = if is_min_function(f, scope, context) {
+		BuiltInFunction::Min
+	} else if is_unshadowed(f, "max", scope, context) {
+		BuiltInFunction::Max
+	} else {
+		return None;
+	};
+	if args.len() < 2 {
+		return None;
+	}
+	args.reverse();
+	Some((function, args))
+}
+
+/// `min` is the unit for minutes, but it is the `min` function when applied
+/// to a list, e.g. `min([3 m, 10 ft])`
+fn apply_min<I: Interrupt>(
+	arg: Expr,
+	apply_mul_handling: ApplyMulHandling,
+	scope: Option<Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Value> {
+	let arg = evaluate(arg, scope.clone(), attrs, context, int)?;
+	let min = if matches!(arg, Value::Vector(_)) {
+		Value::BuiltInFunction(BuiltInFunction::Min)
+	} else {
+		crate::units::query_unit("min", attrs, context, int)?
+	};
+	min.apply(
+		Expr::Literal(arg),
+		apply_mul_handling,
+		scope,
+		attrs,
+		context,
+		int,
+	)
+}
+
 fn evaluate_add<I: Interrupt>(
 	a: Value,
 	b: Value,
@@ -557,10 +1000,83 @@ fn evaluate_add<I: Interrupt>(
 			scope,
 		),
 		(Value::Date(d), b) => d.add(b, int)?,
+		(Value::TimeOfDay(t), b) => t.add(b, int)?,
+		(a, b) if a.is_matrix() || b.is_matrix() => {
+			crate::matrix::evaluate_bop(Bop::Plus, a, b, scope, int)?
+		}
+		(a, b) if a.is_vector_or_quaternion() || b.is_vector_or_quaternion() => {
+			crate::vector::evaluate_bop(Bop::Plus, a, b, scope, int)?
+		}
+		(a, b) if a.is_symbolic() || b.is_symbolic() => {
+			crate::symbolic::evaluate_bop(Bop::Plus, a, b, scope, int)?
+		}
+		(a, b) if a.is_interval() || b.is_interval() => {
+			crate::interval::evaluate_bop(Bop::Plus, a, b, scope, int)?
+		}
 		_ => return Err(FendError::ExpectedANumber),
 	})
 }
 
+/// Returns whether the expression is a percentage like `10%` or
+/// `(2 + 3) percent`, which adding to or subtracting from a number changes the
+/// number by that percentage
+fn is_percentage(expr: &Expr) -> bool {
+	match expr {
+		Expr::Parens(x) => is_percentage(x),
+		Expr::Apply(_, unit) | Expr::ApplyMul(_, unit) => {
+			matches!(&**unit, Expr::Ident(ident) if matches!(ident.as_str(), "%" | "percent"))
+		}
+		_ => false,
+	}
+}
+
+/// Returns whether the number is itself a percentage, e.g. in `4% + 3%`
+fn is_percentage_unit<I: Interrupt>(num: &Number, int: &I) -> FResult<bool> {
+	Ok(num.unit_equal_to("%", int)? || num.unit_equal_to("percent", int)?)
+}
+
+/// Converts a value into several units at once, e.g. `200 min to h and min`,
+/// which is parsed as `(200 min to h) and min`. Returns `None` if the targets
+/// aren't all units, in which case `and` is a bitwise operator.
+fn evaluate_mixed_units<I: Interrupt>(
+	lhs: &Expr,
+	last_target: &Expr,
+	scope: Option<Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Option<Value>> {
+	let mut targets = vec![last_target];
+	let mut lhs = lhs;
+	let value = loop {
+		match lhs {
+			Expr::Bop(Bop::Bitwise(BitwiseBop::And), a, b) => {
+				targets.push(b);
+				lhs = a;
+			}
+			Expr::As(value, target) => {
+				targets.push(target);
+				break value;
+			}
+			_ => return Ok(None),
+		}
+	};
+	let mut units = Vec::with_capacity(targets.len());
+	for target in targets.into_iter().rev() {
+		let Ok(Value::Num(unit)) = evaluate(target.clone(), scope.clone(), attrs, context, int)
+		else {
+			return Ok(None);
+		};
+		if unit.is_unitless(int)? {
+			return Ok(None);
+		}
+		units.push(*unit);
+	}
+	let value = evaluate((**value).clone(), scope, attrs, context, int)?.expect_num()?;
+	Ok(Some(Value::MixedUnits(value.convert_to_mixed(units, int)?)))
+}
+
+#[allow(clippy::too_many_lines)]
 fn evaluate_as<I: Interrupt>(
 	a: Expr,
 	b: Expr,
@@ -570,12 +1086,27 @@ fn evaluate_as<I: Interrupt>(
 	int: &I,
 ) -> FResult<Value> {
 	if let Expr::Ident(ident) = &b {
+		if let Some(system) = CoordinateSystem::from_name(ident.as_str()) {
+			return match evaluate(a, scope.clone(), attrs, context, int)? {
+				Value::Vector(v) => Ok(Value::Vector(v.convert_to(
+					system,
+					scope.as_ref(),
+					attrs,
+					context,
+					int,
+				)?)),
+				other => Err(FendError::ExpectedAVector(other.type_name())),
+			};
+		}
 		match ident.as_str() {
 			"bool" | "boolean" => {
 				let num = evaluate(a, scope, attrs, context, int)?.expect_num()?;
 				return Ok(Value::Bool(!num.is_zero(int)?));
 			}
 			"date" => {
+				if cfg!(not(feature = "date")) {
+					return Err(FendError::FeatureDisabled("date"));
+				}
 				let a = evaluate(a, scope, attrs, context, int)?;
 				return if let Value::String(s) = a {
 					Ok(Value::Date(crate::date::Date::parse(s.as_ref())?))
@@ -583,6 +1114,37 @@ fn evaluate_as<I: Interrupt>(
 					Err(FendError::ExpectedAString)
 				};
 			}
+			"datetime" => {
+				if cfg!(not(feature = "date")) {
+					return Err(FendError::FeatureDisabled("date"));
+				}
+				let mut num = evaluate(a, scope, attrs, context, int)?.expect_num()?;
+				// timestamps can also be given with a unit, e.g. in milliseconds
+				if !num.is_unitless(int)? {
+					let second =
+						crate::units::query_unit("s", attrs, context, int)?.expect_num()?;
+					num = num.convert_to(second.clone(), int)?.div(second, int)?;
+				}
+				let seconds = num.floor(int)?.try_as_i64(int)?;
+				return Ok(Value::ZonedDateTime(
+					crate::date::ZonedDateTime::from_unix_time(
+						seconds,
+						crate::date::TimeZone::Offset(0),
+					)?,
+				));
+			}
+			"unix" | "unix_time" | "timestamp" => {
+				let Value::ZonedDateTime(time) = evaluate(a, scope, attrs, context, int)? else {
+					return Err(FendError::ExpectedATimeWithTimeZone);
+				};
+				let seconds = time.unix_time(crate::date::Date::today(context).ok())?;
+				let result = Number::from(seconds.unsigned_abs());
+				return Ok(Value::Num(Box::new(if seconds < 0 {
+					-result
+				} else {
+					result
+				})));
+			}
 			"string" => {
 				return Ok(Value::String(
 					evaluate(a, scope, attrs, context, int)?
@@ -622,6 +1184,28 @@ fn evaluate_as<I: Interrupt>(
 				}
 				return Err(FendError::ExpectedANumber);
 			}
+			"si" | "eng" | "engineering" => {
+				let num = evaluate(a, scope, attrs, context, int)?.expect_num()?;
+				let engineering = ident.as_str() != "si";
+				return Ok(Value::Num(Box::new(crate::units::with_best_prefix(
+					num,
+					engineering,
+					attrs,
+					context,
+					int,
+				)?)));
+			}
+			"jdn" | "julian_day" | "julian_day_number" | "mjd" | "modified_julian_date" => {
+				let Value::Date(date) = evaluate(a, scope, attrs, context, int)? else {
+					return Err(FendError::ExpectedADateLiteral);
+				};
+				let mut jdn = date.julian_day_number();
+				if matches!(ident.as_str(), "mjd" | "modified_julian_date") {
+					jdn -= crate::date::MJD_EPOCH;
+				}
+				let result = Number::from(jdn.unsigned_abs());
+				return Ok(Value::Num(Box::new(if jdn < 0 { -result } else { result })));
+			}
 			"roman" | "roman_numeral" => {
 				let a = evaluate(a, scope, attrs, context, int)?
 					.expect_num()?
@@ -645,33 +1229,232 @@ fn evaluate_as<I: Interrupt>(
 		}
 	}
 	Ok(match evaluate(b, scope.clone(), attrs, context, int)? {
-		Value::Num(b) => Value::Num(Box::new(
-			evaluate(a, scope, attrs, context, int)?
-				.expect_num()?
-				.convert_to(*b, int)?,
-		)),
-		Value::Format(fmt) => Value::Num(Box::new(
-			evaluate(a, scope, attrs, context, int)?
-				.expect_num()?
-				.with_format(fmt),
-		)),
+		Value::Num(b) => {
+			let a = evaluate(a, scope.clone(), attrs, context, int)?;
+			if let Value::Interval(a) = a {
+				return Ok(Value::Interval(Box::new(
+					a.map(|n| n.convert_to((*b).clone(), int), int)?,
+				)));
+			}
+			let a = a.expect_num()?;
+			if !context.show_conversion_steps {
+				return Ok(Value::Num(Box::new(a.convert_to(*b, int)?)));
+			}
+			let result = a.clone().convert_to((*b).clone(), int)?;
+			let steps = conversion_steps(&a, &b, &result, scope, attrs, context, int)?;
+			context.conversion_steps.extend(steps);
+			Value::Num(Box::new(result))
+		}
+		Value::Format(fmt) => {
+			evaluate(a, scope, attrs, context, int)?.map_numbers(|n| Ok(n.with_format(fmt)), int)?
+		}
 		Value::Dp => {
 			return Err(FendError::SpecifyNumDp);
 		}
 		Value::Sf => {
 			return Err(FendError::SpecifyNumSf);
 		}
-		Value::Base(base) => Value::Num(Box::new(
-			evaluate(a, scope, attrs, context, int)?
-				.expect_num()?
-				.with_base(base),
-		)),
+		Value::Base(base) => {
+			evaluate(a, scope, attrs, context, int)?.map_numbers(|n| Ok(n.with_base(base)), int)?
+		}
+		Value::TimeZone(zone) => match evaluate(a, scope, attrs, context, int)? {
+			Value::ZonedDateTime(time) => {
+				Value::ZonedDateTime(time.convert_to(zone, crate::date::Date::today(context).ok())?)
+			}
+			_ => return Err(FendError::ExpectedATimeWithTimeZone),
+		},
 		other => {
 			return Err(FendError::CannotConvertValueTo(other.type_name()));
 		}
 	})
 }
 
+fn push_conversion_step<I: Interrupt>(
+	steps: &mut Vec<String>,
+	lhs: &Number,
+	rhs: &Number,
+	context: &crate::Context,
+	int: &I,
+) -> FResult<()> {
+	let lhs = lhs.format(context, int)?.to_string();
+	let rhs = rhs.format(context, int)?.to_string();
+	let step = format!("{lhs} = {rhs}");
+	if lhs != rhs && !steps.contains(&step) {
+		steps.push(step);
+	}
+	Ok(())
+}
+
+/// Returns the steps used to convert `a` into the unit of `b`, going through
+/// base units, e.g. `1 mile = 1609.344 meters`, `5 miles = 8046.72 meters`
+/// and `1 kilometer = 1000 meters`
+fn conversion_steps<I: Interrupt>(
+	a: &Number,
+	b: &Number,
+	result: &Number,
+	scope: Option<Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Vec<String>> {
+	let base_units = a.base_unit_components(int)?;
+	let mut steps = vec![];
+	if base_units.is_empty() {
+		return Ok(steps);
+	}
+	// currencies are converted using exchange rates rather than base units
+	if base_units.iter().any(|unit| unit.name == "BASE_CURRENCY") {
+		let one = a.unit_one();
+		let converted = one.clone().convert_to(b.clone(), int)?;
+		push_conversion_step(&mut steps, &one, &converted, context, int)?;
+		return Ok(steps);
+	}
+	let affine = a.is_absolute_temperature(int)?;
+	let base_expr: Vec<String> = base_units
+		.iter()
+		.map(|unit| format!("{}^({})", unit.name, unit.exponent))
+		.collect();
+	let base = evaluate_to_value(&base_expr.join(" "), scope, attrs, context, int)?.expect_num()?;
+	// temperatures have an offset, so showing the factor for a single
+	// degree would be misleading
+	let values = if affine {
+		vec![a.clone(), result.clone()]
+	} else {
+		vec![a.unit_one(), a.clone(), b.clone()]
+	};
+	for value in values {
+		let factor = value.unit_one().convert_to(base.clone(), int)?;
+		if !affine && factor.compare(&base, int)? == Some(cmp::Ordering::Equal) {
+			// e.g. `m / s` and `meter / second`
+			continue;
+		}
+		let converted = value.clone().convert_to(base.clone(), int)?;
+		push_conversion_step(&mut steps, &value, &converted, context, int)?;
+	}
+	Ok(steps)
+}
+
+/// Creates a function like `n: p: k: ...` that passes all of its arguments
+/// to a built-in function as a vector, so that it can be called as e.g.
+/// `binompmf(10, 0.5, 7)`. Functions whose arguments are themselves vectors
+/// (like `linreg`) evaluate each element of this vector separately.
+fn curried_built_in_function(
+	func: BuiltInFunction,
+	params: &[&'static str],
+	scope: Option<Arc<Scope>>,
+) -> Value {
+	let args = params
+		.iter()
+		.map(|&param| Expr::Ident(Ident::new_str(param)))
+		.collect();
+	let mut body = Expr::Apply(
+		Box::new(Expr::Literal(Value::BuiltInFunction(func))),
+		Box::new(Expr::Vector(args)),
+	);
+	for &param in params[1..].iter().rev() {
+		body = Expr::Fn(Ident::new_str(param), Box::new(body));
+	}
+	Value::Fn(Ident::new_str(params[0]), Box::new(body), scope)
+}
+
+/// Identifiers that can't be assigned to unless
+/// [`crate::Context::set_allow_shadowing_constants`] is enabled
+const BUILT_IN_CONSTANTS: &[&str] = &[
+	"pi", "\u{3c0}", "tau", "\u{3c4}", "e", "phi", "i", "true", "false",
+];
+
+/// Trigonometric, hyperbolic, logarithmic and probability functions, which
+/// need the `transcendental` feature
+#[cfg(feature = "transcendental")]
+fn resolve_transcendental_identifier<I: Interrupt>(
+	ident: &Ident,
+	scope: Option<&Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Option<Value>> {
+	Ok(Some(match ident.as_str() {
+		"sin" => Value::BuiltInFunction(BuiltInFunction::Sin),
+		"cos" => Value::BuiltInFunction(BuiltInFunction::Cos),
+		"tan" => Value::BuiltInFunction(BuiltInFunction::Tan),
+		"asin" => Value::BuiltInFunction(BuiltInFunction::Asin),
+		"acos" => Value::BuiltInFunction(BuiltInFunction::Acos),
+		"atan" => Value::BuiltInFunction(BuiltInFunction::Atan),
+		"sinh" => Value::BuiltInFunction(BuiltInFunction::Sinh),
+		"cosh" => Value::BuiltInFunction(BuiltInFunction::Cosh),
+		"tanh" => Value::BuiltInFunction(BuiltInFunction::Tanh),
+		"asinh" => Value::BuiltInFunction(BuiltInFunction::Asinh),
+		"acosh" => Value::BuiltInFunction(BuiltInFunction::Acosh),
+		"atanh" => Value::BuiltInFunction(BuiltInFunction::Atanh),
+		"cis" => evaluate_to_value(
+			"theta => cos theta + i * sin theta",
+			scope.cloned(),
+			attrs,
+			context,
+			int,
+		)?,
+		"ln" => Value::BuiltInFunction(BuiltInFunction::Ln),
+		"log2" => Value::BuiltInFunction(BuiltInFunction::Log2),
+		"log" | "log10" => Value::BuiltInFunction(BuiltInFunction::Log10),
+		"angle_between" => evaluate_to_value(
+			"a: b: arg((a dot b) / (a dot a) \
+				+ i * sqrt((b dot b) / (a dot a) - ((a dot b) / (a dot a))^2))",
+			scope.cloned(),
+			attrs,
+			context,
+			int,
+		)?,
+		"from_axis_angle" => evaluate_to_value(
+			"axis: angle: cos(angle / 2) + quaternion (sin(angle / 2) * (normalize axis))",
+			scope.cloned(),
+			attrs,
+			context,
+			int,
+		)?,
+		"to_axis_angle" => Value::BuiltInFunction(BuiltInFunction::ToAxisAngle),
+		"from_euler" => Value::BuiltInFunction(BuiltInFunction::FromEuler),
+		"to_euler" => Value::BuiltInFunction(BuiltInFunction::ToEuler),
+		"exp" => Value::BuiltInFunction(BuiltInFunction::Exp),
+		"normpdf" => Value::BuiltInFunction(BuiltInFunction::NormPdf),
+		"normcdf" => Value::BuiltInFunction(BuiltInFunction::NormCdf),
+		"invnorm" => Value::BuiltInFunction(BuiltInFunction::InvNorm),
+		"poissonpmf" => curried_built_in_function(
+			BuiltInFunction::PoissonPmf,
+			&["lambda", "k"],
+			scope.cloned(),
+		),
+		"poissoncdf" => curried_built_in_function(
+			BuiltInFunction::PoissonCdf,
+			&["lambda", "k"],
+			scope.cloned(),
+		),
+		"tpdf" => curried_built_in_function(BuiltInFunction::TPdf, &["t", "nu"], scope.cloned()),
+		"tcdf" => curried_built_in_function(BuiltInFunction::TCdf, &["t", "nu"], scope.cloned()),
+		"invt" => curried_built_in_function(BuiltInFunction::InvT, &["p", "nu"], scope.cloned()),
+		_ => return Ok(None),
+	}))
+}
+
+#[cfg(not(feature = "transcendental"))]
+fn resolve_transcendental_identifier<I: Interrupt>(
+	ident: &Ident,
+	_scope: Option<&Arc<Scope>>,
+	_attrs: Attrs,
+	_context: &mut crate::Context,
+	_int: &I,
+) -> FResult<Option<Value>> {
+	match ident.as_str() {
+		"sin" | "cos" | "tan" | "asin" | "acos" | "atan" | "sinh" | "cosh" | "tanh" | "asinh"
+		| "acosh" | "atanh" | "cis" | "ln" | "log2" | "log" | "log10" | "exp" | "angle_between"
+		| "from_axis_angle" | "to_axis_angle" | "from_euler" | "to_euler" | "normpdf"
+		| "normcdf" | "invnorm" | "poissonpmf" | "poissoncdf" | "tpdf" | "tcdf" | "invt" => {
+			Err(FendError::FeatureDisabled("transcendental"))
+		}
+		_ => Ok(None),
+	}
+}
+
+#[allow(clippy::too_many_lines)]
 pub(crate) fn resolve_identifier<I: Interrupt>(
 	ident: &Ident,
 	scope: Option<Arc<Scope>>,
@@ -698,6 +1481,11 @@ pub(crate) fn resolve_identifier<I: Interrupt>(
 	if let Some(val) = context.variables.get(ident.as_str()) {
 		return Ok(val.clone());
 	}
+	if let Some(val) =
+		resolve_transcendental_identifier(ident, scope.as_ref(), attrs, context, int)?
+	{
+		return Ok(val);
+	}
 	Ok(match ident.as_str() {
 		"pi" | "\u{3c0}" => Value::Num(Box::new(Number::pi())),
 		"tau" | "\u{3c4}" => Value::Num(Box::new(Number::pi().mul(2.into(), int)?)),
@@ -707,9 +1495,24 @@ pub(crate) fn resolve_identifier<I: Interrupt>(
 		"true" => Value::Bool(true),
 		"false" => Value::Bool(false),
 		"sample" | "roll" => Value::BuiltInFunction(BuiltInFunction::Sample),
+		"mplus" => Value::BuiltInFunction(BuiltInFunction::MemoryAdd),
+		"mminus" => Value::BuiltInFunction(BuiltInFunction::MemorySubtract),
+		"mrecall" => context
+			.memory
+			.get("")
+			.cloned()
+			.unwrap_or_else(|| Value::Num(Box::new(Number::from(0)))),
+		"mclear" => {
+			context.memory.remove("");
+			Value::Unit
+		}
+		"store" => Value::BuiltInFunction(BuiltInFunction::Store),
+		"recall" => Value::BuiltInFunction(BuiltInFunction::Recall),
 		"mean" | "average" => Value::BuiltInFunction(BuiltInFunction::Mean),
 		"sqrt" => evaluate_to_value("x: x^(1/2)", scope, attrs, context, int)?,
 		"cbrt" => evaluate_to_value("x: x^(1/3)", scope, attrs, context, int)?,
+		"root" => curried_built_in_function(BuiltInFunction::Root, &["x", "n"], scope),
+		"isqrt" => Value::BuiltInFunction(BuiltInFunction::Isqrt),
 		"real" | "re" | "Re" => Value::BuiltInFunction(BuiltInFunction::Real),
 		"imag" | "im" | "Im" => Value::BuiltInFunction(BuiltInFunction::Imag),
 		"conjugate" => Value::BuiltInFunction(BuiltInFunction::Conjugate),
@@ -719,36 +1522,79 @@ pub(crate) fn resolve_identifier<I: Interrupt>(
 		"floor" => Value::BuiltInFunction(BuiltInFunction::Floor),
 		"ceil" => Value::BuiltInFunction(BuiltInFunction::Ceil),
 		"round" => Value::BuiltInFunction(BuiltInFunction::Round),
-		"sin" => Value::BuiltInFunction(BuiltInFunction::Sin),
-		"cos" => Value::BuiltInFunction(BuiltInFunction::Cos),
-		"tan" => Value::BuiltInFunction(BuiltInFunction::Tan),
-		"asin" => Value::BuiltInFunction(BuiltInFunction::Asin),
-		"acos" => Value::BuiltInFunction(BuiltInFunction::Acos),
-		"atan" => Value::BuiltInFunction(BuiltInFunction::Atan),
-		"sinh" => Value::BuiltInFunction(BuiltInFunction::Sinh),
-		"cosh" => Value::BuiltInFunction(BuiltInFunction::Cosh),
-		"tanh" => Value::BuiltInFunction(BuiltInFunction::Tanh),
-		"asinh" => Value::BuiltInFunction(BuiltInFunction::Asinh),
-		"acosh" => Value::BuiltInFunction(BuiltInFunction::Acosh),
-		"atanh" => Value::BuiltInFunction(BuiltInFunction::Atanh),
-		"cis" => evaluate_to_value(
-			"theta => cos theta + i * sin theta",
-			scope,
-			attrs,
-			context,
-			int,
-		)?,
-		"ln" => Value::BuiltInFunction(BuiltInFunction::Ln),
-		"log2" => Value::BuiltInFunction(BuiltInFunction::Log2),
-		"log" | "log10" => Value::BuiltInFunction(BuiltInFunction::Log10),
 		"not" => Value::BuiltInFunction(BuiltInFunction::Not),
 		"fib" | "fibonacci" => Value::BuiltInFunction(BuiltInFunction::Fibonacci),
-		"exp" => evaluate_to_value("x: e^x", scope, attrs, context, int)?,
+		"gamma" => Value::BuiltInFunction(BuiltInFunction::Gamma),
+		"factor" => Value::BuiltInFunction(BuiltInFunction::Factor),
+		"isprime" => Value::BuiltInFunction(BuiltInFunction::IsPrime),
+		"nextprime" => Value::BuiltInFunction(BuiltInFunction::NextPrime),
+		"prevprime" => Value::BuiltInFunction(BuiltInFunction::PrevPrime),
+		"norm" => Value::BuiltInFunction(BuiltInFunction::Norm),
+		"normalize" => Value::BuiltInFunction(BuiltInFunction::Normalize),
+		"polar" => Value::BuiltInFunction(BuiltInFunction::Polar),
+		"spherical" => Value::BuiltInFunction(BuiltInFunction::Spherical),
+		"cylindrical" => Value::BuiltInFunction(BuiltInFunction::Cylindrical),
+		"quaternion" => Value::BuiltInFunction(BuiltInFunction::Quaternion),
+		"binompmf" => curried_built_in_function(BuiltInFunction::BinomPmf, &["n", "p", "k"], scope),
+		"binomcdf" => curried_built_in_function(BuiltInFunction::BinomCdf, &["n", "p", "k"], scope),
+		"linreg" => curried_built_in_function(BuiltInFunction::LinReg, &["xs", "ys"], scope),
+		"correlation" => {
+			curried_built_in_function(BuiltInFunction::Correlation, &["xs", "ys"], scope)
+		}
+		"max" | "maximum" => Value::BuiltInFunction(BuiltInFunction::Max),
+		// `min` on its own is the unit for minutes, see `apply_min`
+		"minimum" => Value::BuiltInFunction(BuiltInFunction::Min),
+		"sum" => Value::BuiltInFunction(BuiltInFunction::Sum),
+		"sort" => Value::BuiltInFunction(BuiltInFunction::Sort),
+		"product" => Value::BuiltInFunction(BuiltInFunction::Product),
+		"median" => Value::BuiltInFunction(BuiltInFunction::Median),
+		"mode" => Value::BuiltInFunction(BuiltInFunction::Mode),
+		"variance" => Value::BuiltInFunction(BuiltInFunction::Variance),
+		"stddev" => Value::BuiltInFunction(BuiltInFunction::StdDev),
+		"transpose" => Value::BuiltInFunction(BuiltInFunction::Transpose),
+		"det" | "determinant" => Value::BuiltInFunction(BuiltInFunction::Determinant),
+		"inverse" => Value::BuiltInFunction(BuiltInFunction::Inverse),
+		"expand" => Value::BuiltInFunction(BuiltInFunction::Expand),
+		"diff" => curried_built_in_function(BuiltInFunction::Diff, &["f", "x"], scope),
+		"clamp" => curried_built_in_function(BuiltInFunction::Clamp, &["x", "lo", "hi"], scope),
+		"divmod" => curried_built_in_function(BuiltInFunction::DivMod, &["a", "b"], scope),
+		"powmod" => curried_built_in_function(BuiltInFunction::PowMod, &["b", "e", "m"], scope),
+		"modinv" => curried_built_in_function(BuiltInFunction::ModInv, &["a", "m"], scope),
+		"compound" => curried_built_in_function(
+			BuiltInFunction::Compound,
+			&["principal", "rate", "n", "years"],
+			scope,
+		),
+		"pmt" => curried_built_in_function(BuiltInFunction::Pmt, &["rate", "nper", "pv"], scope),
+		"amortize" => {
+			curried_built_in_function(BuiltInFunction::Amortize, &["rate", "nper", "pv"], scope)
+		}
+		"npv" => curried_built_in_function(BuiltInFunction::Npv, &["rate", "cashflows"], scope),
+		"map" => curried_built_in_function(BuiltInFunction::Map, &["f", "list"], scope),
+		"filter" => curried_built_in_function(BuiltInFunction::Filter, &["f", "list"], scope),
+		"solve" => curried_built_in_function(BuiltInFunction::Solve, &["a", "b"], scope),
+		"wrap" => curried_built_in_function(BuiltInFunction::Wrap, &["x", "bits"], scope),
+		"wrap_signed" => {
+			curried_built_in_function(BuiltInFunction::WrapSigned, &["x", "bits"], scope)
+		}
+		"u8" | "wrap8" => evaluate_to_value("x: wrap(x, 8)", scope, attrs, context, int)?,
+		"u16" | "wrap16" => evaluate_to_value("x: wrap(x, 16)", scope, attrs, context, int)?,
+		"u32" | "wrap32" => evaluate_to_value("x: wrap(x, 32)", scope, attrs, context, int)?,
+		"u64" | "wrap64" => evaluate_to_value("x: wrap(x, 64)", scope, attrs, context, int)?,
+		"u128" | "wrap128" => evaluate_to_value("x: wrap(x, 128)", scope, attrs, context, int)?,
+		"i8" => evaluate_to_value("x: wrap_signed(x, 8)", scope, attrs, context, int)?,
+		"i16" => evaluate_to_value("x: wrap_signed(x, 16)", scope, attrs, context, int)?,
+		"i32" => evaluate_to_value("x: wrap_signed(x, 32)", scope, attrs, context, int)?,
+		"i64" => evaluate_to_value("x: wrap_signed(x, 64)", scope, attrs, context, int)?,
+		"i128" => evaluate_to_value("x: wrap_signed(x, 128)", scope, attrs, context, int)?,
+		"approx_eq" => {
+			curried_built_in_function(BuiltInFunction::ApproxEq, &["a", "b", "tol"], scope)
+		}
 		"approx." | "approximately" => Value::BuiltInFunction(BuiltInFunction::Approximately),
 		"auto" => Value::Format(FormattingStyle::Auto),
 		"exact" => Value::Format(FormattingStyle::Exact),
 		"frac" | "fraction" => Value::Format(FormattingStyle::ImproperFraction),
-		"mixed_frac" | "mixed_fraction" => Value::Format(FormattingStyle::MixedFraction),
+		"mixed" | "mixed_frac" | "mixed_fraction" => Value::Format(FormattingStyle::MixedFraction),
 		"float" => Value::Format(FormattingStyle::ExactFloat),
 		"dp" => Value::Dp,
 		"sf" => Value::Sf,
@@ -770,11 +1616,43 @@ pub(crate) fn resolve_identifier<I: Interrupt>(
 			("mass".into(), eval_box!("5.97237e24 kg")),
 			("volume".into(), eval_box!("1.08321e12 km^3")),
 		]),
+		#[cfg(feature = "date")]
 		"today" => Value::Date(crate::date::Date::today(context)?),
+		#[cfg(feature = "date")]
 		"tomorrow" => Value::Date(crate::date::Date::today(context)?.next()),
+		#[cfg(feature = "date")]
 		"yesterday" => Value::Date(crate::date::Date::today(context)?.prev()),
+		#[cfg(feature = "date")]
+		"now" => Value::ZonedDateTime(crate::date::ZonedDateTime::now(context)?),
+		#[cfg(not(feature = "date"))]
+		"today" | "tomorrow" | "yesterday" | "now" => return Err(FendError::FeatureDisabled("date")),
+		#[cfg(feature = "date")]
+		"from_jdn" => Value::BuiltInFunction(BuiltInFunction::FromJdn),
+		#[cfg(feature = "date")]
+		"from_mjd" => Value::BuiltInFunction(BuiltInFunction::FromMjd),
+		#[cfg(not(feature = "date"))]
+		"from_jdn" | "from_mjd" => return Err(FendError::FeatureDisabled("date")),
 		"trans" => Value::String(Cow::Borrowed("🏳️‍⚧️")),
-		_ => return crate::units::query_unit(ident.as_str(), attrs, context, int),
+		_ => {
+			return match crate::units::query_unit(ident.as_str(), attrs, context, int) {
+				// unbound variables like `x` can be kept symbolic, e.g. `2x + 3x`
+				// is `5x`
+				Err(FendError::IdentifierNotFound(ident))
+					if crate::symbolic::is_variable_name(ident.as_str(), context) =>
+				{
+					Ok(crate::symbolic::variable(ident.as_str()))
+				}
+				Err(FendError::IdentifierNotFound(ident)) => Err(
+					match crate::suggestion::suggest_identifier(ident.as_str(), context) {
+						Some(suggestion) => {
+							FendError::IdentifierNotFoundDidYouMean(ident, suggestion)
+						}
+						None => FendError::IdentifierNotFound(ident),
+					},
+				),
+				result => result,
+			};
+		}
 	})
 }
 
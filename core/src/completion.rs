@@ -0,0 +1,254 @@
+use crate::{Completion, CompletionKind, Context};
+
+pub(crate) const FUNCTIONS: &[&str] = &[
+	"abs",
+	"acos",
+	"acosh",
+	"amortize",
+	"angle_between",
+	"approx_eq",
+	"approximately",
+	"arg",
+	"asin",
+	"asinh",
+	"atan",
+	"atanh",
+	"average",
+	"binomcdf",
+	"binompmf",
+	"cbrt",
+	"ceil",
+	"cis",
+	"clamp",
+	"compound",
+	"conjugate",
+	"correlation",
+	"cos",
+	"cosh",
+	"cylindrical",
+	"det",
+	"diff",
+	"divmod",
+	"exp",
+	"expand",
+	"factor",
+	"fibonacci",
+	"filter",
+	"floor",
+	"from_axis_angle",
+	"from_euler",
+	"from_jdn",
+	"from_mjd",
+	"gamma",
+	"i128",
+	"i16",
+	"i32",
+	"i64",
+	"i8",
+	"imag",
+	"inverse",
+	"invnorm",
+	"invt",
+	"isqrt",
+	"isprime",
+	"linreg",
+	"ln",
+	"map",
+	"max",
+	"log",
+	"log10",
+	"log2",
+	"mclear",
+	"mean",
+	"median",
+	"min",
+	"mminus",
+	"mode",
+	"modinv",
+	"mplus",
+	"mrecall",
+	"nextprime",
+	"norm",
+	"normalize",
+	"normcdf",
+	"normpdf",
+	"not",
+	"npv",
+	"pmt",
+	"poissoncdf",
+	"poissonpmf",
+	"polar",
+	"powmod",
+	"prevprime",
+	"product",
+	"quaternion",
+	"real",
+	"recall",
+	"roll",
+	"root",
+	"round",
+	"sample",
+	"sin",
+	"sinh",
+	"sort",
+	"spherical",
+	"sqrt",
+	"stddev",
+	"store",
+	"sum",
+	"tan",
+	"tanh",
+	"tcdf",
+	"to_axis_angle",
+	"to_euler",
+	"tpdf",
+	"transpose",
+	"u128",
+	"u16",
+	"u32",
+	"u64",
+	"u8",
+	"variance",
+	"wrap",
+	"wrap128",
+	"wrap16",
+	"wrap32",
+	"wrap64",
+	"wrap8",
+	"wrap_signed",
+];
+
+pub(crate) const KEYWORDS: &[&str] = &[
+	"and",
+	"as",
+	"auto",
+	"base",
+	"binary",
+	"bool",
+	"cartesian",
+	"character",
+	"choose",
+	"codepoint",
+	"cross",
+	"date",
+	"datetime",
+	"decimal",
+	"dot",
+	"dp",
+	"engineering",
+	"exact",
+	"false",
+	"float",
+	"for",
+	"formula",
+	"fraction",
+	"given",
+	"hex",
+	"in",
+	"jdn",
+	"mixed_fraction",
+	"mjd",
+	"mod",
+	"now",
+	"octal",
+	"of",
+	"or",
+	"per",
+	"permute",
+	"roman",
+	"sf",
+	"si",
+	"solve",
+	"string",
+	"to",
+	"today",
+	"tomorrow",
+	"true",
+	"unit",
+	"unitless",
+	"unix",
+	"where",
+	"xor",
+	"yesterday",
+];
+
+fn is_word_char(ch: char) -> bool {
+	ch.is_alphanumeric() || ch == '_'
+}
+
+/// Returns the byte index where the word at the end of `input` starts
+fn word_start(input: &str) -> usize {
+	input
+		.char_indices()
+		.rev()
+		.take_while(|(_, ch)| is_word_char(*ch))
+		.last()
+		.map_or(input.len(), |(idx, _)| idx)
+}
+
+fn kind_rank(kind: CompletionKind) -> u8 {
+	match kind {
+		CompletionKind::Variable => 0,
+		CompletionKind::Function => 1,
+		CompletionKind::Keyword => 2,
+		CompletionKind::Unit => 3,
+		CompletionKind::Other => 4,
+	}
+}
+
+pub(crate) fn get_completions(input: &str, context: &Context) -> (usize, Vec<Completion>) {
+	if let Some(greek) = crate::get_greek_letter_completion(input) {
+		return greek;
+	}
+
+	let start = word_start(input);
+	let word = &input[start..];
+	// don't suggest completions for numbers like `5` or `0x1f`
+	if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit()) {
+		return (input.len(), vec![]);
+	}
+
+	let mut result: Vec<Completion> = vec![];
+	let mut add = |name: &str, kind| {
+		if name.starts_with(word)
+			&& name != word
+			&& !result.iter().any(|c: &Completion| c.display == name)
+		{
+			result.push(Completion {
+				display: name.to_string(),
+				insert: name.to_string(),
+				kind,
+			});
+		}
+	};
+
+	for name in context.variables.keys() {
+		if name != "_" {
+			add(name, CompletionKind::Variable);
+		}
+	}
+	for name in FUNCTIONS {
+		add(name, CompletionKind::Function);
+	}
+	for name in KEYWORDS {
+		add(name, CompletionKind::Keyword);
+	}
+	for (singular, _, _) in &context.custom_units {
+		add(singular, CompletionKind::Unit);
+	}
+	for (name, _) in &context.user_units {
+		add(name, CompletionKind::Unit);
+	}
+	for completion in crate::units::get_completions_for_prefix(word) {
+		add(completion.display(), CompletionKind::Unit);
+	}
+
+	result.sort_by(|a, b| {
+		kind_rank(a.kind)
+			.cmp(&kind_rank(b.kind))
+			.then(a.display.len().cmp(&b.display.len()))
+			.then_with(|| a.display.cmp(&b.display))
+	});
+
+	(start, result)
+}
@@ -4,14 +4,27 @@ mod day;
 mod day_of_week;
 mod month;
 mod parser;
+mod time_of_day;
+mod time_zone;
 mod year;
 
 use day::Day;
 pub(crate) use day_of_week::DayOfWeek;
 pub(crate) use month::Month;
+pub(crate) use time_of_day::TimeOfDay;
+pub(crate) use time_zone::{TimeZone, ZonedDateTime};
 use year::Year;
 
-use crate::{error::FendError, ident::Ident, result::FResult, value::Value, Interrupt};
+use crate::{
+	error::FendError, ident::Ident, num::Number, result::FResult, value::Value, Interrupt,
+};
+
+/// The Julian Day Number of 17 November 1858, which is day 0 of the
+/// Modified Julian Date
+pub(crate) const MJD_EPOCH: i64 = 2_400_001;
+
+/// The Julian Day Number of 1 January 1970
+const UNIX_EPOCH_JDN: i64 = 2_440_588;
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub(crate) struct Date {
@@ -25,24 +38,14 @@ impl Date {
 		let Some(current_time_info) = &context.current_time else {
 			return Err(FendError::UnableToGetCurrentDate);
 		};
-		let mut ms_since_epoch: i64 = current_time_info.elapsed_unix_time_ms.try_into().unwrap();
-		ms_since_epoch -= current_time_info.timezone_offset_secs * 1000;
-		let mut days = ms_since_epoch / 86_400_000; // no leap seconds
-		let mut year = Year::new(1970);
-		while days >= year.number_of_days().into() {
-			year = year.next();
-			days -= i64::from(year.number_of_days());
-		}
-		let mut month = Month::January;
-		while days >= month.number_of_days(year).into() {
-			month = month.next();
-			days -= i64::from(month.number_of_days(year));
-		}
-		Ok(Self {
-			year,
-			month,
-			day: Day::new(days.try_into().unwrap()),
-		})
+		let ms_since_epoch = i64::try_from(current_time_info.elapsed_unix_time_ms)
+			.ok()
+			.and_then(|ms| {
+				ms.checked_sub(current_time_info.timezone_offset_secs.checked_mul(1000)?)
+			})
+			.ok_or(FendError::ValueTooLarge)?;
+		let days = ms_since_epoch.div_euclid(86_400_000); // no leap seconds
+		Self::from_julian_day_number(UNIX_EPOCH_JDN + days)
 	}
 
 	fn day_of_week(self) -> DayOfWeek {
@@ -75,6 +78,7 @@ impl Date {
 		}
 	}
 
+	#[cfg(feature = "date")]
 	pub(crate) fn next(self) -> Self {
 		if self.day.value() < Month::number_of_days(self.month, self.year) {
 			Self {
@@ -97,6 +101,7 @@ impl Date {
 		}
 	}
 
+	#[cfg(feature = "date")]
 	pub(crate) fn prev(self) -> Self {
 		if self.day.value() > 1 {
 			Self {
@@ -120,7 +125,9 @@ impl Date {
 		}
 	}
 
-	pub(crate) fn diff_months(self, mut months: i64) -> FResult<Self> {
+	/// Adds a number of months, using the last day of the month if the day
+	/// doesn't exist, e.g. 31 January + 1 month is 28 or 29 February
+	pub(crate) fn diff_months(self, mut months: i64) -> Self {
 		let mut result = self;
 		while months >= 12 {
 			result.year = result.year.next();
@@ -148,26 +155,67 @@ impl Date {
 			}
 			months += 1;
 		}
-		if result.day.value() > Month::number_of_days(result.month, result.year) {
-			let mut before = result;
-			before.day = Day::new(Month::number_of_days(before.month, before.year));
-			let mut after = result;
-			if after.month == Month::December {
-				after.month = Month::January;
-				after.year = after.year.next();
-			} else {
-				after.month = after.month.next();
-			}
-			after.day = Day::new(1);
-			return Err(FendError::NonExistentDate {
-				year: result.year.value(),
-				month: result.month,
-				expected_day: result.day.value(),
-				before,
-				after,
-			});
+		let days_in_month = Month::number_of_days(result.month, result.year);
+		if result.day.value() > days_in_month {
+			result.day = Day::new(days_in_month);
 		}
-		Ok(result)
+		result
+	}
+
+	/// Returns the Julian Day Number of this date, i.e. the number of days
+	/// since 1 January 4713 BC in the proleptic Julian calendar
+	pub(crate) fn julian_day_number(self) -> i64 {
+		// the algorithm uses astronomical year numbering, where 1 BC is year 0
+		let year = i64::from(self.year.value());
+		let year = if year < 0 { year + 1 } else { year };
+		let month = i64::from(self.month.as_u8());
+		let a = (14 - month) / 12;
+		let y = year + 4800 - a;
+		let m = month + 12 * a - 3;
+		i64::from(self.day.value()) + (153 * m + 2) / 5 + 365 * y + y.div_euclid(4)
+			- y.div_euclid(100)
+			+ y.div_euclid(400)
+			- 32045
+	}
+
+	#[allow(clippy::many_single_char_names)]
+	fn from_julian_day_number(jdn: i64) -> FResult<Self> {
+		// keeps the year within the range of an `i32`
+		if !(-700_000_000_000..=700_000_000_000).contains(&jdn) {
+			return Err(FendError::ValueTooLarge);
+		}
+		let a = jdn + 32044;
+		let b = (4 * a + 3).div_euclid(146_097);
+		let c = a - (146_097 * b).div_euclid(4);
+		let d = (4 * c + 3).div_euclid(1461);
+		let e = c - (1461 * d).div_euclid(4);
+		let m = (5 * e + 2).div_euclid(153);
+		let day = e - (153 * m + 2).div_euclid(5) + 1;
+		let month = m + 3 - 12 * (m / 10);
+		let year = 100 * b + d - 4800 + m / 10;
+		let year = if year <= 0 { year - 1 } else { year };
+		Ok(Self {
+			year: Year::new(year.try_into().map_err(|_| FendError::ValueTooLarge)?),
+			month: u8::try_from(month)
+				.ok()
+				.and_then(|month| Month::try_from(month).ok())
+				.ok_or(FendError::ValueTooLarge)?,
+			day: Day::new(day.try_into().map_err(|_| FendError::ValueTooLarge)?),
+		})
+	}
+
+	/// Converts a Julian Day Number, or a Modified Julian Date if `modified`
+	/// is set, into a date
+	pub(crate) fn from_day_number<I: Interrupt>(
+		num: Number,
+		modified: bool,
+		int: &I,
+	) -> FResult<Self> {
+		let mut jdn = num.try_as_i64(int)?;
+		if modified {
+			jdn = jdn.checked_add(MJD_EPOCH).ok_or(FendError::ValueTooLarge)?;
+		}
+		Self::from_julian_day_number(jdn)
 	}
 
 	pub(crate) fn parse(s: &str) -> FResult<Self> {
@@ -198,52 +246,44 @@ impl Date {
 	}
 
 	pub(crate) fn add<I: Interrupt>(self, rhs: Value, int: &I) -> FResult<Value> {
-		let rhs = rhs.expect_num()?;
-		if rhs.unit_equal_to("day", int)? {
-			let num_days = rhs.try_as_usize_unit(int)?;
-			let mut result = self;
-			for _ in 0..num_days {
-				result = result.next();
-			}
-			Ok(Value::Date(result))
-		} else {
-			Err(FendError::ExpectedANumber)
-		}
+		self.shift(rhs, 1, int)
 	}
 
 	pub(crate) fn sub<I: Interrupt>(self, rhs: Value, int: &I) -> FResult<Value> {
-		let rhs = rhs.expect_num()?;
+		self.shift(rhs, -1, int)
+	}
 
-		if rhs.unit_equal_to("day", int)? {
-			let num_days = rhs.try_as_usize_unit(int)?;
-			let mut result = self;
-			for _ in 0..num_days {
-				result = result.prev();
-			}
-			Ok(Value::Date(result))
+	/// Moves the date forwards (if `sign` is 1) or backwards (if `sign` is -1)
+	/// by a number of days, weeks, months or years
+	fn shift<I: Interrupt>(self, rhs: Value, sign: i64, int: &I) -> FResult<Value> {
+		let rhs = rhs.expect_num()?;
+		// whether to count in days (or otherwise in months), and how many
+		// days or months each unit corresponds to
+		let (in_days, factor) = if rhs.unit_equal_to("day", int)? {
+			(true, 1)
 		} else if rhs.unit_equal_to("week", int)? {
-			let num_weeks = rhs.try_as_usize_unit(int)?;
-			let mut result = self;
-			for _ in 0..num_weeks {
-				for _ in 0..7 {
-					result = result.prev();
-				}
-			}
-			Ok(Value::Date(result))
+			(true, 7)
 		} else if rhs.unit_equal_to("month", int)? {
-			let num_months = rhs.try_as_usize_unit(int)?;
-			let result = self
-				.diff_months(-i64::try_from(num_months).map_err(|_| FendError::ValueTooLarge)?)?;
-			Ok(Value::Date(result))
+			(false, 1)
 		} else if rhs.unit_equal_to("year", int)? {
-			let num_years = rhs.try_as_usize_unit(int)?;
-			let num_months = num_years * 12;
-			let result = self
-				.diff_months(-i64::try_from(num_months).map_err(|_| FendError::ValueTooLarge)?)?;
-			Ok(Value::Date(result))
+			(false, 12)
 		} else {
-			Err(FendError::ExpectedANumber)
-		}
+			return Err(FendError::ExpectedANumber);
+		};
+		let amount = i64::try_from(rhs.try_as_usize_unit(int)?)
+			.ok()
+			.and_then(|amount| amount.checked_mul(factor * sign))
+			.ok_or(FendError::ValueTooLarge)?;
+		let result = if in_days {
+			let jdn = self
+				.julian_day_number()
+				.checked_add(amount)
+				.ok_or(FendError::ValueTooLarge)?;
+			Self::from_julian_day_number(jdn)?
+		} else {
+			self.diff_months(amount)
+		};
+		Ok(Value::Date(result))
 	}
 }
 
@@ -0,0 +1,159 @@
+use crate::num::Number;
+use crate::result::FResult;
+use crate::value::Value;
+use crate::{Deserialize, FendError, Interrupt, Serialize};
+use std::{cmp, fmt, io};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A time of day on a 24-hour clock, e.g. `14:30`
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) struct TimeOfDay {
+	// seconds since midnight
+	seconds: u32,
+}
+
+fn parse_digits(input: &str, min_len: usize, max_len: usize) -> Option<(u32, &str)> {
+	let len = input.bytes().take_while(u8::is_ascii_digit).count();
+	if len < min_len || len > max_len {
+		return None;
+	}
+	Some((input[..len].parse().ok()?, &input[len..]))
+}
+
+impl TimeOfDay {
+	/// Parses a time like `14:30` or `9:05:30` at the start of `input`,
+	/// returning the remaining input
+	pub(crate) fn parse_prefix(input: &str) -> Option<(Self, &str)> {
+		let (hours, input) = parse_digits(input, 1, 2)?;
+		let (minutes, mut input) = parse_digits(input.strip_prefix(':')?, 2, 2)?;
+		let mut seconds = 0;
+		if let Some((s, remaining)) = input
+			.strip_prefix(':')
+			.and_then(|input| parse_digits(input, 2, 2))
+		{
+			seconds = s;
+			input = remaining;
+		}
+		if hours > 23 || minutes > 59 || seconds > 59 {
+			return None;
+		}
+		Some((
+			Self {
+				seconds: hours * 3600 + minutes * 60 + seconds,
+			},
+			input,
+		))
+	}
+
+	/// Parses a time on a 12-hour clock like `3pm` or `11:30 am` at the start
+	/// of `input`, returning the remaining input
+	pub(crate) fn parse_prefix_12_hour(input: &str) -> Option<(Self, &str)> {
+		let (hours, mut input) = parse_digits(input, 1, 2)?;
+		let (mut minutes, mut seconds) = (0, 0);
+		if let Some((m, remaining)) = input
+			.strip_prefix(':')
+			.and_then(|input| parse_digits(input, 2, 2))
+		{
+			minutes = m;
+			input = remaining;
+			if let Some((s, remaining)) = input
+				.strip_prefix(':')
+				.and_then(|input| parse_digits(input, 2, 2))
+			{
+				seconds = s;
+				input = remaining;
+			}
+		}
+		let input = input.trim_start_matches(' ');
+		let pm = match input.get(..2)?.to_ascii_lowercase().as_str() {
+			"am" => false,
+			"pm" => true,
+			_ => return None,
+		};
+		let input = &input[2..];
+		if !(1..=12).contains(&hours)
+			|| minutes > 59
+			|| seconds > 59
+			|| input.starts_with(|ch: char| ch.is_alphanumeric() || ch == '_')
+		{
+			return None;
+		}
+		let hours = hours % 12 + if pm { 12 } else { 0 };
+		Some((
+			Self::from_seconds_since_midnight(hours * 3600 + minutes * 60 + seconds),
+			input,
+		))
+	}
+
+	pub(crate) fn from_seconds_since_midnight(seconds: u32) -> Self {
+		Self {
+			seconds: seconds % 86_400,
+		}
+	}
+
+	pub(crate) fn seconds_since_midnight(self) -> u32 {
+		self.seconds
+	}
+
+	/// The number of seconds from `rhs` to `self`
+	pub(crate) fn seconds_since(self, rhs: Self) -> i64 {
+		i64::from(self.seconds) - i64::from(rhs.seconds)
+	}
+
+	pub(crate) fn add<I: Interrupt>(self, rhs: Value, int: &I) -> FResult<Value> {
+		self.shift(rhs, 1, int)
+	}
+
+	pub(crate) fn sub<I: Interrupt>(self, rhs: Value, int: &I) -> FResult<Value> {
+		self.shift(rhs, -1, int)
+	}
+
+	/// Moves the time forwards (if `sign` is 1) or backwards (if `sign` is -1)
+	/// by a duration, wrapping around at midnight
+	fn shift<I: Interrupt>(self, rhs: Value, sign: i64, int: &I) -> FResult<Value> {
+		let rhs = rhs.expect_num()?.convert_to(
+			Number::new_base_unit("second".into(), "seconds".into()),
+			int,
+		)?;
+		let negative = rhs.real_sign() == Some(cmp::Ordering::Less);
+		let seconds = i64::try_from(rhs.abs(int)?.try_as_usize_unit(int)?)
+			.map_err(|_| FendError::ValueTooLarge)?
+			.rem_euclid(SECONDS_PER_DAY);
+		let seconds = if negative { -seconds } else { seconds } * sign;
+		let seconds = (i64::from(self.seconds) + seconds).rem_euclid(SECONDS_PER_DAY);
+		Ok(Value::TimeOfDay(Self {
+			seconds: seconds.try_into().map_err(|_| FendError::ValueTooLarge)?,
+		}))
+	}
+
+	pub(crate) fn serialize(self, write: &mut impl io::Write) -> FResult<()> {
+		self.seconds.serialize(write)?;
+		Ok(())
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		let seconds = u32::deserialize(read)?;
+		if i64::from(seconds) >= SECONDS_PER_DAY {
+			return Err(FendError::DeserializationError);
+		}
+		Ok(Self { seconds })
+	}
+}
+
+impl fmt::Debug for TimeOfDay {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (hours, minutes) = (self.seconds / 3600, self.seconds / 60 % 60);
+		write!(f, "{hours:02}:{minutes:02}")?;
+		if !self.seconds.is_multiple_of(60) {
+			write!(f, ":{:02}", self.seconds % 60)?;
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Display for TimeOfDay {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
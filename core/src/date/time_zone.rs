@@ -0,0 +1,465 @@
+use super::{Date, Day, Month, TimeOfDay, Year, UNIX_EPOCH_JDN};
+use crate::result::FResult;
+use crate::{Deserialize, FendError, Serialize};
+use std::{fmt, io};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+const SECONDS_PER_HOUR: i64 = 3600;
+
+/// Daylight saving time rules. Only the current rules are included, and
+/// daylight saving time is always one hour ahead of standard time.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(not(feature = "timezones"), allow(dead_code))]
+enum DstRule {
+	/// second Sunday in March to first Sunday in November, at 02:00 local time
+	UnitedStates,
+	/// last Sunday in March to last Sunday in October, at 01:00 UTC
+	Europe,
+	/// first Sunday in October to first Sunday in April, at 02:00 local
+	/// standard time
+	Australia,
+	/// last Sunday in September to first Sunday in April, at 02:00 local
+	/// standard time
+	NewZealand,
+}
+
+#[derive(Eq, PartialEq)]
+pub(crate) struct Zone {
+	name: &'static str,
+	/// offset of standard time from UTC, in minutes
+	offset: i32,
+	dst: Option<DstRule>,
+}
+
+macro_rules! zones {
+	($($name:literal $offset:literal $($dst:ident)?),* $(,)?) => {
+		&[$(Zone {
+			name: $name,
+			offset: $offset,
+			dst: zones!(@dst $($dst)?),
+		}),*]
+	};
+	(@dst) => { None };
+	(@dst $dst:ident) => { Some(DstRule::$dst) };
+}
+
+// abbreviations always refer to a fixed offset, e.g. `EST` is UTC-5 even in
+// summer, while locations follow their daylight saving time rules
+#[cfg(feature = "timezones")]
+const ZONES: &[Zone] = zones![
+	"UTC" 0, "GMT" 0, "WET" 0, "WEST" 60, "BST" 60, "CET" 60, "CEST" 120,
+	"EET" 120, "EEST" 180, "MSK" 180, "SAST" 120, "IST" 330, "HKT" 480,
+	"SGT" 480, "AWST" 480, "JST" 540, "KST" 540, "ACST" 570, "ACDT" 630,
+	"AEST" 600, "AEDT" 660, "NZST" 720, "NZDT" 780, "NST" -210, "NDT" -150,
+	"AST" -240, "ADT" -180, "EST" -300, "EDT" -240, "CST" -360, "CDT" -300,
+	"MST" -420, "MDT" -360, "PST" -480, "PDT" -420, "AKST" -540, "AKDT" -480,
+	"HST" -600,
+	"Europe/London" 0 Europe, "Europe/Dublin" 0 Europe, "Europe/Lisbon" 0 Europe,
+	"Europe/Paris" 60 Europe, "Europe/Berlin" 60 Europe, "Europe/Madrid" 60 Europe,
+	"Europe/Rome" 60 Europe, "Europe/Amsterdam" 60 Europe,
+	"Europe/Brussels" 60 Europe, "Europe/Vienna" 60 Europe,
+	"Europe/Zurich" 60 Europe, "Europe/Stockholm" 60 Europe,
+	"Europe/Oslo" 60 Europe, "Europe/Copenhagen" 60 Europe,
+	"Europe/Prague" 60 Europe, "Europe/Warsaw" 60 Europe,
+	"Europe/Budapest" 60 Europe, "Europe/Athens" 120 Europe,
+	"Europe/Helsinki" 120 Europe, "Europe/Kyiv" 120 Europe,
+	"Europe/Istanbul" 180, "Europe/Moscow" 180,
+	"Africa/Lagos" 60, "Africa/Johannesburg" 120, "Africa/Nairobi" 180,
+	"Asia/Dubai" 240, "Asia/Tehran" 210, "Asia/Karachi" 300, "Asia/Kolkata" 330,
+	"Asia/Kathmandu" 345, "Asia/Bangkok" 420, "Asia/Jakarta" 420,
+	"Asia/Shanghai" 480, "Asia/Hong_Kong" 480, "Asia/Singapore" 480,
+	"Asia/Taipei" 480, "Asia/Manila" 480, "Asia/Tokyo" 540, "Asia/Seoul" 540,
+	"Australia/Perth" 480, "Australia/Darwin" 570,
+	"Australia/Adelaide" 570 Australia, "Australia/Brisbane" 600,
+	"Australia/Sydney" 600 Australia, "Australia/Melbourne" 600 Australia,
+	"Australia/Hobart" 600 Australia, "Pacific/Auckland" 720 NewZealand,
+	"Pacific/Honolulu" -600, "America/Anchorage" -540 UnitedStates,
+	"America/Los_Angeles" -480 UnitedStates, "America/Vancouver" -480 UnitedStates,
+	"America/Phoenix" -420, "America/Denver" -420 UnitedStates,
+	"America/Chicago" -360 UnitedStates, "America/Mexico_City" -360,
+	"America/New_York" -300 UnitedStates, "America/Toronto" -300 UnitedStates,
+	"America/Bogota" -300, "America/Lima" -300, "America/Halifax" -240 UnitedStates,
+	"America/St_Johns" -210 UnitedStates, "America/Sao_Paulo" -180,
+	"America/Argentina/Buenos_Aires" -180,
+];
+
+#[cfg(not(feature = "timezones"))]
+const ZONES: &[Zone] = zones![];
+
+/// A time zone, either from the built-in database or a UTC offset like
+/// `UTC+8`
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) enum TimeZone {
+	Named(&'static Zone),
+	/// offset from UTC in minutes
+	Offset(i32),
+}
+
+fn is_valid_after_name(ch: Option<char>) -> bool {
+	!ch.is_some_and(|ch| ch.is_alphanumeric() || ch == '_' || ch == '/')
+}
+
+/// Parses an offset like `+8`, `-05:30` or `+0545`, returning minutes
+fn parse_offset(input: &str) -> Option<(i32, &str)> {
+	let sign = match input.chars().next()? {
+		'+' => 1,
+		'-' => -1,
+		_ => return None,
+	};
+	let input = &input[1..];
+	let digits = input.bytes().take_while(u8::is_ascii_digit).count();
+	let (hours, minutes, remaining) = match digits {
+		1 | 2 => {
+			let hours: i32 = input[..digits].parse().ok()?;
+			let remaining = &input[digits..];
+			let minutes_len = remaining
+				.strip_prefix(':')
+				.map_or(0, |s| s.bytes().take_while(u8::is_ascii_digit).count());
+			if minutes_len == 2 {
+				(hours, remaining[1..3].parse().ok()?, &remaining[3..])
+			} else {
+				(hours, 0, remaining)
+			}
+		}
+		4 => (
+			input[..2].parse().ok()?,
+			input[2..4].parse().ok()?,
+			&input[4..],
+		),
+		_ => return None,
+	};
+	if hours > 14 || minutes > 59 || !is_valid_after_name(remaining.chars().next()) {
+		return None;
+	}
+	Some((sign * (hours * 60 + minutes), remaining))
+}
+
+/// The number of seconds between 1 January 1970 and midnight on `date`
+fn unix_day_start(date: Date) -> i64 {
+	(date.julian_day_number() - UNIX_EPOCH_JDN) * SECONDS_PER_DAY
+}
+
+/// Splits a local time (in seconds since 1 January 1970) into a date and a
+/// time of day
+fn split_local_time(local: i64) -> FResult<(Date, TimeOfDay)> {
+	let date = Date::from_julian_day_number(UNIX_EPOCH_JDN + local.div_euclid(SECONDS_PER_DAY))?;
+	let seconds =
+		u32::try_from(local.rem_euclid(SECONDS_PER_DAY)).map_err(|_| FendError::ValueTooLarge)?;
+	Ok((date, TimeOfDay::from_seconds_since_midnight(seconds)))
+}
+
+/// Finds the `n`-th Sunday of a month, or the last one if `n` is zero
+fn sunday(year: i32, month: u8, n: u8) -> FResult<Date> {
+	let month = Month::try_from(month).map_err(|_| FendError::ValueTooLarge)?;
+	let date = |day| Date {
+		year: Year::new(year),
+		month,
+		day: Day::new(day),
+	};
+	// days since Sunday, using the fact that JDN 0 was a Monday
+	let weekday = |date: Date| (date.julian_day_number() + 1).rem_euclid(7);
+	let jdn = if n == 0 {
+		let last = date(month.number_of_days(Year::new(year)));
+		last.julian_day_number() - weekday(last)
+	} else {
+		let first = date(1);
+		first.julian_day_number() + (7 - weekday(first)) % 7 + 7 * (i64::from(n) - 1)
+	};
+	Date::from_julian_day_number(jdn)
+}
+
+impl DstRule {
+	/// Returns whether daylight saving time is in effect at `utc` (in seconds
+	/// since 1 January 1970)
+	fn is_active(self, utc: i64, offset: i64) -> FResult<bool> {
+		let local = utc + offset;
+		let year =
+			Date::from_julian_day_number(UNIX_EPOCH_JDN + local.div_euclid(SECONDS_PER_DAY))?
+				.year
+				.value();
+		// transitions at 02:00 local standard time
+		let local_2am = |date| unix_day_start(date) + 2 * SECONDS_PER_HOUR - offset;
+		Ok(match self {
+			Self::UnitedStates => {
+				let start = local_2am(sunday(year, 3, 2)?);
+				// 02:00 daylight saving time
+				let end = local_2am(sunday(year, 11, 1)?) - SECONDS_PER_HOUR;
+				(start..end).contains(&utc)
+			}
+			Self::Europe => {
+				let start = unix_day_start(sunday(year, 3, 0)?) + SECONDS_PER_HOUR;
+				let end = unix_day_start(sunday(year, 10, 0)?) + SECONDS_PER_HOUR;
+				(start..end).contains(&utc)
+			}
+			Self::Australia | Self::NewZealand => {
+				let start = if self == Self::Australia {
+					sunday(year, 10, 1)?
+				} else {
+					sunday(year, 9, 0)?
+				};
+				let (start, end) = (local_2am(start), local_2am(sunday(year, 4, 1)?));
+				!(end..start).contains(&utc)
+			}
+		})
+	}
+}
+
+impl TimeZone {
+	/// Parses a time zone name like `EST`, `Europe/London` or `UTC+8` at the
+	/// start of `input`, returning the remaining input
+	pub(crate) fn parse_prefix(input: &str) -> Option<(Self, &str)> {
+		let zone = ZONES
+			.iter()
+			.filter(|zone| {
+				input.starts_with(zone.name)
+					&& (is_valid_after_name(input[zone.name.len()..].chars().next())
+						|| matches!(zone.name, "UTC" | "GMT"))
+			})
+			.max_by_key(|zone| zone.name.len())?;
+		let remaining = &input[zone.name.len()..];
+		if matches!(zone.name, "UTC" | "GMT") {
+			if let Some((offset, remaining)) = parse_offset(remaining) {
+				return Some((Self::Offset(offset), remaining));
+			}
+			if !is_valid_after_name(remaining.chars().next()) {
+				return None;
+			}
+		}
+		Some((Self::Named(zone), remaining))
+	}
+
+	fn standard_offset(self) -> i64 {
+		i64::from(match self {
+			Self::Named(zone) => zone.offset,
+			Self::Offset(offset) => offset,
+		}) * 60
+	}
+
+	/// The offset from UTC in seconds at the given time (in seconds since 1
+	/// January 1970), or the standard offset if no time is given
+	fn offset_at(self, utc: Option<i64>) -> FResult<i64> {
+		let offset = self.standard_offset();
+		if let (Self::Named(Zone { dst: Some(dst), .. }), Some(utc)) = (self, utc) {
+			if dst.is_active(utc, offset)? {
+				return Ok(offset + SECONDS_PER_HOUR);
+			}
+		}
+		Ok(offset)
+	}
+
+	/// Converts a local time (in seconds since 1 January 1970) into UTC. Local
+	/// times that occur twice when the clocks go back are treated as daylight
+	/// saving time.
+	fn to_utc(self, local: i64, dst_aware: bool) -> FResult<i64> {
+		let standard = local - self.standard_offset();
+		if !dst_aware {
+			return Ok(standard);
+		}
+		let daylight = standard - SECONDS_PER_HOUR;
+		Ok(local - self.offset_at(Some(daylight))?)
+	}
+
+	pub(crate) fn serialize(self, write: &mut impl io::Write) -> FResult<()> {
+		match self {
+			Self::Named(zone) => {
+				0u8.serialize(write)?;
+				zone.name.serialize(write)?;
+			}
+			Self::Offset(offset) => {
+				1u8.serialize(write)?;
+				offset.serialize(write)?;
+			}
+		}
+		Ok(())
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		Ok(match u8::deserialize(read)? {
+			0 => {
+				let name = String::deserialize(read)?;
+				Self::Named(
+					ZONES
+						.iter()
+						.find(|zone| zone.name == name)
+						.ok_or(FendError::DeserializationError)?,
+				)
+			}
+			1 => Self::Offset(i32::deserialize(read)?),
+			_ => return Err(FendError::DeserializationError),
+		})
+	}
+}
+
+impl fmt::Debug for TimeZone {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Named(zone) => write!(f, "{}", zone.name),
+			Self::Offset(0) => write!(f, "UTC"),
+			Self::Offset(offset) => {
+				let sign = if *offset < 0 { '-' } else { '+' };
+				let (hours, minutes) = (offset.abs() / 60, offset.abs() % 60);
+				write!(f, "UTC{sign}{hours}")?;
+				if minutes != 0 {
+					write!(f, ":{minutes:02}")?;
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+impl fmt::Display for TimeZone {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
+
+/// A time of day in a specific time zone, optionally on a given date, e.g.
+/// `3pm EST` or `@2024-07-01 15:00 America/New_York`
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) struct ZonedDateTime {
+	date: Option<Date>,
+	time: TimeOfDay,
+	zone: TimeZone,
+	/// for times without a date, the number of days this time is after the
+	/// time it was converted from
+	day_offset: i64,
+}
+
+impl ZonedDateTime {
+	/// Parses a time followed by a time zone, e.g. `3pm EST` or
+	/// `14:30 UTC+8`, at the start of `input`
+	pub(crate) fn parse_prefix(date: Option<Date>, input: &str) -> Option<(Self, &str)> {
+		let (time, remaining) =
+			TimeOfDay::parse_prefix_12_hour(input).or_else(|| TimeOfDay::parse_prefix(input))?;
+		let zone_start = remaining.trim_start();
+		if zone_start.len() == remaining.len() {
+			return None;
+		}
+		let (zone, remaining) = TimeZone::parse_prefix(zone_start)?;
+		Some((
+			Self {
+				date,
+				time,
+				zone,
+				day_offset: 0,
+			},
+			remaining,
+		))
+	}
+
+	/// The current date and time, in the time zone the context was set to
+	#[cfg(feature = "date")]
+	pub(crate) fn now(context: &crate::Context) -> FResult<Self> {
+		let Some(current_time_info) = &context.current_time else {
+			return Err(FendError::UnableToGetCurrentDate);
+		};
+		let utc = i64::try_from(current_time_info.elapsed_unix_time_ms / 1000)
+			.map_err(|_| FendError::ValueTooLarge)?;
+		// the offset is the number of seconds the time zone is behind UTC
+		let offset = i32::try_from(-current_time_info.timezone_offset_secs / 60)
+			.map_err(|_| FendError::ValueTooLarge)?;
+		Self::from_unix_time(utc, TimeZone::Offset(offset))
+	}
+
+	/// Converts a unix timestamp (in seconds since 1 January 1970 UTC) into a
+	/// date and time
+	pub(crate) fn from_unix_time(utc: i64, zone: TimeZone) -> FResult<Self> {
+		let local = utc
+			.checked_add(zone.offset_at(Some(utc))?)
+			.ok_or(FendError::ValueTooLarge)?;
+		let (date, time) = split_local_time(local)?;
+		Ok(Self {
+			date: Some(date),
+			time,
+			zone,
+			day_offset: 0,
+		})
+	}
+
+	/// Converts this time into a unix timestamp. If no date was given, today
+	/// is used.
+	pub(crate) fn unix_time(self, today: Option<Date>) -> FResult<i64> {
+		let date = self
+			.date
+			.or(today)
+			.ok_or(FendError::UnableToGetCurrentDate)?;
+		let local = unix_day_start(date)
+			+ self.day_offset * SECONDS_PER_DAY
+			+ i64::from(self.time.seconds_since_midnight());
+		self.zone.to_utc(local, true)
+	}
+
+	/// Converts this time into another time zone. If no date was given,
+	/// daylight saving time is determined using `today`, or ignored if that
+	/// is unknown too.
+	pub(crate) fn convert_to(self, zone: TimeZone, today: Option<Date>) -> FResult<Self> {
+		let (date, dst_aware) = match self.date.or(today) {
+			Some(date) => (date, true),
+			None => (Date::from_julian_day_number(UNIX_EPOCH_JDN)?, false),
+		};
+		let local = unix_day_start(date) + i64::from(self.time.seconds_since_midnight());
+		let utc = self.zone.to_utc(local, dst_aware)?;
+		let local = utc + zone.offset_at(dst_aware.then_some(utc))?;
+		let (new_date, time) = split_local_time(local)?;
+		Ok(Self {
+			date: self.date.map(|_| new_date),
+			time,
+			zone,
+			day_offset: self.day_offset + new_date.julian_day_number() - date.julian_day_number(),
+		})
+	}
+
+	pub(crate) fn serialize(self, write: &mut impl io::Write) -> FResult<()> {
+		match self.date {
+			Some(date) => {
+				true.serialize(write)?;
+				date.serialize(write)?;
+			}
+			None => false.serialize(write)?,
+		}
+		self.time.serialize(write)?;
+		self.zone.serialize(write)?;
+		i32::try_from(self.day_offset)
+			.map_err(|_| FendError::ValueTooLarge)?
+			.serialize(write)?;
+		Ok(())
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		Ok(Self {
+			date: if bool::deserialize(read)? {
+				Some(Date::deserialize(read)?)
+			} else {
+				None
+			},
+			time: TimeOfDay::deserialize(read)?,
+			zone: TimeZone::deserialize(read)?,
+			day_offset: i64::from(i32::deserialize(read)?),
+		})
+	}
+}
+
+impl fmt::Debug for ZonedDateTime {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(date) = self.date {
+			write!(f, "{date}, ")?;
+		}
+		write!(f, "{} {}", self.time, self.zone)?;
+		if self.date.is_none() {
+			match self.day_offset {
+				0 => (),
+				1 => write!(f, " (next day)")?,
+				-1 => write!(f, " (previous day)")?,
+				n => write!(f, " ({n:+} days)")?,
+			}
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Display for ZonedDateTime {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:?}")
+	}
+}
@@ -46,14 +46,6 @@ impl Year {
 		}
 	}
 
-	pub(crate) fn number_of_days(self) -> u16 {
-		if self.is_leap_year() {
-			366
-		} else {
-			365
-		}
-	}
-
 	pub(crate) fn serialize(self, write: &mut impl io::Write) -> FResult<()> {
 		self.value().serialize(write)?;
 		Ok(())
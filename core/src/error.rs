@@ -1,6 +1,6 @@
-use std::{error, fmt, io};
+use std::{error, fmt, io, ops};
 
-use crate::{date, num::Range};
+use crate::num::Range;
 
 #[derive(Debug)]
 #[non_exhaustive]
@@ -18,6 +18,7 @@ pub(crate) enum FendError {
 	DeserializationError,
 	Wrap(Box<dyn error::Error + Send + Sync + 'static>),
 	NoExchangeRatesAvailable,
+	FeatureDisabled(&'static str),
 	OutOfRange {
 		value: Box<dyn crate::format::DisplayDebug>,
 		range: Range<Box<dyn crate::format::DisplayDebug>>,
@@ -48,6 +49,7 @@ pub(crate) enum FendError {
 	IsNotAFunction(String),
 	IsNotAFunctionOrNumber(String),
 	IdentifierNotFound(crate::ident::Ident),
+	IdentifierNotFoundDidYouMean(crate::ident::Ident, String),
 	ExpectedACharacter,
 	StringCannotBeLonger,
 	StringCannotBeEmpty,
@@ -80,18 +82,59 @@ pub(crate) enum FendError {
 		from_base: String,
 		to_base: String,
 	},
+	AddingAbsoluteTemperatures,
+	CannotShadowConstant(String),
 	RootsOfNegativeNumbers,
 	NonIntegerNegRoots,
 	CannotConvertValueTo(&'static str),
 	ExpectedADateLiteral,
-	NonExistentDate {
-		year: i32,
-		month: date::Month,
-		expected_day: u8,
-		before: date::Date,
-		after: date::Date,
-	},
+	ExpectedATimeWithTimeZone,
 	RomanNumeralZero,
+	ExpectedAnEquation,
+	UnknownFormula(String),
+	NotAVariableInFormula(String, String),
+	MissingFormulaValue(String),
+	CannotSolveFor(String),
+	UnsolvedEquation,
+	ExpectedAVariableToSolveFor,
+	ExpectedAVector(&'static str),
+	VectorDimensionMismatch(usize, usize),
+	ExpectedA3dVector,
+	InvalidQuaternionComponents(usize),
+	CannotMultiplyVectors,
+	InvalidOperandsForVectorArithmetic,
+	ExpectedCartesianVector(&'static str),
+	CoordinateDimensionMismatch(&'static str, usize, usize),
+	TooFewDataPoints(usize),
+	InconsistentMatrixRows,
+	MatrixDimensionMismatch([usize; 2], [usize; 2]),
+	ExpectedASquareMatrix(usize, usize),
+	ExpectedAMatrix(&'static str),
+	SingularMatrix,
+	NoSolution,
+	InfinitelyManySolutions,
+	InvalidOperandsForMatrixArithmetic,
+	ExpectedARegisterName,
+	EmptyMemoryRegister(String),
+	InvalidOperandsForSymbolicArithmetic,
+	CannotDivideBySymbolicSum,
+	UnsupportedSymbolicFunction(&'static str),
+	ExpectedAVariable,
+	ClampBoundsReversed,
+	InvalidOperandsForIntervalArithmetic,
+	DivideByIntervalContainingZero,
+	ExpectedAnIntegerRange,
+	RangeTooLarge(usize),
+	TooManyPeriods(usize),
+	GammaOfNonPositiveInteger,
+	FactorOfZero,
+	NoPrimeLessThanTwo,
+	NoModularInverse,
+	ValueDoesNotFitInBits(u64),
+	WidthMustBePositive,
+	// a syntax error, together with the (1-based) column of the input where
+	// it was found and the byte range of the offending token
+	AtColumn(Box<Self>, usize, ops::Range<usize>),
 }
 
 impl fmt::Display for FendError {
@@ -100,6 +143,7 @@ impl fmt::Display for FendError {
 		match self {
 			Self::Interrupted => write!(f, "interrupted"),
 			Self::ParseError(e) => write!(f, "{e}"),
+			Self::AtColumn(e, column, _) => write!(f, "{e} at column {column}"),
 			Self::DeserializationError => write!(f, "failed to deserialize object"),
 			Self::FactorialComplex => write!(f, "factorial is not supported for complex numbers"),
 			Self::IoError(_) => write!(f, "I/O error"),
@@ -108,6 +152,10 @@ impl fmt::Display for FendError {
 				"unable to parse a valid base prefix, expected 0b, 0o, or 0x"
 			),
 			Self::NoExchangeRatesAvailable => write!(f, "exchange rates are not available"),
+			Self::FeatureDisabled(feature) => write!(
+				f,
+				"this build of fend does not support the `{feature}` feature"
+			),
 			Self::IncompatibleConversion {
 				from,
 				to,
@@ -119,6 +167,13 @@ impl fmt::Display for FendError {
 					"cannot convert from {from} to {to}: units '{from_base}' and '{to_base}' are incompatible"
 				)
 			}
+			Self::AddingAbsoluteTemperatures => write!(
+				f,
+				"cannot add two absolute temperatures: use kelvin for temperature differences, e.g. `20 °C + 5 K`"
+			),
+			Self::CannotShadowConstant(name) => {
+				write!(f, "cannot assign to '{name}' because it is a built-in constant")
+			}
 			Self::NonIntegerNegRoots => write!(f, "cannot compute non-integer or negative roots"),
 			Self::RootsOfNegativeNumbers => {
 				write!(f, "roots of negative numbers are not supported")
@@ -192,6 +247,9 @@ impl fmt::Display for FendError {
 			Self::IsNotAFunction(s) => write!(f, "'{s}' is not a function"),
 			Self::IsNotAFunctionOrNumber(s) => write!(f, "'{s}' is not a function or number"),
 			Self::IdentifierNotFound(s) => write!(f, "unknown identifier '{s}'"),
+			Self::IdentifierNotFoundDidYouMean(s, suggestion) => {
+				write!(f, "unknown identifier '{s}'; did you mean '{suggestion}'?")
+			}
 			Self::ExpectedACharacter => write!(f, "expected a character"),
 			Self::ExpectedADigit(ch) => write!(f, "expected a digit, found '{ch}'"),
 			Self::ExpectedChar(ex, fnd) => write!(f, "expected '{ex}', found '{fnd}'"),
@@ -229,19 +287,172 @@ impl fmt::Display for FendError {
 			Self::FormattingError(_) => write!(f, "error during formatting"),
 			Self::Wrap(e) => write!(f, "{e}"),
 			Self::ExpectedADateLiteral => write!(f, "Expected a date literal, e.g. @1970-01-01"),
-			Self::NonExistentDate {
-				year,
-				month,
-				expected_day,
-				before,
-				after,
-			} => {
-				write!(
-					f,
-					"{month} {expected_day}, {year} does not exist, did you mean {before} or {after}?",
-				)
+			Self::ExpectedATimeWithTimeZone => {
+				write!(f, "expected a time with a time zone, e.g. 3pm EST")
 			}
 			Self::RomanNumeralZero => write!(f, "zero cannot be represented as a roman numeral"),
+			Self::ExpectedAnEquation => write!(f, "expected an equation, e.g. `V = I R`"),
+			Self::UnknownFormula(name) => write!(f, "unknown formula '{name}'"),
+			Self::NotAVariableInFormula(var, formula) => {
+				write!(f, "'{var}' is not a variable in formula '{formula}'")
+			}
+			Self::MissingFormulaValue(var) => write!(f, "missing a value for '{var}'"),
+			Self::CannotSolveFor(var) => write!(f, "unable to solve for '{var}'"),
+			Self::UnsolvedEquation => write!(
+				f,
+				"an equation can only be solved, e.g. `solve(2x + 3 = 11, x)`"
+			),
+			Self::ExpectedAVariableToSolveFor => {
+				write!(f, "expected a variable to solve for, e.g. `solve(2x = 4, x)`")
+			}
+			Self::ExpectedAVector(t) => write!(f, "expected a vector (found {t})"),
+			Self::VectorDimensionMismatch(a, b) => write!(
+				f,
+				"cannot combine a vector with {a} components and a vector with {b} components"
+			),
+			Self::ExpectedA3dVector => write!(f, "expected a vector with 3 components"),
+			Self::InvalidQuaternionComponents(n) => write!(
+				f,
+				"expected a vector with 3 or 4 components to create a quaternion, but found {n}"
+			),
+			Self::CannotMultiplyVectors => write!(
+				f,
+				"cannot multiply two vectors, try `dot` or `cross` instead"
+			),
+			Self::InvalidOperandsForVectorArithmetic => {
+				write!(f, "invalid operands for vector arithmetic")
+			}
+			Self::ExpectedCartesianVector(system) => write!(
+				f,
+				"expected a vector in cartesian coordinates (found {system} coordinates), \
+				try converting it with `to cartesian`"
+			),
+			Self::CoordinateDimensionMismatch(system, expected, found) => write!(
+				f,
+				"{system} coordinates need {expected} components, but found {found}"
+			),
+			Self::TooFewDataPoints(n) => {
+				write!(f, "expected at least 2 data points, but found {n}")
+			}
+			Self::InconsistentMatrixRows => {
+				write!(f, "all rows of a matrix must have the same length")
+			}
+			Self::MatrixDimensionMismatch([rows_a, cols_a], [rows_b, cols_b]) => write!(
+				f,
+				"incompatible matrix dimensions {rows_a}×{cols_a} and {rows_b}×{cols_b}"
+			),
+			Self::ExpectedASquareMatrix(rows, cols) => {
+				write!(f, "expected a square matrix, but found a {rows}×{cols} matrix")
+			}
+			Self::ExpectedAMatrix(t) => write!(f, "expected a matrix (found {t})"),
+			Self::SingularMatrix => write!(f, "the matrix is singular, so it has no inverse"),
+			Self::NoSolution => write!(f, "there is no solution"),
+			Self::InfinitelyManySolutions => write!(f, "there are infinitely many solutions"),
+			Self::InvalidOperandsForMatrixArithmetic => {
+				write!(f, "invalid operands for matrix arithmetic")
+			}
+			Self::ExpectedARegisterName => write!(
+				f,
+				"expected the name of a memory register, e.g. `store(tax_rate, 0.19)`"
+			),
+			Self::EmptyMemoryRegister(name) => write!(f, "memory register '{name}' is empty"),
+			Self::InvalidOperandsForSymbolicArithmetic => {
+				write!(f, "invalid operands for symbolic arithmetic")
+			}
+			Self::CannotDivideBySymbolicSum => {
+				write!(f, "cannot divide by an expression with more than one term")
+			}
+			Self::UnsupportedSymbolicFunction(name) => {
+				write!(f, "`{name}` can't be applied to variables like `x`")
+			}
+			Self::ExpectedAVariable => {
+				write!(f, "expected a variable to differentiate by, e.g. `diff(x^2, x)`")
+			}
+			Self::ClampBoundsReversed => write!(
+				f,
+				"the lower bound of `clamp` must not be greater than the upper bound"
+			),
+			Self::InvalidOperandsForIntervalArithmetic => {
+				write!(f, "invalid operands for interval arithmetic")
+			}
+			Self::DivideByIntervalContainingZero => {
+				write!(f, "cannot divide by an interval that contains zero")
+			}
+			Self::ExpectedAnIntegerRange => {
+				write!(f, "expected a list or a range of integers like `1..10`")
+			}
+			Self::RangeTooLarge(max) => {
+				write!(f, "ranges used as lists can have at most {max} elements")
+			}
+			Self::TooManyPeriods(max) => {
+				write!(f, "amortization schedules can have at most {max} periods")
+			}
+			Self::GammaOfNonPositiveInteger => write!(
+				f,
+				"the gamma function is undefined for zero and negative integers"
+			),
+			Self::FactorOfZero => write!(f, "zero has no prime factorization"),
+			Self::NoPrimeLessThanTwo => write!(f, "there are no prime numbers less than 2"),
+			Self::WidthMustBePositive => write!(f, "the width must be at least 1 bit"),
+			Self::ValueDoesNotFitInBits(bits) => {
+				write!(f, "the value does not fit in {bits} bits")
+			}
+			Self::NoModularInverse => write!(
+				f,
+				"no modular inverse exists because the numbers are not coprime"
+			),
+		}
+	}
+}
+
+impl FendError {
+	pub(crate) fn kind(&self) -> crate::ErrorKind {
+		use crate::ErrorKind;
+		match self {
+			Self::Interrupted => ErrorKind::Interrupted,
+			Self::AtColumn(..)
+			| Self::ParseError(_)
+			| Self::InvalidBasePrefix
+			| Self::InvalidDiceSyntax
+			| Self::ExpectedADigit(_)
+			| Self::ExpectedChar(..)
+			| Self::ExpectedDigitSeparator(_)
+			| Self::DigitSeparatorsNotAllowed
+			| Self::DigitSeparatorsOnlyBetweenDigits
+			| Self::InvalidCharAtBeginningOfIdent(_)
+			| Self::UnexpectedChar(_)
+			| Self::UnterminatedStringLiteral
+			| Self::UnknownBackslashEscapeSequence(_)
+			| Self::BackslashXOutOfRange
+			| Self::ExpectedALetterOrCode
+			| Self::InvalidUnicodeEscapeSequence
+			| Self::ExpectedADateLiteral => ErrorKind::Syntax,
+			Self::IdentifierNotFound(_)
+			| Self::IdentifierNotFoundDidYouMean(..)
+			| Self::UnknownFormula(_) => ErrorKind::UnknownIdentifier,
+			Self::DivideByZero | Self::ModuloByZero | Self::DivideByIntervalContainingZero => {
+				ErrorKind::DivisionByZero
+			}
+			Self::IncompatibleConversion { .. } => ErrorKind::IncompatibleUnits,
+			Self::OutOfRange { .. }
+			| Self::ValueTooLarge
+			| Self::ExponentTooLarge
+			| Self::ValueDoesNotFitInBits(_)
+			| Self::RangeTooLarge(_)
+			| Self::TooManyPeriods(_) => ErrorKind::OutOfRange,
+			Self::FeatureDisabled(_)
+			| Self::NoExchangeRatesAvailable
+			| Self::RandomNumbersNotAvailable
+			| Self::UnableToGetCurrentDate => ErrorKind::Unavailable,
+			_ => ErrorKind::Other,
+		}
+	}
+
+	/// The byte range of the input where a syntax error was found
+	pub(crate) fn span(&self) -> Option<ops::Range<usize>> {
+		match self {
+			Self::AtColumn(_, _, span) => Some(span.clone()),
+			_ => None,
 		}
 	}
 }
@@ -1,30 +1,72 @@
-use std::sync::Arc;
+use std::{ops, sync::Arc};
 
 use crate::{
-	ast, error::Interrupt, lexer, parser, result::FResult, scope::Scope, value::Value, Span,
+	ast,
+	error::{FendError, Interrupt},
+	lexer, natural_language, parser, render,
+	result::FResult,
+	scope::Scope,
+	value::Value,
+	ImplicitMultiplication, ResultInfo, Span,
 };
 
-pub(crate) fn evaluate_to_value<I: Interrupt>(
+/// Adds the column and byte range of the offending token to syntax errors.
+/// An empty range is extended to cover the next character.
+fn at_column(error: FendError, input: &str, span: ops::Range<usize>) -> FendError {
+	let column = input[..span.start].chars().count() + 1;
+	let end = if span.is_empty() {
+		span.start + input[span.start..].chars().next().map_or(0, char::len_utf8)
+	} else {
+		span.end
+	};
+	FendError::AtColumn(Box::new(error), column, span.start..end)
+}
+
+/// `number_syntax` is only set for user input, so that built-in definitions
+/// are always parsed the same way
+pub(crate) fn parse<I: Interrupt>(
 	input: &str,
-	scope: Option<Arc<Scope>>,
-	attrs: Attrs,
-	context: &mut crate::Context,
+	implicit_multiplication: ImplicitMultiplication,
+	number_syntax: Option<lexer::NumberSyntax>,
 	int: &I,
-) -> FResult<Value> {
-	let lex = lexer::lex(input, int);
+) -> FResult<ast::Expr> {
+	let mut lex = lexer::lex(input, int).with_number_syntax(number_syntax.unwrap_or_default());
 	let mut tokens = vec![];
-	let mut missing_open_parens: i32 = 0;
-	for token in lex {
-		let token = token?;
+	let mut token_spans = vec![];
+	let mut missing_open_parens: usize = 0;
+	while let Some((token, span)) = lex.next_with_span() {
+		let token = match token {
+			Ok(token) => token,
+			Err(e @ (FendError::UnexpectedChar(_) | FendError::UnterminatedStringLiteral)) => {
+				return Err(at_column(e, input, span));
+			}
+			Err(e) => return Err(e),
+		};
 		if matches!(token, lexer::Token::Symbol(lexer::Symbol::CloseParens)) {
 			missing_open_parens += 1;
 		}
 		tokens.push(token);
+		token_spans.push(span);
 	}
 	for _ in 0..missing_open_parens {
 		tokens.insert(0, lexer::Token::Symbol(lexer::Symbol::OpenParens));
+		token_spans.insert(0, 0..0);
 	}
-	let parsed = parser::parse_tokens(&tokens)?;
+	parser::parse_tokens(&tokens, implicit_multiplication).map_err(|(e, token_idx)| match token_idx
+	{
+		Some(token_idx) => at_column(e.into(), input, token_spans[token_idx].clone()),
+		None => e.into(),
+	})
+}
+
+pub(crate) fn evaluate_to_value<I: Interrupt>(
+	input: &str,
+	scope: Option<Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Value> {
+	let parsed = parse(input, context.implicit_multiplication, None, int)?;
 	let result = ast::evaluate(parsed, scope, attrs, context, int)?;
 	Ok(result)
 }
@@ -49,7 +91,7 @@ impl Default for Attrs {
 	}
 }
 
-fn parse_attrs(mut input: &str) -> (Attrs, &str) {
+pub(crate) fn parse_attrs(mut input: &str) -> (Attrs, &str) {
 	let mut attrs = Attrs::default();
 	while input.starts_with('@') {
 		if let Some(remaining) = input.strip_prefix("@debug ") {
@@ -71,26 +113,108 @@ fn parse_attrs(mut input: &str) -> (Attrs, &str) {
 	(attrs, input)
 }
 
-/// This also saves the calculation result in a variable `_` and `ans`
+/// Conversions, assignments and multiple statements don't have a useful
+/// symbolic form
+fn has_symbolic_form(expr: &ast::Expr) -> bool {
+	match expr {
+		ast::Expr::Parens(inner) => has_symbolic_form(inner),
+		ast::Expr::As(..)
+		| ast::Expr::Assign(..)
+		| ast::Expr::DefineUnit(..)
+		| ast::Expr::DefineFormula(..)
+		| ast::Expr::SolveFormula(..)
+		| ast::Expr::Statements(..) => false,
+		_ => true,
+	}
+}
+
+fn parse_and_evaluate<I: Interrupt>(
+	input: &str,
+	scope: Option<Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<(bool, Value)> {
+	let parsed = parse(
+		input,
+		context.implicit_multiplication,
+		context.input_number_syntax(),
+		int,
+	)?;
+	let symbolic = has_symbolic_form(&parsed);
+	let value = ast::evaluate(parsed, scope, attrs, context, int)?;
+	Ok((symbolic, value))
+}
+
+/// If `save_result` is set, this also saves the calculation result in a
+/// variable `_` and `ans`
 pub(crate) fn evaluate_to_spans<I: Interrupt>(
 	input: &str,
 	scope: Option<Arc<Scope>>,
+	save_result: bool,
 	context: &mut crate::Context,
 	int: &I,
-) -> FResult<(Vec<Span>, bool, Attrs)> {
+) -> FResult<(Vec<Span>, Attrs, ResultInfo, Value)> {
+	let input_with_attrs = input;
 	let (attrs, input) = parse_attrs(input);
-	let value = evaluate_to_value(input, scope, attrs, context, int)?;
-	context.variables.insert("_".to_string(), value.clone());
-	context.variables.insert("ans".to_string(), value.clone());
-	Ok((
-		if attrs.debug {
-			vec![Span::from_string(format!("{value:?}"))]
-		} else {
-			let mut spans = vec![];
-			value.format(0, &mut spans, attrs, context, int)?;
-			spans
-		},
-		value.is_unit(),
-		attrs,
-	))
+	// attributes are ASCII, so their length in bytes is their length in columns
+	let attrs_len = input_with_attrs.len() - input.len();
+	context.conversion_steps.clear();
+	let (input, symbolic, value) =
+		match parse_and_evaluate(input, scope.clone(), attrs, context, int) {
+			Ok((symbolic, value)) => (input.to_string(), symbolic, value),
+			Err(FendError::Interrupted) => return Err(FendError::Interrupted),
+			Err(e) => {
+				// fall back to treating the input as a question like
+				// `how many seconds in a fortnight`
+				let e = match e {
+					FendError::AtColumn(e, column, span) => FendError::AtColumn(
+						e,
+						column + attrs_len,
+						span.start + attrs_len..span.end + attrs_len,
+					),
+					e => e,
+				};
+				let Some(rewritten) = natural_language::rewrite(input) else {
+					return Err(e);
+				};
+				context.conversion_steps.clear();
+				// once the input reads as a question, errors in the
+				// rewritten expression (e.g. `what is xyzzyq`) are more
+				// useful than the original parse error
+				let (symbolic, value) = parse_and_evaluate(&rewritten, scope, attrs, context, int)?;
+				(rewritten, symbolic, value)
+			}
+		};
+	if save_result {
+		context.variables.insert("_".to_string(), value.clone());
+		context.variables.insert("ans".to_string(), value.clone());
+	}
+	let spans = if attrs.debug {
+		vec![Span::from_string(format!("{value:?}"))]
+	} else if let (true, Value::Num(n)) = (context.exact_and_approximate, &value) {
+		let n = n.clone().simplify(attrs, context, int)?;
+		let mut formatted = n.format(context, int)?;
+		if !formatted.is_exact() {
+			let exact_form = n.exact_form(context, int)?;
+			if let Some(exact_form) =
+				exact_form.or_else(|| symbolic.then(|| input.trim().to_string()))
+			{
+				formatted.set_exact_form(exact_form);
+			}
+		}
+		let mut spans = vec![];
+		formatted.spans(&mut spans, attrs);
+		spans
+	} else {
+		let mut spans = vec![];
+		value.format(0, &mut spans, attrs, context, int)?;
+		spans
+	};
+	let plain: String = spans.iter().map(|span| span.string.as_str()).collect();
+	let mut info = value.result_info(attrs, context, int)?;
+	info.renderings = render::render(&value, &plain, attrs, context, int)?;
+	info.conversion_steps = std::mem::take(&mut context.conversion_steps);
+	render::add_json_steps(&mut info.renderings.json, &info.conversion_steps);
+	Ok((spans, attrs, info, value))
 }
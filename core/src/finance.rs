@@ -0,0 +1,133 @@
+use crate::error::{FendError, Interrupt};
+use crate::interrupt::test_int;
+use crate::num::Number;
+use crate::result::FResult;
+use crate::value::built_in_function::BuiltInFunction;
+use crate::value::Value;
+use crate::vector::Vector;
+
+// These functions only use exact arithmetic (with integer exponents), so
+// results like `compound(1000, 5%, 1, 10)` are calculated exactly.
+
+/// The value of `principal` after `years` years at the annual interest
+/// `rate`, compounded `n` times per year
+fn compound<I: Interrupt>(
+	principal: Number,
+	rate: Number,
+	n: Number,
+	years: Number,
+	int: &I,
+) -> FResult<Number> {
+	let growth = Number::from(1).add(rate.div(n.clone(), int)?, int)?;
+	principal.mul(growth.pow(n.mul(years, int)?, int)?, int)
+}
+
+/// The payment per period needed to pay off a loan of `pv` over `nper`
+/// periods at the interest `rate` per period
+fn pmt<I: Interrupt>(rate: Number, nper: Number, pv: Number, int: &I) -> FResult<Number> {
+	if rate.is_zero(int)? {
+		return pv.div(nper, int);
+	}
+	// pv * rate / (1 - (1 + rate)^-nper)
+	let discount = Number::from(1).add(rate.clone(), int)?.pow(-nper, int)?;
+	pv.mul(rate, int)?
+		.div(Number::from(1).sub(discount, int)?, int)
+}
+
+/// The net present value of cash flows at the end of each period, discounted
+/// at `rate` per period. As in spreadsheets, the first cash flow is
+/// discounted by one period.
+pub(crate) fn npv<I: Interrupt>(rate: Number, cashflows: Vector, int: &I) -> FResult<Number> {
+	let discount = Number::from(1).add(rate, int)?;
+	let mut factor = Number::from(1);
+	let mut result = Number::from(0);
+	for cashflow in cashflows.into_cartesian_components()? {
+		test_int(int)?;
+		factor = factor.mul(discount.clone(), int)?;
+		result = result.add(cashflow.div(factor.clone(), int)?, int)?;
+	}
+	Ok(result)
+}
+
+/// Loans can be amortized over at most this many periods, e.g. 100 years of
+/// monthly payments
+const MAX_AMORTIZATION_PERIODS: usize = 1200;
+
+/// Rounds an amount of money to hundredths, e.g. cents
+fn round_to_cents<I: Interrupt>(amount: Number, int: &I) -> FResult<Number> {
+	amount
+		.mul(Number::from(100), int)?
+		.round(int)?
+		.div(Number::from(100), int)
+}
+
+/// The amortization schedule of a loan of `pv` over `nper` periods at the
+/// interest `rate` per period. Like on a bank statement, amounts are rounded
+/// to cents and the last payment is adjusted so that the loan is paid off
+/// exactly. Each column is a list with one entry per period, so the result
+/// can be shown as a table.
+fn amortize<I: Interrupt>(rate: &Number, nper: Number, pv: Number, int: &I) -> FResult<Value> {
+	let periods = nper.clone().try_as_usize(int)?;
+	if periods > MAX_AMORTIZATION_PERIODS {
+		return Err(FendError::TooManyPeriods(MAX_AMORTIZATION_PERIODS));
+	}
+	// percentages are converted so that interest is rounded in the same unit
+	// as the loan
+	let rate = rate.clone().convert_to(Number::from(1), int)?;
+	let mut payment = round_to_cents(pmt(rate.clone(), nper, pv.clone(), int)?, int)?;
+	let mut balance = pv;
+	let mut columns: [Vec<Number>; 5] = Default::default();
+	for period in 1..=periods {
+		test_int(int)?;
+		let interest = round_to_cents(balance.clone().mul(rate.clone(), int)?, int)?;
+		if period == periods {
+			payment = balance.clone().add(interest.clone(), int)?;
+		}
+		let principal = payment.clone().sub(interest.clone(), int)?;
+		balance = balance.sub(principal.clone(), int)?;
+		let row = [
+			Number::from(period as u64),
+			payment.clone(),
+			interest,
+			principal,
+			balance.clone(),
+		];
+		for (column, value) in columns.iter_mut().zip(row) {
+			column.push(value);
+		}
+	}
+	let names = ["period", "payment", "interest", "principal", "balance"];
+	Ok(Value::Object(
+		names
+			.into_iter()
+			.zip(columns)
+			.map(|(name, column)| (name.into(), Box::new(Value::Vector(Vector::new(column)))))
+			.collect(),
+	))
+}
+
+/// Evaluates a financial function. Functions with several parameters receive
+/// them as a vector.
+pub(crate) fn evaluate<I: Interrupt>(
+	func: BuiltInFunction,
+	arg: Value,
+	int: &I,
+) -> FResult<Number> {
+	match func {
+		BuiltInFunction::Compound => {
+			let [principal, rate, n, years] = arg.expect_vector()?.into_array()?;
+			compound(principal, rate, n, years, int)
+		}
+		BuiltInFunction::Pmt => {
+			let [rate, nper, pv] = arg.expect_vector()?.into_array()?;
+			pmt(rate, nper, pv, int)
+		}
+		_ => Err(FendError::ExpectedANumber),
+	}
+}
+
+/// Evaluates `amortize(rate, nper, pv)`
+pub(crate) fn evaluate_schedule<I: Interrupt>(arg: Value, int: &I) -> FResult<Value> {
+	let [rate, nper, pv] = arg.expect_vector()?.into_array()?;
+	amortize(&rate, nper, pv, int)
+}
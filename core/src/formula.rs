@@ -0,0 +1,517 @@
+use std::{io, sync::Arc};
+
+use crate::ast::{Bop, Expr};
+use crate::error::{FendError, Interrupt};
+use crate::eval::{evaluate_to_value, parse};
+use crate::ident::Ident;
+use crate::num::Number;
+use crate::result::FResult;
+use crate::scope::Scope;
+use crate::serialize::{Deserialize, Serialize};
+use crate::value::Value;
+use crate::Attrs;
+
+/// A formula's name and equation, together with the unit of each variable
+type BuiltinFormula = (
+	&'static str,
+	&'static str,
+	&'static [(&'static str, &'static str)],
+);
+
+const BUILTIN_FORMULAS: &[BuiltinFormula] = &[
+	(
+		"ohms_law",
+		"V = I R",
+		&[("V", "volt"), ("I", "ampere"), ("R", "ohm")],
+	),
+	(
+		"electrical_power",
+		"P = V I",
+		&[("P", "watt"), ("V", "volt"), ("I", "ampere")],
+	),
+	(
+		"speed",
+		"v = d / t",
+		&[("v", "m/s"), ("d", "meter"), ("t", "second")],
+	),
+	(
+		"newtons_second_law",
+		"F = m a",
+		&[("F", "newton"), ("m", "kg"), ("a", "m/s^2")],
+	),
+	(
+		"kinetic_energy",
+		"E = 1/2 m v^2",
+		&[("E", "joule"), ("m", "kg"), ("v", "m/s")],
+	),
+	(
+		"density",
+		"rho = m / V",
+		&[("rho", "kg/m^3"), ("m", "kg"), ("V", "m^3")],
+	),
+	(
+		"pressure",
+		"p = F / A",
+		&[("p", "pascal"), ("F", "newton"), ("A", "m^2")],
+	),
+	(
+		"ideal_gas_law",
+		"p V = n N_A boltzmann T",
+		&[("p", "pascal"), ("V", "m^3"), ("n", "mol"), ("T", "kelvin")],
+	),
+	(
+		"wave_speed",
+		"v = f lambda",
+		&[("v", "m/s"), ("f", "hertz"), ("lambda", "meter")],
+	),
+];
+
+/// A formula defined with e.g.
+/// `formula ohms_law: V = I R where V in volts, I in amperes, R in ohms`
+#[derive(Clone, Debug)]
+pub(crate) struct Formula {
+	name: String,
+	equation: String,
+	/// The unit of each variable. This is empty if no units were given,
+	/// in which case any identifier can be solved for.
+	units: Vec<(String, String)>,
+}
+
+impl Formula {
+	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
+		self.name.as_str().serialize(write)?;
+		self.equation.as_str().serialize(write)?;
+		self.units.len().serialize(write)?;
+		for (variable, unit) in &self.units {
+			variable.as_str().serialize(write)?;
+			unit.as_str().serialize(write)?;
+		}
+		Ok(())
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		let name = String::deserialize(read)?;
+		let equation = String::deserialize(read)?;
+		let len = usize::deserialize(read)?;
+		let mut units = Vec::with_capacity(len);
+		for _ in 0..len {
+			units.push((String::deserialize(read)?, String::deserialize(read)?));
+		}
+		Ok(Self {
+			name,
+			equation,
+			units,
+		})
+	}
+}
+
+/// Splits an equation like `V = I R` into its two sides
+fn split_equation(equation: &str) -> FResult<(&str, &str)> {
+	match equation.split_once('=') {
+		Some((lhs, rhs)) if !rhs.contains('=') && !lhs.trim().is_empty() => {
+			Ok((lhs.trim(), rhs.trim()))
+		}
+		_ => Err(FendError::ExpectedAnEquation),
+	}
+}
+
+/// Defines a formula like
+/// `formula ohms_law: V = I R where V in volts, I in amperes, R in ohms`
+pub(crate) fn define_formula<I: Interrupt>(
+	name: &Ident,
+	equation: &Expr,
+	units: Vec<(Ident, Expr)>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<()> {
+	let mut formula = Formula {
+		name: name.to_string(),
+		equation: equation.format(attrs, context, int)?,
+		units: vec![],
+	};
+	for (variable, unit) in units {
+		// make sure that the unit exists
+		crate::ast::evaluate(unit.clone(), None, attrs, context, int)?.expect_num()?;
+		formula
+			.units
+			.push((variable.to_string(), unit.format(attrs, context, int)?));
+	}
+	context.formulas.retain(|f| f.name != formula.name);
+	context.formulas.push(formula);
+	Ok(())
+}
+
+fn get_formula(name: &str, context: &crate::Context) -> FResult<Formula> {
+	if let Some(formula) = context.formulas.iter().find(|f| f.name == name) {
+		return Ok(formula.clone());
+	}
+	for (builtin_name, equation, units) in BUILTIN_FORMULAS {
+		if *builtin_name == name {
+			return Ok(Formula {
+				name: name.to_string(),
+				equation: (*equation).to_string(),
+				units: units
+					.iter()
+					.map(|(var, unit)| ((*var).to_string(), (*unit).to_string()))
+					.collect(),
+			});
+		}
+	}
+	Err(FendError::UnknownFormula(name.to_string()))
+}
+
+fn contains(expr: &Expr, var: &str) -> bool {
+	match expr {
+		Expr::Literal(_) => false,
+		Expr::Ident(ident) => ident.as_str() == var,
+		Expr::Parens(x)
+		| Expr::UnaryMinus(x)
+		| Expr::UnaryPlus(x)
+		| Expr::UnaryDiv(x)
+		| Expr::Factorial(x)
+		| Expr::Of(_, x)
+		| Expr::Assign(_, x)
+		| Expr::DefineUnit(_, x) => contains(x, var),
+		Expr::Fn(arg, body) => arg.as_str() != var && contains(body, var),
+		Expr::Bop(_, a, b)
+		| Expr::Apply(a, b)
+		| Expr::ApplyFunctionCall(a, b)
+		| Expr::ApplyMul(a, b)
+		| Expr::As(a, b)
+		| Expr::Equality(_, a, b)
+		| Expr::ApproxEquality(a, b)
+		| Expr::Equation(a, b)
+		| Expr::Statements(a, b) => contains(a, var) || contains(b, var),
+		Expr::DefineFormula(_, x, list) | Expr::SolveFormula(x, _, list) => {
+			contains(x, var) || list.iter().any(|(_, x)| contains(x, var))
+		}
+		Expr::Vector(components) => components.iter().any(|c| contains(c, var)),
+	}
+}
+
+fn bop(op: Bop, a: Expr, b: Expr) -> Expr {
+	Expr::Bop(op, Box::new(a), Box::new(b))
+}
+
+fn call(function: &str, arg: Expr) -> Expr {
+	Expr::ApplyFunctionCall(
+		Box::new(Expr::Ident(Ident::new_string(function.to_string()))),
+		Box::new(arg),
+	)
+}
+
+fn number(n: u64) -> Expr {
+	Expr::Literal(Value::Num(Box::new(n.into())))
+}
+
+/// Returns the inverse of `function` applied to `value`, e.g. `value^2`
+/// for `sqrt`
+fn invert_function(function: &str, value: Expr) -> Option<Expr> {
+	Some(match function {
+		"sqrt" => bop(Bop::Pow, value, number(2)),
+		"cbrt" => bop(Bop::Pow, value, number(3)),
+		"ln" => call("exp", value),
+		"exp" => call("ln", value),
+		"log" | "log10" => bop(Bop::Pow, number(10), value),
+		"log2" => bop(Bop::Pow, number(2), value),
+		"sin" | "cos" | "tan" => call(&format!("a{function}"), value),
+		"asin" | "acos" | "atan" => call(&function[1..], value),
+		_ => return None,
+	})
+}
+
+/// Rearranges `expr = other` so that `var` is on its own, and returns the
+/// expression that `var` is equal to
+fn isolate(expr: Expr, other: Expr, var: &str) -> FResult<Expr> {
+	let cannot_solve = || FendError::CannotSolveFor(var.to_string());
+	match expr {
+		Expr::Ident(ident) if ident.as_str() == var => Ok(other),
+		Expr::Parens(x) | Expr::UnaryPlus(x) => isolate(*x, other, var),
+		Expr::UnaryMinus(x) => isolate(*x, Expr::UnaryMinus(Box::new(other)), var),
+		Expr::UnaryDiv(x) => isolate(*x, Expr::UnaryDiv(Box::new(other)), var),
+		Expr::Bop(op, a, b) => {
+			let (in_a, in_b) = (contains(&a, var), contains(&b, var));
+			if in_a && in_b {
+				return Err(cannot_solve());
+			}
+			match (op, in_a) {
+				(Bop::Plus | Bop::ImplicitPlus, true) => {
+					isolate(*a, bop(Bop::Minus, other, *b), var)
+				}
+				(Bop::Plus | Bop::ImplicitPlus, false) => {
+					isolate(*b, bop(Bop::Minus, other, *a), var)
+				}
+				(Bop::Minus, true) => isolate(*a, bop(Bop::Plus, other, *b), var),
+				(Bop::Minus, false) => isolate(*b, bop(Bop::Minus, *a, other), var),
+				(Bop::Mul, true) => isolate(*a, bop(Bop::Div, other, *b), var),
+				(Bop::Mul, false) => isolate(*b, bop(Bop::Div, other, *a), var),
+				(Bop::Div, true) => isolate(*a, bop(Bop::Mul, other, *b), var),
+				(Bop::Div, false) => isolate(*b, bop(Bop::Div, *a, other), var),
+				(Bop::Pow, true) => {
+					let exponent = bop(Bop::Div, number(1), *b);
+					isolate(*a, bop(Bop::Pow, other, exponent), var)
+				}
+				(Bop::Pow, false) => {
+					let exponent = bop(Bop::Div, call("ln", other), call("ln", *a));
+					isolate(*b, exponent, var)
+				}
+				_ => Err(cannot_solve()),
+			}
+		}
+		Expr::Apply(a, b) | Expr::ApplyFunctionCall(a, b) | Expr::ApplyMul(a, b) => {
+			let (in_a, in_b) = (contains(&a, var), contains(&b, var));
+			if in_a && in_b {
+				return Err(cannot_solve());
+			}
+			if in_b {
+				if let Expr::Ident(function) = &*a {
+					if let Some(inverse) = invert_function(function.as_str(), other.clone()) {
+						return isolate(*b, inverse, var);
+					}
+				}
+				isolate(*b, bop(Bop::Div, other, *a), var)
+			} else {
+				isolate(*a, bop(Bop::Div, other, *b), var)
+			}
+		}
+		_ => Err(cannot_solve()),
+	}
+}
+
+/// Parses both sides of an equation like `V = I R`
+fn parse_equation<I: Interrupt>(
+	equation: &str,
+	context: &crate::Context,
+	int: &I,
+) -> FResult<(Expr, Expr)> {
+	let (lhs, rhs) = split_equation(equation)?;
+	let number_syntax = context.input_number_syntax();
+	Ok((
+		parse(lhs, context.implicit_multiplication, number_syntax, int)?,
+		parse(rhs, context.implicit_multiplication, number_syntax, int)?,
+	))
+}
+
+/// Returns the value of `var` given both sides of an equation like `V = I R`
+fn solve_equation<I: Interrupt>(
+	lhs: Expr,
+	rhs: Expr,
+	var: &str,
+	scope: Option<Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Value> {
+	let solution = match (contains(&lhs, var), contains(&rhs, var)) {
+		(true, false) => isolate(lhs, rhs, var)?,
+		(false, true) => isolate(rhs, lhs, var)?,
+		_ => return Err(FendError::CannotSolveFor(var.to_string())),
+	};
+	crate::ast::evaluate(solution, scope, attrs, context, int)
+}
+
+/// Solves a polynomial equation of degree at most 2 exactly, given its
+/// coefficients starting with the constant term. Complex roots are included,
+/// and irrational roots are kept exact, e.g. `1 + sqrt(2)`.
+fn polynomial_roots<I: Interrupt>(
+	coefficients: Vec<Option<Number>>,
+	int: &I,
+) -> FResult<Vec<Value>> {
+	let mut coefficients = coefficients.into_iter();
+	let constant = coefficients.next().flatten();
+	let linear = coefficients.next().flatten();
+	let Some(quadratic) = coefficients.next().flatten() else {
+		return match (linear, constant) {
+			(Some(linear), Some(constant)) => {
+				Ok(vec![Value::Num(Box::new((-constant).div(linear, int)?))])
+			}
+			(Some(linear), None) => Ok(vec![Value::Num(Box::new(
+				Number::from(0).div(linear, int)?,
+			))]),
+			(None, Some(_)) => Err(FendError::NoSolution),
+			(None, None) => Err(FendError::InfinitelyManySolutions),
+		};
+	};
+	// `x^2 + 2hx + q = 0` has the roots `-h ± sqrt(h^2 - q)`
+	let h = linear
+		.map(|linear| linear.div(quadratic.clone().mul(Number::from(2), int)?, int))
+		.transpose()?;
+	let q = constant
+		.map(|constant| constant.div(quadratic, int))
+		.transpose()?;
+	let discriminant = match (h.clone(), q) {
+		(Some(h), Some(q)) => h.clone().mul(h, int)?.sub(q, int)?,
+		(Some(h), None) => h.clone().mul(h, int)?,
+		(None, Some(q)) => -q,
+		(None, None) => Number::from(0),
+	};
+	let centre = h.map_or_else(|| Number::from(0), |h| -h);
+	if discriminant.is_zero(int)? {
+		return Ok(vec![Value::Num(Box::new(centre))]);
+	}
+	// square roots of negative numbers are calculated as `i sqrt(-d)` so
+	// that they stay exact
+	let negative = discriminant.real_sign() == Some(std::cmp::Ordering::Less);
+	let discriminant = if negative {
+		-discriminant
+	} else {
+		discriminant
+	};
+	let mut offset = if let Some(offset) = crate::symbolic::exact_sqrt(&discriminant, int)? {
+		offset
+	} else {
+		let half = Number::from(1).div(Number::from(2), int)?;
+		Value::Num(Box::new(discriminant.pow(half, int)?))
+	};
+	if negative {
+		offset = value_bop(Bop::Mul, offset, Value::Num(Box::new(Number::i())), int)?;
+	}
+	let negated = crate::symbolic::negate(offset.clone(), int)?;
+	if centre.is_zero(int)? {
+		return Ok(vec![negated, offset]);
+	}
+	let centre = Value::Num(Box::new(centre));
+	Ok(vec![
+		value_bop(Bop::Plus, centre.clone(), negated, int)?,
+		value_bop(Bop::Plus, centre, offset, int)?,
+	])
+}
+
+/// Adds or multiplies roots, which are numbers or symbolic expressions
+fn value_bop<I: Interrupt>(op: Bop, a: Value, b: Value, int: &I) -> FResult<Value> {
+	match (a, b) {
+		(Value::Num(a), Value::Num(b)) => Ok(Value::Num(Box::new(match op {
+			Bop::Plus => a.add(*b, int)?,
+			_ => a.mul(*b, int)?,
+		}))),
+		(a, b) => crate::symbolic::evaluate_bop(op, a, b, None, int),
+	}
+}
+
+/// Solves e.g. `solve(x^2 - 5x + 6 = 0, x)` given both sides of the
+/// equation, returning every root of linear and quadratic equations. Other
+/// equations are rearranged like in `solve x^3 = 8 for x`.
+pub(crate) fn solve_for_roots<I: Interrupt>(
+	lhs: Expr,
+	rhs: Expr,
+	var: &str,
+	scope: Option<Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Value> {
+	let difference = bop(
+		Bop::Minus,
+		Expr::Parens(Box::new(lhs.clone())),
+		Expr::Parens(Box::new(rhs.clone())),
+	);
+	let symbolic_scope = Some(Arc::new(Scope::with_variable(
+		Ident::new_string(var.to_string()),
+		Expr::Literal(crate::symbolic::variable(var)),
+		None,
+		scope.clone(),
+	)));
+	let difference = crate::symbolic::with_variables(context, |context| {
+		crate::ast::evaluate(difference, symbolic_scope, attrs, context, int)
+	});
+	let coefficients = match difference {
+		Ok(value) => crate::symbolic::polynomial_coefficients(value, var, int)?,
+		Err(FendError::Interrupted) => return Err(FendError::Interrupted),
+		Err(_) => None,
+	};
+	match coefficients {
+		Some(coefficients) if coefficients.len() <= 3 => {
+			let mut roots = polynomial_roots(coefficients, int)?;
+			if roots.len() == 1 {
+				return Ok(roots.remove(0));
+			}
+			if roots.iter().all(|root| matches!(root, Value::Num(_))) {
+				let roots = roots
+					.into_iter()
+					.map(Value::expect_num)
+					.collect::<FResult<_>>()?;
+				return Ok(Value::Vector(crate::vector::Vector::new(roots)));
+			}
+			Ok(Value::List(roots))
+		}
+		_ => solve_equation(lhs, rhs, var, scope, attrs, context, int),
+	}
+}
+
+/// Solves e.g. `solve ohms_law for R given V = 12 V, I = 0.5 A`, where
+/// `formula` is either the name of a formula or an equation like
+/// `y = 2x + 3`
+pub(crate) fn solve<I: Interrupt>(
+	formula: Expr,
+	var: &Ident,
+	givens: Vec<(Ident, Expr)>,
+	scope: Option<Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Value> {
+	let var = var.as_str();
+	let (formula, lhs, rhs) = match formula {
+		Expr::Ident(name) => {
+			let formula = get_formula(name.as_str(), context)?;
+			let (lhs, rhs) = parse_equation(&formula.equation, context, int)?;
+			(formula, lhs, rhs)
+		}
+		Expr::Equation(lhs, rhs) => {
+			let equation = Formula {
+				name: String::new(),
+				equation: String::new(),
+				units: vec![],
+			};
+			(equation, *lhs, *rhs)
+		}
+		_ => return Err(FendError::ExpectedAnEquation),
+	};
+	let unit_of = |name: &str| {
+		formula
+			.units
+			.iter()
+			.find(|(var, _)| var == name)
+			.map(|(_, unit)| unit.clone())
+	};
+	if !formula.units.is_empty() && unit_of(var).is_none() {
+		return Err(FendError::NotAVariableInFormula(
+			var.to_string(),
+			formula.name.clone(),
+		));
+	}
+	let mut solve_scope = scope;
+	let mut given_names = vec![];
+	for (name, value) in givens {
+		let mut value = crate::ast::evaluate(value, solve_scope.clone(), attrs, context, int)?;
+		if !formula.units.is_empty() {
+			let unit = unit_of(name.as_str()).ok_or_else(|| {
+				FendError::NotAVariableInFormula(name.to_string(), formula.name.clone())
+			})?;
+			// check that the value has the right dimensions
+			let unit = evaluate_to_value(&unit, None, attrs, context, int)?.expect_num()?;
+			value = Value::Num(Box::new(value.expect_num()?.convert_to(unit, int)?));
+		}
+		given_names.push(name.to_string());
+		solve_scope = Some(Arc::new(Scope::with_variable(
+			name,
+			Expr::Literal(value),
+			None,
+			solve_scope,
+		)));
+	}
+	for (name, _) in &formula.units {
+		if name != var && !given_names.contains(name) {
+			return Err(FendError::MissingFormulaValue(name.clone()));
+		}
+	}
+	let result = solve_equation(lhs, rhs, var, solve_scope, attrs, context, int)?;
+	if let Some(unit) = unit_of(var) {
+		let unit = evaluate_to_value(&unit, None, attrs, context, int)?.expect_num()?;
+		return Ok(Value::Num(Box::new(
+			result.expect_num()?.convert_to(unit, int)?,
+		)));
+	}
+	Ok(result)
+}
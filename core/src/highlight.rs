@@ -0,0 +1,92 @@
+use std::ops::Range;
+
+use crate::completion::{FUNCTIONS, KEYWORDS};
+use crate::interrupt::Never;
+use crate::lexer::{self, Token};
+use crate::{eval, units, SpanKind};
+
+/// A part of the input string together with its semantic kind. The spans
+/// returned by [`highlight`] cover the entire input, in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HighlightSpan {
+	range: Range<usize>,
+	kind: SpanKind,
+}
+
+impl HighlightSpan {
+	/// Returns the byte range of this span in the input string.
+	#[must_use]
+	pub fn range(&self) -> Range<usize> {
+		self.range.clone()
+	}
+
+	#[must_use]
+	pub fn kind(&self) -> SpanKind {
+		self.kind
+	}
+}
+
+fn classify_ident(ident: &str) -> SpanKind {
+	if ident == "true" || ident == "false" {
+		SpanKind::Boolean
+	} else if FUNCTIONS.contains(&ident) {
+		SpanKind::BuiltInFunction
+	} else if KEYWORDS.contains(&ident) {
+		SpanKind::Keyword
+	} else if ident.starts_with(['\'', '"']) || units::is_builtin_unit(ident) {
+		SpanKind::Unit
+	} else {
+		SpanKind::Ident
+	}
+}
+
+fn classify_token(token: &Token, text: &str) -> SpanKind {
+	match token {
+		Token::Num(_) => SpanKind::Number,
+		Token::Ident(ident) => classify_ident(ident.as_str()),
+		Token::StringLiteral(_) => SpanKind::String,
+		Token::Date(_) | Token::TimeOfDay(_) | Token::TimeZone(_) | Token::ZonedDateTime(_) => {
+			SpanKind::Date
+		}
+		// symbols like `to`, `of` or `mod` are keywords
+		Token::Symbol(_) if text.starts_with(char::is_alphabetic) => SpanKind::Keyword,
+		Token::Symbol(_) => SpanKind::Operator,
+	}
+}
+
+/// Splits the input into spans for syntax highlighting, without evaluating
+/// it. Identifiers are classified based on built-in functions, keywords and
+/// units only, so user-defined variables are shown as
+/// [`SpanKind::Ident`]. If the input cannot be tokenised, the remaining
+/// input is returned as a single [`SpanKind::Error`] span.
+#[must_use]
+pub fn highlight(input: &str) -> Vec<HighlightSpan> {
+	let mut result = vec![];
+	let mut push = |range: Range<usize>, kind| {
+		if !range.is_empty() {
+			result.push(HighlightSpan { range, kind });
+		}
+	};
+
+	// attributes like `@debug` at the start of the input
+	let (_, remaining) = eval::parse_attrs(input);
+	let offset = input.len() - remaining.len();
+	push(0..offset, SpanKind::Keyword);
+
+	let mut prev_end = offset;
+	let mut lexer = lexer::lex(remaining, &Never);
+	while let Some((token, range)) = lexer.next_with_span() {
+		let range = range.start + offset..range.end + offset;
+		push(prev_end..range.start, SpanKind::Whitespace);
+		let Ok(token) = token else {
+			prev_end = input.len();
+			push(range.start..input.len(), SpanKind::Error);
+			break;
+		};
+		let kind = classify_token(&token, &input[range.clone()]);
+		prev_end = range.end;
+		push(range, kind);
+	}
+	push(prev_end..input.len(), SpanKind::Whitespace);
+	result
+}
@@ -23,8 +23,9 @@ impl Ident {
 
 	pub(crate) fn is_prefix_unit(&self) -> bool {
 		// when changing this also make sure to change number output formatting
-		// lexer identifier splitting
-		["$", "\u{a3}", "\u{a5}"].contains(&&*self.0)
+		// lexer identifier splitting. Euros can be written as `€5`, but are
+		// shown as `5€`.
+		["$", "\u{a3}", "\u{a5}", "\u{20ac}"].contains(&&*self.0)
 	}
 
 	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
@@ -0,0 +1,280 @@
+use crate::ast::{Bop, Expr};
+use crate::error::{FendError, Interrupt};
+use crate::ident::Ident;
+use crate::num::Number;
+use crate::result::FResult;
+use crate::scope::Scope;
+use crate::value::Value;
+use crate::vector::Vector;
+use crate::{Attrs, Span};
+use std::sync::Arc;
+use std::{cmp, io};
+
+/// A closed range of numbers like `10..12`, or a measurement with some
+/// uncertainty like `5 ± 0.1` (which is stored as `4.9..5.1`). Arithmetic on
+/// intervals returns the range of every possible result.
+#[derive(Clone, Debug)]
+pub(crate) struct Interval {
+	lo: Number,
+	hi: Number,
+}
+
+fn compare<I: Interrupt>(a: &Number, b: &Number, int: &I) -> FResult<cmp::Ordering> {
+	a.compare(b, int)?
+		.ok_or(FendError::InvalidOperandsForIntervalArithmetic)
+}
+
+impl Interval {
+	/// Creates the interval between `a` and `b`, in either order
+	fn new<I: Interrupt>(a: Number, b: Number, int: &I) -> FResult<Self> {
+		Ok(match compare(&a, &b, int)? {
+			cmp::Ordering::Greater => Self { lo: b, hi: a },
+			_ => Self { lo: a, hi: b },
+		})
+	}
+
+	/// Returns the smallest interval containing all of the given numbers
+	fn containing<I: Interrupt>(numbers: Vec<Number>, int: &I) -> FResult<Self> {
+		let mut numbers = numbers.into_iter();
+		let first = numbers.next().ok_or(FendError::ExpectedANumber)?;
+		let mut result = Self {
+			lo: first.clone(),
+			hi: first,
+		};
+		for n in numbers {
+			if compare(&n, &result.lo, int)? == cmp::Ordering::Less {
+				result.lo = n;
+			} else if compare(&n, &result.hi, int)? == cmp::Ordering::Greater {
+				result.hi = n;
+			}
+		}
+		Ok(result)
+	}
+
+	fn from_number(n: Number) -> Self {
+		Self {
+			lo: n.clone(),
+			hi: n,
+		}
+	}
+
+	/// Whether zero lies inside this interval (including its endpoints).
+	/// Units are ignored, so e.g. `-1 m..1 m` contains zero.
+	fn contains_zero(&self) -> FResult<bool> {
+		let (Some(lo), Some(hi)) = (self.lo.real_sign(), self.hi.real_sign()) else {
+			return Err(FendError::InvalidOperandsForIntervalArithmetic);
+		};
+		Ok(lo != cmp::Ordering::Greater && hi != cmp::Ordering::Less)
+	}
+
+	/// Intervals with equal endpoints are simplified to a single number
+	fn into_value<I: Interrupt>(self, int: &I) -> FResult<Value> {
+		Ok(
+			if compare(&self.lo, &self.hi, int)? == cmp::Ordering::Equal {
+				Value::Num(Box::new(self.lo))
+			} else {
+				Value::Interval(Box::new(self))
+			},
+		)
+	}
+
+	/// Applies an increasing function like a unit conversion to both
+	/// endpoints
+	pub(crate) fn map<I: Interrupt>(
+		self,
+		mut f: impl FnMut(Number) -> FResult<Number>,
+		int: &I,
+	) -> FResult<Self> {
+		Self::new(f(self.lo)?, f(self.hi)?, int)
+	}
+
+	fn add<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		Self::new(self.lo.add(rhs.lo, int)?, self.hi.add(rhs.hi, int)?, int)
+	}
+
+	fn sub<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		Self::new(self.lo.sub(rhs.hi, int)?, self.hi.sub(rhs.lo, int)?, int)
+	}
+
+	fn mul<I: Interrupt>(self, rhs: &Self, int: &I) -> FResult<Self> {
+		Self::containing(
+			vec![
+				self.lo.clone().mul(rhs.lo.clone(), int)?,
+				self.lo.mul(rhs.hi.clone(), int)?,
+				self.hi.clone().mul(rhs.lo.clone(), int)?,
+				self.hi.mul(rhs.hi.clone(), int)?,
+			],
+			int,
+		)
+	}
+
+	fn div<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		if rhs.contains_zero()? {
+			return Err(FendError::DivideByIntervalContainingZero);
+		}
+		let reciprocal = Self::new(
+			Number::from(1).div(rhs.hi, int)?,
+			Number::from(1).div(rhs.lo, int)?,
+			int,
+		)?;
+		self.mul(&reciprocal, int)
+	}
+
+	fn neg(self) -> Self {
+		Self {
+			lo: -self.hi,
+			hi: -self.lo,
+		}
+	}
+
+	fn pow<I: Interrupt>(self, exponent: Number, int: &I) -> FResult<Self> {
+		if !self.contains_zero()? {
+			// powers are monotonic on either side of zero
+			return Self::new(
+				self.lo.pow(exponent.clone(), int)?,
+				self.hi.pow(exponent, int)?,
+				int,
+			);
+		}
+		if exponent.real_sign() == Some(cmp::Ordering::Less) {
+			return Err(FendError::DivideByIntervalContainingZero);
+		}
+		let integer_exponent = match exponent.clone().try_as_i64(int) {
+			Ok(n) => Some(n),
+			Err(FendError::Interrupted) => return Err(FendError::Interrupted),
+			Err(_) => None,
+		};
+		let lo = self.lo.pow(exponent.clone(), int)?;
+		let hi = self.hi.pow(exponent, int)?;
+		if integer_exponent.is_some_and(|n| n % 2 == 0) {
+			// e.g. `(-1..2)^2` is `0..4`, because `0^2` is the minimum
+			let zero = lo.clone().mul(Number::from(0), int)?;
+			Self::containing(vec![zero, lo, hi], int)
+		} else {
+			Self::new(lo, hi, int)
+		}
+	}
+
+	pub(crate) fn compare<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
+		Ok(
+			self.lo.compare(&other.lo, int)? == Some(cmp::Ordering::Equal)
+				&& self.hi.compare(&other.hi, int)? == Some(cmp::Ordering::Equal),
+		)
+	}
+
+	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
+		self.lo.serialize(write)?;
+		self.hi.serialize(write)?;
+		Ok(())
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		Ok(Self {
+			lo: Number::deserialize(read)?,
+			hi: Number::deserialize(read)?,
+		})
+	}
+
+	pub(crate) fn format<I: Interrupt>(
+		&self,
+		spans: &mut Vec<Span>,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<()> {
+		self.lo
+			.clone()
+			.simplify(attrs, ctx, int)?
+			.format(ctx, int)?
+			.spans(spans, attrs);
+		spans.push(Span::from_string("..".to_string()));
+		self.hi
+			.clone()
+			.simplify(attrs, ctx, int)?
+			.format(ctx, int)?
+			.spans(spans, attrs);
+		Ok(())
+	}
+
+	/// Lists the integers in a range like `1..10`, so that it can be used
+	/// with functions like `sum` and `map`
+	pub(crate) fn into_list<I: Interrupt>(self, int: &I) -> FResult<Vector> {
+		const MAX_LEN: usize = 1_000_000;
+		let as_integer = |n: Number| match n.try_as_i64(int) {
+			Err(FendError::Interrupted) => Err(FendError::Interrupted),
+			Err(_) => Err(FendError::ExpectedAnIntegerRange),
+			Ok(n) => Ok(n),
+		};
+		let (lo, hi) = (as_integer(self.lo)?, as_integer(self.hi)?);
+		if usize::try_from(hi.abs_diff(lo)).map_or(true, |len| len >= MAX_LEN) {
+			return Err(FendError::RangeTooLarge(MAX_LEN));
+		}
+		let mut components = vec![];
+		for i in lo..=hi {
+			crate::interrupt::test_int(int)?;
+			let n = Number::from(i.unsigned_abs());
+			components.push(if i < 0 { -n } else { n });
+		}
+		Ok(Vector::new(components))
+	}
+
+	pub(crate) fn get_object_member<I: Interrupt>(self, key: &Ident, int: &I) -> FResult<Value> {
+		let n = match key.as_str() {
+			"min" => self.lo,
+			"max" => self.hi,
+			"mid" => self.lo.add(self.hi, int)?.div(Number::from(2), int)?,
+			_ => return Err(FendError::CouldNotFindKey(key.to_string())),
+		};
+		Ok(Value::Num(Box::new(n)))
+	}
+}
+
+fn expect_interval(value: Value) -> FResult<Interval> {
+	match value {
+		Value::Num(n) => Ok(Interval::from_number(*n)),
+		Value::Interval(i) => Ok(*i),
+		_ => Err(FendError::InvalidOperandsForIntervalArithmetic),
+	}
+}
+
+/// Evaluates `a ± b` or `a..b`
+pub(crate) fn create<I: Interrupt>(bop: Bop, lhs: Value, rhs: Value, int: &I) -> FResult<Value> {
+	let (a, b) = (lhs.expect_num()?, rhs.expect_num()?);
+	let interval = if matches!(bop, Bop::PlusMinus) {
+		Interval::new(a.clone().sub(b.clone(), int)?, a.add(b, int)?, int)?
+	} else {
+		Interval::new(a, b, int)?
+	};
+	interval.into_value(int)
+}
+
+pub(crate) fn negate<I: Interrupt>(value: Value, int: &I) -> FResult<Value> {
+	expect_interval(value)?.neg().into_value(int)
+}
+
+pub(crate) fn evaluate_bop<I: Interrupt>(
+	bop: Bop,
+	lhs: Value,
+	rhs: Value,
+	scope: Option<Arc<Scope>>,
+	int: &I,
+) -> FResult<Value> {
+	let result = match (bop, lhs, rhs) {
+		// e.g. `(1..2) * sqrt 2` is parsed as `((1..2) * sqrt) 2`
+		(bop, f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _)), v) => {
+			return f.wrap_with_expr(|f| Expr::Bop(bop, f, Box::new(Expr::Literal(v))), scope);
+		}
+		(bop, v, f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _))) => {
+			return f.wrap_with_expr(|f| Expr::Bop(bop, Box::new(Expr::Literal(v)), f), scope);
+		}
+		(Bop::Pow, a, Value::Num(b)) => expect_interval(a)?.pow(*b, int)?,
+		(Bop::Plus | Bop::ImplicitPlus, a, b) => {
+			expect_interval(a)?.add(expect_interval(b)?, int)?
+		}
+		(Bop::Minus, a, b) => expect_interval(a)?.sub(expect_interval(b)?, int)?,
+		(Bop::Mul, a, b) => expect_interval(a)?.mul(&expect_interval(b)?, int)?,
+		(Bop::Div, a, b) => expect_interval(a)?.div(expect_interval(b)?, int)?,
+		_ => return Err(FendError::InvalidOperandsForIntervalArithmetic),
+	};
+	result.into_value(int)
+}
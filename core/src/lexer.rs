@@ -1,9 +1,9 @@
-use crate::date::Date;
+use crate::date::{Date, TimeOfDay, TimeZone, ZonedDateTime};
 use crate::error::{FendError, Interrupt};
 use crate::ident::Ident;
 use crate::num::{Base, Number};
 use crate::result::FResult;
-use std::{borrow, convert, fmt};
+use std::{borrow, convert, fmt, ops};
 
 #[derive(Clone, Debug)]
 pub(crate) enum Token {
@@ -12,6 +12,9 @@ pub(crate) enum Token {
 	Symbol(Symbol),
 	StringLiteral(borrow::Cow<'static, str>),
 	Date(Date),
+	TimeOfDay(TimeOfDay),
+	TimeZone(TimeZone),
+	ZonedDateTime(ZonedDateTime),
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -22,6 +25,7 @@ pub(crate) enum Symbol {
 	Sub,
 	Mul,
 	Div,
+	FloorDiv,
 	Mod,
 	Pow,
 	BitwiseAnd,
@@ -39,8 +43,16 @@ pub(crate) enum Symbol {
 	Equals,       // used for assignment
 	DoubleEquals, // used for equality
 	NotEquals,
+	ApproxEquals,
 	Combination,
 	Permutation,
+	OpenBracket,
+	CloseBracket,
+	Comma,
+	DotProduct,
+	CrossProduct,
+	PlusMinus,
+	Range,
 }
 
 impl fmt::Display for Symbol {
@@ -52,6 +64,7 @@ impl fmt::Display for Symbol {
 			Self::Sub => "-",
 			Self::Mul => "*",
 			Self::Div => "/",
+			Self::FloorDiv => "//",
 			Self::Mod => "mod",
 			Self::Pow => "^",
 			Self::BitwiseAnd => "&",
@@ -69,8 +82,16 @@ impl fmt::Display for Symbol {
 			Self::Equals => "=",
 			Self::DoubleEquals => "==",
 			Self::NotEquals => "!=",
+			Self::ApproxEquals => "~=",
 			Self::Combination => "nCr",
 			Self::Permutation => "nPr",
+			Self::OpenBracket => "[",
+			Self::CloseBracket => "]",
+			Self::Comma => ",",
+			Self::DotProduct => "dot",
+			Self::CrossProduct => "cross",
+			Self::PlusMinus => "\u{b1}",
+			Self::Range => "..",
 		};
 		write!(f, "{s}")?;
 		Ok(())
@@ -106,13 +127,54 @@ fn parse_fixed_char(input: &str, ch: char) -> FResult<((), &str)> {
 	}
 }
 
-fn parse_digit_separator(input: &str) -> FResult<((), &str)> {
-	let (parsed_ch, input) = parse_char(input)?;
-	if parsed_ch == '_' || parsed_ch == ',' {
-		Ok(((), input))
-	} else {
-		Err(FendError::ExpectedDigitSeparator(parsed_ch))
+/// The characters used as the decimal point and to group digits when
+/// parsing decimal numbers
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct NumberSyntax {
+	decimal_point: char,
+	// in addition to `_`, which can always be used
+	group_separator: Option<char>,
+}
+
+impl Default for NumberSyntax {
+	fn default() -> Self {
+		Self {
+			decimal_point: '.',
+			group_separator: Some(','),
+		}
+	}
+}
+
+impl From<crate::DigitSeparators> for NumberSyntax {
+	fn from(separators: crate::DigitSeparators) -> Self {
+		let decimal_point = separators.decimal_point();
+		Self {
+			decimal_point,
+			group_separator: match separators.group_separator() {
+				None if decimal_point == '.' => Some(','),
+				separator => separator,
+			},
+		}
+	}
+}
+
+fn parse_digit_separator(input: &str, syntax: NumberSyntax) -> FResult<((), &str)> {
+	let (parsed_ch, remaining) = parse_char(input)?;
+	if parsed_ch == '_' || (parsed_ch == ',' && syntax.decimal_point == '.') {
+		return Ok(((), remaining));
+	}
+	if Some(parsed_ch) == syntax.group_separator {
+		// spaces only separate groups of exactly three digits, so that
+		// e.g. `5 12` isn't read as `512`
+		if parsed_ch == ' ' {
+			let digits = remaining.bytes().take_while(u8::is_ascii_digit).count();
+			if digits != 3 {
+				return Err(FendError::ExpectedDigitSeparator(parsed_ch));
+			}
+		}
+		return Ok(((), remaining));
 	}
+	Err(FendError::ExpectedDigitSeparator(parsed_ch))
 }
 
 // Parses a plain integer with no whitespace and no base prefix.
@@ -121,13 +183,14 @@ fn parse_integer<'a, E: From<FendError>>(
 	input: &'a str,
 	allow_digit_separator: bool,
 	base: Base,
+	syntax: NumberSyntax,
 	process_digit: &mut impl FnMut(u8) -> Result<(), E>,
 ) -> Result<((), &'a str), E> {
 	let (digit, mut input) = parse_ascii_digit(input, base)?;
 	process_digit(digit)?;
 	let mut parsed_digit_separator;
 	loop {
-		if let Ok(((), remaining)) = parse_digit_separator(input) {
+		if let Ok(((), remaining)) = parse_digit_separator(input, syntax) {
 			input = remaining;
 			parsed_digit_separator = true;
 			if !allow_digit_separator {
@@ -163,20 +226,23 @@ fn parse_base_prefix(input: &str) -> FResult<(Base, &str)> {
 		Ok((Base::from_zero_based_prefix_char(ch)?, input))
 	} else {
 		let mut custom_base: u8 = 0;
-		let ((), input) = parse_integer(input, false, Base::default(), &mut |digit| -> Result<
-			(),
-			FendError,
-		> {
-			let error = FendError::BaseTooLarge;
-			if custom_base > 3 {
-				return Err(error);
-			}
-			custom_base = 10 * custom_base + digit;
-			if custom_base > 36 {
-				return Err(error);
-			}
-			Ok(())
-		})?;
+		let ((), input) = parse_integer(
+			input,
+			false,
+			Base::default(),
+			NumberSyntax::default(),
+			&mut |digit| -> Result<(), FendError> {
+				let error = FendError::BaseTooLarge;
+				if custom_base > 3 {
+					return Err(error);
+				}
+				custom_base = 10 * custom_base + digit;
+				if custom_base > 36 {
+					return Err(error);
+				}
+				Ok(())
+			},
+		)?;
 		if custom_base < 2 {
 			return Err(FendError::BaseTooSmall);
 		}
@@ -210,15 +276,21 @@ fn parse_recurring_digits<'a, I: Interrupt>(
 	let mut recurring_number_num = Number::from(0);
 	let mut recurring_number_den = Number::from(1);
 	let base_as_u64 = u64::from(base.base_as_u8());
-	let ((), input) = parse_integer(input, true, base, &mut |digit| -> FResult<()> {
-		let digit_as_u64 = u64::from(digit);
-		recurring_number_num = recurring_number_num
-			.clone()
-			.mul(base_as_u64.into(), int)?
-			.add(digit_as_u64.into(), int)?;
-		recurring_number_den = recurring_number_den.clone().mul(base_as_u64.into(), int)?;
-		Ok(())
-	})?;
+	let ((), input) = parse_integer(
+		input,
+		true,
+		base,
+		NumberSyntax::default(),
+		&mut |digit| -> FResult<()> {
+			let digit_as_u64 = u64::from(digit);
+			recurring_number_num = recurring_number_num
+				.clone()
+				.mul(base_as_u64.into(), int)?
+				.add(digit_as_u64.into(), int)?;
+			recurring_number_den = recurring_number_den.clone().mul(base_as_u64.into(), int)?;
+			Ok(())
+		},
+	)?;
 	recurring_number_den = recurring_number_den.clone().sub(1.into(), int)?;
 	for _ in 0..num_nonrec_digits {
 		recurring_number_den = recurring_number_den.clone().mul(base_as_u64.into(), int)?;
@@ -235,6 +307,7 @@ fn parse_recurring_digits<'a, I: Interrupt>(
 fn parse_basic_number<'a, I: Interrupt>(
 	mut input: &'a str,
 	base: Base,
+	syntax: NumberSyntax,
 	int: &I,
 ) -> FResult<(Number, &'a str)> {
 	let mut is_dice_with_no_count = false;
@@ -252,36 +325,37 @@ fn parse_basic_number<'a, I: Interrupt>(
 	let base_as_u64 = u64::from(base.base_as_u8());
 	let mut is_integer = true;
 
-	if parse_fixed_char(input, '.').is_err() && !is_dice_with_no_count {
-		let ((), remaining) = parse_integer(input, true, base, &mut |digit| -> FResult<()> {
-			res = res
-				.clone()
-				.mul(base_as_u64.into(), int)?
-				.add(u64::from(digit).into(), int)?;
-			Ok(())
-		})?;
+	if parse_fixed_char(input, syntax.decimal_point).is_err() && !is_dice_with_no_count {
+		let mut digits = String::new();
+		let ((), remaining) =
+			parse_integer(input, true, base, syntax, &mut |digit| -> FResult<()> {
+				digits.extend(char::from_digit(digit.into(), base.base_as_u8().into()));
+				Ok(())
+			})?;
+		res = Number::from_digits(&digits, base, int)?;
 		input = remaining;
 	}
 
 	// parse decimal point and at least one digit
-	if let Ok(((), remaining)) = parse_fixed_char(input, '.') {
+	if let Ok(((), remaining)) = parse_fixed_char(input, syntax.decimal_point) {
 		is_integer = false;
 		let mut num_nonrec_digits = 0;
 		let mut numerator = Number::zero_with_base(base);
 		let mut denominator = Number::zero_with_base(base).add(1.into(), int)?;
 		if parse_fixed_char(remaining, '(').is_err() {
-			let ((), remaining) = parse_integer(remaining, true, base, &mut |digit| -> Result<
-				(),
-				FendError,
-			> {
-				numerator = numerator
-					.clone()
-					.mul(base_as_u64.into(), int)?
-					.add(u64::from(digit).into(), int)?;
-				denominator = denominator.clone().mul(base_as_u64.into(), int)?;
-				num_nonrec_digits += 1;
-				Ok(())
-			})?;
+			let ((), remaining) =
+				parse_integer(remaining, true, base, syntax, &mut |digit| -> Result<
+					(),
+					FendError,
+				> {
+					numerator = numerator
+						.clone()
+						.mul(base_as_u64.into(), int)?
+						.add(u64::from(digit).into(), int)?;
+					denominator = denominator.clone().mul(base_as_u64.into(), int)?;
+					num_nonrec_digits += 1;
+					Ok(())
+				})?;
 			input = remaining;
 		} else {
 			input = remaining;
@@ -306,15 +380,20 @@ fn parse_basic_number<'a, I: Interrupt>(
 						.map_err(|_| FendError::InvalidDiceSyntax)?
 				};
 				let mut face_count = 0_u32;
-				let ((), remaining2) =
-					parse_integer(remaining, false, base, &mut |digit| -> FResult<()> {
+				let ((), remaining2) = parse_integer(
+					remaining,
+					false,
+					base,
+					syntax,
+					&mut |digit| -> FResult<()> {
 						face_count = face_count
 							.checked_mul(base.base_as_u8().into())
 							.ok_or(FendError::InvalidDiceSyntax)?
 							.checked_add(digit.into())
 							.ok_or(FendError::InvalidDiceSyntax)?;
 						Ok(())
-					})?;
+					},
+				)?;
 				if dice_count == 0 || face_count == 0 {
 					return Err(FendError::InvalidDiceSyntax);
 				}
@@ -360,7 +439,7 @@ fn parse_basic_number<'a, I: Interrupt>(
 				let mut exp = Number::zero_with_base(base);
 				let base_num = Number::from(u64::from(base.base_as_u8()));
 				let ((), remaining2) =
-					parse_integer(input, true, base, &mut |digit| -> FResult<()> {
+					parse_integer(input, true, base, syntax, &mut |digit| -> FResult<()> {
 						exp = (exp.clone().mul(base_num.clone(), int)?)
 							.add(u64::from(digit).into(), int)?;
 						Ok(())
@@ -421,9 +500,19 @@ fn parse_power_number(input: &str) -> FResult<(Vec<u64>, &str)> {
 	Ok((digits, input))
 }
 
-fn parse_number<'a, I: Interrupt>(input: &'a str, int: &I) -> FResult<(Number, &'a str)> {
+fn parse_number<'a, I: Interrupt>(
+	input: &'a str,
+	syntax: NumberSyntax,
+	int: &I,
+) -> FResult<(Number, &'a str)> {
 	let (base, input) = parse_base_prefix(input).unwrap_or((Base::default(), input));
-	let (res, input) = parse_basic_number(input, base, int)?;
+	// other bases always use the default syntax
+	let syntax = if base.base_as_u8() == 10 {
+		syntax
+	} else {
+		NumberSyntax::default()
+	};
+	let (res, input) = parse_basic_number(input, base, syntax, int)?;
 	Ok((res, input))
 }
 
@@ -440,7 +529,7 @@ fn is_valid_in_ident(ch: char, prev: Option<char>) -> bool {
 		'㏌', '㏏', '㏐', '㏓', '㏔', '㏕', '㏖', '㏗', '㏙', '㏛', '㏜', '㏝',
 	];
 	let only_valid_by_themselves = ['%', '‰', '‱', '′', '″', '’', '”', 'π'];
-	let split_on_subsequent_digit = ['$', '£', '¥'];
+	let split_on_subsequent_digit = ['$', '£', '¥', '€'];
 	let always_invalid = ['λ'];
 	if always_invalid.contains(&ch) {
 		false
@@ -470,7 +559,9 @@ fn parse_ident(input: &str, allow_dots: bool) -> FResult<(Token, &str)> {
 	let (_, mut remaining) = input.split_at(byte_idx);
 	let mut prev_char = first_char;
 	while let Ok((next_char, remaining_input)) = parse_char(remaining) {
-		if !is_valid_in_ident(next_char, Some(prev_char)) || next_char == '.' && !allow_dots {
+		if !is_valid_in_ident(next_char, Some(prev_char))
+			|| next_char == '.' && (!allow_dots || remaining_input.starts_with('.'))
+		{
 			break;
 		}
 		remaining = remaining_input;
@@ -489,6 +580,8 @@ fn parse_ident(input: &str, allow_dots: bool) -> FResult<(Token, &str)> {
 			"or" | "OR" => Token::Symbol(Symbol::BitwiseOr),
 			"nCr" | "choose" => Token::Symbol(Symbol::Combination),
 			"nPr" | "permute" => Token::Symbol(Symbol::Permutation),
+			"dot" => Token::Symbol(Symbol::DotProduct),
+			"cross" => Token::Symbol(Symbol::CrossProduct),
 			_ => Token::Ident(Ident::new_string(ident.to_string())),
 		},
 		input,
@@ -509,6 +602,7 @@ fn parse_symbol(ch: char, input: &mut &str) -> FResult<Token> {
 		'(' => Symbol::OpenParens,
 		')' => Symbol::CloseParens,
 		'+' => Symbol::Add,
+		'\u{b1}' => Symbol::PlusMinus, // plus-minus sign
 		'!' => {
 			if test_next('=') {
 				Symbol::NotEquals
@@ -525,7 +619,14 @@ fn parse_symbol(ch: char, input: &mut &str) -> FResult<Token> {
 				Symbol::Mul
 			}
 		}
-		'/' | '\u{f7}' | '\u{2215}' => Symbol::Div, // unicode division symbol and slash
+		'/' => {
+			if test_next('/') {
+				Symbol::FloorDiv
+			} else {
+				Symbol::Div
+			}
+		}
+		'\u{f7}' | '\u{2215}' => Symbol::Div, // unicode division symbol and slash
 		'^' => Symbol::Pow,
 		'&' => Symbol::BitwiseAnd,
 		'|' => Symbol::BitwiseOr,
@@ -539,9 +640,17 @@ fn parse_symbol(ch: char, input: &mut &str) -> FResult<Token> {
 				Symbol::Equals
 			}
 		}
-		'\u{2260}' => Symbol::NotEquals,       // unicode not equal to symbol
+		'\u{2260}' => Symbol::NotEquals, // unicode not equal to symbol
+		'~' if test_next('=') => Symbol::ApproxEquals,
+		'\u{2248}' => Symbol::ApproxEquals, // unicode almost equal to symbol
 		'\\' | '\u{3bb}' => Symbol::Backslash, // lambda symbol
-		'.' => Symbol::Dot,
+		'.' => {
+			if test_next('.') {
+				Symbol::Range
+			} else {
+				Symbol::Dot
+			}
+		}
 		'<' => {
 			if test_next('<') {
 				Symbol::ShiftLeft
@@ -551,14 +660,10 @@ fn parse_symbol(ch: char, input: &mut &str) -> FResult<Token> {
 				return Err(FendError::UnexpectedChar(ch));
 			}
 		}
-		'>' => {
-			if test_next('>') {
-				Symbol::ShiftRight
-			} else {
-				return Err(FendError::UnexpectedChar(ch));
-			}
-		}
+		'>' if test_next('>') => Symbol::ShiftRight,
 		';' => Symbol::Semicolon,
+		'[' => Symbol::OpenBracket,
+		']' => Symbol::CloseBracket,
 		_ => return Err(FendError::UnexpectedChar(ch)),
 	}))
 }
@@ -712,9 +817,16 @@ fn parse_quote_unit(input: &str) -> (Token, &str) {
 
 pub(crate) struct Lexer<'a, 'b, I: Interrupt> {
 	input: &'a str,
+	input_len: usize,
 	// normally 0; 1 after backslash; 2 after ident after backslash
 	after_backslash_state: u8,
 	after_number_or_to: bool,
+	// whether the previous token was a function name (or `nCr`/`nPr`)
+	after_callee: bool,
+	// unclosed `(` and `[`, with the innermost one last, and whether they
+	// contain the arguments of a function call
+	open_delimiters: Vec<(Symbol, bool)>,
+	number_syntax: NumberSyntax,
 	int: &'b I,
 }
 
@@ -769,7 +881,25 @@ fn parse_date(input: &str) -> FResult<(Date, &str)> {
 	Ok((res, result_remaining))
 }
 
-impl<'a, 'b, I: Interrupt> Lexer<'a, 'b, I> {
+/// Parses a time of day like `14:30`, a time with a time zone like `3pm EST`,
+/// or a time zone like `UTC+8`
+fn parse_time_or_time_zone(input: &str) -> Option<(Token, &str)> {
+	let ch = input.chars().next()?;
+	if ch.is_ascii_digit() && cfg!(feature = "date") {
+		if cfg!(feature = "timezones") {
+			if let Some((time, remaining)) = ZonedDateTime::parse_prefix(None, input) {
+				return Some((Token::ZonedDateTime(time), remaining));
+			}
+		}
+		TimeOfDay::parse_prefix(input).map(|(time, remaining)| (Token::TimeOfDay(time), remaining))
+	} else if cfg!(feature = "timezones") && is_valid_in_ident(ch, None) {
+		TimeZone::parse_prefix(input).map(|(zone, remaining)| (Token::TimeZone(zone), remaining))
+	} else {
+		None
+	}
+}
+
+impl<I: Interrupt> Lexer<'_, '_, I> {
 	fn next_token(&mut self) -> FResult<Option<Token>> {
 		skip_whitespace_and_comments(&mut self.input);
 		let (ch, following) = {
@@ -778,14 +908,41 @@ impl<'a, 'b, I: Interrupt> Lexer<'a, 'b, I> {
 			let following = chars.next();
 			(ch, following)
 		};
+		// inside brackets and function call parentheses, commas separate
+		// vector components or arguments instead of being part of numbers
+		// or identifiers, e.g. `nCr(5,2)`. Elsewhere, only a comma followed
+		// by whitespace is a separator, e.g. between function arguments or
+		// in `given V = 12 V, I = 0.5 A`, so that `(1,000)` still works.
+		let element_len = match self.open_delimiters.last() {
+			Some((Symbol::OpenBracket, _) | (Symbol::OpenParens, true)) => {
+				self.input.find(',').unwrap_or(self.input.len())
+			}
+			Some((Symbol::OpenParens, false)) | None => self
+				.input
+				.match_indices(',')
+				.map(|(idx, _)| idx)
+				.find(|&idx| self.input[idx + 1..].starts_with(char::is_whitespace))
+				.unwrap_or(self.input.len()),
+			_ => self.input.len(),
+		};
+		let element = &self.input[..element_len];
+		let is_separator = ch == Some(',')
+			&& (!self.open_delimiters.is_empty() || following.is_some_and(char::is_whitespace));
 		Ok(Some(match ch {
 			Some(ch) => {
-				if ch.is_ascii_digit()
-					|| (ch == '.' && self.after_backslash_state == 0)
+				if let Some((token, remaining)) = parse_time_or_time_zone(self.input) {
+					// time of day (e.g. 14:30 or 3pm EST), or a time zone
+					self.input = remaining;
+					token
+				} else if ch.is_ascii_digit()
+					|| (ch == '.' && following != Some('.') && self.after_backslash_state == 0)
 					|| (ch == 'd' && following.is_some() && following.unwrap().is_ascii_digit())
 				{
-					let (num, remaining) = parse_number(self.input, self.int)?;
-					self.input = remaining;
+					// in a range like `10..12`, the number ends before the `..`
+					let number_len = element.find("..").unwrap_or(element_len);
+					let (num, remaining) =
+						parse_number(&element[..number_len], self.number_syntax, self.int)?;
+					self.input = &self.input[number_len - remaining.len()..];
 					Token::Num(num)
 				} else if ch == '\'' || ch == '"' {
 					if self.after_number_or_to {
@@ -800,9 +957,23 @@ impl<'a, 'b, I: Interrupt> Lexer<'a, 'b, I> {
 					}
 				} else if ch == '@' {
 					// date literal, e.g. @1970-01-01
+					if cfg!(not(feature = "date")) {
+						return Err(FendError::FeatureDisabled("date"));
+					}
 					let (date, remaining) = parse_date(self.input)?;
-					self.input = remaining;
-					Token::Date(date)
+					let zoned_time = cfg!(feature = "timezones")
+						.then(|| ZonedDateTime::parse_prefix(Some(date), remaining.trim_start()))
+						.flatten()
+						.filter(|_| remaining.starts_with(char::is_whitespace));
+					if let Some((time, remaining)) = zoned_time {
+						// date and time with a time zone, e.g.
+						// @2024-07-01 15:00 America/New_York
+						self.input = remaining;
+						Token::ZonedDateTime(time)
+					} else {
+						self.input = remaining;
+						Token::Date(date)
+					}
 				} else if self.input.starts_with("#\"") {
 					// raw string literal
 					let (_, remaining) = self.input.split_at(2);
@@ -815,11 +986,14 @@ impl<'a, 'b, I: Interrupt> Lexer<'a, 'b, I> {
 					let (_terminator, remaining) = remaining.split_at(2);
 					self.input = remaining;
 					Token::StringLiteral(literal.to_string().into())
+				} else if is_separator {
+					let (_, remaining) = self.input.split_at(1);
+					self.input = remaining;
+					Token::Symbol(Symbol::Comma)
 				} else if is_valid_in_ident(ch, None) {
 					// dots aren't allowed in idents after a backslash
-					let (ident, remaining) =
-						parse_ident(self.input, self.after_backslash_state != 1)?;
-					self.input = remaining;
+					let (ident, remaining) = parse_ident(element, self.after_backslash_state != 1)?;
+					self.input = &self.input[element_len - remaining.len()..];
 					ident
 				} else {
 					let (_, remaining) = self.input.split_at(ch.len_utf8());
@@ -832,7 +1006,28 @@ impl<'a, 'b, I: Interrupt> Lexer<'a, 'b, I> {
 	}
 }
 
-impl<'a, I: Interrupt> Iterator for Lexer<'a, '_, I> {
+impl<I: Interrupt> Lexer<'_, '_, I> {
+	/// Uses the given decimal point and digit separators when parsing
+	/// decimal numbers
+	pub(crate) fn with_number_syntax(self, number_syntax: NumberSyntax) -> Self {
+		Self {
+			number_syntax,
+			..self
+		}
+	}
+
+	/// Returns the next token together with its byte range in the input.
+	/// Whitespace and comments are not included in the range.
+	pub(crate) fn next_with_span(&mut self) -> Option<(FResult<Token>, ops::Range<usize>)> {
+		skip_whitespace_and_comments(&mut self.input);
+		let start = self.input_len - self.input.len();
+		let token = self.next()?;
+		let end = self.input_len - self.input.len();
+		Some((token, start..end))
+	}
+}
+
+impl<I: Interrupt> Iterator for Lexer<'_, '_, I> {
 	type Item = FResult<Token>;
 
 	fn next(&mut self) -> Option<Self::Item> {
@@ -845,6 +1040,26 @@ impl<'a, I: Interrupt> Iterator for Lexer<'a, '_, I> {
 			res,
 			Some(Ok(Token::Num(_) | Token::Symbol(Symbol::UnitConversion)))
 		);
+		let after_callee = self.after_callee;
+		self.after_callee = matches!(
+			res,
+			Some(Ok(
+				Token::Ident(_) | Token::Symbol(Symbol::Combination | Symbol::Permutation)
+			))
+		);
+		match res {
+			Some(Ok(Token::Symbol(Symbol::OpenParens))) => {
+				self.open_delimiters
+					.push((Symbol::OpenParens, after_callee));
+			}
+			Some(Ok(Token::Symbol(Symbol::OpenBracket))) => {
+				self.open_delimiters.push((Symbol::OpenBracket, false));
+			}
+			Some(Ok(Token::Symbol(Symbol::CloseBracket | Symbol::CloseParens))) => {
+				self.open_delimiters.pop();
+			}
+			_ => (),
+		}
 		if matches!(res, Some(Ok(Token::Symbol(Symbol::Backslash)))) {
 			self.after_backslash_state = 1;
 		} else if self.after_backslash_state == 1 {
@@ -863,8 +1078,12 @@ impl<'a, I: Interrupt> Iterator for Lexer<'a, '_, I> {
 pub(crate) fn lex<'a, 'b, I: Interrupt>(input: &'a str, int: &'b I) -> Lexer<'a, 'b, I> {
 	Lexer {
 		input,
+		input_len: input.len(),
 		after_backslash_state: 0,
 		after_number_or_to: false,
+		after_callee: false,
+		open_delimiters: vec![],
+		number_syntax: NumberSyntax::default(),
 		int,
 	}
 }
@@ -22,30 +22,44 @@
 //! ```
 
 mod ast;
+mod completion;
 mod date;
 mod error;
 mod eval;
+mod finance;
 mod format;
+mod formula;
+mod highlight;
 mod ident;
 mod inline_substitutions;
 mod interrupt;
+mod interval;
 /// This module is not meant to be used by other crates. It may change or be removed at any point.
 pub mod json;
 mod lexer;
+mod locale;
+mod matrix;
+mod natural_language;
 mod num;
 mod parser;
+mod probability;
+mod render;
 mod result;
 mod scope;
 mod serialize;
+mod suggestion;
+mod symbolic;
 mod units;
 mod value;
+mod vector;
 
 use std::sync::Arc;
-use std::{collections::HashMap, fmt, io};
+use std::{collections::HashMap, fmt, fmt::Write, io, ops};
 
 use error::FendError;
 pub(crate) use eval::Attrs;
 pub use interrupt::Interrupt;
+pub use locale::Locale;
 use result::FResult;
 use serialize::{Deserialize, Serialize};
 
@@ -56,6 +70,69 @@ pub struct FendResult {
 	span_result: Vec<Span>,
 	is_unit: bool, // is this the () type
 	attrs: eval::Attrs,
+	info: ResultInfo,
+}
+
+#[derive(PartialEq, Eq, Debug, Default)]
+struct ResultInfo {
+	is_boolean: bool,
+	rational: Option<ExactRational>,
+	units: Vec<UnitComponent>,
+	base_units: Vec<UnitComponent>,
+	canonical: Option<String>,
+	table: Option<Table>,
+	renderings: render::Renderings,
+	conversion_steps: Vec<String>,
+}
+
+/// An exact rational number in lowest terms. The numerator and denominator
+/// are stored as decimal strings since they can be arbitrarily large.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExactRational {
+	negative: bool,
+	numerator: String,
+	denominator: String,
+}
+
+impl ExactRational {
+	/// Returns whether or not the number is negative. Zero is never negative.
+	#[must_use]
+	pub fn is_negative(&self) -> bool {
+		self.negative
+	}
+
+	/// Returns the numerator (without sign) in base 10.
+	#[must_use]
+	pub fn numerator(&self) -> &str {
+		self.numerator.as_str()
+	}
+
+	/// Returns the denominator in base 10. This is `"1"` for integers.
+	#[must_use]
+	pub fn denominator(&self) -> &str {
+		self.denominator.as_str()
+	}
+}
+
+/// A single unit together with its exponent, e.g. `km` with exponent `2`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnitComponent {
+	name: String,
+	exponent: String,
+}
+
+impl UnitComponent {
+	/// Returns the name of the unit, including any prefix.
+	#[must_use]
+	pub fn name(&self) -> &str {
+		self.name.as_str()
+	}
+
+	/// Returns the exponent of the unit, e.g. `"1"`, `"-2"` or `"1/2"`.
+	#[must_use]
+	pub fn exponent(&self) -> &str {
+		self.exponent.as_str()
+	}
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -70,6 +147,11 @@ pub enum SpanKind {
 	Ident,
 	Boolean,
 	Other,
+	Operator,
+	Unit,
+	Error,
+	/// A negative amount of money in accounting style, e.g. `($5)`
+	NegativeAmount,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -134,6 +216,13 @@ impl FendResult {
 			span_result: vec![],
 			is_unit: true,
 			attrs: Attrs::default(),
+			info: ResultInfo {
+				renderings: render::Renderings {
+					json: "{\"type\":\"()\",\"plain\":\"\"}".to_string(),
+					..render::Renderings::default()
+				},
+				..ResultInfo::default()
+			},
 		}
 	}
 
@@ -144,6 +233,128 @@ impl FendResult {
 	pub fn has_trailing_newline(&self) -> bool {
 		self.attrs.trailing_newline
 	}
+
+	/// Returns whether or not the result is a boolean (`true` or `false`).
+	#[must_use]
+	pub fn is_boolean(&self) -> bool {
+		self.info.is_boolean
+	}
+
+	/// Returns the numeric value of the result as an exact rational number,
+	/// expressed in the units returned by [`FendResult::units`]. This is
+	/// `None` if the result is not a number, or if it is approximate,
+	/// complex or irrational.
+	#[must_use]
+	pub fn exact_rational(&self) -> Option<&ExactRational> {
+		self.info.rational.as_ref()
+	}
+
+	/// Returns the units of a numeric result as they are displayed, e.g.
+	/// `km` and `h^-1` for `5 km/h`. This is empty for unitless numbers
+	/// and non-numeric results.
+	pub fn units(&self) -> impl Iterator<Item = &UnitComponent> {
+		self.info.units.iter()
+	}
+
+	/// Returns the base units (e.g. `meter`, `second` or `kilogram`) that
+	/// a numeric result is made up of, sorted by name. For example,
+	/// `5 km/h` returns `meter` and `second^-1`, and `5 °C` returns `kelvin`.
+	pub fn base_units(&self) -> impl Iterator<Item = &UnitComponent> {
+		self.info.base_units.iter()
+	}
+
+	/// Returns a canonical, versioned text representation of the result
+	/// that can be stored and compared across fend versions. Two results
+	/// compare equal if they represent the same value, regardless of how
+	/// they are formatted: `1 km` and `1000 m` have the same canonical value,
+	/// as do `1 °C` and `274.15 K`.
+	///
+	/// The representation consists of `;`-separated fields, starting with
+	/// a version tag (currently `fend1`) and the kind of value:
+	///
+	/// * numbers: `fend1;number;<exact|approx>;<rational>;<base units>`,
+	///   where the rational is written as `[-]numerator/denominator` in
+	///   lowest terms and base units are written as `name^exponent`,
+	///   separated by spaces and sorted by name,
+	///   e.g. `fend1;number;exact;5/18;meter^1 second^-1`
+	/// * booleans: `fend1;bool;true` or `fend1;bool;false`
+	/// * strings: `fend1;string;<string>`, with the string escaped as in JSON
+	/// * the `()` type: `fend1;unit`
+	///
+	/// This returns `None` for other values, such as functions, dates,
+	/// complex or irrational numbers, and probability distributions.
+	#[must_use]
+	pub fn canonical_value(&self) -> Option<&str> {
+		self.info.canonical.as_deref()
+	}
+
+	/// Returns the result rendered in the given format. All formats are
+	/// computed during evaluation, so this is cheap to call repeatedly,
+	/// e.g. to offer several "copy as" options.
+	#[must_use]
+	pub fn get_result_in_format(&self, format: ResultFormat) -> &str {
+		match format {
+			ResultFormat::Plain => self.plain_result.as_str(),
+			ResultFormat::Latex => self.info.renderings.latex.as_str(),
+			ResultFormat::Json => self.info.renderings.json.as_str(),
+			ResultFormat::Spoken => self.info.renderings.spoken.as_str(),
+		}
+	}
+
+	/// Returns the result as a table if it consists of rows of values,
+	/// e.g. an object with one row per property. This can be used to show
+	/// the result with aligned columns or to export it as CSV.
+	#[must_use]
+	pub fn table(&self) -> Option<&Table> {
+		self.info.table.as_ref()
+	}
+
+	/// Returns the steps taken in unit conversions, with the exact factors
+	/// that were applied, e.g. `1 mile = 1609.344 meters`,
+	/// `5 miles = 8046.72 meters` and `1 kilometer = 1000 meters` for
+	/// `5 miles to km`. This is empty unless enabled with
+	/// [`Context::set_show_conversion_steps`].
+	pub fn conversion_steps(&self) -> impl Iterator<Item = &str> {
+		self.info.conversion_steps.iter().map(String::as_str)
+	}
+}
+
+/// A format that results can be rendered in, see
+/// [`FendResult::get_result_in_format`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResultFormat {
+	/// The main result as plain text, e.g. `approx. 3.1415926535`
+	Plain,
+	/// A LaTeX math expression, e.g. `5\,\frac{\mathrm{m}}{\mathrm{s}}`
+	Latex,
+	/// A JSON object containing the result type and plain text result, and
+	/// for numbers the number, units and whether the result is approximate
+	Json,
+	/// English text suitable for text-to-speech, e.g. `5 meters per second`
+	Spoken,
+}
+
+/// A result that can be shown as a table. All cells are formatted as
+/// plain strings, and every row has one cell per header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+	headers: Vec<String>,
+	rows: Vec<Vec<String>>,
+}
+
+impl Table {
+	/// Returns the column headers.
+	#[must_use]
+	pub fn headers(&self) -> &[String] {
+		&self.headers
+	}
+
+	/// Returns the rows of the table.
+	#[must_use]
+	pub fn rows(&self) -> &[Vec<String>] {
+		&self.rows
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -200,14 +411,40 @@ where
 /// and recreate the context for every calculation, depending on
 /// which is easier.
 #[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Context {
 	current_time: Option<CurrentTimeInfo>,
 	variables: HashMap<String, value::Value>,
+	// calculator memory registers, where the unnamed register is ""
+	memory: HashMap<String, value::Value>,
 	fc_mode: FCMode,
 	random_u32: Option<fn() -> u32>,
+	// state of the seeded random number generator, which takes precedence
+	// over `random_u32`
+	random_state: Option<u64>,
 	output_mode: OutputMode,
 	get_exchange_rate: Option<Arc<dyn ExchangeRateFn + Send + Sync>>,
 	custom_units: Vec<(String, String, String)>,
+	// units defined with e.g. `unit bottle = 0.75 liters`, as (name, definition)
+	user_units: Vec<(String, String)>,
+	formulas: Vec<formula::Formula>,
+	scientific_notation: ScientificNotation,
+	digit_format: num::DigitFormat,
+	unit_exponent_notation: UnitExponentNotation,
+	exact_and_approximate: bool,
+	accounting_negatives: bool,
+	derived_units: bool,
+	allow_shadowing_constants: bool,
+	// whether unbound single-letter identifiers are kept symbolic; this is
+	// always the case inside e.g. `expand` or `diff`
+	symbolic_variables: bool,
+	multiplication_sign: MultiplicationSign,
+	show_conversion_steps: bool,
+	conversion_steps: Vec<String>,
+	locale: Locale,
+	implicit_multiplication: ImplicitMultiplication,
+	unit_system: UnitSystem,
+	parse_digit_separators: bool,
 }
 
 impl fmt::Debug for Context {
@@ -215,13 +452,191 @@ impl fmt::Debug for Context {
 		f.debug_struct("Context")
 			.field("current_time", &self.current_time)
 			.field("variables", &self.variables)
+			.field("memory", &self.memory)
 			.field("fc_mode", &self.fc_mode)
 			.field("random_u32", &self.random_u32)
+			.field("random_state", &self.random_state)
 			.field("output_mode", &self.output_mode)
+			.field("scientific_notation", &self.scientific_notation)
+			.field("digit_format", &self.digit_format)
+			.field("unit_exponent_notation", &self.unit_exponent_notation)
+			.field("exact_and_approximate", &self.exact_and_approximate)
+			.field("accounting_negatives", &self.accounting_negatives)
+			.field("derived_units", &self.derived_units)
+			.field("allow_shadowing_constants", &self.allow_shadowing_constants)
+			.field("symbolic_variables", &self.symbolic_variables)
+			.field("multiplication_sign", &self.multiplication_sign)
+			.field("show_conversion_steps", &self.show_conversion_steps)
+			.field("locale", &self.locale)
+			.field("implicit_multiplication", &self.implicit_multiplication)
+			.field("unit_system", &self.unit_system)
+			.field("parse_digit_separators", &self.parse_digit_separators)
+			.field("user_units", &self.user_units)
 			.finish_non_exhaustive()
 	}
 }
 
+/// How units with negative exponents are shown in results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum UnitExponentNotation {
+	/// Use a slash if there is exactly one unit with a negative exponent,
+	/// e.g. `m / s`, and negative exponents otherwise, e.g. `kg m^-1 s^-2`.
+	/// This is the default.
+	#[default]
+	Mixed,
+	/// Always use a slash, e.g. `kg / (m s^2)`
+	Slash,
+	/// Always use negative exponents, e.g. `m s^-1`
+	NegativeExponents,
+	/// Use negative exponents written as Unicode superscripts, e.g. `m s⁻¹`
+	Superscripts,
+}
+
+/// The sign used to multiply units in results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum MultiplicationSign {
+	/// Separate units with a space, e.g. `kg m`. This is the default.
+	#[default]
+	Space,
+	/// Use an asterisk, e.g. `kg * m`
+	Asterisk,
+	/// Use a middle dot, e.g. `kg·m`
+	Dot,
+	/// Use a multiplication sign, e.g. `kg × m`
+	Cross,
+}
+
+impl MultiplicationSign {
+	fn separator(self) -> &'static str {
+		match self {
+			Self::Space => " ",
+			Self::Asterisk => " * ",
+			Self::Dot => "\u{b7}",
+			Self::Cross => " \u{d7} ",
+		}
+	}
+}
+
+/// The sign used for fractions and to divide units in results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum DivisionSign {
+	/// Use a slash, e.g. `1/3` or `m / s`. This is the default.
+	#[default]
+	Slash,
+	/// Use the Unicode division slash, e.g. `1∕3` or `m ∕ s`
+	DivisionSlash,
+}
+
+impl DivisionSign {
+	fn symbol(self) -> char {
+		match self {
+			Self::Slash => '/',
+			Self::DivisionSlash => '\u{2215}',
+		}
+	}
+}
+
+/// How implicit multiplication (e.g. `2x` or `3(4+5)`) is grouped when it
+/// follows a division.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum ImplicitMultiplication {
+	/// Implicit multiplication has the same precedence as explicit
+	/// multiplication and division, and is evaluated from left to right, so
+	/// `1/2x` means `(1/2)x`. This is the default.
+	#[default]
+	LeftToRight,
+	/// Implicit multiplication binds more tightly than division, so `1/2x`
+	/// means `1/(2x)` and `60 km / 2 h` means `60 km / (2 h)`
+	BeforeDivision,
+}
+
+/// Which units fend picks when it simplifies a result with mixed units, e.g.
+/// `3 ft * 2 m`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum UnitSystem {
+	/// Use SI units such as `m^3`, `newton` or `pascal`. This is the default.
+	#[default]
+	Metric,
+	/// Prefer imperial (US customary) units such as `ft^2`, `gallon` or
+	/// `psi`, falling back to SI units where there is no common imperial one
+	Imperial,
+}
+
+/// How the digits of decimal numbers are grouped, and which character is
+/// used as the decimal point.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum DigitSeparators {
+	/// Don't group digits, and use a decimal point, e.g. `1234567.5`. This
+	/// is the default.
+	#[default]
+	None,
+	/// Separate thousands with commas, e.g. `1,234,567.5`
+	Comma,
+	/// Separate thousands with spaces, e.g. `1 234 567.5`
+	Space,
+	/// Separate thousands with periods and use a decimal comma, e.g.
+	/// `1.234.567,5`
+	PeriodDecimalComma,
+	/// Separate thousands with spaces and use a decimal comma, e.g.
+	/// `1 234 567,5`
+	SpaceDecimalComma,
+	/// Don't group digits, but use a decimal comma, e.g. `1234567,5`
+	DecimalComma,
+}
+
+impl DigitSeparators {
+	fn group_separator(self) -> Option<char> {
+		match self {
+			Self::None | Self::DecimalComma => None,
+			Self::Comma => Some(','),
+			Self::Space | Self::SpaceDecimalComma => Some(' '),
+			Self::PeriodDecimalComma => Some('.'),
+		}
+	}
+
+	fn decimal_point(self) -> char {
+		match self {
+			Self::None | Self::Comma | Self::Space => '.',
+			Self::PeriodDecimalComma | Self::SpaceDecimalComma | Self::DecimalComma => ',',
+		}
+	}
+}
+
+/// How recurring digits are shown in results.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum RecurringDigitsNotation {
+	/// Wrap the recurring digits in parentheses, e.g. `0.1(6)`. This is the
+	/// default.
+	#[default]
+	Parentheses,
+	/// Draw a line above each recurring digit using the Unicode combining
+	/// overline character, e.g. `0.16̅`. Not all fonts and terminals
+	/// render this correctly.
+	Overline,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+struct ScientificNotation {
+	upper: Option<i32>,
+	lower: Option<i32>,
+}
+
+impl ScientificNotation {
+	/// Whether a number with the given decimal exponent should be shown
+	/// in scientific notation
+	fn applies_to(self, exponent: i64) -> bool {
+		self.upper.is_some_and(|upper| exponent >= i64::from(upper))
+			|| self.lower.is_some_and(|lower| exponent < i64::from(lower))
+	}
+}
+
 impl Default for Context {
 	fn default() -> Self {
 		Self::new()
@@ -235,16 +650,33 @@ impl Context {
 		Self {
 			current_time: None,
 			variables: HashMap::new(),
+			memory: HashMap::new(),
 			fc_mode: FCMode::CelsiusFahrenheit,
 			random_u32: None,
+			random_state: None,
 			output_mode: OutputMode::SimpleText,
 			get_exchange_rate: None,
 			custom_units: vec![],
+			user_units: vec![],
+			formulas: vec![],
+			scientific_notation: ScientificNotation::default(),
+			digit_format: num::DigitFormat::default(),
+			unit_exponent_notation: UnitExponentNotation::default(),
+			exact_and_approximate: false,
+			accounting_negatives: false,
+			derived_units: true,
+			allow_shadowing_constants: false,
+			symbolic_variables: false,
+			multiplication_sign: MultiplicationSign::default(),
+			show_conversion_steps: false,
+			conversion_steps: vec![],
+			locale: Locale::default(),
+			implicit_multiplication: ImplicitMultiplication::default(),
+			unit_system: UnitSystem::default(),
+			parse_digit_separators: false,
 		}
 	}
 
-	/// This method currently has no effect!
-	///
 	/// Set the current time. This API will likely change in the future!
 	///
 	/// The first argument (`ms_since_1970`) must be the number of elapsed milliseconds
@@ -253,12 +685,11 @@ impl Context {
 	///
 	/// The second argument (`tz_offset_secs`) is the current time zone
 	/// offset to UTC, in seconds.
-	pub fn set_current_time_v1(&mut self, _ms_since_1970: u64, _tz_offset_secs: i64) {
-		// self.current_time = Some(CurrentTimeInfo {
-		//     elapsed_unix_time_ms: ms_since_1970,
-		//     timezone_offset_secs: tz_offset_secs,
-		// });
-		self.current_time = None;
+	pub fn set_current_time_v1(&mut self, ms_since_1970: u64, tz_offset_secs: i64) {
+		self.current_time = Some(CurrentTimeInfo {
+			elapsed_unix_time_ms: ms_since_1970,
+			timezone_offset_secs: tz_offset_secs,
+		});
 	}
 
 	/// Define the units `C` and `F` as coulomb and farad instead of degrees
@@ -272,9 +703,35 @@ impl Context {
 		self.random_u32 = Some(random_u32);
 	}
 
-	/// Clear the random number generator after setting it with via [`Self::set_random_u32_fn`]
+	/// Use a deterministic random number generator with the given seed, so
+	/// that e.g. `roll d6` gives the same sequence of results for the same
+	/// seed. This takes precedence over [`Self::set_random_u32_fn`].
+	pub fn set_random_seed(&mut self, seed: u64) {
+		self.random_state = Some(seed);
+	}
+
+	/// Clear the random number generator after setting it with via
+	/// [`Self::set_random_u32_fn`] or [`Self::set_random_seed`]
 	pub fn disable_rng(&mut self) {
 		self.random_u32 = None;
+		self.random_state = None;
+	}
+
+	fn next_random_u32(&mut self) -> FResult<u32> {
+		if let Some(state) = &mut self.random_state {
+			// SplitMix64
+			*state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+			let mut z = *state;
+			z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+			z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+			z ^= z >> 31;
+			#[allow(clippy::cast_possible_truncation)]
+			return Ok((z >> 32) as u32);
+		}
+		let random_u32 = self
+			.random_u32
+			.ok_or(FendError::RandomNumbersNotAvailable)?;
+		Ok(random_u32())
 	}
 
 	/// Change the output mode to fixed-width terminal style. This enables ASCII
@@ -283,9 +740,169 @@ impl Context {
 		self.output_mode = OutputMode::TerminalFixedWidth;
 	}
 
-	fn serialize_variables_internal(&self, write: &mut impl io::Write) -> FResult<()> {
-		self.variables.len().serialize(write)?;
-		for (k, v) in &self.variables {
+	/// Show results in scientific notation (e.g. `1.5e15`) if their absolute
+	/// value is at least `10^upper` or less than `10^lower`. Passing `None`
+	/// disables the corresponding threshold. Scientific notation is only used
+	/// for decimal numbers, and is disabled by default.
+	pub fn set_scientific_notation_thresholds(&mut self, upper: Option<i32>, lower: Option<i32>) {
+		self.scientific_notation = ScientificNotation { upper, lower };
+	}
+
+	/// Keep trailing zeros when a number of decimal places or significant
+	/// figures is requested, e.g. `2.5 to 2 dp` becomes `2.50` instead of
+	/// `2.5`. This is disabled by default.
+	pub fn set_keep_trailing_zeros(&mut self, keep: bool) {
+		self.digit_format.trailing_zeros.keep = keep;
+	}
+
+	/// Also pad integers with a decimal point and trailing zeros when a
+	/// number of decimal places or significant figures is requested, e.g.
+	/// `3 to 2 dp` becomes `3.00` instead of `3`. This only has an effect if
+	/// [`Context::set_keep_trailing_zeros`] is enabled.
+	pub fn set_integer_decimal_point(&mut self, enable: bool) {
+		self.digit_format.trailing_zeros.integer_decimal_point = enable;
+	}
+
+	/// Show exact results as fractions instead of decimals by default, e.g.
+	/// `2.75` becomes `11/4`. Approximate results like `pi` are still shown
+	/// as decimals, and `to float` shows a single result as a decimal. This
+	/// is disabled by default.
+	pub fn set_prefer_fractions(&mut self, enable: bool) {
+		self.digit_format.prefer_fractions = enable;
+	}
+
+	/// Separate the digits of binary results into groups of the given size,
+	/// e.g. `0b1010_1100` for groups of 4 (nibbles) or 8 (bytes). Digits are
+	/// not grouped by default.
+	pub fn set_binary_digit_grouping(&mut self, group_size: Option<usize>) {
+		self.digit_format.grouping.binary = group_size.filter(|&size| size > 0);
+	}
+
+	/// Separate the digits of hexadecimal results into groups of the given
+	/// size, e.g. `0xdead_beef` for groups of 4. Digits are not grouped by
+	/// default.
+	pub fn set_hex_digit_grouping(&mut self, group_size: Option<usize>) {
+		self.digit_format.grouping.hex = group_size.filter(|&size| size > 0);
+	}
+
+	/// Choose how recurring digits are shown, e.g. `0.1(6)` or `0.16̅`.
+	/// Front-ends should only use overlines if they can display them.
+	pub fn set_recurring_digits_notation(&mut self, notation: RecurringDigitsNotation) {
+		self.digit_format.recurring = notation;
+	}
+
+	/// Choose how units with negative exponents are shown in results, e.g.
+	/// `m / s` or `m s^-1`. This does not affect other numbers.
+	pub fn set_unit_exponent_notation(&mut self, notation: UnitExponentNotation) {
+		self.unit_exponent_notation = notation;
+	}
+
+	/// Show approximate results together with their exact or symbolic form,
+	/// e.g. `1/3 ≈ 0.3333333333` or `sqrt(2) ≈ 1.4142135619`, instead of
+	/// `approx. 0.3333333333`. This is disabled by default.
+	pub fn set_exact_and_approximate(&mut self, enable: bool) {
+		self.exact_and_approximate = enable;
+	}
+
+	/// Show negative amounts of money in parentheses, e.g. `($1234.56)`
+	/// instead of `-$1234.56`, as is common in financial documents. This is
+	/// disabled by default.
+	pub fn set_accounting_negatives(&mut self, enable: bool) {
+		self.accounting_negatives = enable;
+	}
+
+	/// Replace combinations of base units in results with a matching SI
+	/// derived unit, e.g. `1 kg m / s^2` becomes `1 newton`. If this is
+	/// disabled, such results are shown as `1 kg m / s^2`. This is enabled
+	/// by default.
+	pub fn set_derived_units(&mut self, enable: bool) {
+		self.derived_units = enable;
+	}
+
+	/// Choose the sign used to multiply units in results, e.g. `N m`,
+	/// `N * m`, `N·m` or `N × m`.
+	pub fn set_multiplication_sign(&mut self, sign: MultiplicationSign) {
+		self.multiplication_sign = sign;
+	}
+
+	/// Record the conversion factors used in unit conversions, e.g.
+	/// `1 mile = 1609.344 meters` and `1 kilometer = 1000 meters` for
+	/// `5 miles to km`. These can be retrieved using
+	/// [`FendResult::conversion_steps`]. This is disabled by default.
+	pub fn set_show_conversion_steps(&mut self, enable: bool) {
+		self.show_conversion_steps = enable;
+	}
+
+	/// Allow variables to be assigned names of built-in constants like `pi`,
+	/// `e` or `i`, e.g. `e = 5`. This is disabled by default, so that such
+	/// assignments return an error instead of silently changing the meaning
+	/// of later calculations.
+	pub fn set_allow_shadowing_constants(&mut self, allow: bool) {
+		self.allow_shadowing_constants = allow;
+	}
+
+	/// Keep unbound single-letter identifiers like `x` as symbolic
+	/// variables, so that e.g. `2x + 3x` is `5x`. This is disabled by
+	/// default, so that such identifiers are reported as unknown. Symbolic
+	/// variables can always be used inside `expand`, `diff` and `solve`.
+	pub fn set_symbolic_variables(&mut self, enable: bool) {
+		self.symbolic_variables = enable;
+	}
+
+	/// Choose the sign used in fractions and to divide units in results,
+	/// e.g. `1/3` and `m / s`, or `1∕3` and `m ∕ s`.
+	pub fn set_division_sign(&mut self, sign: DivisionSign) {
+		self.digit_format.division = sign;
+	}
+
+	/// Choose how the digits of decimal results are grouped, and whether a
+	/// decimal point or a decimal comma is used, e.g. `1,234,567.5` or
+	/// `1.234.567,5`. Binary and hexadecimal grouping is configured with
+	/// [`Context::set_binary_digit_grouping`] and
+	/// [`Context::set_hex_digit_grouping`].
+	pub fn set_digit_separators(&mut self, separators: DigitSeparators) {
+		self.digit_format.grouping.decimal = separators;
+	}
+
+	/// Also use the separators from [`Context::set_digit_separators`] when
+	/// parsing numbers in the input, so that e.g. `1.234,5` means 1234.5
+	/// when using [`DigitSeparators::PeriodDecimalComma`]. This is disabled
+	/// by default, in which case `,` and `_` can separate digits and `.` is
+	/// the decimal point.
+	pub fn set_parse_digit_separators(&mut self, enable: bool) {
+		self.parse_digit_separators = enable;
+	}
+
+	fn input_number_syntax(&self) -> Option<lexer::NumberSyntax> {
+		self.parse_digit_separators
+			.then(|| self.digit_format.grouping.decimal.into())
+	}
+
+	/// Choose the language used for error messages. This defaults to
+	/// English.
+	pub fn set_locale(&mut self, locale: Locale) {
+		self.locale = locale;
+	}
+
+	/// Choose whether implicit multiplication binds more tightly than
+	/// division, i.e. whether `1/2x` means `(1/2)x` (the default) or `1/(2x)`.
+	pub fn set_implicit_multiplication(&mut self, implicit_multiplication: ImplicitMultiplication) {
+		self.implicit_multiplication = implicit_multiplication;
+	}
+
+	/// Choose whether results with mixed units are simplified to metric
+	/// units (the default) or to imperial units, e.g. whether `3 ft * 2 m`
+	/// is shown in `m^2` or in `ft^2`.
+	pub fn set_unit_system(&mut self, unit_system: UnitSystem) {
+		self.unit_system = unit_system;
+	}
+
+	fn serialize_values(
+		values: &HashMap<String, value::Value>,
+		write: &mut impl io::Write,
+	) -> FResult<()> {
+		values.len().serialize(write)?;
+		for (k, v) in values {
 			k.as_str().serialize(write)?;
 			v.serialize(write)?;
 		}
@@ -299,20 +916,23 @@ impl Context {
 	/// # Errors
 	/// This function returns an error if the input cannot be serialized.
 	pub fn serialize_variables(&self, write: &mut impl io::Write) -> Result<(), String> {
-		match self.serialize_variables_internal(write) {
+		match Self::serialize_values(&self.variables, write) {
 			Ok(()) => Ok(()),
 			Err(e) => Err(e.to_string()),
 		}
 	}
 
-	fn deserialize_variables_internal(&mut self, read: &mut impl io::Read) -> FResult<()> {
+	fn deserialize_values(
+		values: &mut HashMap<String, value::Value>,
+		read: &mut impl io::Read,
+	) -> FResult<()> {
 		let len = usize::deserialize(read)?;
-		self.variables.clear();
-		self.variables.reserve(len);
+		values.clear();
+		values.reserve(len);
 		for _ in 0..len {
 			let s = String::deserialize(read)?;
 			let v = value::Value::deserialize(read)?;
-			self.variables.insert(s, v);
+			values.insert(s, v);
 		}
 		Ok(())
 	}
@@ -324,12 +944,138 @@ impl Context {
 	/// Returns an error if the input byte stream is invalid and cannot be
 	/// deserialized.
 	pub fn deserialize_variables(&mut self, read: &mut impl io::Read) -> Result<(), String> {
-		match self.deserialize_variables_internal(read) {
+		match Self::deserialize_values(&mut self.variables, read) {
+			Ok(()) => Ok(()),
+			Err(e) => Err(e.to_string()),
+		}
+	}
+
+	/// Serializes the calculator memory registers (used by e.g. `mplus` and
+	/// `store`) to a stream of bytes, so that they can be restored in a later
+	/// session. Like [`Context::serialize_variables`], the format is NOT
+	/// stable.
+	///
+	/// # Errors
+	/// This function returns an error if the memory cannot be serialized.
+	pub fn serialize_memory(&self, write: &mut impl io::Write) -> Result<(), String> {
+		match Self::serialize_values(&self.memory, write) {
 			Ok(()) => Ok(()),
 			Err(e) => Err(e.to_string()),
 		}
 	}
 
+	/// Deserializes the given calculator memory registers, replacing all prior
+	/// registers in the given context.
+	///
+	/// # Errors
+	/// Returns an error if the input byte stream is invalid and cannot be
+	/// deserialized.
+	pub fn deserialize_memory(&mut self, read: &mut impl io::Read) -> Result<(), String> {
+		match Self::deserialize_values(&mut self.memory, read) {
+			Ok(()) => Ok(()),
+			Err(e) => Err(e.to_string()),
+		}
+	}
+
+	fn serialize_user_units_internal(&self, write: &mut impl io::Write) -> FResult<()> {
+		self.user_units.len().serialize(write)?;
+		for (name, definition) in &self.user_units {
+			name.as_str().serialize(write)?;
+			definition.as_str().serialize(write)?;
+		}
+		Ok(())
+	}
+
+	/// Serializes all units defined with e.g. `unit bottle = 0.75 liters` to a
+	/// stream of bytes, so that they can be restored in a later session. Like
+	/// [`Context::serialize_variables`], the format is NOT stable.
+	///
+	/// # Errors
+	/// This function returns an error if the units cannot be serialized.
+	pub fn serialize_user_units(&self, write: &mut impl io::Write) -> Result<(), String> {
+		self.serialize_user_units_internal(write)
+			.map_err(|e| e.to_string())
+	}
+
+	fn deserialize_user_units_internal(read: &mut impl io::Read) -> FResult<Vec<(String, String)>> {
+		let len = usize::deserialize(read)?;
+		let mut user_units = Vec::with_capacity(len);
+		for _ in 0..len {
+			user_units.push((String::deserialize(read)?, String::deserialize(read)?));
+		}
+		Ok(user_units)
+	}
+
+	/// Deserializes the given units, replacing all units previously defined
+	/// with `unit` in the given context.
+	///
+	/// # Errors
+	/// Returns an error if the input byte stream is invalid and cannot be
+	/// deserialized.
+	pub fn deserialize_user_units(&mut self, read: &mut impl io::Read) -> Result<(), String> {
+		self.user_units = Self::deserialize_user_units_internal(read).map_err(|e| e.to_string())?;
+		Ok(())
+	}
+
+	fn serialize_internal(&self, write: &mut impl io::Write) -> FResult<()> {
+		Self::serialize_values(&self.variables, write)?;
+		Self::serialize_values(&self.memory, write)?;
+		self.serialize_user_units_internal(write)?;
+		self.formulas.len().serialize(write)?;
+		for formula in &self.formulas {
+			formula.serialize(write)?;
+		}
+		Ok(())
+	}
+
+	/// Serializes the session state of this context to a stream of bytes:
+	/// variables (including `ans`), memory registers, units defined with
+	/// `unit` and formulas defined with `formula`. Settings like the output
+	/// format are not included. Like [`Context::serialize_variables`], the
+	/// format is NOT stable.
+	///
+	/// # Errors
+	/// This function returns an error if the state cannot be serialized.
+	pub fn serialize(&self, write: &mut impl io::Write) -> Result<(), String> {
+		self.serialize_internal(write).map_err(|e| e.to_string())
+	}
+
+	fn deserialize_internal(&mut self, read: &mut impl io::Read) -> FResult<()> {
+		let mut variables = HashMap::new();
+		Self::deserialize_values(&mut variables, read)?;
+		let mut memory = HashMap::new();
+		Self::deserialize_values(&mut memory, read)?;
+		let user_units = Self::deserialize_user_units_internal(read)?;
+		let len = usize::deserialize(read)?;
+		let mut formulas = Vec::with_capacity(len);
+		for _ in 0..len {
+			formulas.push(formula::Formula::deserialize(read)?);
+		}
+		self.variables = variables;
+		self.memory = memory;
+		self.user_units = user_units;
+		self.formulas = formulas;
+		Ok(())
+	}
+
+	/// Restores state saved with [`Context::serialize`], replacing all
+	/// prior variables, memory registers, units defined with `unit` and
+	/// formulas in this context. The context is left unchanged if the input
+	/// is invalid.
+	///
+	/// # Errors
+	/// Returns an error if the input byte stream is invalid and cannot be
+	/// deserialized.
+	pub fn deserialize(&mut self, read: &mut impl io::Read) -> Result<(), String> {
+		self.deserialize_internal(read).map_err(|e| e.to_string())
+	}
+
+	/// Defines (or redefines) a unit, e.g. for `unit bottle = 0.75 liters`
+	pub(crate) fn define_unit(&mut self, name: &str, definition: String) {
+		self.user_units.retain(|(existing, _)| existing != name);
+		self.user_units.push((name.to_string(), definition));
+	}
+
 	/// Set a handler function for loading exchange rates.
 	pub fn set_exchange_rate_handler_v1<T: ExchangeRateFn + 'static + Send + Sync>(
 		&mut self,
@@ -387,29 +1133,197 @@ pub fn evaluate(input: &str, context: &mut Context) -> Result<FendResult, String
 	evaluate_with_interrupt(input, context, &interrupt::Never)
 }
 
+/// Evaluates a string using a new default [`Context`], and returns the main
+/// result as a string. Use [`evaluate`] to keep variables between
+/// calculations or to change any settings. As the default context has no
+/// current time, `today` and `now` return an error.
+///
+/// For example, passing in the string `"1 + 1"` will return `"2"`.
+///
+/// # Errors
+/// It returns an error if the given string is invalid.
+/// This may be due to parser or runtime errors.
+pub fn evaluate_standalone(input: &str) -> Result<String, String> {
+	let mut context = Context::new();
+	Ok(evaluate(input, &mut context)?.get_main_result().to_string())
+}
+
+/// The category of an [`EvaluationError`], for programmatic handling of
+/// errors.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+	/// The input could not be parsed, e.g. `1 +` or an unterminated string
+	Syntax,
+	/// An unknown variable, unit or function was used
+	UnknownIdentifier,
+	/// Division or modulo by zero
+	DivisionByZero,
+	/// A conversion between incompatible units, e.g. `1 kg to m`
+	IncompatibleUnits,
+	/// A value was too large or outside of the allowed range
+	OutOfRange,
+	/// Something that isn't available in this context was required, such
+	/// as random numbers, exchange rates or a disabled feature
+	Unavailable,
+	/// The calculation was interrupted
+	Interrupted,
+	/// Any other error
+	Other,
+}
+
+impl ErrorKind {
+	fn as_str(self) -> &'static str {
+		match self {
+			Self::Syntax => "syntax",
+			Self::UnknownIdentifier => "unknown_identifier",
+			Self::DivisionByZero => "division_by_zero",
+			Self::IncompatibleUnits => "incompatible_units",
+			Self::OutOfRange => "out_of_range",
+			Self::Unavailable => "unavailable",
+			Self::Interrupted => "interrupted",
+			Self::Other => "other",
+		}
+	}
+}
+
+/// An error returned by [`evaluate_with_error_details`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EvaluationError {
+	kind: ErrorKind,
+	message: String,
+	span: Option<ops::Range<usize>>,
+}
+
+impl EvaluationError {
+	/// The category of this error
+	#[must_use]
+	pub fn kind(&self) -> ErrorKind {
+		self.kind
+	}
+
+	/// The error message, translated according to [`Context::set_locale`]
+	#[must_use]
+	pub fn message(&self) -> &str {
+		&self.message
+	}
+
+	/// The byte range of the input containing the offending token. This is
+	/// only available for syntax errors.
+	#[must_use]
+	pub fn span(&self) -> Option<ops::Range<usize>> {
+		self.span.clone()
+	}
+
+	/// Returns the error as a JSON object, e.g.
+	/// `{"type":"error","kind":"syntax","message":"...","span":[4,5]}`.
+	/// The span is omitted if it isn't known.
+	#[must_use]
+	pub fn to_json(&self) -> String {
+		let mut result = format!(
+			"{{\"type\":\"error\",\"kind\":\"{}\",\"message\":\"",
+			self.kind.as_str()
+		);
+		json::escape_string(&self.message, &mut result);
+		result.push('"');
+		if let Some(span) = &self.span {
+			write!(result, ",\"span\":[{},{}]", span.start, span.end).unwrap();
+		}
+		result.push('}');
+		result
+	}
+}
+
+impl fmt::Display for EvaluationError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+impl std::error::Error for EvaluationError {}
+
 fn evaluate_with_interrupt_internal(
 	input: &str,
 	context: &mut Context,
 	int: &impl Interrupt,
-) -> Result<FendResult, String> {
+) -> Result<FendResult, EvaluationError> {
 	if input.is_empty() {
 		// no or blank input: return no output
 		return Ok(FendResult::empty());
 	}
-	let (result, is_unit, attrs) = match eval::evaluate_to_spans(input, None, context, int) {
-		Ok(value) => value,
-		Err(e) => return Err(e.to_string()),
-	};
+	evaluate_in_scope(input, None, true, context, int).map(|(result, _)| result)
+}
+
+fn evaluate_in_scope(
+	input: &str,
+	scope: Option<Arc<scope::Scope>>,
+	save_result: bool,
+	context: &mut Context,
+	int: &impl Interrupt,
+) -> Result<(FendResult, value::Value), EvaluationError> {
+	let (result, attrs, info, value) =
+		match eval::evaluate_to_spans(input, scope, save_result, context, int) {
+			Ok(value) => value,
+			Err(e) => {
+				return Err(EvaluationError {
+					kind: e.kind(),
+					message: context.locale.translate_error(&e),
+					span: e.span(),
+				})
+			}
+		};
 	let mut plain_result = String::new();
 	for s in &result {
 		plain_result.push_str(&s.string);
 	}
-	Ok(FendResult {
+	let result = FendResult {
 		plain_result,
 		span_result: result,
-		is_unit,
+		is_unit: value.is_unit(),
 		attrs,
-	})
+		info,
+	};
+	Ok((result, value))
+}
+
+/// A calculated value that can be used in later calculations with
+/// [`evaluate_with_values`], e.g. as an entry on the stack of a Reverse
+/// Polish Notation calculator.
+#[derive(Clone)]
+pub struct StoredValue(value::Value);
+
+impl fmt::Debug for StoredValue {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("StoredValue").field(&self.0).finish()
+	}
+}
+
+/// Evaluates `input` with each of the given names bound to a value from an
+/// earlier call, e.g. `a + b`, and returns the result together with its
+/// value. Values are used as they are, so exact results stay exact and
+/// nothing is evaluated twice. Unlike [`evaluate_with_interrupt`], this
+/// doesn't save the result in `_` or `ans`.
+///
+/// # Errors
+/// It returns an error if the given string is invalid.
+/// This may be due to parser or runtime errors.
+pub fn evaluate_with_values(
+	input: &str,
+	values: &[(&str, &StoredValue)],
+	context: &mut Context,
+	int: &impl Interrupt,
+) -> Result<(FendResult, StoredValue), EvaluationError> {
+	let mut scope = None;
+	for (name, value) in values {
+		scope = Some(Arc::new(scope::Scope::with_variable(
+			ident::Ident::new_string((*name).to_string()),
+			ast::Expr::Literal(value.0.clone()),
+			None,
+			scope,
+		)));
+	}
+	let (result, value) = evaluate_in_scope(input, scope, false, context, int)?;
+	Ok((result, StoredValue(value)))
 }
 
 /// This function evaluates a string using the given context and the provided
@@ -425,9 +1339,38 @@ pub fn evaluate_with_interrupt(
 	context: &mut Context,
 	int: &impl Interrupt,
 ) -> Result<FendResult, String> {
+	evaluate_with_interrupt_internal(input, context, int).map_err(|e| e.message)
+}
+
+/// Like [`evaluate_with_interrupt`], but errors also include their kind and,
+/// for syntax errors, the location of the offending token in the input, so
+/// that it can be highlighted.
+///
+/// # Errors
+/// It returns an error if the given string is invalid.
+/// This may be due to parser or runtime errors.
+pub fn evaluate_with_error_details(
+	input: &str,
+	context: &mut Context,
+	int: &impl Interrupt,
+) -> Result<FendResult, EvaluationError> {
 	evaluate_with_interrupt_internal(input, context, int)
 }
 
+/// Evaluates the given string and returns the result as a JSON object, for
+/// consumption by scripts and editor plugins.
+///
+/// Successful results are formatted as in [`ResultFormat::Json`], e.g.
+/// `{"type":"number","plain":"5 km","approx":false,"number":"5","units":[...]}`,
+/// and errors as in [`EvaluationError::to_json`], with the type `error`.
+#[must_use]
+pub fn evaluate_to_json(input: &str, context: &mut Context, int: &impl Interrupt) -> String {
+	match evaluate_with_interrupt_internal(input, context, int) {
+		Ok(result) => result.get_result_in_format(ResultFormat::Json).to_string(),
+		Err(e) => e.to_json(),
+	}
+}
+
 /// Evaluate the given string to use as a live preview.
 ///
 /// Unlike the normal evaluation functions, `evaluate_preview_with_interrupt`
@@ -435,6 +1378,10 @@ pub fn evaluate_with_interrupt(
 /// for displaying as a live preview: overly long output, multi-line output,
 /// unit types etc. are all filtered out. RNG functions (e.g. `roll d6`) are
 /// also disabled. Currency conversions (exchange rates) are disabled.
+///
+/// Incomplete input such as `1 + 2 *` or `(1 + 2` is evaluated on a
+/// best-effort basis, by ignoring trailing operators and closing any open
+/// brackets.
 pub fn evaluate_preview_with_interrupt(
 	input: &str,
 	context: &mut Context,
@@ -445,9 +1392,17 @@ pub fn evaluate_preview_with_interrupt(
 	// because we want variables to still work in multi-statement inputs
 	// like `a = 2; 5a`.
 	let context_clone = context.clone();
-	context.random_u32 = None;
+	context.disable_rng();
 	context.get_exchange_rate = None;
-	let result = evaluate_with_interrupt_internal(input, context, int);
+	let mut result = evaluate_with_interrupt_internal(input, context, int);
+	let mut completed_input = None;
+	if matches!(&result, Err(e) if e.kind == ErrorKind::Syntax) {
+		// parsing failed, so nothing has been evaluated yet
+		if let Some(completed) = complete_partial_input(input) {
+			result = evaluate_with_interrupt_internal(&completed, context, int);
+			completed_input = Some(completed);
+		}
+	}
 	*context = context_clone;
 	let Ok(result) = result else {
 		return empty;
@@ -457,6 +1412,7 @@ pub fn evaluate_preview_with_interrupt(
 		|| result.is_unit_type()
 		|| s.len() > 50
 		|| s.trim() == input.trim()
+		|| completed_input.is_some_and(|completed| s.trim() == completed.trim())
 		|| s.contains(|c| c < ' ')
 	{
 		return empty;
@@ -464,10 +1420,59 @@ pub fn evaluate_preview_with_interrupt(
 	result
 }
 
+/// Like [`evaluate_preview_with_interrupt`], but without an interrupt and
+/// without requiring a mutable context.
+#[must_use]
+pub fn evaluate_preview(input: &str, context: &Context) -> FendResult {
+	let mut context = context.clone();
+	evaluate_preview_with_interrupt(input, &mut context, &interrupt::Never)
+}
+
+/// Makes a best-effort attempt at completing input that is still being
+/// typed, by removing trailing operators and closing any open brackets,
+/// e.g. `(1 + 2 *` becomes `(1 + 2)`.
+fn complete_partial_input(input: &str) -> Option<String> {
+	let mut trimmed = input.trim_end();
+	loop {
+		let shorter = trimmed.trim_end_matches(|c: char| {
+			c.is_whitespace() || "+-*/^\u{d7}\u{f7}\u{b7},=(".contains(c)
+		});
+		let shorter = shorter
+			.strip_suffix(" to")
+			.or_else(|| shorter.strip_suffix(" as"))
+			.unwrap_or(shorter);
+		if shorter == trimmed {
+			break;
+		}
+		trimmed = shorter;
+	}
+	let mut closing_brackets = vec![];
+	for ch in trimmed.chars() {
+		match ch {
+			'(' => closing_brackets.push(')'),
+			'[' => closing_brackets.push(']'),
+			')' | ']' => {
+				let expected = closing_brackets.pop();
+				if expected != Some(ch) {
+					return None;
+				}
+			}
+			_ => (),
+		}
+	}
+	let mut result = trimmed.to_string();
+	result.extend(closing_brackets.into_iter().rev());
+	if result.is_empty() || result == input {
+		return None;
+	}
+	Some(result)
+}
+
 #[derive(Debug)]
 pub struct Completion {
 	display: String,
 	insert: String,
+	kind: CompletionKind,
 }
 
 impl Completion {
@@ -480,6 +1485,22 @@ impl Completion {
 	pub fn insert(&self) -> &str {
 		&self.insert
 	}
+
+	/// Returns what kind of item this completion refers to.
+	#[must_use]
+	pub fn kind(&self) -> CompletionKind {
+		self.kind
+	}
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CompletionKind {
+	Variable,
+	Function,
+	Keyword,
+	Unit,
+	Other,
 }
 
 static GREEK_LOWERCASE_LETTERS: [(&str, &str); 24] = [
@@ -535,11 +1556,11 @@ static GREEK_UPPERCASE_LETTERS: [(&str, &str); 24] = [
 	("Omega", "Ω"),
 ];
 
-#[must_use]
-pub fn get_completions_for_prefix(mut prefix: &str) -> (usize, Vec<Completion>) {
-	if let Some((prefix, letter)) = prefix.rsplit_once('\\') {
-		if letter.starts_with(|c: char| c.is_ascii_alphabetic()) && letter.len() <= 7 {
-			return if letter.starts_with(|c: char| c.is_ascii_uppercase()) {
+fn get_greek_letter_completion(input: &str) -> Option<(usize, Vec<Completion>)> {
+	let (prefix, letter) = input.rsplit_once('\\')?;
+	if letter.starts_with(|c: char| c.is_ascii_alphabetic()) && letter.len() <= 7 {
+		return Some(
+			if letter.starts_with(|c: char| c.is_ascii_uppercase()) {
 				GREEK_UPPERCASE_LETTERS
 			} else {
 				GREEK_LOWERCASE_LETTERS
@@ -552,10 +1573,19 @@ pub fn get_completions_for_prefix(mut prefix: &str) -> (usize, Vec<Completion>)
 					vec![Completion {
 						display: prefix.to_string(),
 						insert: l.1.to_string(),
+						kind: CompletionKind::Other,
 					}],
 				)
-			});
-		}
+			}),
+		);
+	}
+	None
+}
+
+#[must_use]
+pub fn get_completions_for_prefix(mut prefix: &str) -> (usize, Vec<Completion>) {
+	if let Some(greek) = get_greek_letter_completion(prefix) {
+		return greek;
 	}
 
 	let mut prepend = "";
@@ -575,6 +1605,19 @@ pub fn get_completions_for_prefix(mut prefix: &str) -> (usize, Vec<Completion>)
 	(position, res)
 }
 
+/// Returns completions for the word at the end of `input`, taking variables
+/// and custom units defined in the given context into account.
+///
+/// Candidates are ranked by kind (variables first, followed by functions,
+/// keywords and units), then by length. The returned index is the byte
+/// offset in `input` where the replacement starts: each candidate's
+/// `insert()` string should replace everything from that offset onwards.
+#[must_use]
+pub fn get_completions(input: &str, context: &Context) -> (usize, Vec<Completion>) {
+	completion::get_completions(input, context)
+}
+
+pub use highlight::{highlight, HighlightSpan};
 pub use inline_substitutions::substitute_inline_fend_expressions;
 
 const fn get_version_as_str() -> &'static str {
@@ -0,0 +1,150 @@
+use crate::{error::FendError, parser::ParseError};
+
+/// The language used for error messages.
+///
+/// The translations are part of fend-core, so every front-end (e.g. the CLI
+/// and the WebAssembly bindings) shows the same messages. Errors that have
+/// not been translated yet are shown in English.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum Locale {
+	/// English. This is the default.
+	#[default]
+	English,
+	/// German
+	German,
+}
+
+impl Locale {
+	/// Finds the locale for a language tag like `de` or `de-AT`, or a POSIX
+	/// locale name like `de_DE.UTF-8` (as used in the `LANG` environment
+	/// variable). Returns `None` if the language is not supported.
+	#[must_use]
+	pub fn from_language_tag(tag: &str) -> Option<Self> {
+		let language = tag
+			.split(['_', '-', '.', '@'])
+			.next()
+			.unwrap_or_default()
+			.to_ascii_lowercase();
+		match language.as_str() {
+			"en" | "c" | "posix" => Some(Self::English),
+			"de" => Some(Self::German),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn translate_error(self, error: &FendError) -> String {
+		let translation = match self {
+			Self::English => None,
+			Self::German => german(error),
+		};
+		translation.unwrap_or_else(|| error.to_string())
+	}
+}
+
+fn german(error: &FendError) -> Option<String> {
+	Some(match error {
+		FendError::Interrupted => "unterbrochen".to_string(),
+		FendError::ParseError(e) => german_parse_error(e)?,
+		FendError::AtColumn(e, column, _) => {
+			format!("{} in Spalte {column}", Locale::German.translate_error(e))
+		}
+		FendError::DivideByZero => "Division durch null".to_string(),
+		FendError::ModuloByZero => "Modulo durch null".to_string(),
+		FendError::ExponentTooLarge => "Exponent ist zu groß".to_string(),
+		FendError::ValueTooLarge => "Wert ist zu groß".to_string(),
+		FendError::ZeroToThePowerOfZero => "null hoch null ist nicht definiert".to_string(),
+		FendError::FactorialComplex => {
+			"die Fakultät wird für komplexe Zahlen nicht unterstützt".to_string()
+		}
+		FendError::NoExchangeRatesAvailable => "Wechselkurse sind nicht verfügbar".to_string(),
+		FendError::FeatureDisabled(feature) => {
+			format!("dieser Build von fend unterstützt die Funktion `{feature}` nicht")
+		}
+		FendError::OutOfRange { value, range } => {
+			format!("{value} muss im Intervall {range} liegen")
+		}
+		FendError::IncompatibleConversion {
+			from,
+			to,
+			from_base,
+			to_base,
+		} => format!(
+			"{from} kann nicht in {to} umgerechnet werden: die Einheiten '{from_base}' \
+			und '{to_base}' sind inkompatibel"
+		),
+		FendError::RootsOfNegativeNumbers => {
+			"Wurzeln aus negativen Zahlen werden nicht unterstützt".to_string()
+		}
+		FendError::BaseTooSmall => "die Basis muss mindestens 2 sein".to_string(),
+		FendError::BaseTooLarge => "die Basis darf nicht größer als 36 sein".to_string(),
+		FendError::SpecifyNumDp => {
+			"bitte geben Sie die Anzahl der Nachkommastellen an, z. B. '10 dp'".to_string()
+		}
+		FendError::SpecifyNumSf => {
+			"bitte geben Sie die Anzahl der signifikanten Stellen an, z. B. '10 sf'".to_string()
+		}
+		FendError::NegativeNumbersNotAllowed => "negative Zahlen sind nicht erlaubt".to_string(),
+		FendError::FractionToInteger => {
+			"ein Bruch kann nicht in eine ganze Zahl umgewandelt werden".to_string()
+		}
+		FendError::MustBeAnInteger(x) => format!("{x} ist keine ganze Zahl"),
+		FendError::RandomNumbersNotAvailable => "Zufallszahlen sind nicht verfügbar".to_string(),
+		FendError::UnableToGetCurrentDate => "das aktuelle Datum ist nicht verfügbar".to_string(),
+		FendError::ExpectedANumber => "Zahl erwartet".to_string(),
+		FendError::ExpectedARealNumber => "reelle Zahl erwartet".to_string(),
+		FendError::ExpectedARationalNumber => "rationale Zahl erwartet".to_string(),
+		FendError::ExpectedAUnitlessNumber => "einheitenlose Zahl erwartet".to_string(),
+		FendError::ExpectedAString => "Zeichenkette erwartet".to_string(),
+		FendError::ExpectedAnObject => "Objekt erwartet".to_string(),
+		FendError::IsNotAFunction(s) => format!("'{s}' ist keine Funktion"),
+		FendError::IsNotAFunctionOrNumber(s) => {
+			format!("'{s}' ist weder eine Funktion noch eine Zahl")
+		}
+		FendError::IdentifierNotFound(s) => format!("unbekannter Bezeichner '{s}'"),
+		FendError::IdentifierNotFoundDidYouMean(s, suggestion) => {
+			format!("unbekannter Bezeichner '{s}'; meinten Sie '{suggestion}'?")
+		}
+		FendError::UnexpectedChar(ch) => format!("unerwartetes Zeichen '{ch}'"),
+		FendError::UnterminatedStringLiteral => {
+			"nicht abgeschlossenes Zeichenkettenliteral".to_string()
+		}
+		FendError::InvalidDiceSyntax => {
+			"ungültige Würfelsyntax, versuchen Sie z. B. `4d6`".to_string()
+		}
+		FendError::RomanNumeralZero => {
+			"null kann nicht als römische Zahl dargestellt werden".to_string()
+		}
+		FendError::UnknownFormula(name) => format!("unbekannte Formel '{name}'"),
+		FendError::VectorDimensionMismatch(a, b) => format!(
+			"ein Vektor mit {a} Komponenten und ein Vektor mit {b} Komponenten \
+			können nicht kombiniert werden"
+		),
+		FendError::CannotMultiplyVectors => "zwei Vektoren können nicht multipliziert \
+			werden, versuchen Sie stattdessen `dot` oder `cross`"
+			.to_string(),
+		FendError::TooFewDataPoints(n) => {
+			format!("mindestens 2 Datenpunkte erwartet, aber {n} gefunden")
+		}
+		_ => return None,
+	})
+}
+
+fn german_parse_error(error: &ParseError) -> Option<String> {
+	Some(match error {
+		ParseError::ExpectedAToken => "Token erwartet".to_string(),
+		ParseError::ExpectedToken(found, expected) => {
+			format!("'{found}' gefunden, aber '{expected}' erwartet")
+		}
+		ParseError::FoundInvalidTokenWhileExpecting(sym) => {
+			format!("ungültiges Token gefunden, '{sym}' erwartet")
+		}
+		ParseError::ExpectedANumber => "Zahl erwartet".to_string(),
+		ParseError::ExpectedIdentifier
+		| ParseError::ExpectedIdentifierAsArgument
+		| ParseError::ExpectedIdentifierInAssignment => "Bezeichner erwartet".to_string(),
+		ParseError::UnexpectedSymbol(s) => format!("Wert erwartet, aber '{s}' gefunden"),
+		ParseError::UnexpectedInput => "unerwartete Eingabe".to_string(),
+		_ => return None,
+	})
+}
@@ -0,0 +1,428 @@
+use crate::ast::{Bop, Expr};
+use crate::error::{FendError, Interrupt};
+use crate::interrupt::test_int;
+use crate::num::Number;
+use crate::result::FResult;
+use crate::scope::Scope;
+use crate::serialize::{Deserialize, Serialize};
+use crate::value::Value;
+use crate::vector::{
+	compare_numbers, deserialize_numbers, format_numbers, serialize_numbers, sum_of_products,
+	Vector,
+};
+use crate::{Attrs, Span};
+use std::io;
+use std::sync::Arc;
+
+/// A matrix like `[[1, 2], [3, 4]]`, stored as a list of rows. Since numbers
+/// are exact fractions where possible, determinants and inverses are exact
+/// too.
+#[derive(Clone, Debug)]
+pub(crate) struct Matrix {
+	rows: Vec<Vec<Number>>,
+}
+
+impl Matrix {
+	/// Creates a matrix from its rows, which must all have the same length
+	pub(crate) fn from_rows(rows: Vec<Vector>) -> FResult<Self> {
+		let rows = rows
+			.into_iter()
+			.map(Vector::into_cartesian_components)
+			.collect::<FResult<Vec<_>>>()?;
+		if rows.is_empty() || rows.iter().any(|row| row.len() != rows[0].len()) {
+			return Err(FendError::InconsistentMatrixRows);
+		}
+		Ok(Self { rows })
+	}
+
+	fn identity(size: usize) -> Self {
+		Self {
+			rows: (0..size)
+				.map(|i| (0..size).map(|j| Number::from(u64::from(i == j))).collect())
+				.collect(),
+		}
+	}
+
+	/// The number of rows and columns
+	fn dimensions(&self) -> [usize; 2] {
+		[self.rows.len(), self.rows[0].len()]
+	}
+
+	fn expect_square(&self) -> FResult<usize> {
+		match self.dimensions() {
+			[rows, cols] if rows == cols => Ok(rows),
+			[rows, cols] => Err(FendError::ExpectedASquareMatrix(rows, cols)),
+		}
+	}
+
+	fn check_dimensions(&self, other: &Self) -> FResult<()> {
+		if self.dimensions() == other.dimensions() {
+			Ok(())
+		} else {
+			Err(FendError::MatrixDimensionMismatch(
+				self.dimensions(),
+				other.dimensions(),
+			))
+		}
+	}
+
+	pub(crate) fn rows(&self) -> &[Vec<Number>] {
+		&self.rows
+	}
+
+	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
+		self.rows.len().serialize(write)?;
+		for row in &self.rows {
+			serialize_numbers(row.iter(), write)?;
+		}
+		Ok(())
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		let len = usize::deserialize(read)?;
+		let mut rows = Vec::with_capacity(len);
+		for _ in 0..len {
+			rows.push(deserialize_numbers(read)?);
+		}
+		Ok(Self { rows })
+	}
+
+	pub(crate) fn compare<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
+		if self.dimensions() != other.dimensions() {
+			return Ok(false);
+		}
+		for (a, b) in self.rows.iter().zip(&other.rows) {
+			if !compare_numbers(a.iter(), b.iter(), int)? {
+				return Ok(false);
+			}
+		}
+		Ok(true)
+	}
+
+	pub(crate) fn format<I: Interrupt>(
+		&self,
+		spans: &mut Vec<Span>,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<()> {
+		spans.push(Span::from_string("[".to_string()));
+		for (i, row) in self.rows.iter().enumerate() {
+			if i > 0 {
+				spans.push(Span::from_string(", ".to_string()));
+			}
+			format_numbers(row.iter(), spans, attrs, ctx, int)?;
+		}
+		spans.push(Span::from_string("]".to_string()));
+		Ok(())
+	}
+
+	pub(crate) fn map(self, f: impl Fn(Number) -> FResult<Number>) -> FResult<Self> {
+		Ok(Self {
+			rows: self
+				.rows
+				.into_iter()
+				.map(|row| row.into_iter().map(&f).collect())
+				.collect::<FResult<_>>()?,
+		})
+	}
+
+	fn zip_with(self, rhs: Self, f: impl Fn(Number, Number) -> FResult<Number>) -> FResult<Self> {
+		self.check_dimensions(&rhs)?;
+		Ok(Self {
+			rows: self
+				.rows
+				.into_iter()
+				.zip(rhs.rows)
+				.map(|(a, b)| a.into_iter().zip(b).map(|(a, b)| f(a, b)).collect())
+				.collect::<FResult<_>>()?,
+		})
+	}
+
+	fn neg(self) -> Self {
+		Self {
+			rows: self
+				.rows
+				.into_iter()
+				.map(|row| row.into_iter().map(|n| -n).collect())
+				.collect(),
+		}
+	}
+
+	pub(crate) fn transpose(self) -> Self {
+		let [rows, cols] = self.dimensions();
+		let mut result = vec![Vec::with_capacity(rows); cols];
+		for row in self.rows {
+			for (j, n) in row.into_iter().enumerate() {
+				result[j].push(n);
+			}
+		}
+		Self { rows: result }
+	}
+
+	fn mul<I: Interrupt>(&self, rhs: &Self, int: &I) -> FResult<Self> {
+		let ([rows, inner], [rhs_rows, cols]) = (self.dimensions(), rhs.dimensions());
+		if inner != rhs_rows {
+			return Err(FendError::MatrixDimensionMismatch(
+				self.dimensions(),
+				rhs.dimensions(),
+			));
+		}
+		let mut result = Vec::with_capacity(rows);
+		for row in &self.rows {
+			let mut result_row = Vec::with_capacity(cols);
+			for j in 0..cols {
+				let terms: Vec<_> = row
+					.iter()
+					.zip(&rhs.rows)
+					.map(|(a, rhs_row)| (true, a, &rhs_row[j]))
+					.collect();
+				result_row.push(sum_of_products(&terms, int)?);
+			}
+			result.push(result_row);
+		}
+		Ok(Self { rows: result })
+	}
+
+	/// Multiplies this matrix by a column vector
+	fn mul_vector<I: Interrupt>(&self, vector: Vector, int: &I) -> FResult<Vector> {
+		let column = Self::from_rows(vec![vector])?.transpose();
+		let product = self.mul(&column, int)?;
+		Ok(Vector::new(product.transpose().rows.swap_remove(0)))
+	}
+
+	/// Multiplies a row vector by this matrix
+	fn vector_mul<I: Interrupt>(&self, vector: Vector, int: &I) -> FResult<Vector> {
+		let product = Self::from_rows(vec![vector])?.mul(self, int)?;
+		Ok(Vector::new(
+			product.rows.into_iter().next().unwrap_or_default(),
+		))
+	}
+
+	/// Subtracts `factor` times row `source` from row `target`
+	fn sub_row_multiple<I: Interrupt>(
+		&mut self,
+		target: usize,
+		source: usize,
+		factor: &Number,
+		int: &I,
+	) -> FResult<()> {
+		for k in 0..self.rows[target].len() {
+			let delta = self.rows[source][k].clone().mul(factor.clone(), int)?;
+			self.rows[target][k] = self.rows[target][k].clone().sub(delta, int)?;
+		}
+		Ok(())
+	}
+
+	/// Reduces a square matrix to row echelon form using Gaussian
+	/// elimination, and applies the same row operations to `other`. Returns
+	/// `None` if the matrix is singular, and otherwise whether an odd number
+	/// of rows were swapped.
+	fn eliminate<I: Interrupt>(
+		&mut self,
+		mut other: Option<&mut Self>,
+		int: &I,
+	) -> FResult<Option<bool>> {
+		let size = self.expect_square()?;
+		let mut swapped = false;
+		for i in 0..size {
+			test_int(int)?;
+			let mut pivot = None;
+			for (j, row) in self.rows.iter().enumerate().skip(i) {
+				if !row[i].is_zero(int)? {
+					pivot = Some(j);
+					break;
+				}
+			}
+			let Some(pivot) = pivot else {
+				return Ok(None);
+			};
+			if pivot != i {
+				self.rows.swap(i, pivot);
+				if let Some(other) = other.as_mut() {
+					other.rows.swap(i, pivot);
+				}
+				swapped = !swapped;
+			}
+			for j in i + 1..size {
+				let factor = self.rows[j][i].clone().div(self.rows[i][i].clone(), int)?;
+				self.sub_row_multiple(j, i, &factor, int)?;
+				if let Some(other) = other.as_mut() {
+					other.sub_row_multiple(j, i, &factor, int)?;
+				}
+			}
+		}
+		Ok(Some(swapped))
+	}
+
+	pub(crate) fn determinant<I: Interrupt>(mut self, int: &I) -> FResult<Number> {
+		let Some(swapped) = self.eliminate(None, int)? else {
+			return Ok(Number::from(0));
+		};
+		let mut result = Number::from(1);
+		for (i, mut row) in self.rows.into_iter().enumerate() {
+			result = result.mul(row.swap_remove(i), int)?;
+		}
+		Ok(if swapped { -result } else { result })
+	}
+
+	/// Calculates the inverse with Gauss-Jordan elimination
+	pub(crate) fn inverse<I: Interrupt>(mut self, int: &I) -> FResult<Self> {
+		let size = self.expect_square()?;
+		let mut result = Self::identity(size);
+		if self.eliminate(Some(&mut result), int)?.is_none() {
+			return Err(FendError::SingularMatrix);
+		}
+		// `self` is now upper triangular, so work upwards from the last row
+		for i in (0..size).rev() {
+			test_int(int)?;
+			let pivot = self.rows[i][i].clone();
+			for n in &mut result.rows[i] {
+				*n = n.clone().div(pivot.clone(), int)?;
+			}
+			for j in 0..i {
+				let factor = self.rows[j][i].clone();
+				result.sub_row_multiple(j, i, &factor, int)?;
+			}
+		}
+		Ok(result)
+	}
+
+	/// Solves the linear system `self * x = b` by reducing the augmented
+	/// matrix `[self | b]` to reduced row echelon form
+	pub(crate) fn solve<I: Interrupt>(self, b: Vector, int: &I) -> FResult<Vector> {
+		let [rows, cols] = self.dimensions();
+		let b = b.into_cartesian_components()?;
+		if b.len() != rows {
+			return Err(FendError::MatrixDimensionMismatch(
+				self.dimensions(),
+				[b.len(), 1],
+			));
+		}
+		let mut augmented = Self {
+			rows: self
+				.rows
+				.into_iter()
+				.zip(b)
+				.map(|(mut row, b)| {
+					row.push(b);
+					row
+				})
+				.collect(),
+		};
+		let mut pivot_cols = vec![];
+		for col in 0..cols {
+			test_int(int)?;
+			let row = pivot_cols.len();
+			let mut pivot = None;
+			for (j, r) in augmented.rows.iter().enumerate().skip(row) {
+				if !r[col].is_zero(int)? {
+					pivot = Some(j);
+					break;
+				}
+			}
+			let Some(pivot) = pivot else {
+				continue;
+			};
+			augmented.rows.swap(row, pivot);
+			let pivot = augmented.rows[row][col].clone();
+			for n in &mut augmented.rows[row] {
+				*n = n.clone().div(pivot.clone(), int)?;
+			}
+			for j in 0..rows {
+				if j != row {
+					let factor = augmented.rows[j][col].clone();
+					augmented.sub_row_multiple(j, row, &factor, int)?;
+				}
+			}
+			pivot_cols.push(col);
+		}
+		// the remaining rows are all zero on the left, e.g. `0 = 1`
+		for row in &augmented.rows[pivot_cols.len()..] {
+			if !row[cols].is_zero(int)? {
+				return Err(FendError::NoSolution);
+			}
+		}
+		if pivot_cols.len() < cols {
+			return Err(FendError::InfinitelyManySolutions);
+		}
+		Ok(Vector::new(
+			augmented
+				.rows
+				.into_iter()
+				.take(cols)
+				.map(|mut row| row.swap_remove(cols))
+				.collect(),
+		))
+	}
+
+	/// Raises a square matrix to an integer power. Negative powers use the
+	/// inverse.
+	fn pow<I: Interrupt>(self, exponent: Number, int: &I) -> FResult<Self> {
+		let size = self.expect_square()?;
+		let exponent = exponent.try_as_i64(int)?;
+		let mut base = if exponent < 0 {
+			self.inverse(int)?
+		} else {
+			self
+		};
+		let mut result = Self::identity(size);
+		let mut exponent = exponent.unsigned_abs();
+		while exponent > 0 {
+			if exponent % 2 == 1 {
+				result = result.mul(&base, int)?;
+			}
+			base = base.mul(&base, int)?;
+			exponent /= 2;
+		}
+		Ok(result)
+	}
+}
+
+/// Evaluates arithmetic where at least one operand is a matrix
+pub(crate) fn evaluate_bop<I: Interrupt>(
+	bop: Bop,
+	lhs: Value,
+	rhs: Value,
+	scope: Option<Arc<Scope>>,
+	int: &I,
+) -> FResult<Value> {
+	Ok(match (bop, lhs, rhs) {
+		// e.g. `[[1, 2], [3, 4]] * sqrt 2` is parsed as `([[1, 2], [3, 4]] * sqrt) 2`
+		(bop, f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _)), v) => {
+			f.wrap_with_expr(|f| Expr::Bop(bop, f, Box::new(Expr::Literal(v))), scope)?
+		}
+		(bop, v, f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _))) => {
+			f.wrap_with_expr(|f| Expr::Bop(bop, Box::new(Expr::Literal(v)), f), scope)?
+		}
+		(Bop::Plus, Value::Matrix(a), Value::Matrix(b)) => {
+			Value::Matrix(Box::new(a.zip_with(*b, |a, b| a.add(b, int))?))
+		}
+		(Bop::Minus, Value::Matrix(a), Value::Matrix(b)) => {
+			Value::Matrix(Box::new(a.zip_with(*b, |a, b| a.sub(b, int))?))
+		}
+		(Bop::Mul, Value::Matrix(a), Value::Matrix(b)) => Value::Matrix(Box::new(a.mul(&b, int)?)),
+		(Bop::Mul, Value::Matrix(m), Value::Vector(v)) => {
+			Value::Vector(m.mul_vector(v.expect_cartesian()?, int)?)
+		}
+		(Bop::Mul, Value::Vector(v), Value::Matrix(m)) => {
+			Value::Vector(m.vector_mul(v.expect_cartesian()?, int)?)
+		}
+		(Bop::Mul, Value::Matrix(m), Value::Num(n))
+		| (Bop::Mul, Value::Num(n), Value::Matrix(m)) => {
+			Value::Matrix(Box::new(m.map(|x| x.mul((*n).clone(), int))?))
+		}
+		(Bop::Div, Value::Matrix(m), Value::Num(n)) => {
+			Value::Matrix(Box::new(m.map(|x| x.div((*n).clone(), int))?))
+		}
+		(Bop::Pow, Value::Matrix(m), Value::Num(n)) => Value::Matrix(Box::new(m.pow(*n, int)?)),
+		_ => return Err(FendError::InvalidOperandsForMatrixArithmetic),
+	})
+}
+
+pub(crate) fn negate(value: Value) -> FResult<Value> {
+	match value {
+		Value::Matrix(m) => Ok(Value::Matrix(Box::new(m.neg()))),
+		_ => Err(FendError::ExpectedANumber),
+	}
+}
@@ -0,0 +1,200 @@
+/// Phrases that can be removed from the start of a question
+const FILLER_PREFIXES: &[&str] = &[
+	"please",
+	"can you",
+	"could you",
+	"tell me",
+	"what is",
+	"what's",
+	"whats",
+	"what are",
+	"how much is",
+	"calculate",
+	"compute",
+	"evaluate",
+	"convert",
+];
+
+/// Phrases that can be removed from the end of a question
+const FILLER_SUFFIXES: &[&str] = &["please", "are there", "is there", "are", "is"];
+
+/// Removes `prefix` from the start of `s` if it is followed by whitespace,
+/// ignoring case
+fn strip_word_prefix<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+	let head = s.get(..prefix.len())?;
+	let rest = &s[prefix.len()..];
+	if head.eq_ignore_ascii_case(prefix) && rest.starts_with(char::is_whitespace) {
+		Some(rest.trim_start())
+	} else {
+		None
+	}
+}
+
+/// Removes `suffix` from the end of `s` if it is preceded by whitespace,
+/// ignoring case
+fn strip_word_suffix<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+	let idx = s.len().checked_sub(suffix.len())?;
+	let tail = s.get(idx..)?;
+	let rest = &s[..idx];
+	if tail.eq_ignore_ascii_case(suffix) && rest.ends_with(char::is_whitespace) {
+		Some(rest.trim_end())
+	} else {
+		None
+	}
+}
+
+fn strip_fillers(mut s: &str) -> &str {
+	'outer: loop {
+		for prefix in FILLER_PREFIXES {
+			if let Some(rest) = strip_word_prefix(s, prefix) {
+				s = rest;
+				continue 'outer;
+			}
+		}
+		for suffix in FILLER_SUFFIXES {
+			if let Some(rest) = strip_word_suffix(s, suffix) {
+				s = rest;
+				continue 'outer;
+			}
+		}
+		return s;
+	}
+}
+
+/// Replaces a leading `a` or `an` with `1`, e.g. `a fortnight`
+fn replace_article(s: &str) -> String {
+	for article in ["a", "an"] {
+		if let Some(rest) = strip_word_prefix(s, article) {
+			return format!("1 {rest}");
+		}
+	}
+	s.to_string()
+}
+
+/// Splits `s` at the first occurrence of the word `word`
+fn split_at_word<'a>(s: &'a str, word: &str) -> Option<(&'a str, &'a str)> {
+	let mut start = 0;
+	for part in s.split_inclusive(char::is_whitespace) {
+		if part.trim_end().eq_ignore_ascii_case(word) && start > 0 {
+			let (before, after) = s.split_at(start);
+			return Some((before.trim_end(), after[part.len()..].trim_start()));
+		}
+		start += part.len();
+	}
+	None
+}
+
+/// Adds the `@` prefix to ISO 8601 dates like `2000-01-01`, which would
+/// otherwise be parsed as subtractions
+fn date_literal(s: &str) -> String {
+	let is_iso_date = s.len() == 10
+		&& s.bytes().enumerate().all(|(i, b)| match i {
+			4 | 7 => b == b'-',
+			_ => b.is_ascii_digit(),
+		});
+	if is_iso_date {
+		format!("@{s}")
+	} else {
+		s.to_string()
+	}
+}
+
+/// Removes `what %` or `what percent(age)` followed by `of` from the start of
+/// `s`, returning the rest
+fn strip_what_percent_of(s: &str) -> Option<&str> {
+	let rest = ["what %", "what percent", "what percentage"]
+		.iter()
+		.find_map(|prefix| strip_word_prefix(s, prefix))?;
+	strip_word_prefix(rest, "of")
+}
+
+/// Rewrites a question in natural language into an expression, e.g.
+/// `how many seconds in a fortnight` becomes `1 fortnight to seconds`.
+/// Returns `None` if nothing needed to be rewritten.
+pub(crate) fn rewrite(input: &str) -> Option<String> {
+	let trimmed = input.trim().trim_end_matches('?').trim_end();
+	let question = strip_fillers(trimmed);
+	let how_many =
+		strip_word_prefix(question, "how many").or_else(|| strip_word_prefix(question, "how much"));
+	let result = if let Some((part, rest)) = split_at_word(question, "is")
+		.and_then(|(part, rest)| Some((part, strip_what_percent_of(rest)?)))
+	{
+		// e.g. `30 is what % of 120`
+		format!("({part}) / ({rest}) to %")
+	} else if let Some((whole, part)) =
+		strip_what_percent_of(question).and_then(|rest| split_at_word(rest, "is"))
+	{
+		// e.g. `what percent of 120 is 30`
+		format!("({part}) / ({whole}) to %")
+	} else if let Some((unit, dates)) = split_at_word(how_many.unwrap_or(question), "between") {
+		// e.g. `days between 1999-12-31 and 2000-03-01`
+		let (start, end) = split_at_word(dates, "and")?;
+		format!(
+			"({}) - ({}) to {}",
+			date_literal(end),
+			date_literal(start),
+			strip_fillers(unit)
+		)
+	} else if let Some(rest) = how_many {
+		// e.g. `seconds are there in a fortnight`
+		let (unit, quantity) = split_at_word(rest, "in")?;
+		format!(
+			"{} to {}",
+			replace_article(strip_fillers(quantity)),
+			strip_fillers(unit)
+		)
+	} else {
+		replace_article(question)
+	};
+	// only punctuation was removed, so this isn't a question
+	if result.is_empty() || result == trimmed {
+		None
+	} else {
+		Some(result)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::rewrite;
+
+	#[test]
+	fn test_rewrite() {
+		assert_eq!(
+			rewrite("what is 5 feet 3 inches in cm").as_deref(),
+			Some("5 feet 3 inches in cm")
+		);
+		assert_eq!(
+			rewrite("How many seconds in a fortnight?").as_deref(),
+			Some("1 fortnight to seconds")
+		);
+		assert_eq!(
+			rewrite("how many cm are there in an inch").as_deref(),
+			Some("1 inch to cm")
+		);
+		assert_eq!(
+			rewrite("convert 70 mph to m/s please").as_deref(),
+			Some("70 mph to m/s")
+		);
+		assert_eq!(rewrite("what's 2+2").as_deref(), Some("2+2"));
+		assert_eq!(
+			rewrite("days between 1999-12-31 and 2000-03-01").as_deref(),
+			Some("(@2000-03-01) - (@1999-12-31) to days")
+		);
+		assert_eq!(
+			rewrite("How many weeks are there between today and @2030-01-01?").as_deref(),
+			Some("(@2030-01-01) - (today) to weeks")
+		);
+		assert_eq!(
+			rewrite("30 is what % of 120?").as_deref(),
+			Some("(30) / (120) to %")
+		);
+		assert_eq!(
+			rewrite("What percentage of 120 is 30").as_deref(),
+			Some("(30) / (120) to %")
+		);
+		assert_eq!(rewrite("5 m to cm"), None);
+		assert_eq!(rewrite("how many"), None);
+		assert_eq!(rewrite("1?"), None);
+	}
+}
@@ -1,6 +1,7 @@
 use std::fmt;
 
 mod base;
+mod bigint;
 mod bigrat;
 mod biguint;
 mod complex;
@@ -12,7 +13,7 @@ mod formatting_style;
 mod real;
 mod unit;
 
-pub(crate) use formatting_style::FormattingStyle;
+pub(crate) use formatting_style::{DigitFormat, FormattingStyle, TrailingZeros};
 
 use crate::error::FendError;
 
@@ -75,7 +76,10 @@ impl<T: fmt::Display> fmt::Display for Range<T> {
 	}
 }
 
-fn out_of_range<T: fmt::Display + fmt::Debug + 'static, U: fmt::Display + fmt::Debug + 'static>(
+pub(crate) fn out_of_range<
+	T: fmt::Display + fmt::Debug + 'static,
+	U: fmt::Display + fmt::Debug + 'static,
+>(
 	value: T,
 	range: Range<U>,
 ) -> FendError {
@@ -73,6 +73,21 @@ impl Base {
 		Ok(())
 	}
 
+	/// The number of characters written by `write_prefix`
+	pub(crate) const fn prefix_len(self) -> usize {
+		match self.0 {
+			BaseEnum::Binary | BaseEnum::Octal | BaseEnum::Hex => 2,
+			BaseEnum::Custom(b) => {
+				if b < 10 {
+					2
+				} else {
+					3
+				}
+			}
+			BaseEnum::Plain(_) => 0,
+		}
+	}
+
 	pub(crate) const fn has_prefix(self) -> bool {
 		!matches!(self.0, BaseEnum::Plain(_))
 	}
@@ -0,0 +1,239 @@
+use crate::error::{FendError, Interrupt};
+use crate::num::bigrat::sign::Sign;
+use crate::num::biguint::BigUint;
+use crate::result::FResult;
+use std::{cmp::Ordering, fmt, ops};
+
+/// A signed arbitrary-precision integer. Zero is always positive.
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub(crate) struct BigInt {
+	sign: Sign,
+	magnitude: BigUint,
+}
+
+impl BigInt {
+	pub(crate) fn new(sign: Sign, magnitude: BigUint) -> Self {
+		let sign = if magnitude == 0.into() {
+			Sign::Positive
+		} else {
+			sign
+		};
+		Self { sign, magnitude }
+	}
+
+	pub(crate) fn into_parts(self) -> (Sign, BigUint) {
+		(self.sign, self.magnitude)
+	}
+
+	pub(crate) fn is_zero(&self) -> bool {
+		self.magnitude == 0.into()
+	}
+
+	pub(crate) fn mul<I: Interrupt>(self, rhs: &Self, int: &I) -> FResult<Self> {
+		Ok(Self::new(
+			Sign::sign_of_product(self.sign, rhs.sign),
+			self.magnitude.mul(&rhs.magnitude, int)?,
+		))
+	}
+
+	/// Computes the quotient (rounded towards zero) and the remainder (with
+	/// the same sign as `self`), matching Rust's `/` and `%` on integers
+	pub(crate) fn divmod<I: Interrupt>(&self, rhs: &Self, int: &I) -> FResult<(Self, Self)> {
+		if rhs.is_zero() {
+			return Err(FendError::DivideByZero);
+		}
+		let (quotient, remainder) = self.magnitude.divmod(&rhs.magnitude, int)?;
+		Ok((
+			Self::new(Sign::sign_of_product(self.sign, rhs.sign), quotient),
+			Self::new(self.sign, remainder),
+		))
+	}
+
+	/// Wraps this value into the range of a `bits`-bit integer, like
+	/// integer overflow in two's complement. Signed results are sign-extended
+	/// from the highest of the `bits` bits.
+	pub(crate) fn wrap_to_width<I: Interrupt>(
+		self,
+		bits: u64,
+		signed: bool,
+		int: &I,
+	) -> FResult<Self> {
+		let low_bits = self.magnitude.low_bits(bits, int)?;
+		let modulus = BigUint::from(1).lshift_n(&bits.into(), int)?;
+		let unsigned = if self.sign == Sign::Negative && low_bits != 0.into() {
+			modulus.clone().sub(&low_bits)
+		} else {
+			low_bits
+		};
+		let sign_bit = BigUint::from(1).lshift_n(&(bits - 1).into(), int)?;
+		if signed && unsigned >= sign_bit {
+			Ok(Self::new(Sign::Negative, modulus.sub(&unsigned)))
+		} else {
+			Ok(Self::from(unsigned))
+		}
+	}
+}
+
+impl ops::Add for BigInt {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self {
+		if self.sign == rhs.sign {
+			return Self::new(self.sign, self.magnitude.add(&rhs.magnitude));
+		}
+		match self.magnitude.cmp(&rhs.magnitude) {
+			Ordering::Less => Self::new(rhs.sign, rhs.magnitude.sub(&self.magnitude)),
+			Ordering::Equal => Self::from(0_u64),
+			Ordering::Greater => Self::new(self.sign, self.magnitude.sub(&rhs.magnitude)),
+		}
+	}
+}
+
+impl ops::Sub for BigInt {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self {
+		self + -rhs
+	}
+}
+
+impl ops::Neg for BigInt {
+	type Output = Self;
+
+	fn neg(self) -> Self {
+		Self::new(self.sign.flip(), self.magnitude)
+	}
+}
+
+impl Ord for BigInt {
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (self.sign, other.sign) {
+			(Sign::Positive, Sign::Positive) => self.magnitude.cmp(&other.magnitude),
+			(Sign::Negative, Sign::Negative) => other.magnitude.cmp(&self.magnitude),
+			(Sign::Positive, Sign::Negative) => Ordering::Greater,
+			(Sign::Negative, Sign::Positive) => Ordering::Less,
+		}
+	}
+}
+
+impl PartialOrd for BigInt {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl From<BigUint> for BigInt {
+	fn from(magnitude: BigUint) -> Self {
+		Self::new(Sign::Positive, magnitude)
+	}
+}
+
+impl From<u64> for BigInt {
+	fn from(n: u64) -> Self {
+		Self::from(BigUint::from(n))
+	}
+}
+
+impl From<i64> for BigInt {
+	fn from(n: i64) -> Self {
+		let sign = if n < 0 {
+			Sign::Negative
+		} else {
+			Sign::Positive
+		};
+		Self::new(sign, n.unsigned_abs().into())
+	}
+}
+
+impl fmt::Debug for BigInt {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.sign == Sign::Negative {
+			write!(f, "-")?;
+		}
+		write!(f, "{:?}", self.magnitude)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::BigInt;
+	use crate::num::biguint::BigUint;
+	type Res = Result<(), crate::error::FendError>;
+
+	fn int(n: i64) -> BigInt {
+		BigInt::from(n)
+	}
+
+	#[test]
+	fn test_add_and_sub() {
+		assert_eq!(int(3) + int(4), int(7));
+		assert_eq!(int(3) + int(-4), int(-1));
+		assert_eq!(int(-3) + int(4), int(1));
+		assert_eq!(int(-3) + int(-4), int(-7));
+		assert_eq!(int(3) - int(4), int(-1));
+		assert_eq!(int(-3) - int(-3), int(0));
+		assert_eq!(int(0) - int(5), int(-5));
+		assert_eq!(-int(0), int(0));
+	}
+
+	#[test]
+	fn test_large_values() {
+		let two_to_the_64 = BigInt::from(BigUint::from(u64::MAX)) + int(1);
+		assert_eq!(int(0) - two_to_the_64.clone() + two_to_the_64, int(0));
+		assert_eq!(
+			BigInt::from(i64::MIN) - int(1) + int(1),
+			BigInt::from(i64::MIN)
+		);
+	}
+
+	#[test]
+	fn test_mul() -> Res {
+		let i = &crate::interrupt::Never;
+		assert_eq!(int(-6).mul(&int(7), i)?, int(-42));
+		assert_eq!(int(-6).mul(&int(-7), i)?, int(42));
+		assert_eq!(int(-6).mul(&int(0), i)?, int(0));
+		Ok(())
+	}
+
+	#[test]
+	fn test_divmod() -> Res {
+		let i = &crate::interrupt::Never;
+		for (a, b) in [(7, 2), (-7, 2), (7, -2), (-7, -2), (6, 3), (-6, 3), (0, -5)] {
+			assert_eq!(
+				int(a).divmod(&int(b), i)?,
+				(int(a / b), int(a % b)),
+				"{a} divmod {b}"
+			);
+		}
+		assert!(int(1).divmod(&int(0), i).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn test_wrap_to_width() -> Res {
+		let i = &crate::interrupt::Never;
+		for n in [-300_i64, -129, -128, -1, 0, 1, 127, 128, 255, 256, 1000] {
+			#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+			let (unsigned, signed) = (n as u8, n as i8);
+			assert_eq!(
+				int(n).wrap_to_width(8, false, i)?,
+				int(unsigned.into()),
+				"u8 {n}"
+			);
+			assert_eq!(
+				int(n).wrap_to_width(8, true, i)?,
+				int(signed.into()),
+				"i8 {n}"
+			);
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn test_cmp() {
+		assert!(int(-5) < int(-4));
+		assert!(int(-1) < int(0));
+		assert!(int(0) < int(1));
+		assert!(int(10) > int(9));
+	}
+}
@@ -1,8 +1,9 @@
 use crate::error::{FendError, Interrupt};
 use crate::format::Format;
 use crate::interrupt::test_int;
+use crate::num::bigint::BigInt;
 use crate::num::biguint::BigUint;
-use crate::num::{Base, Exact, FormattingStyle, Range, RangeBound};
+use crate::num::{Base, DigitFormat, Exact, FormattingStyle, Range, RangeBound, TrailingZeros};
 use crate::result::FResult;
 use std::{cmp, fmt, hash, io, ops};
 
@@ -57,6 +58,10 @@ use super::biguint::{self, FormattedBigUint};
 use super::out_of_range;
 use sign::Sign;
 
+/// Maximum number of digits to compute when looking for recurring digits.
+/// Numbers with longer expansions are approximated instead.
+const MAX_RECURRING_DIGITS: usize = 10_000;
+
 #[derive(Clone)]
 pub(crate) struct BigRat {
 	sign: Sign,
@@ -79,15 +84,11 @@ impl fmt::Debug for BigRat {
 
 impl Ord for BigRat {
 	fn cmp(&self, other: &Self) -> cmp::Ordering {
+		// compare `a/b` and `c/d` as `a*d` and `c*b`
 		let int = &crate::interrupt::Never;
-		let diff = self.clone().add(-other.clone(), int).unwrap();
-		if diff.num == 0.into() {
-			cmp::Ordering::Equal
-		} else if diff.sign == Sign::Positive {
-			cmp::Ordering::Greater
-		} else {
-			cmp::Ordering::Less
-		}
+		let lhs = self.num.clone().mul(&other.den, int).unwrap();
+		let rhs = other.num.clone().mul(&self.den, int).unwrap();
+		BigInt::new(self.sign, lhs).cmp(&BigInt::new(other.sign, rhs))
 	}
 }
 
@@ -179,28 +180,43 @@ impl BigRat {
 		}
 	}
 
-	#[allow(
-		clippy::cast_possible_truncation,
-		clippy::cast_sign_loss,
-		clippy::cast_precision_loss
-	)]
-	pub(crate) fn from_f64<I: Interrupt>(mut f: f64, int: &I) -> FResult<Self> {
-		let negative = f < 0.0;
-		if negative {
-			f = -f;
+	/// Converts a float to the exact rational number it represents
+	pub(crate) fn from_f64<I: Interrupt>(f: f64, int: &I) -> FResult<Self> {
+		if !f.is_finite() {
+			return Err(FendError::ValueTooLarge);
 		}
-		let i = (f * u64::MAX as f64) as u128;
-		let part1 = i as u64;
-		let part2 = (i >> 64) as u64;
+		let bits = f.to_bits();
+		let biased_exponent = (bits >> 52) & 0x7ff;
+		let fraction = bits & ((1 << 52) - 1);
+		// the value is `mantissa * 2^exponent`
+		let (mantissa, exponent) = if biased_exponent == 0 {
+			(fraction, -1074)
+		} else {
+			(
+				fraction | (1 << 52),
+				i64::try_from(biased_exponent).unwrap_or(0) - 1075,
+			)
+		};
+		let shift = BigUint::from(exponent.unsigned_abs());
+		let (num, den) = if exponent >= 0 {
+			(
+				BigUint::from(mantissa).lshift_n(&shift, int)?,
+				BigUint::from(1),
+			)
+		} else {
+			(
+				BigUint::from(mantissa),
+				BigUint::from(1).lshift_n(&shift, int)?,
+			)
+		};
 		Ok(Self {
-			sign: if negative {
+			sign: if f < 0.0 {
 				Sign::Negative
 			} else {
 				Sign::Positive
 			},
-			num: BigUint::from(part1)
-				.add(&BigUint::from(part2).mul(&BigUint::from(u64::MAX), int)?),
-			den: BigUint::from(u64::MAX),
+			num,
+			den,
 		})
 	}
 
@@ -222,6 +238,7 @@ impl BigRat {
 		Self::from_f64(f64::asin(self.into_f64(int)?), int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn acos<I: Interrupt>(self, int: &I) -> FResult<Self> {
 		let one = Self::from(1);
 		if self > one || self < -one {
@@ -231,6 +248,7 @@ impl BigRat {
 	}
 
 	// note that this works for any real number, unlike asin and acos
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn atan<I: Interrupt>(self, int: &I) -> FResult<Self> {
 		Self::from_f64(f64::atan(self.into_f64(int)?), int)
 	}
@@ -239,24 +257,51 @@ impl BigRat {
 		Self::from_f64(f64::atan2(self.into_f64(int)?, rhs.into_f64(int)?), int)
 	}
 
-	pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Self::from_f64(f64::sinh(self.into_f64(int)?), int)
+	pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		if self == 0.into() {
+			return Ok(Exact::new(0.into(), true));
+		}
+		Ok(Exact::new(
+			Self::from_f64(f64::sinh(self.into_f64(int)?), int)?,
+			false,
+		))
 	}
 
-	pub(crate) fn cosh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Self::from_f64(f64::cosh(self.into_f64(int)?), int)
+	pub(crate) fn cosh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		if self == 0.into() {
+			return Ok(Exact::new(1.into(), true));
+		}
+		Ok(Exact::new(
+			Self::from_f64(f64::cosh(self.into_f64(int)?), int)?,
+			false,
+		))
 	}
 
-	pub(crate) fn tanh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Self::from_f64(f64::tanh(self.into_f64(int)?), int)
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn tanh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		if self == 0.into() {
+			return Ok(Exact::new(0.into(), true));
+		}
+		Ok(Exact::new(
+			Self::from_f64(f64::tanh(self.into_f64(int)?), int)?,
+			false,
+		))
 	}
 
-	pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Self::from_f64(f64::asinh(self.into_f64(int)?), int)
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		if self == 0.into() {
+			return Ok(Exact::new(0.into(), true));
+		}
+		Ok(Exact::new(
+			Self::from_f64(f64::asinh(self.into_f64(int)?), int)?,
+			false,
+		))
 	}
 
 	// value must not be less than 1
-	pub(crate) fn acosh<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn acosh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		if self < 1.into() {
 			return Err(out_of_range(
 				self.fm(int)?,
@@ -266,16 +311,29 @@ impl BigRat {
 				},
 			));
 		}
-		Self::from_f64(f64::acosh(self.into_f64(int)?), int)
+		if self == 1.into() {
+			return Ok(Exact::new(0.into(), true));
+		}
+		Ok(Exact::new(
+			Self::from_f64(f64::acosh(self.into_f64(int)?), int)?,
+			false,
+		))
 	}
 
 	// value must be between -1 and 1.
-	pub(crate) fn atanh<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn atanh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		let one: Self = 1.into();
 		if self >= one || self <= -one {
 			return Err(out_of_range(self.fm(int)?, Range::open(-1, 1)));
 		}
-		Self::from_f64(f64::atanh(self.into_f64(int)?), int)
+		if self == 0.into() {
+			return Ok(Exact::new(0.into(), true));
+		}
+		Ok(Exact::new(
+			Self::from_f64(f64::atanh(self.into_f64(int)?), int)?,
+			false,
+		))
 	}
 
 	// For all logs: value must be greater than 0
@@ -298,7 +356,8 @@ impl BigRat {
 		))
 	}
 
-	pub(crate) fn log2<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn log2<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		if self <= 0.into() {
 			return Err(out_of_range(
 				self.fm(int)?,
@@ -308,10 +367,17 @@ impl BigRat {
 				},
 			));
 		}
-		Self::from_f64(f64::log2(self.into_f64(int)?), int)
+		if let Some(res) = self.clone().exact_log(2, int)? {
+			return Ok(Exact::new(res, true));
+		}
+		Ok(Exact::new(
+			Self::from_f64(f64::log2(self.into_f64(int)?), int)?,
+			false,
+		))
 	}
 
-	pub(crate) fn log10<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn log10<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		if self <= 0.into() {
 			return Err(out_of_range(
 				self.fm(int)?,
@@ -321,7 +387,42 @@ impl BigRat {
 				},
 			));
 		}
-		Self::from_f64(f64::log10(self.into_f64(int)?), int)
+		if let Some(res) = self.clone().exact_log(10, int)? {
+			return Ok(Exact::new(res, true));
+		}
+		Ok(Exact::new(
+			Self::from_f64(f64::log10(self.into_f64(int)?), int)?,
+			false,
+		))
+	}
+
+	/// If `self` is an integer power of `base` (e.g. `1000` or `1/8`),
+	/// returns the exponent, otherwise returns `None`
+	#[cfg(feature = "transcendental")]
+	fn exact_log<I: Interrupt>(self, base: u64, int: &I) -> FResult<Option<Self>> {
+		let x = self.simplify(int)?;
+		let (mut value, sign) = if x.den == 1.into() {
+			(x.num, Sign::Positive)
+		} else if x.num == 1.into() {
+			(x.den, Sign::Negative)
+		} else {
+			return Ok(None);
+		};
+		let base = BigUint::from(base);
+		let mut exponent = 0_u64;
+		while value != 1.into() {
+			let (quotient, remainder) = value.divmod(&base, int)?;
+			if remainder != 0.into() {
+				return Ok(None);
+			}
+			value = quotient;
+			exponent += 1;
+		}
+		Ok(Some(Self {
+			sign,
+			num: exponent.into(),
+			den: 1.into(),
+		}))
 	}
 
 	fn apply_uint_op<I: Interrupt, R>(
@@ -344,19 +445,125 @@ impl BigRat {
 		Ok(self.apply_uint_op(BigUint::factorial, int)?.into())
 	}
 
+	/// Returns the prime factors of a non-zero integer, starting with `-1`
+	/// for negative numbers
+	pub(crate) fn prime_factors<I: Interrupt>(self, int: &I) -> FResult<Vec<(Self, usize)>> {
+		let negative = self.sign == Sign::Negative;
+		let abs = if negative { -self } else { self };
+		let factors = abs.apply_uint_op(
+			|n, int| {
+				if n == 0.into() {
+					return Err(FendError::FactorOfZero);
+				}
+				n.prime_factors(int)
+			},
+			int,
+		)?;
+		let mut result = vec![];
+		if negative {
+			result.push((-Self::from(1), 1));
+		}
+		result.extend(factors.into_iter().map(|(p, k)| (p.into(), k)));
+		Ok(result)
+	}
+
+	/// Negative integers are never prime
+	pub(crate) fn is_prime<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		if self.sign == Sign::Negative {
+			(-self.clone()).apply_uint_op(|_, _| Ok(()), int)?;
+			return Ok(false);
+		}
+		self.clone().apply_uint_op(|n, int| n.is_prime(int), int)
+	}
+
+	pub(crate) fn next_prime<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		if self.sign == Sign::Negative {
+			(-self).apply_uint_op(|_, _| Ok(()), int)?;
+			return Ok(2.into());
+		}
+		Ok(self.apply_uint_op(|n, int| n.next_prime(int), int)?.into())
+	}
+
+	pub(crate) fn prev_prime<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		if self.sign == Sign::Negative {
+			(-self).apply_uint_op(|_, _| Ok(()), int)?;
+			return Err(FendError::NoPrimeLessThanTwo);
+		}
+		Ok(self.apply_uint_op(|n, int| n.prev_prime(int), int)?.into())
+	}
+
+	pub(crate) fn isqrt<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(self.apply_uint_op(|n, int| n.isqrt(int), int)?.into())
+	}
+
+	/// Computes `self^exponent mod modulus` without calculating the full
+	/// power. Negative bases are reduced to lie between 0 and `modulus`.
+	pub(crate) fn pow_mod<I: Interrupt>(
+		self,
+		exponent: Self,
+		modulus: Self,
+		int: &I,
+	) -> FResult<Self> {
+		let exponent = exponent.apply_uint_op(|n, _| Ok(n), int)?;
+		let modulus_uint = modulus.clone().apply_uint_op(|n, _| Ok(n), int)?;
+		let base = self.modulo(modulus, int)?;
+		Ok(base
+			.apply_uint_op(|n, int| n.pow_mod(&exponent, &modulus_uint, int), int)?
+			.into())
+	}
+
+	pub(crate) fn mod_inverse<I: Interrupt>(self, modulus: Self, int: &I) -> FResult<Self> {
+		let modulus_uint = modulus.clone().apply_uint_op(|n, _| Ok(n), int)?;
+		let value = self.modulo(modulus, int)?;
+		Ok(value
+			.apply_uint_op(|n, int| n.mod_inverse(&modulus_uint, int), int)?
+			.into())
+	}
+
 	pub(crate) fn floor<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		let float = self.into_f64(int)?.floor();
-		Self::from_f64(float, int)
+		self.floor_div(&1.into(), int)
 	}
 
 	pub(crate) fn ceil<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		let float = self.into_f64(int)?.ceil();
-		Self::from_f64(float, int)
+		// ceil(x) = -floor(-x)
+		Ok(-(-self).floor(int)?)
 	}
 
+	/// Rounds to the nearest integer, with ties rounded away from zero
 	pub(crate) fn round<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		let float = self.into_f64(int)?.round();
-		Self::from_f64(float, int)
+		let half = Self::from(1).div(&2.into(), int)?;
+		if self < 0.into() {
+			Ok(-(-self).add(half, int)?.floor(int)?)
+		} else {
+			self.add(half, int)?.floor(int)
+		}
+	}
+
+	pub(crate) fn wrap_to_width<I: Interrupt>(
+		mut self,
+		bits: u64,
+		signed: bool,
+		int: &I,
+	) -> FResult<Self> {
+		self = self.simplify(int)?;
+		if self.den != 1.into() {
+			let n = self.fm(int)?;
+			return Err(FendError::MustBeAnInteger(Box::new(n)));
+		}
+		let (sign, num) = BigInt::new(self.sign, self.num)
+			.wrap_to_width(bits, signed, int)?
+			.into_parts();
+		Ok(Self {
+			sign,
+			num,
+			den: 1.into(),
+		})
+	}
+
+	pub(crate) fn bitwise_not<I: Interrupt>(self, width: u64, int: &I) -> FResult<Self> {
+		Ok(self
+			.apply_uint_op(|n, int| n.bitwise_not(width, int), int)?
+			.into())
 	}
 
 	pub(crate) fn bitwise<I: Interrupt>(
@@ -387,55 +594,17 @@ impl BigRat {
 
 	/// compute a + b
 	fn add_internal<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
-		// a + b == -((-a) + (-b))
-		if self.sign == Sign::Negative {
-			return Ok(-((-self).add_internal(-rhs, int)?));
-		}
-
-		assert_eq!(self.sign, Sign::Positive);
-
-		Ok(if self.den == rhs.den {
-			if rhs.sign == Sign::Negative && self.num < rhs.num {
-				Self {
-					sign: Sign::Negative,
-					num: rhs.num.sub(&self.num),
-					den: self.den,
-				}
-			} else {
-				Self {
-					sign: Sign::Positive,
-					num: if rhs.sign == Sign::Positive {
-						self.num.add(&rhs.num)
-					} else {
-						self.num.sub(&rhs.num)
-					},
-					den: self.den,
-				}
-			}
+		let (a, b, den) = if self.den == rhs.den {
+			(self.num, rhs.num, self.den)
 		} else {
 			let gcd = BigUint::gcd(self.den.clone(), rhs.den.clone(), int)?;
 			let new_denominator = self.den.clone().mul(&rhs.den, int)?.div(&gcd, int)?;
 			let a = self.num.mul(&rhs.den, int)?.div(&gcd, int)?;
 			let b = rhs.num.mul(&self.den, int)?.div(&gcd, int)?;
-
-			if rhs.sign == Sign::Negative && a < b {
-				Self {
-					sign: Sign::Negative,
-					num: b.sub(&a),
-					den: new_denominator,
-				}
-			} else {
-				Self {
-					sign: Sign::Positive,
-					num: if rhs.sign == Sign::Positive {
-						a.add(&b)
-					} else {
-						a.sub(&b)
-					},
-					den: new_denominator,
-				}
-			}
-		})
+			(a, b, new_denominator)
+		};
+		let (sign, num) = (BigInt::new(self.sign, a) + BigInt::new(rhs.sign, b)).into_parts();
+		Self { sign, num, den }.simplify(int)
 	}
 
 	fn simplify<I: Interrupt>(mut self, int: &I) -> FResult<Self> {
@@ -448,37 +617,157 @@ impl BigRat {
 		Ok(self)
 	}
 
+	/// Returns whether the number is negative, and its numerator and
+	/// denominator in lowest terms as decimal strings.
+	pub(crate) fn to_decimal_parts<I: Interrupt>(
+		&self,
+		int: &I,
+	) -> FResult<(bool, String, String)> {
+		let x = self.clone().simplify(int)?;
+		let negative = x.sign == Sign::Negative && x.num != 0.into();
+		let options = biguint::FormatOptions::default();
+		let num = x.num.format(&options, int)?.value.to_string();
+		let den = x.den.format(&options, int)?.value.to_string();
+		Ok((negative, num, den))
+	}
+
+	/// Returns `floor(log10(abs(self)))`, i.e. the power of ten used when
+	/// writing the number in scientific notation. The number must not be zero.
+	pub(crate) fn decimal_exponent<I: Interrupt>(&self, int: &I) -> FResult<i64> {
+		self.exponent(Base::from_plain_base(10)?, int)
+	}
+
+	/// Returns `floor(log_b(abs(self)))`, where `b` is the given base.
+	/// The number must not be zero.
+	fn exponent<I: Interrupt>(&self, base: Base, int: &I) -> FResult<i64> {
+		let options = biguint::FormatOptions {
+			base,
+			write_base_prefix: false,
+			sf_limit: None,
+		};
+		let num_digits = self.num.format(&options, int)?.value.num_digits();
+		let den_digits = self.den.format(&options, int)?.value.num_digits();
+		let base = BigUint::from(u64::from(base.base_as_u8()));
+		let too_large = |_| FendError::ExponentTooLarge;
+		// the result is either `num_digits - den_digits` or one less than that
+		let (num, den, exponent) = if num_digits >= den_digits {
+			let diff = u64::try_from(num_digits - den_digits).map_err(too_large)?;
+			let scale = BigUint::pow(&base, &diff.into(), int)?;
+			let exponent = i64::try_from(diff).map_err(too_large)?;
+			(
+				self.num.clone(),
+				self.den.clone().mul(&scale, int)?,
+				exponent,
+			)
+		} else {
+			let diff = u64::try_from(den_digits - num_digits).map_err(too_large)?;
+			let scale = BigUint::pow(&base, &diff.into(), int)?;
+			let exponent = -i64::try_from(diff).map_err(too_large)?;
+			(
+				self.num.clone().mul(&scale, int)?,
+				self.den.clone(),
+				exponent,
+			)
+		};
+		Ok(if num < den { exponent - 1 } else { exponent })
+	}
+
+	/// Rounds to the number of decimal places or significant figures given
+	/// by `style`. Other styles leave the number unchanged.
+	pub(crate) fn round_to_style<I: Interrupt>(
+		self,
+		style: FormattingStyle,
+		base: Base,
+		int: &I,
+	) -> FResult<Self> {
+		let places = match style {
+			FormattingStyle::DecimalPlaces(dp) => i64::try_from(dp).unwrap_or(i64::MAX),
+			FormattingStyle::SignificantFigures(sf) if self != 0.into() => {
+				let sf = i64::try_from(sf).unwrap_or(i64::MAX);
+				sf.saturating_sub(1 + self.exponent(base, int)?)
+			}
+			_ => return Ok(self),
+		};
+		self.round_to_places(base, places, int)
+	}
+
+	/// Rounds to the given number of digits after the point (or, if
+	/// `places` is negative, to a multiple of `base^-places`). Ties are
+	/// rounded to the nearest even digit.
+	fn round_to_places<I: Interrupt>(self, base: Base, places: i64, int: &I) -> FResult<Self> {
+		let base = BigUint::from(u64::from(base.base_as_u8()));
+		let scale = BigUint::pow(&base, &places.unsigned_abs().into(), int)?;
+		let (num, den) = if places >= 0 {
+			(self.num.mul(&scale, int)?, self.den)
+		} else {
+			(self.num, self.den.mul(&scale, int)?)
+		};
+		let (mut quotient, remainder) = num.divmod(&den, int)?;
+		let twice_remainder = remainder.mul(&2.into(), int)?;
+		let round_up = match twice_remainder.cmp(&den) {
+			cmp::Ordering::Less => false,
+			cmp::Ordering::Greater => true,
+			cmp::Ordering::Equal => !quotient.is_even(int)?,
+		};
+		if round_up {
+			quotient = quotient.add(&1.into());
+		}
+		let (num, den) = if places >= 0 {
+			(quotient, scale)
+		} else {
+			(quotient.mul(&scale, int)?, 1.into())
+		};
+		Self {
+			sign: self.sign,
+			num,
+			den,
+		}
+		.simplify(int)
+	}
+
 	pub(crate) fn div<I: Interrupt>(self, rhs: &Self, int: &I) -> FResult<Self> {
 		if rhs.num == 0.into() {
 			return Err(FendError::DivideByZero);
 		}
-		Ok(Self {
+		Self {
 			sign: Sign::sign_of_product(self.sign, rhs.sign),
 			num: self.num.mul(&rhs.den, int)?,
 			den: self.den.mul(&rhs.num, int)?,
-		})
+		}
+		.simplify(int)
 	}
 
-	pub(crate) fn modulo<I: Interrupt>(mut self, mut rhs: Self, int: &I) -> FResult<Self> {
+	/// Rounds `self / rhs` down to the nearest integer, e.g. `-7 // 2` is `-4`
+	pub(crate) fn floor_div<I: Interrupt>(self, rhs: &Self, int: &I) -> FResult<Self> {
 		if rhs.num == 0.into() {
-			return Err(FendError::ModuloByZero);
+			return Err(FendError::DivideByZero);
 		}
-		self = self.simplify(int)?;
-		rhs = rhs.simplify(int)?;
-		if (self.sign == Sign::Negative && self.num != 0.into())
-			|| rhs.sign == Sign::Negative
-			|| self.den != 1.into()
-			|| rhs.den != 1.into()
-		{
-			return Err(FendError::ModuloForPositiveInts);
+		let num = BigInt::new(self.sign, self.num).mul(&rhs.den.clone().into(), int)?;
+		let den = BigInt::new(rhs.sign, rhs.num.clone()).mul(&self.den.into(), int)?;
+		let (mut quotient, remainder) = num.divmod(&den, int)?;
+		// `divmod` rounds towards zero, so negative quotients may be one too large
+		let zero = BigInt::from(0_u64);
+		if !remainder.is_zero() && (num < zero) != (den < zero) {
+			quotient = quotient - 1_u64.into();
 		}
+		let (sign, num) = quotient.into_parts();
 		Ok(Self {
-			sign: Sign::Positive,
-			num: self.num.divmod(&rhs.num, int)?.1,
+			sign,
+			num,
 			den: 1.into(),
 		})
 	}
 
+	/// Computes `self - rhs * floor(self / rhs)`, so the result always has
+	/// the same sign as `rhs`, e.g. `-7 mod 3` is `2` and `7 mod -3` is `-2`
+	pub(crate) fn modulo<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		if rhs.num == 0.into() {
+			return Err(FendError::ModuloByZero);
+		}
+		let quotient = self.clone().floor_div(&rhs, int)?;
+		self.add(-rhs.mul(&quotient, int)?, int)?.simplify(int)
+	}
+
 	// test if this fraction has a terminating representation
 	// e.g. in base 10: 1/4 = 0.25, but not 1/3
 	fn terminates_in_base<I: Interrupt>(&self, base: Base, int: &I) -> FResult<bool> {
@@ -531,7 +820,14 @@ impl BigRat {
 				formatted_int.exact,
 			)
 		};
-		Ok(Exact::new(FormattedBigRat { sign, ty }, exact))
+		Ok(Exact::new(
+			FormattedBigRat {
+				sign,
+				ty,
+				division_sign: crate::DivisionSign::default(),
+			},
+			exact,
+		))
 	}
 
 	fn format_as_fraction<I: Interrupt>(
@@ -599,7 +895,11 @@ impl BigRat {
 				)
 			};
 		Ok(Exact::new(
-			FormattedBigRat { sign, ty },
+			FormattedBigRat {
+				sign,
+				ty,
+				division_sign: crate::DivisionSign::default(),
+			},
 			formatted_den.exact && prefix_exact && num_exact,
 		))
 	}
@@ -637,15 +937,7 @@ impl BigRat {
 			MaxDigitsToPrint::DecimalPlaces(n)
 		} else if let FormattingStyle::SignificantFigures(sf) = style {
 			let num_digits_of_int_part = formatted_integer_part.value.num_digits();
-			let dp = if sf > num_digits_of_int_part {
-				// we want more significant figures than what was printed
-				// in the int component
-				sf - num_digits_of_int_part
-			} else {
-				// no more digits, we already exhausted the number of significant
-				// figures
-				0
-			};
+			let dp = sf.saturating_sub(num_digits_of_int_part);
 			if integer_part == 0.into() {
 				// if the integer part is 0, we don't want leading zeroes
 				// after the decimal point to affect the number of non-zero
@@ -693,6 +985,7 @@ impl BigRat {
 					!term.is_empty() && base.base_as_u8() > 10,
 					term,
 				),
+				division_sign: crate::DivisionSign::default(),
 			},
 			formatted_integer_part.exact && formatted_trailing_digits.exact,
 		))
@@ -754,8 +1047,14 @@ impl BigRat {
 				int,
 			);
 		}
+		let next_digit_limited = |i: usize, num: BigUint, base: &BigUint| {
+			if i >= MAX_RECURRING_DIGITS {
+				return Err(NextDigitErr::CycleTooLong);
+			}
+			next_digit(i, num, base)
+		};
 		match Self::brents_algorithm(
-			next_digit,
+			next_digit_limited,
 			fold_digits,
 			numerator.clone(),
 			&b,
@@ -777,6 +1076,18 @@ impl BigRat {
 			Err(NextDigitErr::Terminated) => {
 				panic!("decimal number terminated unexpectedly");
 			}
+			Err(NextDigitErr::CycleTooLong) => {
+				// too many recurring digits to print, so approximate instead
+				Self::format_trailing_digits(
+					base,
+					numerator,
+					denominator,
+					MaxDigitsToPrint::DecimalPlaces(10),
+					terminating,
+					print_integer_part,
+					int,
+				)
+			}
 			Err(NextDigitErr::Error(e)) => Err(e),
 		}
 	}
@@ -833,7 +1144,7 @@ impl BigRat {
 						i += 1;
 					}
 				}
-				Err(NextDigitErr::Terminated) => {
+				Err(NextDigitErr::Terminated | NextDigitErr::CycleTooLong) => {
 					let sign = if let Some(actual_sign) = actual_sign {
 						actual_sign
 					} else {
@@ -1033,11 +1344,12 @@ impl BigRat {
 	}
 
 	pub(crate) fn mul<I: Interrupt>(self, rhs: &Self, int: &I) -> FResult<Self> {
-		Ok(Self {
+		Self {
 			sign: Sign::sign_of_product(self.sign, rhs.sign),
 			num: self.num.mul(&rhs.num, int)?,
 			den: self.den.mul(&rhs.den, int)?,
-		})
+		}
+		.simplify(int)
 	}
 
 	pub(crate) fn add<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
@@ -1053,22 +1365,24 @@ impl BigRat {
 	}
 
 	pub(crate) fn combination<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
-		let n_factorial = self.clone().factorial(int)?;
-		let r_factorial = rhs.clone().factorial(int)?;
-		let n_minus_r_factorial = self.add(-rhs, int)?.factorial(int)?;
-		let denominator = r_factorial.mul(&n_minus_r_factorial, int)?;
-		n_factorial.div(&denominator, int)
+		let k = rhs.apply_uint_op(|k, _int| Ok(k), int)?;
+		Ok(self
+			.apply_uint_op(|n, int| BigUint::combination(&n, &k, int), int)?
+			.into())
 	}
 
 	pub(crate) fn permutation<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
-		let n_factorial = self.clone().factorial(int)?;
-		let n_minus_r_factorial = self.add(-rhs, int)?.factorial(int)?;
-		n_factorial.div(&n_minus_r_factorial, int)
+		let k = rhs.apply_uint_op(|k, _int| Ok(k), int)?;
+		Ok(self
+			.apply_uint_op(|n, int| BigUint::permutation(&n, &k, int), int)?
+			.into())
 	}
 }
 enum NextDigitErr {
 	Error(FendError),
 	Terminated,
+	/// No recurring digits were found within `MAX_RECURRING_DIGITS` digits
+	CycleTooLong,
 }
 
 impl From<FendError> for NextDigitErr {
@@ -1124,16 +1438,43 @@ pub(crate) struct FormatOptions {
 	pub(crate) style: FormattingStyle,
 	pub(crate) term: &'static str,
 	pub(crate) use_parens_if_fraction: bool,
+	pub(crate) digit_format: DigitFormat,
 }
 
 impl Format for BigRat {
 	type Params = FormatOptions;
 	type Out = FormattedBigRat;
 
+	fn format<I: Interrupt>(&self, params: &Self::Params, int: &I) -> FResult<Exact<Self::Out>> {
+		let grouping = params.digit_format.grouping.group_size(params.base);
+		let decimal_point = params.digit_format.grouping.decimal_point(params.base);
+		let mut res = self
+			.format_unpadded(params, int)?
+			.apply(|res| res.apply_recurring_notation(params.digit_format.recurring))
+			.apply(|res| FormattedBigRat {
+				division_sign: params.digit_format.division,
+				..res
+			});
+		if !params.base.has_prefix() {
+			res = res.apply(|res| {
+				res.pad_trailing_zeros(params.style, params.digit_format.trailing_zeros)
+			});
+		}
+		// grouping happens last because padding relies on `.` being the only
+		// non-digit character
+		Ok(res.apply(|res| res.group_digits(params.base, grouping, decimal_point)))
+	}
+}
+
+impl BigRat {
 	// Formats as an integer if possible, or a terminating float, otherwise as
 	// either a fraction or a potentially approximated floating-point number.
 	// The result 'exact' field indicates whether the number was exact or not.
-	fn format<I: Interrupt>(&self, params: &Self::Params, int: &I) -> FResult<Exact<Self::Out>> {
+	fn format_unpadded<I: Interrupt>(
+		&self,
+		params: &FormatOptions,
+		int: &I,
+	) -> FResult<Exact<FormattedBigRat>> {
 		let base = params.base;
 		let style = params.style;
 		let term = params.term;
@@ -1223,6 +1564,138 @@ pub(crate) struct FormattedBigRat {
 	// whether or not to print a minus sign
 	sign: Sign,
 	ty: FormattedBigRatType,
+	division_sign: crate::DivisionSign,
+}
+
+impl FormattedBigRat {
+	// Replaces `0.1(6)` with `0.16̅` if overlines are enabled
+	fn apply_recurring_notation(self, notation: crate::RecurringDigitsNotation) -> Self {
+		let ty = match self.ty {
+			FormattedBigRatType::Decimal(s, space, term)
+				if notation == crate::RecurringDigitsNotation::Overline =>
+			{
+				let s = match s.split_once('(') {
+					Some((non_recurring, recurring)) => {
+						let mut result = non_recurring.to_string();
+						for ch in recurring.trim_end_matches(')').chars() {
+							result.push(ch);
+							result.push('\u{305}');
+						}
+						result
+					}
+					None => s,
+				};
+				FormattedBigRatType::Decimal(s, space, term)
+			}
+			ty => ty,
+		};
+		Self {
+			sign: self.sign,
+			division_sign: self.division_sign,
+			ty,
+		}
+	}
+
+	// Separates the digits of the integer part into groups, e.g. `0b1010_1100`
+	// or `1,234,567`, and replaces the decimal point if necessary
+	fn group_digits(
+		self,
+		base: Base,
+		grouping: Option<(usize, char)>,
+		decimal_point: char,
+	) -> Self {
+		if grouping.is_none() && decimal_point == '.' {
+			return self;
+		}
+		let ty = match self.ty {
+			FormattedBigRatType::Integer(i, space, term, use_parens) => {
+				FormattedBigRatType::Integer(
+					i.map(|i| i.with_grouping(grouping)),
+					space,
+					term,
+					use_parens,
+				)
+			}
+			FormattedBigRatType::Fraction(integer, num, space, term, den, term2, use_parens) => {
+				FormattedBigRatType::Fraction(
+					integer.map(|i| i.with_grouping(grouping)),
+					num.map(|n| n.with_grouping(grouping)),
+					space,
+					term,
+					den.with_grouping(grouping),
+					term2,
+					use_parens,
+				)
+			}
+			FormattedBigRatType::Decimal(s, space, term) => {
+				let (prefix, digits) = s.split_at(base.prefix_len());
+				let (integer, fraction) = digits.split_at(digits.find('.').unwrap_or(digits.len()));
+				let integer = match grouping {
+					Some((size, separator)) => biguint::group_digits(integer, size, separator),
+					None => integer.to_string(),
+				};
+				let fraction = match fraction.strip_prefix('.') {
+					Some(fraction) => format!("{decimal_point}{fraction}"),
+					None => fraction.to_string(),
+				};
+				FormattedBigRatType::Decimal(format!("{prefix}{integer}{fraction}"), space, term)
+			}
+		};
+		Self {
+			sign: self.sign,
+			division_sign: self.division_sign,
+			ty,
+		}
+	}
+
+	// Adds trailing zeros up to the number of decimal places or significant
+	// figures given by `style`
+	fn pad_trailing_zeros(self, style: FormattingStyle, trailing_zeros: TrailingZeros) -> Self {
+		let (mut s, space, term) = match self.ty {
+			FormattedBigRatType::Integer(Some(i), space, term, false)
+				if trailing_zeros.keep && trailing_zeros.integer_decimal_point =>
+			{
+				(i.to_string(), space, term)
+			}
+			FormattedBigRatType::Decimal(s, space, term) if trailing_zeros.keep => (s, space, term),
+			ty => {
+				return Self {
+					sign: self.sign,
+					division_sign: self.division_sign,
+					ty,
+				}
+			}
+		};
+		let missing_zeros = match style {
+			FormattingStyle::DecimalPlaces(dp) => {
+				dp.saturating_sub(s.find('.').map_or(0, |idx| s.len() - idx - 1))
+			}
+			FormattingStyle::SignificantFigures(sf) => {
+				let sig_figs = s
+					.trim_start_matches(['0', '.'])
+					.chars()
+					.filter(char::is_ascii_alphanumeric)
+					.count();
+				if sig_figs == 0 {
+					0
+				} else {
+					sf.saturating_sub(sig_figs)
+				}
+			}
+			_ => 0,
+		};
+		if missing_zeros > 0 {
+			if !s.contains('.') {
+				s.push('.');
+			}
+			s.push_str(&"0".repeat(missing_zeros));
+		}
+		Self {
+			sign: self.sign,
+			division_sign: self.division_sign,
+			ty: FormattedBigRatType::Decimal(s, space, term),
+		}
+	}
 }
 
 impl fmt::Display for FormattedBigRat {
@@ -1259,7 +1732,7 @@ impl fmt::Display for FormattedBigRat {
 				if *space && !isuf.is_empty() {
 					write!(f, " ")?;
 				}
-				write!(f, "{isuf}/{den}")?;
+				write!(f, "{isuf}{}{den}", self.division_sign.symbol())?;
 				if *space && !isuf2.is_empty() {
 					write!(f, " ")?;
 				}
@@ -1330,4 +1803,28 @@ mod tests {
 			}
 		);
 	}
+
+	#[test]
+	fn test_results_are_reduced() -> FResult<()> {
+		let int = &crate::interrupt::Never;
+		let frac = |sign, num: u64, den: u64| BigRat {
+			sign,
+			num: num.into(),
+			den: den.into(),
+		};
+		let sixth = frac(Sign::Positive, 1, 6);
+		let third = frac(Sign::Positive, 1, 3);
+		let res = sixth.clone().add(sixth.clone(), int)?;
+		assert_eq!((res.num, res.den), (1.into(), 3.into()));
+		let res = third.clone().mul(&frac(Sign::Positive, 3, 4), int)?;
+		assert_eq!((res.num, res.den), (1.into(), 4.into()));
+		let res = sixth.div(&third, int)?;
+		assert_eq!((res.num, res.den), (1.into(), 2.into()));
+		let res = third.clone().add(-third, int)?;
+		assert_eq!(
+			(res.sign, res.num, res.den),
+			(Sign::Positive, 0.into(), 1.into())
+		);
+		Ok(())
+	}
 }
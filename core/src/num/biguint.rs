@@ -1,10 +1,12 @@
 use crate::error::{FendError, Interrupt};
 use crate::format::Format;
 use crate::interrupt::test_int;
+use crate::num::bigint::BigInt;
+use crate::num::bigrat::sign::Sign;
 use crate::num::{out_of_range, Base, Exact, Range, RangeBound};
 use crate::result::FResult;
 use crate::serialize::{Deserialize, Serialize};
-use std::cmp::{max, Ordering};
+use std::cmp::{max, min, Ordering};
 use std::{fmt, hash, io};
 
 #[derive(Clone)]
@@ -34,6 +36,10 @@ const fn truncate(n: u128) -> u64 {
 	n as u64
 }
 
+/// Numbers where both operands have at least this many limbs are multiplied
+/// using Karatsuba multiplication instead of schoolbook multiplication
+const KARATSUBA_THRESHOLD: usize = 32;
+
 impl BigUint {
 	fn bits(&self) -> u64 {
 		match self {
@@ -204,6 +210,38 @@ impl BigUint {
 		a.pow_internal(b.get(0), int)
 	}
 
+	/// Computes the integer square root, i.e. the square root rounded down
+	pub(crate) fn isqrt<I: Interrupt>(&self, int: &I) -> FResult<Self> {
+		self.nth_root(2, int)
+	}
+
+	/// Computes the `n`-th root rounded down, using Newton's method
+	pub(crate) fn nth_root<I: Interrupt>(&self, n: u64, int: &I) -> FResult<Self> {
+		if self.is_zero() || n == 1 {
+			return Ok(self.clone());
+		}
+		let root_bits = self.bits().div_ceil(n);
+		if root_bits <= 1 {
+			// `self` is less than `2^n`
+			return Ok(Self::from(1));
+		}
+		// start with a guess that is too large: the sequence then decreases
+		// monotonically until it reaches the rounded-down root
+		let mut guess = Small(1).lshift_n(&root_bits.into(), int)?;
+		loop {
+			test_int(int)?;
+			let next = guess
+				.clone()
+				.mul(&(n - 1).into(), int)?
+				.add(&self.clone().div(&guess.pow_internal(n - 1, int)?, int)?)
+				.div(&n.into(), int)?;
+			if next >= guess {
+				return Ok(guess);
+			}
+			guess = next;
+		}
+	}
+
 	// computes the exact `n`-th root if possible, otherwise the next lower integer
 	pub(crate) fn root_n<I: Interrupt>(self, n: &Self, int: &I) -> FResult<Exact<Self>> {
 		if self == 0.into() || self == 1.into() || n == &Self::from(1) {
@@ -218,25 +256,9 @@ impl BigUint {
 				},
 			});
 		}
-		let max_bits = self.bits() / n.get(0) + 1;
-		let mut low_guess = Self::from(1);
-		let mut high_guess = Small(1).lshift_n(&(max_bits + 1).into(), int)?;
-		let result = loop {
-			test_int(int)?;
-			let mut guess = low_guess.clone().add(&high_guess);
-			guess.rshift(int)?;
-
-			let res = Self::pow(&guess, n, int)?;
-			match res.cmp(&self) {
-				Ordering::Equal => break Exact::new(guess, true),
-				Ordering::Greater => high_guess = guess,
-				Ordering::Less => low_guess = guess,
-			}
-			if high_guess.clone().sub(&low_guess) <= 1.into() {
-				break Exact::new(low_guess, false);
-			}
-		};
-		Ok(result)
+		let root = self.nth_root(n.get(0), int)?;
+		let exact = root.pow_internal(n.get(0), int)? == self;
+		Ok(Exact::new(root, exact))
 	}
 
 	fn pow_internal<I: Interrupt>(&self, mut exponent: u64, int: &I) -> FResult<Self> {
@@ -253,31 +275,6 @@ impl BigUint {
 		Ok(result)
 	}
 
-	fn lshift<I: Interrupt>(&mut self, int: &I) -> FResult<()> {
-		match self {
-			Small(n) => {
-				if *n & 0xc000_0000_0000_0000 == 0 {
-					*n <<= 1;
-				} else {
-					*self = Large(vec![*n << 1, *n >> 63]);
-				}
-			}
-			Large(value) => {
-				if value[value.len() - 1] & (1_u64 << 63) != 0 {
-					value.push(0);
-				}
-				for i in (0..value.len()).rev() {
-					test_int(int)?;
-					value[i] <<= 1;
-					if i != 0 {
-						value[i] |= value[i - 1] >> 63;
-					}
-				}
-			}
-		}
-		Ok(())
-	}
-
 	fn rshift<I: Interrupt>(&mut self, int: &I) -> FResult<()> {
 		match self {
 			Small(n) => *n >>= 1,
@@ -319,28 +316,60 @@ impl BigUint {
 		if self == other {
 			return Ok((Self::from(1), Self::from(0)));
 		}
-		if other == &Self::from(2) {
-			let mut div_result = self.clone();
-			div_result.rshift(int)?;
-			let modulo = self.get(0) & 1;
-			return Ok((div_result, Self::from(modulo)));
+		if let Some(k) = other.power_of_two_exponent() {
+			test_int(int)?;
+			return Ok((
+				Self::from_limbs(shr_n(self.limbs(), k)),
+				Self::from_limbs(low_bits(self.limbs(), k)),
+			));
+		}
+		let dividend = trim(self.limbs().to_vec());
+		let divisor = trim(other.limbs().to_vec());
+		if divisor.len() == 1 {
+			return self.divmod_small(divisor[0], int);
 		}
-		// binary long division
-		let mut q = Self::from(0);
-		let mut r = Self::from(0);
+		let (q, r) = divmod_limbs(dividend, &divisor, int)?;
+		Ok((Self::from_limbs(q), Self::from_limbs(r)))
+	}
+
+	/// divides by a single non-zero limb, one limb at a time
+	fn divmod_small<I: Interrupt>(&self, divisor: u64, int: &I) -> FResult<(Self, Self)> {
+		let mut q = vec![0; self.value_len()];
+		let mut r = 0;
 		for i in (0..self.value_len()).rev() {
 			test_int(int)?;
-			for j in (0..64).rev() {
-				r.lshift(int)?;
-				let bit_of_self = u64::from((self.get(i) & (1 << j)) != 0);
-				r.set(0, r.get(0) | bit_of_self);
-				if &r >= other {
-					r = r.sub(other);
-					q.set(i, q.get(i) | (1 << j));
-				}
-			}
+			let n = (u128::from(r) << 64) | u128::from(self.get(i));
+			q[i] = truncate(n / u128::from(divisor));
+			r = truncate(n % u128::from(divisor));
+		}
+		Ok((Self::from_limbs(q), Self::from(r)))
+	}
+
+	/// Creates a number from little-endian limbs, using `Small` when possible
+	fn from_limbs(limbs: Vec<u64>) -> Self {
+		let limbs = trim(limbs);
+		if limbs.len() == 1 {
+			Small(limbs[0])
+		} else {
+			Large(limbs)
+		}
+	}
+
+	/// Returns `k` if this number is exactly `2^k`
+	fn power_of_two_exponent(&self) -> Option<usize> {
+		let limbs = self.limbs();
+		let top = limbs.iter().rposition(|&limb| limb != 0)?;
+		if !limbs[top].is_power_of_two() || limbs[..top].iter().any(|&limb| limb != 0) {
+			return None;
+		}
+		Some(top * 64 + limbs[top].trailing_zeros() as usize)
+	}
+
+	fn limbs(&self) -> &[u64] {
+		match self {
+			Small(n) => std::slice::from_ref(n),
+			Large(value) => value,
 		}
-		Ok((q, r))
 	}
 
 	/// computes self *= other
@@ -349,6 +378,25 @@ impl BigUint {
 			*self = Self::from(0);
 			return Ok(());
 		}
+		if let Some(k) = other.power_of_two_exponent() {
+			test_int(int)?;
+			*self = Self::from_limbs(shl_n(self.limbs(), k));
+			return Ok(());
+		}
+		if let Some(k) = self.power_of_two_exponent() {
+			test_int(int)?;
+			*self = Self::from_limbs(shl_n(other.limbs(), k));
+			return Ok(());
+		}
+		if self.value_len() >= KARATSUBA_THRESHOLD && other.value_len() >= KARATSUBA_THRESHOLD {
+			*self = Self::from_limbs(mul_karatsuba(self.limbs(), other.limbs(), int)?);
+			return Ok(());
+		}
+		self.mul_schoolbook(other, int)
+	}
+
+	/// computes self *= other in O(n * m) time
+	fn mul_schoolbook<I: Interrupt>(&mut self, other: &Self, int: &I) -> FResult<()> {
 		let self_clone = self.clone();
 		self.make_large();
 		match self {
@@ -391,6 +439,272 @@ impl BigUint {
 		Ok(res)
 	}
 
+	/// Computes `n! / (n - k)!` as `n * (n - 1) * ... * (n - k + 1)`
+	pub(crate) fn permutation<I: Interrupt>(n: &Self, k: &Self, int: &I) -> FResult<Self> {
+		if k > n {
+			return Ok(0.into());
+		}
+		let mut res = Self::from(1);
+		let mut factor = n.clone().sub(k);
+		while &factor < n {
+			test_int(int)?;
+			factor = factor.add(&1.into());
+			res = res.mul(&factor, int)?;
+		}
+		Ok(res)
+	}
+
+	/// Computes `n! / (k! (n - k)!)` one factor at a time. After `i` steps
+	/// the result is `(n - k + i) choose i`, so every division is exact.
+	pub(crate) fn combination<I: Interrupt>(n: &Self, k: &Self, int: &I) -> FResult<Self> {
+		if k > n {
+			return Ok(0.into());
+		}
+		let k = min(k.clone(), n.clone().sub(k));
+		let start = n.clone().sub(&k);
+		let mut res = Self::from(1);
+		let mut i = Self::from(0);
+		while i < k {
+			test_int(int)?;
+			i = i.add(&1.into());
+			res = res.mul(&start.clone().add(&i), int)?.div(&i, int)?;
+		}
+		Ok(res)
+	}
+
+	/// Computes `self^exponent mod modulus` by repeated squaring
+	pub(crate) fn pow_mod<I: Interrupt>(
+		&self,
+		exponent: &Self,
+		modulus: &Self,
+		int: &I,
+	) -> FResult<Self> {
+		if modulus.is_zero() {
+			return Err(FendError::ModuloByZero);
+		}
+		let mut result = Self::from(1).rem(modulus, int)?;
+		let mut base = self.rem(modulus, int)?;
+		let mut exponent = exponent.clone();
+		while !exponent.is_zero() {
+			test_int(int)?;
+			if !exponent.is_even(int)? {
+				result = result.mul(&base, int)?.rem(modulus, int)?;
+			}
+			base = base.clone().mul(&base, int)?.rem(modulus, int)?;
+			exponent.rshift(int)?;
+		}
+		Ok(result)
+	}
+
+	/// Returns `(g, x, y)` where `g` is the greatest common divisor of `a`
+	/// and `b`, and `a * x + b * y = g`
+	pub(crate) fn extended_gcd<I: Interrupt>(
+		a: &Self,
+		b: &Self,
+		int: &I,
+	) -> FResult<(Self, BigInt, BigInt)> {
+		let (mut old_remainder, mut remainder) = (a.clone(), b.clone());
+		let (mut old_x, mut x) = (BigInt::from(1_u64), BigInt::from(0_u64));
+		let (mut old_y, mut y) = (BigInt::from(0_u64), BigInt::from(1_u64));
+		while !remainder.is_zero() {
+			test_int(int)?;
+			let (quotient, next_remainder) = old_remainder.divmod(&remainder, int)?;
+			let quotient = BigInt::from(quotient);
+			old_remainder = remainder;
+			remainder = next_remainder;
+			let next_x = old_x - quotient.clone().mul(&x, int)?;
+			old_x = x;
+			x = next_x;
+			let next_y = old_y - quotient.mul(&y, int)?;
+			old_y = y;
+			y = next_y;
+		}
+		Ok((old_remainder, old_x, old_y))
+	}
+
+	/// Finds `x` such that `self * x` is congruent to 1 modulo `modulus`
+	pub(crate) fn mod_inverse<I: Interrupt>(&self, modulus: &Self, int: &I) -> FResult<Self> {
+		if modulus.is_zero() {
+			return Err(FendError::ModuloByZero);
+		}
+		let (gcd, x, _) = Self::extended_gcd(&self.rem(modulus, int)?, modulus, int)?;
+		// 0 has an inverse modulo 1
+		if gcd > 1.into() {
+			return Err(FendError::NoModularInverse);
+		}
+		let (sign, magnitude) = x.into_parts();
+		let magnitude = magnitude.rem(modulus, int)?;
+		if sign == Sign::Negative && !magnitude.is_zero() {
+			Ok(modulus.clone().sub(&magnitude))
+		} else {
+			Ok(magnitude)
+		}
+	}
+
+	/// Miller-Rabin primality test. Using the first 12 primes as bases makes
+	/// the result exact for all numbers below 3.3 * 10^24, and larger
+	/// composites are very unlikely to be reported as prime.
+	pub(crate) fn is_prime<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+		if self < &2.into() {
+			return Ok(false);
+		}
+		for p in BASES {
+			if self == &p.into() {
+				return Ok(true);
+			}
+			if self.rem(&p.into(), int)?.is_zero() {
+				return Ok(false);
+			}
+		}
+		// write `self - 1` as `d * 2^s` with odd `d`
+		let n_minus_one = self.clone().sub(&1.into());
+		let mut d = n_minus_one.clone();
+		let mut s = 0;
+		while d.is_even(int)? {
+			d.rshift(int)?;
+			s += 1;
+		}
+		'bases: for a in BASES {
+			let mut x = Self::from(a).pow_mod(&d, self, int)?;
+			if x == 1.into() || x == n_minus_one {
+				continue;
+			}
+			for _ in 1..s {
+				test_int(int)?;
+				x = x.clone().mul(&x, int)?.rem(self, int)?;
+				if x == n_minus_one {
+					continue 'bases;
+				}
+			}
+			return Ok(false);
+		}
+		Ok(true)
+	}
+
+	/// Returns the smallest prime number greater than `self`
+	pub(crate) fn next_prime<I: Interrupt>(&self, int: &I) -> FResult<Self> {
+		let mut candidate = self.clone().add(&1.into());
+		while !candidate.is_prime(int)? {
+			test_int(int)?;
+			candidate = candidate.add(&1.into());
+		}
+		Ok(candidate)
+	}
+
+	/// Returns the largest prime number less than `self`
+	pub(crate) fn prev_prime<I: Interrupt>(&self, int: &I) -> FResult<Self> {
+		if self <= &2.into() {
+			return Err(FendError::NoPrimeLessThanTwo);
+		}
+		let mut candidate = self.clone().sub(&1.into());
+		while !candidate.is_prime(int)? {
+			test_int(int)?;
+			candidate = candidate.sub(&1.into());
+		}
+		Ok(candidate)
+	}
+
+	/// Finds a non-trivial factor of the odd composite number `self`, using
+	/// Brent's variant of Pollard's rho algorithm
+	fn pollard_rho<I: Interrupt>(&self, int: &I) -> FResult<Self> {
+		// number of steps between each gcd calculation
+		const BATCH_SIZE: u64 = 128;
+		let abs_diff = |a: &Self, b: &Self| {
+			if a > b {
+				a.clone().sub(b)
+			} else {
+				b.clone().sub(a)
+			}
+		};
+		let mut constant = Self::from(1);
+		loop {
+			let f = |x: &Self| x.clone().mul(x, int)?.add(&constant).rem(self, int);
+			let mut y = Self::from(2);
+			let mut x = y.clone();
+			let mut saved_y = y.clone();
+			let mut product = Self::from(1);
+			let mut gcd = Self::from(1);
+			let mut cycle_length = 1;
+			while gcd == 1.into() {
+				x = y.clone();
+				for _ in 0..cycle_length {
+					y = f(&y)?;
+				}
+				let mut steps = 0;
+				while steps < cycle_length && gcd == 1.into() {
+					saved_y = y.clone();
+					for _ in 0..BATCH_SIZE.min(cycle_length - steps) {
+						test_int(int)?;
+						y = f(&y)?;
+						product = product.mul(&abs_diff(&x, &y), int)?.rem(self, int)?;
+					}
+					gcd = Self::gcd(product.clone(), self.clone(), int)?;
+					steps += BATCH_SIZE;
+				}
+				cycle_length *= 2;
+			}
+			if &gcd == self {
+				// the batch skipped past the factor, so step through it again
+				loop {
+					test_int(int)?;
+					saved_y = f(&saved_y)?;
+					gcd = Self::gcd(abs_diff(&x, &saved_y), self.clone(), int)?;
+					if gcd != 1.into() {
+						break;
+					}
+				}
+			}
+			if &gcd != self {
+				return Ok(gcd);
+			}
+			constant = constant.add(&1.into());
+		}
+	}
+
+	/// Returns the prime factors of `self` in ascending order, together
+	/// with their multiplicities. Small factors are found by trial division,
+	/// and larger ones with Pollard's rho algorithm.
+	pub(crate) fn prime_factors<I: Interrupt>(mut self, int: &I) -> FResult<Vec<(Self, usize)>> {
+		const TRIAL_DIVISION_LIMIT: u64 = 1000;
+		let mut factors = vec![];
+		let mut divisor = 2;
+		while divisor < TRIAL_DIVISION_LIMIT && Self::from(divisor * divisor) <= self {
+			loop {
+				test_int(int)?;
+				let (quotient, remainder) = self.divmod(&divisor.into(), int)?;
+				if !remainder.is_zero() {
+					break;
+				}
+				factors.push(Self::from(divisor));
+				self = quotient;
+			}
+			divisor += if divisor == 2 { 1 } else { 2 };
+		}
+		let mut remaining = vec![];
+		if self > 1.into() {
+			remaining.push(self);
+		}
+		while let Some(n) = remaining.pop() {
+			if n.is_prime(int)? {
+				factors.push(n);
+			} else {
+				let factor = n.pollard_rho(int)?;
+				remaining.push(n.div(&factor, int)?);
+				remaining.push(factor);
+			}
+		}
+		factors.sort();
+		let mut result: Vec<(Self, usize)> = vec![];
+		for factor in factors {
+			match result.last_mut() {
+				Some((prime, count)) if *prime == factor => *count += 1,
+				_ => result.push((factor, 1)),
+			}
+		}
+		Ok(result)
+	}
+
 	pub(crate) fn fibonacci<I: Interrupt>(mut n: usize, int: &I) -> FResult<Self> {
 		if n == 0 {
 			return Ok(0.into());
@@ -443,7 +757,7 @@ impl BigUint {
 			Ordering::Equal => return Self::from(0),
 			Ordering::Less => unreachable!("number would be less than 0"),
 			Ordering::Greater => (),
-		};
+		}
 		if other.is_zero() {
 			return self;
 		}
@@ -518,6 +832,47 @@ impl BigUint {
 		})
 	}
 
+	/// Parses a non-negative integer in the given base (between 2 and 36).
+	/// Digits may be separated by underscores, e.g. `1_000_000`.
+	pub(crate) fn from_str_radix<I: Interrupt>(s: &str, radix: u8, int: &I) -> FResult<Self> {
+		let radix = Base::from_plain_base(radix)?.base_as_u8();
+		let radix_u64 = u64::from(radix);
+		let mut res = Self::from(0);
+		// digits are collected into `chunk` until it would overflow, so that
+		// only one big multiplication is needed per limb
+		let mut chunk = 0;
+		let mut chunk_scale = 1;
+		let mut prev_was_digit = false;
+		for ch in s.chars() {
+			if ch == '_' {
+				if !prev_was_digit {
+					return Err(FendError::DigitSeparatorsOnlyBetweenDigits);
+				}
+				prev_was_digit = false;
+				continue;
+			}
+			let digit = ch
+				.to_digit(radix.into())
+				.ok_or(FendError::ExpectedADigit(ch))?;
+			if chunk_scale > u64::MAX / radix_u64 {
+				test_int(int)?;
+				res = res.mul(&chunk_scale.into(), int)?.add(&chunk.into());
+				(chunk, chunk_scale) = (0, 1);
+			}
+			chunk = chunk * radix_u64 + u64::from(digit);
+			chunk_scale *= radix_u64;
+			prev_was_digit = true;
+		}
+		if !prev_was_digit {
+			return Err(if s.is_empty() {
+				FendError::ExpectedACharacter
+			} else {
+				FendError::DigitSeparatorsOnlyBetweenDigits
+			});
+		}
+		Ok(res.mul(&chunk_scale.into(), int)?.add(&chunk.into()))
+	}
+
 	pub(crate) fn bitwise_and(self, rhs: &Self) -> Self {
 		match (self, rhs) {
 			(Small(a), Small(b)) => Small(a & *b),
@@ -581,36 +936,350 @@ impl BigUint {
 		}
 	}
 
-	pub(crate) fn lshift_n<I: Interrupt>(mut self, rhs: &Self, int: &I) -> FResult<Self> {
-		let mut rhs = rhs.try_as_usize(int)?;
-		if rhs > 64 {
-			self.make_large();
-			match &mut self {
-				Large(v) => {
-					while rhs >= 64 {
-						v.insert(0, 0);
-						rhs -= 64;
-					}
-				}
-				Small(_) => unreachable!(),
-			}
+	/// Returns `2^bits - 1`, i.e. a number consisting of `bits` one bits
+	fn all_ones<I: Interrupt>(bits: u64, int: &I) -> FResult<Self> {
+		Ok(Small(1).lshift_n(&bits.into(), int)?.sub(&1.into()))
+	}
+
+	/// Keeps only the lowest `bits` bits, i.e. computes `self mod 2^bits`
+	pub(crate) fn low_bits<I: Interrupt>(self, bits: u64, int: &I) -> FResult<Self> {
+		Ok(self.bitwise_and(&Self::all_ones(bits, int)?))
+	}
+
+	/// Flips the lowest `width` bits. `self` must fit in `width` bits.
+	pub(crate) fn bitwise_not<I: Interrupt>(&self, width: u64, int: &I) -> FResult<Self> {
+		let mask = Self::all_ones(width, int)?;
+		if self > &mask {
+			return Err(FendError::ValueDoesNotFitInBits(width));
 		}
-		for _ in 0..rhs {
-			self.lshift(int)?;
+		Ok(mask.bitwise_xor(self))
+	}
+
+	pub(crate) fn lshift_n<I: Interrupt>(self, rhs: &Self, int: &I) -> FResult<Self> {
+		let rhs = rhs.try_as_usize(int)?;
+		if self.is_zero() {
+			return Ok(self);
 		}
-		Ok(self)
+		Ok(Self::from_limbs(shl_n(self.limbs(), rhs)))
 	}
 
-	pub(crate) fn rshift_n<I: Interrupt>(mut self, rhs: &Self, int: &I) -> FResult<Self> {
+	pub(crate) fn rshift_n<I: Interrupt>(self, rhs: &Self, int: &I) -> FResult<Self> {
 		let rhs = rhs.try_as_usize(int)?;
-		for _ in 0..rhs {
-			if self.is_zero() {
+		Ok(Self::from_limbs(shr_n(self.limbs(), rhs)))
+	}
+}
+
+/// Removes leading zero limbs, keeping at least one limb
+fn trim(mut limbs: Vec<u64>) -> Vec<u64> {
+	while limbs.len() > 1 && limbs.last() == Some(&0) {
+		limbs.pop();
+	}
+	limbs
+}
+
+/// computes `limbs << k`
+fn shl_n(limbs: &[u64], k: usize) -> Vec<u64> {
+	let (limb_shift, bit_shift) = (k / 64, k % 64);
+	let mut res = vec![0; limb_shift];
+	res.reserve(limbs.len() + 1);
+	if bit_shift == 0 {
+		res.extend_from_slice(limbs);
+		return res;
+	}
+	let mut carry = 0;
+	for &limb in limbs {
+		res.push((limb << bit_shift) | carry);
+		carry = limb >> (64 - bit_shift);
+	}
+	res.push(carry);
+	res
+}
+
+/// computes `limbs >> k`
+fn shr_n(limbs: &[u64], k: usize) -> Vec<u64> {
+	let (limb_shift, bit_shift) = (k / 64, k % 64);
+	if limb_shift >= limbs.len() {
+		return vec![0];
+	}
+	let limbs = &limbs[limb_shift..];
+	if bit_shift == 0 {
+		return limbs.to_vec();
+	}
+	(0..limbs.len())
+		.map(|i| {
+			let next = limbs.get(i + 1).copied().unwrap_or(0);
+			(limbs[i] >> bit_shift) | (next << (64 - bit_shift))
+		})
+		.collect()
+}
+
+/// computes `limbs mod 2^k`
+fn low_bits(limbs: &[u64], k: usize) -> Vec<u64> {
+	let (limb_count, bit_count) = (k / 64, k % 64);
+	let mut res = limbs[..limb_count.min(limbs.len())].to_vec();
+	if bit_count != 0 && limb_count < limbs.len() {
+		res.push(limbs[limb_count] & ((1 << bit_count) - 1));
+	}
+	if res.is_empty() {
+		res.push(0);
+	}
+	res
+}
+
+/// computes `acc += other << (64 * shift)`
+fn add_limbs_at(acc: &mut Vec<u64>, other: &[u64], shift: usize) {
+	if acc.len() < other.len() + shift {
+		acc.resize(other.len() + shift, 0);
+	}
+	let mut carry = false;
+	let mut i = shift;
+	for &b in other {
+		let (sum, c1) = acc[i].overflowing_add(b);
+		let (sum, c2) = sum.overflowing_add(u64::from(carry));
+		acc[i] = sum;
+		carry = c1 || c2;
+		i += 1;
+	}
+	while carry {
+		if i == acc.len() {
+			acc.push(0);
+		}
+		let (sum, c) = acc[i].overflowing_add(1);
+		acc[i] = sum;
+		carry = c;
+		i += 1;
+	}
+}
+
+/// computes `acc -= other`, where `acc` must be at least as large as `other`
+fn sub_limbs(acc: &mut [u64], other: &[u64]) {
+	let mut borrow = false;
+	for (i, a) in acc.iter_mut().enumerate() {
+		let b = other.get(i).copied().unwrap_or(0);
+		if i >= other.len() && !borrow {
+			break;
+		}
+		let (diff, b1) = a.overflowing_sub(b);
+		let (diff, b2) = diff.overflowing_sub(u64::from(borrow));
+		*a = diff;
+		borrow = b1 || b2;
+	}
+	assert!(!borrow, "number would be less than 0");
+}
+
+fn add_limbs(a: &[u64], b: &[u64]) -> Vec<u64> {
+	let mut res = a.to_vec();
+	add_limbs_at(&mut res, b, 0);
+	res
+}
+
+fn mul_limbs_schoolbook<I: Interrupt>(a: &[u64], b: &[u64], int: &I) -> FResult<Vec<u64>> {
+	let mut res = vec![0; a.len() + b.len()];
+	for (i, &x) in a.iter().enumerate() {
+		test_int(int)?;
+		let mut carry = 0;
+		for (j, &y) in b.iter().enumerate() {
+			let sum = u128::from(x) * u128::from(y) + u128::from(res[i + j]) + u128::from(carry);
+			res[i + j] = truncate(sum);
+			carry = truncate(sum >> 64);
+		}
+		res[i + b.len()] = carry;
+	}
+	Ok(res)
+}
+
+/// Divides `u` by `v` using Knuth's Algorithm D (The Art of Computer
+/// Programming, Vol. 2, 4.3.1). `v` must have at least two limbs and no
+/// leading zero limbs, and `u` must be at least as long as `v`.
+#[allow(
+	clippy::cast_possible_truncation,
+	clippy::cast_possible_wrap,
+	clippy::cast_sign_loss,
+	clippy::many_single_char_names
+)]
+fn divmod_limbs<I: Interrupt>(
+	mut u: Vec<u64>,
+	v: &[u64],
+	int: &I,
+) -> FResult<(Vec<u64>, Vec<u64>)> {
+	let n = v.len();
+	let m = u.len() - n;
+	// normalize so that the most significant limb of the divisor has its top
+	// bit set, which keeps the quotient digit estimates within 2 of the result
+	let shift = v[n - 1].leading_zeros();
+	let shl = |a: u64, b: u64| {
+		if shift == 0 {
+			a
+		} else {
+			(a << shift) | (b >> (64 - shift))
+		}
+	};
+	let v: Vec<u64> = (0..n)
+		.map(|i| shl(v[i], if i == 0 { 0 } else { v[i - 1] }))
+		.collect();
+	u.push(0);
+	for i in (0..=m + n).rev() {
+		u[i] = shl(u[i], if i == 0 { 0 } else { u[i - 1] });
+	}
+
+	let base = 1_u128 << 64;
+	let mut q = vec![0; m + 1];
+	for j in (0..=m).rev() {
+		test_int(int)?;
+		let numerator = (u128::from(u[j + n]) << 64) | u128::from(u[j + n - 1]);
+		let mut qhat = numerator / u128::from(v[n - 1]);
+		let mut rhat = numerator % u128::from(v[n - 1]);
+		while qhat >= base
+			|| qhat * u128::from(v[n - 2]) > ((rhat << 64) | u128::from(u[j + n - 2]))
+		{
+			qhat -= 1;
+			rhat += u128::from(v[n - 1]);
+			if rhat >= base {
 				break;
 			}
-			self.rshift(int)?;
 		}
-		Ok(self)
+
+		// u[j..=j + n] -= qhat * v
+		let mut borrow: i128 = 0;
+		for i in 0..n {
+			let p = qhat * u128::from(v[i]);
+			let t = i128::from(u[i + j]) - borrow - i128::from(truncate(p));
+			u[i + j] = t as u64;
+			borrow = (p >> 64) as i128 - (t >> 64);
+		}
+		let t = i128::from(u[j + n]) - borrow;
+		u[j + n] = t as u64;
+
+		if t < 0 {
+			// qhat was one too large, so add v back
+			qhat -= 1;
+			let mut carry = 0;
+			for i in 0..n {
+				let sum = u128::from(u[i + j]) + u128::from(v[i]) + carry;
+				u[i + j] = truncate(sum);
+				carry = sum >> 64;
+			}
+			u[j + n] = u[j + n].wrapping_add(truncate(carry));
+		}
+		q[j] = truncate(qhat);
+	}
+
+	// the remainder is in u[..n], but still needs to be unnormalized
+	let r = (0..n)
+		.map(|i| {
+			if shift == 0 {
+				u[i]
+			} else {
+				(u[i] >> shift) | (u[i + 1] << (64 - shift))
+			}
+		})
+		.collect();
+	Ok((trim(q), trim(r)))
+}
+
+/// Multiplies two little-endian limb slices using Karatsuba multiplication,
+/// which needs O(n^1.58) limb multiplications instead of O(n^2). Operands are
+/// split into high and low halves `a = a1 * B + a0` and `b = b1 * B + b0`, and
+/// the product is assembled from the three products `a0 * b0`, `a1 * b1` and
+/// `(a0 + a1) * (b0 + b1)`.
+fn mul_karatsuba<I: Interrupt>(a: &[u64], b: &[u64], int: &I) -> FResult<Vec<u64>> {
+	let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+	if b.len() < KARATSUBA_THRESHOLD {
+		return Ok(trim(mul_limbs_schoolbook(a, b, int)?));
+	}
+	test_int(int)?;
+	let half = a.len().div_ceil(2);
+	let (a0, a1) = a.split_at(half);
+	if b.len() <= half {
+		// unbalanced operands: only split the larger one
+		let mut res = mul_karatsuba(a0, b, int)?;
+		add_limbs_at(&mut res, &mul_karatsuba(a1, b, int)?, half);
+		return Ok(trim(res));
+	}
+	let (b0, b1) = b.split_at(half);
+	let z0 = mul_karatsuba(a0, b0, int)?;
+	let z2 = mul_karatsuba(a1, b1, int)?;
+	let mut z1 = mul_karatsuba(&add_limbs(a0, a1), &add_limbs(b0, b1), int)?;
+	sub_limbs(&mut z1, &z0);
+	sub_limbs(&mut z1, &z2);
+	let mut res = z0;
+	add_limbs_at(&mut res, &trim(z1), half);
+	add_limbs_at(&mut res, &z2, 2 * half);
+	Ok(trim(res))
+}
+
+impl BigUint {
+	/// Returns the digits of this number in the given base, starting with the
+	/// least significant digit. The number is recursively split in half by
+	/// dividing by `base^(k * 2^i)`, so that converting a number with `n` limbs
+	/// takes much less than the O(n^2) time needed to divide out one group
+	/// of digits at a time.
+	fn digits_reversed<I: Interrupt>(&self, base: Base, int: &I) -> FResult<String> {
+		let base_as_u128 = u128::from(base.base_as_u8());
+		// the largest power of the base that fits in a u128
+		let mut chunk = base_as_u128;
+		let mut chunk_digits = 1;
+		while chunk <= u128::MAX / base_as_u128 {
+			chunk *= base_as_u128;
+			chunk_digits += 1;
+		}
+		// powers[i] = (base^(chunk_digits * 2^i), chunk_digits * 2^i)
+		let mut powers = vec![(
+			Self::from_limbs(vec![truncate(chunk), truncate(chunk >> 64)]),
+			chunk_digits,
+		)];
+		loop {
+			test_int(int)?;
+			let (power, digits) = &powers[powers.len() - 1];
+			let square = power.clone().mul(power, int)?;
+			if &square > self {
+				break;
+			}
+			let digits = digits * 2;
+			powers.push((square, digits));
+		}
+		let mut output = String::new();
+		push_digits_reversed(self.clone(), &powers, base_as_u128, 0, &mut output, int)?;
+		Ok(output)
+	}
+}
+
+/// Appends the digits of `num` to `output`, starting with the least significant
+/// digit, and padded with zeroes to at least `min_digits` digits. `num` must be
+/// less than `powers[powers.len() - 1].0^2`, or less than `base^chunk_digits`
+/// if `powers` is empty.
+fn push_digits_reversed<I: Interrupt>(
+	num: BigUint,
+	powers: &[(BigUint, usize)],
+	base: u128,
+	min_digits: usize,
+	output: &mut String,
+	int: &I,
+) -> FResult<()> {
+	test_int(int)?;
+	let Some(((power, power_digits), smaller_powers)) = powers.split_last() else {
+		let mut value = u128::from(num.get(1)) << 64 | u128::from(num.get(0));
+		let mut num_digits = 0;
+		while value != 0 || num_digits < min_digits {
+			output.push(Base::digit_as_char(truncate(value % base)).unwrap());
+			value /= base;
+			num_digits += 1;
+		}
+		return Ok(());
+	};
+	if &num < power {
+		return push_digits_reversed(num, smaller_powers, base, min_digits, output, int);
 	}
+	let (upper, lower) = num.divmod(power, int)?;
+	push_digits_reversed(lower, smaller_powers, base, *power_digits, output, int)?;
+	push_digits_reversed(
+		upper,
+		smaller_powers,
+		base,
+		min_digits.saturating_sub(*power_digits),
+		output,
+		int,
+	)
 }
 
 impl Ord for BigUint {
@@ -698,72 +1367,36 @@ impl Format for BigUint {
 				FormattedBigUint {
 					base: base_prefix,
 					ty: FormattedBigUintType::Zero,
+					grouping: None,
 				},
 				true,
 			));
 		}
 
-		let mut num = self.clone();
+		let num = self;
 		Ok(
 			if num.value_len() == 1 && params.base.base_as_u8() == 10 && params.sf_limit.is_none() {
 				Exact::new(
 					FormattedBigUint {
 						base: base_prefix,
 						ty: FormattedBigUintType::Simple(num.get(0)),
+						grouping: None,
 					},
 					true,
 				)
 			} else {
-				let base_as_u128: u128 = params.base.base_as_u8().into();
-				let mut divisor = base_as_u128;
-				let mut rounds = 1;
-				// note that the string is reversed: this is the number of trailing zeroes while
-				// printing, but actually the number of leading zeroes in the final number
-				let mut num_trailing_zeroes = 0;
-				let mut num_leading_zeroes = 0;
-				let mut finished_counting_leading_zeroes = false;
-				while divisor
-					< u128::MAX
-						.checked_div(base_as_u128)
-						.expect("base appears to be 0")
-				{
-					divisor *= base_as_u128;
-					rounds += 1;
-				}
-				let divisor = Self::Large(vec![truncate(divisor), truncate(divisor >> 64)]);
-				let mut output = String::with_capacity(rounds);
-				while !num.is_zero() {
-					test_int(int)?;
-					let divmod_res = num.divmod(&divisor, int)?;
-					let mut digit_group_value =
-						u128::from(divmod_res.1.get(1)) << 64 | u128::from(divmod_res.1.get(0));
-					for _ in 0..rounds {
-						let digit_value = digit_group_value % base_as_u128;
-						digit_group_value /= base_as_u128;
-						let ch = Base::digit_as_char(truncate(digit_value)).unwrap();
-						if ch == '0' {
-							num_trailing_zeroes += 1;
-						} else {
-							for _ in 0..num_trailing_zeroes {
-								output.push('0');
-								if !finished_counting_leading_zeroes {
-									num_leading_zeroes += 1;
-								}
-							}
-							finished_counting_leading_zeroes = true;
-							num_trailing_zeroes = 0;
-							output.push(ch);
-						}
-					}
-					num = divmod_res.0;
-				}
+				let output = num.digits_reversed(params.base, int)?;
+				// note that the string is reversed: these are the trailing zeroes of
+				// the final number
+				let num_leading_zeroes = output.bytes().take_while(|&ch| ch == b'0').count();
 				let exact = params
 					.sf_limit
-					.map_or(true, |sf| sf >= output.len() - num_leading_zeroes);
+					.is_none_or(|sf| sf >= output.len() - num_leading_zeroes);
 				Exact::new(
 					FormattedBigUint {
 						base: base_prefix,
 						ty: FormattedBigUintType::Complex(output, params.sf_limit),
+						grouping: None,
 					},
 					exact,
 				)
@@ -784,6 +1417,8 @@ enum FormattedBigUintType {
 pub(crate) struct FormattedBigUint {
 	base: Option<Base>,
 	ty: FormattedBigUintType,
+	// separate every `group_size` digits with the given character
+	grouping: Option<(usize, char)>,
 }
 
 impl fmt::Display for FormattedBigUint {
@@ -791,24 +1426,49 @@ impl fmt::Display for FormattedBigUint {
 		if let Some(base) = self.base {
 			base.write_prefix(f)?;
 		}
-		match &self.ty {
-			FormattedBigUintType::Zero => write!(f, "0")?,
-			FormattedBigUintType::Simple(i) => write!(f, "{i}")?,
-			FormattedBigUintType::Complex(s, sf_limit) => {
-				for (i, ch) in s.chars().rev().enumerate() {
+		let digits = match &self.ty {
+			FormattedBigUintType::Zero => "0".to_string(),
+			FormattedBigUintType::Simple(i) => i.to_string(),
+			FormattedBigUintType::Complex(s, sf_limit) => s
+				.chars()
+				.rev()
+				.enumerate()
+				.map(|(i, ch)| {
 					if sf_limit.is_some() && &Some(i) >= sf_limit {
-						write!(f, "0")?;
+						'0'
 					} else {
-						write!(f, "{ch}")?;
+						ch
 					}
-				}
-			}
+				})
+				.collect(),
+		};
+		match self.grouping {
+			Some((size, separator)) => write!(f, "{}", group_digits(&digits, size, separator))?,
+			None => write!(f, "{digits}")?,
 		}
 		Ok(())
 	}
 }
 
+/// Inserts `separator` between every `group_size` digits, counting from
+/// the right, e.g. `10101100` becomes `1010_1100`
+pub(crate) fn group_digits(digits: &str, group_size: usize, separator: char) -> String {
+	let len = digits.chars().count();
+	let mut result = String::with_capacity(len + len / group_size.max(1));
+	for (i, ch) in digits.chars().enumerate() {
+		if i > 0 && (len - i).is_multiple_of(group_size) {
+			result.push(separator);
+		}
+		result.push(ch);
+	}
+	result
+}
+
 impl FormattedBigUint {
+	pub(crate) fn with_grouping(self, grouping: Option<(usize, char)>) -> Self {
+		Self { grouping, ..self }
+	}
+
 	pub(crate) fn num_digits(&self) -> usize {
 		match &self.ty {
 			FormattedBigUintType::Zero => 1,
@@ -827,7 +1487,9 @@ impl FormattedBigUint {
 #[cfg(test)]
 mod tests {
 	use super::BigUint;
-	type Res = Result<(), crate::error::FendError>;
+	use crate::error::FendError;
+	use crate::num::bigint::BigInt;
+	type Res = Result<(), FendError>;
 
 	#[test]
 	fn test_sqrt() -> Res {
@@ -938,7 +1600,7 @@ mod tests {
 		let int = &crate::interrupt::Never;
 		let mut n = BigUint::from(1);
 		for _ in 0..100 {
-			n.lshift(int)?;
+			n = n.lshift_n(&1.into(), int)?;
 			assert_eq!(n.get(0) & 1, 0);
 		}
 		Ok(())
@@ -967,8 +1629,7 @@ mod tests {
 	#[test]
 	fn test_large_lshift() -> Res {
 		let int = &crate::interrupt::Never;
-		let mut a = BigUint::from(9_223_372_036_854_775_808);
-		a.lshift(int)?;
+		let a = BigUint::from(9_223_372_036_854_775_808).lshift_n(&1.into(), int)?;
 		assert!(!a.is_zero());
 		Ok(())
 	}
@@ -982,4 +1643,306 @@ mod tests {
 		);
 		Ok(())
 	}
+
+	fn pseudo_random_limbs(len: usize, seed: u64) -> Vec<u64> {
+		let mut state = seed;
+		(0..len)
+			.map(|_| {
+				state = state
+					.wrapping_mul(6_364_136_223_846_793_005)
+					.wrapping_add(1_442_695_040_888_963_407);
+				state
+			})
+			.collect()
+	}
+
+	fn assert_karatsuba_matches_schoolbook(a: Vec<u64>, b: Vec<u64>) -> Res {
+		let int = &crate::interrupt::Never;
+		let a = BigUint::Large(a);
+		let b = BigUint::Large(b);
+		let mut expected = a.clone();
+		expected.mul_schoolbook(&b, int)?;
+		assert_eq!(a.mul(&b, int)?, expected);
+		Ok(())
+	}
+
+	#[test]
+	fn test_karatsuba_multiplication() -> Res {
+		for (len_a, len_b) in [(32, 32), (33, 47), (64, 64), (100, 37), (257, 300)] {
+			let a = pseudo_random_limbs(len_a, 1);
+			let b = pseudo_random_limbs(len_b, 2);
+			assert_karatsuba_matches_schoolbook(a, b)?;
+		}
+		// exercise carries in every limb
+		assert_karatsuba_matches_schoolbook(vec![u64::MAX; 150], vec![u64::MAX; 97])?;
+		// unbalanced operands, and leading zero limbs
+		let mut b = pseudo_random_limbs(40, 3);
+		b.extend([0, 0, 0]);
+		assert_karatsuba_matches_schoolbook(pseudo_random_limbs(500, 4), b)?;
+		Ok(())
+	}
+
+	#[test]
+	fn test_karatsuba_square() -> Res {
+		let int = &crate::interrupt::Never;
+		// (2^(64 * 40) - 1)^2 = 2^(64 * 80) - 2^(64 * 40 + 1) + 1
+		let a = BigUint::Large(vec![u64::MAX; 40]);
+		let mut expected = vec![0; 80];
+		expected[0] = 1;
+		expected[40] = u64::MAX - 1;
+		expected[41..].fill(u64::MAX);
+		assert_eq!(a.clone().mul(&a, int)?, BigUint::Large(expected));
+		Ok(())
+	}
+
+	fn assert_divmod_is_correct(a: &BigUint, b: &BigUint) -> Res {
+		let int = &crate::interrupt::Never;
+		let (q, r) = a.divmod(b, int)?;
+		assert!(r < *b);
+		assert_eq!(q.mul(b, int)?.add(&r), *a);
+		Ok(())
+	}
+
+	#[test]
+	fn test_multi_limb_division() -> Res {
+		for (len_a, len_b) in [(2, 2), (5, 2), (9, 4), (40, 33), (120, 7)] {
+			let a = BigUint::Large(pseudo_random_limbs(len_a, 5));
+			let b = BigUint::Large(pseudo_random_limbs(len_b, 6));
+			assert_divmod_is_correct(&a, &b)?;
+		}
+		// quotient digit estimates that are too large, and leading zero limbs
+		let a = BigUint::Large(vec![0, 0, 0, 1 << 63, 0]);
+		let b = BigUint::Large(vec![1, 0, 1 << 63]);
+		assert_divmod_is_correct(&a, &b)?;
+		let a = BigUint::Large(vec![u64::MAX; 6]);
+		let b = BigUint::Large(vec![u64::MAX, u64::MAX - 1, 0]);
+		assert_divmod_is_correct(&a, &b)?;
+		let a = BigUint::Large(vec![0, 0, u64::MAX - 1, u64::MAX]);
+		let b = BigUint::Large(vec![u64::MAX, u64::MAX]);
+		assert_divmod_is_correct(&a, &b)?;
+		// single-limb divisors
+		let a = BigUint::Large(pseudo_random_limbs(10, 7));
+		assert_divmod_is_correct(&a, &BigUint::from(3))?;
+		assert_divmod_is_correct(&a, &BigUint::Large(vec![u64::MAX, 0]))?;
+		Ok(())
+	}
+
+	#[test]
+	fn test_shifts() -> Res {
+		let int = &crate::interrupt::Never;
+		let a = BigUint::Large(pseudo_random_limbs(5, 8));
+		for k in [0, 1, 63, 64, 65, 130, 320, 400] {
+			let shifted = a.clone().lshift_n(&BigUint::from(k), int)?;
+			assert_eq!(shifted.clone().rshift_n(&BigUint::from(k), int)?, a);
+			let mut expected = a.clone();
+			for _ in 0..k {
+				expected = expected.clone().add(&expected);
+			}
+			assert_eq!(shifted, expected, "{k}");
+		}
+		assert_eq!(a.clone().rshift_n(&BigUint::from(400), int)?, 0.into());
+		assert_eq!(
+			BigUint::from(0).lshift_n(&BigUint::from(100), int)?,
+			0.into()
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn test_power_of_two_fast_paths() -> Res {
+		let int = &crate::interrupt::Never;
+		assert_eq!(BigUint::from(1).power_of_two_exponent(), Some(0));
+		assert_eq!(BigUint::from(0).power_of_two_exponent(), None);
+		assert_eq!(BigUint::from(12).power_of_two_exponent(), None);
+		assert_eq!(
+			BigUint::Large(vec![0, 8, 0]).power_of_two_exponent(),
+			Some(67)
+		);
+		assert_eq!(BigUint::Large(vec![1, 8]).power_of_two_exponent(), None);
+		let a = BigUint::Large(pseudo_random_limbs(6, 9));
+		for k in [1, 7, 64, 100, 200, 500] {
+			let power_of_two = BigUint::from(1).lshift_n(&BigUint::from(k), int)?;
+			let mut expected = a.clone();
+			expected.mul_schoolbook(&power_of_two, int)?;
+			assert_eq!(a.clone().mul(&power_of_two, int)?, expected);
+			assert_eq!(power_of_two.clone().mul(&a, int)?, expected);
+			let (q, r) = a.divmod(&power_of_two, int)?;
+			assert_eq!(q, a.clone().rshift_n(&BigUint::from(k), int)?);
+			assert_divmod_is_correct(&a, &power_of_two)?;
+			assert!(r < power_of_two);
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn test_from_str_radix() -> Res {
+		let int = &crate::interrupt::Never;
+		let parse = |s: &str, radix| BigUint::from_str_radix(s, radix, int);
+		assert_eq!(parse("0", 10)?, 0.into());
+		assert_eq!(parse("1_000_000", 10)?, 1_000_000.into());
+		assert_eq!(parse("ff", 16)?, 255.into());
+		assert_eq!(parse("FF", 16)?, 255.into());
+		assert_eq!(parse("777", 8)?, 511.into());
+		assert_eq!(parse("zz", 36)?, 1295.into());
+		assert_eq!(parse("18446744073709551615", 10)?, u64::MAX.into());
+		assert_eq!(
+			parse("18446744073709551616", 10)?,
+			BigUint::Large(vec![0, 1])
+		);
+		assert_eq!(
+			parse(&"1".repeat(130), 2)?,
+			BigUint::Large(vec![u64::MAX, u64::MAX, 3])
+		);
+		let power = BigUint::pow(&10.into(), &100.into(), int)?;
+		assert_eq!(parse(&format!("1{}", "0".repeat(100)), 10)?, power);
+		assert!(matches!(
+			parse("12a", 10),
+			Err(FendError::ExpectedADigit('a'))
+		));
+		assert!(matches!(parse("2", 2), Err(FendError::ExpectedADigit('2'))));
+		assert!(matches!(
+			parse("1__0", 10),
+			Err(FendError::DigitSeparatorsOnlyBetweenDigits)
+		));
+		assert!(matches!(
+			parse("_1", 10),
+			Err(FendError::DigitSeparatorsOnlyBetweenDigits)
+		));
+		assert!(matches!(
+			parse("1_", 10),
+			Err(FendError::DigitSeparatorsOnlyBetweenDigits)
+		));
+		assert!(matches!(parse("", 10), Err(FendError::ExpectedACharacter)));
+		assert!(matches!(parse("1", 1), Err(FendError::BaseTooSmall)));
+		assert!(matches!(parse("1", 37), Err(FendError::BaseTooLarge)));
+		Ok(())
+	}
+
+	#[test]
+	fn test_digits_reversed() -> Res {
+		let int = &crate::interrupt::Never;
+		let digits = |n: &BigUint, base| -> Result<String, FendError> {
+			let base = crate::num::Base::from_plain_base(base)?;
+			Ok(n.digits_reversed(base, int)?.chars().rev().collect())
+		};
+		let ten_to_the_500 = BigUint::pow(&10.into(), &500.into(), int)?;
+		assert_eq!(
+			digits(&ten_to_the_500, 10)?,
+			format!("1{}", "0".repeat(500))
+		);
+		// zeroes at the boundaries between the halves need to be kept
+		let n = ten_to_the_500.clone().add(&7.into());
+		assert_eq!(digits(&n, 10)?, format!("1{}7", "0".repeat(499)));
+		let n = ten_to_the_500.mul(&BigUint::from(123_456_789), int)?;
+		assert_eq!(digits(&n, 10)?, format!("123456789{}", "0".repeat(500)));
+		let n = BigUint::from(1).lshift_n(&1000.into(), int)?;
+		assert_eq!(digits(&n, 2)?, format!("1{}", "0".repeat(1000)));
+		assert_eq!(digits(&n, 16)?, format!("1{}", "0".repeat(250)));
+		assert_eq!(digits(&u64::MAX.into(), 36)?, "3w5e11264sgsf");
+		Ok(())
+	}
+
+	#[test]
+	fn test_combinations_match_factorials() -> Res {
+		let int = &crate::interrupt::Never;
+		let factorial = |n: u64| BigUint::from(n).factorial(int);
+		for n in 0..12_u64 {
+			for k in 0..=n {
+				let n_fact = factorial(n)?;
+				let n_minus_k_fact = factorial(n - k)?;
+				let permutations = n_fact.clone().div(&n_minus_k_fact, int)?;
+				let combinations = permutations.clone().div(&factorial(k)?, int)?;
+				assert_eq!(
+					BigUint::permutation(&n.into(), &k.into(), int)?,
+					permutations
+				);
+				assert_eq!(
+					BigUint::combination(&n.into(), &k.into(), int)?,
+					combinations
+				);
+			}
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn test_prime_factors() -> Res {
+		let int = &crate::interrupt::Never;
+		for n in 2..2000_u64 {
+			let factors = BigUint::from(n).prime_factors(int)?;
+			let mut product = BigUint::from(1);
+			for (prime, count) in factors {
+				assert!(prime.is_prime(int)?, "{prime:?} is not prime");
+				for _ in 0..count {
+					product = product.mul(&prime, int)?;
+				}
+			}
+			assert_eq!(product, BigUint::from(n));
+		}
+		// a Carmichael number and a strong pseudoprime to base 2
+		assert!(!BigUint::from(561).is_prime(int)?);
+		assert!(!BigUint::from(2047).is_prime(int)?);
+		assert!(BigUint::from(1_000_000_007).is_prime(int)?);
+		Ok(())
+	}
+
+	#[test]
+	fn test_mod_inverse() -> Res {
+		let int = &crate::interrupt::Never;
+		let modulus = BigUint::from(26);
+		for a in 1..26_u64 {
+			let result = BigUint::from(a).mod_inverse(&modulus, int);
+			if a % 2 == 0 || a == 13 {
+				assert!(result.is_err());
+			} else {
+				let inverse = result?;
+				let product = inverse.mul(&a.into(), int)?;
+				assert_eq!(product.divmod(&modulus, int)?.1, BigUint::from(1));
+			}
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn test_extended_gcd() -> Res {
+		let int = &crate::interrupt::Never;
+		for (a, b, expected_gcd) in [
+			(240_u64, 46_u64, 2_u64),
+			(46, 240, 2),
+			(17, 5, 1),
+			(0, 7, 7),
+			(7, 0, 7),
+			(12, 12, 12),
+		] {
+			let (gcd, x, y) = BigUint::extended_gcd(&a.into(), &b.into(), int)?;
+			assert_eq!(gcd, BigUint::from(expected_gcd));
+			let sum = BigInt::from(a).mul(&x, int)? + BigInt::from(b).mul(&y, int)?;
+			assert_eq!(sum, BigInt::from(gcd), "{a}, {b}");
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn test_nth_root() -> Res {
+		let int = &crate::interrupt::Never;
+		for n in 1..6_u32 {
+			for x in 0..3000_u64 {
+				let root = BigUint::from(x).nth_root(n.into(), int)?;
+				let expected = (0..=x).take_while(|r| r.pow(n) <= x).last();
+				assert_eq!(
+					root,
+					BigUint::from(expected.unwrap_or(0)),
+					"{n}-th root of {x}"
+				);
+			}
+		}
+		let ten_to_the_40 = BigUint::pow(&10.into(), &40.into(), int)?;
+		let ten_to_the_20 = BigUint::pow(&10.into(), &20.into(), int)?;
+		assert_eq!(ten_to_the_40.isqrt(int)?, ten_to_the_20);
+		assert_eq!(
+			ten_to_the_40.sub(&1.into()).isqrt(int)?,
+			ten_to_the_20.sub(&1.into())
+		);
+		Ok(())
+	}
 }
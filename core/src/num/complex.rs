@@ -1,7 +1,7 @@
 use crate::error::{FendError, Interrupt};
 use crate::num::real::{self, Real};
 use crate::num::Exact;
-use crate::num::{Base, FormattingStyle};
+use crate::num::{Base, DigitFormat, FormattingStyle};
 use crate::result::FResult;
 use std::cmp::Ordering;
 use std::ops::Neg;
@@ -66,6 +66,13 @@ impl Complex {
 		self.real.try_as_i64(int)
 	}
 
+	pub(crate) fn try_as_f64<I: Interrupt>(self, int: &I) -> FResult<f64> {
+		if !self.imag.is_zero() {
+			return Err(FendError::ExpectedARealNumber);
+		}
+		self.real.try_as_f64(int)
+	}
+
 	#[inline]
 	pub(crate) fn real(&self) -> Real {
 		self.real.clone()
@@ -144,7 +151,7 @@ impl Complex {
 			let rem = rhs.clone().real.modulo(4.into(), int);
 			// Reduced case: (ix)^y = x^y * i^y
 			if self.real.is_zero() && rhs.imag.is_zero() {
-				if let Ok(n) = rhs.real.try_as_usize(int) {
+				if let Ok(n) = rhs.real.clone().try_as_usize(int) {
 					return self.pow_n(n, int);
 				}
 
@@ -171,7 +178,7 @@ impl Complex {
 				if !self.imag.is_definitely_one() {
 					result = self
 						.imag
-						.pow(2.into(), int)?
+						.pow(rhs.real, int)?
 						.apply(Self::from)
 						.mul(&result, int)?;
 				}
@@ -243,8 +250,23 @@ impl Complex {
 		style: FormattingStyle,
 		base: Base,
 		use_parentheses: UseParentheses,
+		digits: DigitFormat,
 		int: &I,
 	) -> FResult<Exact<Formatted>> {
+		// an explicit number of decimal places or significant figures rounds
+		// the number (to nearest, ties to even) instead of truncating it
+		if let FormattingStyle::DecimalPlaces(_) | FormattingStyle::SignificantFigures(_) = style {
+			let real = self.real.clone().round_to_style(style, base, int)?;
+			let imag = self.imag.clone().round_to_style(style, base, int)?;
+			if !real.exact || !imag.exact {
+				let rounded = Self {
+					real: real.value,
+					imag: imag.value,
+				};
+				return rounded.format(false, style, base, use_parentheses, digits, int);
+			}
+		}
+
 		let style = if !exact && style == FormattingStyle::Auto {
 			FormattingStyle::DecimalPlaces(10)
 		} else if !self.imag.is_zero() && style == FormattingStyle::Auto {
@@ -255,7 +277,9 @@ impl Complex {
 
 		if self.imag.is_zero() {
 			let use_parens = use_parentheses == UseParentheses::IfComplexOrFraction;
-			let x = self.real.format(base, style, false, use_parens, int)?;
+			let x = self
+				.real
+				.format(base, style, false, use_parens, digits, int)?;
 			return Ok(Exact::new(
 				Formatted {
 					first_component: x.value,
@@ -269,7 +293,9 @@ impl Complex {
 
 		Ok(if self.real.is_zero() {
 			let use_parens = use_parentheses == UseParentheses::IfComplexOrFraction;
-			let x = self.imag.format(base, style, true, use_parens, int)?;
+			let x = self
+				.imag
+				.format(base, style, true, use_parens, digits, int)?;
 			Exact::new(
 				Formatted {
 					first_component: x.value,
@@ -281,14 +307,17 @@ impl Complex {
 			)
 		} else {
 			let mut exact = exact;
-			let real_part = self.real.format(base, style, false, false, int)?;
+			let real_part = self.real.format(base, style, false, false, digits, int)?;
 			exact = exact && real_part.exact;
 			let (positive, imag_part) = if self.imag.is_pos() {
-				(true, self.imag.format(base, style, true, false, int)?)
+				(
+					true,
+					self.imag.format(base, style, true, false, digits, int)?,
+				)
 			} else {
 				(
 					false,
-					(-self.imag.clone()).format(base, style, true, false, int)?,
+					(-self.imag.clone()).format(base, style, true, false, digits, int)?,
 				)
 			};
 			exact = exact && imag_part.exact;
@@ -327,8 +356,8 @@ impl Complex {
 		if self.imag.is_zero() {
 			Ok(self.real.sin(int)?.apply(Self::from))
 		} else {
-			let cosh = Exact::new(self.imag.clone().cosh(int)?, false);
-			let sinh = Exact::new(self.imag.sinh(int)?, false);
+			let cosh = self.imag.clone().cosh(int)?;
+			let sinh = self.imag.sinh(int)?;
 
 			let real = self.real.clone().sin(int)?.mul(cosh.re(), int)?;
 			let imag = self.real.cos(int)?.mul(sinh.re(), int)?;
@@ -348,8 +377,8 @@ impl Complex {
 		if self.imag.is_zero() {
 			Ok(self.real.cos(int)?.apply(Self::from))
 		} else {
-			let cosh = Exact::new(self.imag.clone().cosh(int)?, false);
-			let sinh = Exact::new(self.imag.sinh(int)?, false);
+			let cosh = self.imag.clone().cosh(int)?;
+			let sinh = self.imag.sinh(int)?;
 			let exact_real = Exact::new(self.real, true);
 
 			let real = exact_real.value.clone().cos(int)?.mul(cosh.re(), int)?;
@@ -364,7 +393,11 @@ impl Complex {
 		}
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn tan<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		if self.imag.is_zero() {
+			return Ok(self.real.tan(int)?.apply(Self::from));
+		}
 		let num = self.clone().sin(int)?;
 		let den = self.cos(int)?;
 		num.div(den, int)
@@ -385,40 +418,39 @@ impl Complex {
 			.try_and_then(|x| x.ln(int))
 	}
 
-	pub(crate) fn asin<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	pub(crate) fn asin<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		// Real asin is defined for -1 <= x <= 1
 		if self.imag.is_zero() && self.real.between_plus_minus_one_incl(int)? {
-			Ok(Self::from(self.real.asin(int)?))
+			Ok(self.real.asin(int)?.apply(Self::from))
 		} else {
 			// asin(z) = -i * ln(i * z + sqrt(1 - z^2))
 			Ok(self
 				.asin_ln(int)?
 				.mul(&Exact::new(Self::i(), true), int)?
-				.neg()
-				.value)
+				.neg())
 		}
 	}
 
-	pub(crate) fn acos<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn acos<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		// Real acos is defined for -1 <= x <= 1
 		if self.imag.is_zero() && self.real.between_plus_minus_one_incl(int)? {
-			Ok(Self::from(self.real.acos(int)?))
+			Ok(self.real.acos(int)?.apply(Self::from))
 		} else {
 			// acos(z) = pi/2 + i * ln(i * z + sqrt(1 - z^2))
 			let half_pi = Exact::new(Self::pi(), true).div(Exact::new(Self::from(2), true), int)?;
-			Ok(half_pi
-				.add(
-					self.asin_ln(int)?.mul(&Exact::new(Self::i(), true), int)?,
-					int,
-				)?
-				.value)
+			half_pi.add(
+				self.asin_ln(int)?.mul(&Exact::new(Self::i(), true), int)?,
+				int,
+			)
 		}
 	}
 
-	pub(crate) fn atan<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn atan<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		// Real atan is defined for all real numbers
 		if self.imag.is_zero() {
-			Ok(Self::from(self.real.atan(int)?))
+			Ok(self.real.atan(int)?.apply(Self::from))
 		} else {
 			// i/2 * (ln(-iz+1) - ln(iz+1))
 			let half_i = Exact::new(Self::i(), true).div(Exact::new(Self::from(2), true), int)?;
@@ -429,65 +461,75 @@ impl Complex {
 				.mul(&Exact::new(Self::i(), true), int)?
 				.add(Exact::new(Self::from(1), true), int)?;
 
-			Ok(half_i
-				.mul(
-					&z1.try_and_then(|z| z.ln(int))?
-						.add(z2.try_and_then(|z| z.ln(int))?.neg(), int)?,
-					int,
-				)?
-				.value)
+			half_i.mul(
+				&z1.try_and_then(|z| z.ln(int))?
+					.add(z2.try_and_then(|z| z.ln(int))?.neg(), int)?,
+				int,
+			)
 		}
 	}
 
-	pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		if self.imag.is_zero() {
-			Ok(Self::from(self.real.sinh(int)?))
+			Ok(self.real.sinh(int)?.apply(Self::from))
 		} else {
 			// sinh(a+bi)=sinh(a)cos(b)+icosh(a)sin(b)
-			let sinh = Exact::new(self.real.clone().sinh(int)?, false);
+			let sinh = self.real.clone().sinh(int)?;
 			let cos = self.imag.clone().cos(int)?;
-			let cosh = Exact::new(self.real.cosh(int)?, false);
+			let cosh = self.real.cosh(int)?;
 			let sin = self.imag.sin(int)?;
 
-			Ok(Self {
-				real: sinh.mul(cos.re(), int)?.value,
-				imag: cosh.mul(sin.re(), int)?.value,
-			})
+			let real = sinh.mul(cos.re(), int)?;
+			let imag = cosh.mul(sin.re(), int)?;
+			Ok(Exact::new(
+				Self {
+					real: real.value,
+					imag: imag.value,
+				},
+				real.exact && imag.exact,
+			))
 		}
 	}
 
-	pub(crate) fn cosh<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn cosh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		if self.imag.is_zero() {
-			Ok(Self::from(self.real.cosh(int)?))
+			Ok(self.real.cosh(int)?.apply(Self::from))
 		} else {
 			// cosh(a+bi)=cosh(a)cos(b)+isinh(a)sin(b)
-			let cosh = Exact::new(self.real.clone().cosh(int)?, false);
+			let cosh = self.real.clone().cosh(int)?;
 			let cos = self.imag.clone().cos(int)?;
-			let sinh = Exact::new(self.real.sinh(int)?, false);
+			let sinh = self.real.sinh(int)?;
 			let sin = self.imag.sin(int)?;
 
-			Ok(Self {
-				real: cosh.mul(cos.re(), int)?.value,
-				imag: sinh.mul(sin.re(), int)?.value,
-			})
+			let real = cosh.mul(cos.re(), int)?;
+			let imag = sinh.mul(sin.re(), int)?;
+			Ok(Exact::new(
+				Self {
+					real: real.value,
+					imag: imag.value,
+				},
+				real.exact && imag.exact,
+			))
 		}
 	}
 
-	pub(crate) fn tanh<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn tanh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		if self.imag.is_zero() {
-			Ok(Self::from(self.real.tanh(int)?))
+			Ok(self.real.tanh(int)?.apply(Self::from))
 		} else {
 			// tanh(a+bi)=sinh(a+bi)/cosh(a+bi)
-			Exact::new(self.clone().sinh(int)?, false)
-				.div(Exact::new(self.cosh(int)?, false), int)
-				.map(|x| x.value)
+			self.clone().sinh(int)?.div(self.cosh(int)?, int)
 		}
 	}
 
-	pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		// Real asinh is defined for all real numbers
 		if self.imag.is_zero() {
-			Ok(Self::from(self.real.asinh(int)?))
+			Ok(self.real.asinh(int)?.apply(Self::from))
 		} else {
 			// asinh(z)=ln(z+sqrt(z^2+1))
 			let exact = Exact::new(self, true);
@@ -497,16 +539,15 @@ impl Complex {
 				.mul(&exact, int)?
 				.add(Exact::new(Self::from(1), true), int)?
 				.try_and_then(|x| x.frac_pow(half.value, int))?;
-			sqrt.add(exact, int)?
-				.try_and_then(|x| x.ln(int))
-				.map(|x| x.value)
+			sqrt.add(exact, int)?.try_and_then(|x| x.ln(int))
 		}
 	}
 
-	pub(crate) fn acosh<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn acosh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		// Real acosh is defined for x >= 1
 		if self.imag.is_zero() && self.real.compare(&1.into(), int)? != Ordering::Less {
-			Ok(Self::from(self.real.acosh(int)?))
+			Ok(self.real.acosh(int)?.apply(Self::from))
 		} else {
 			// acosh(z)=ln(z+sqrt(z^2-1))
 			let exact = Exact::new(self, true);
@@ -516,17 +557,16 @@ impl Complex {
 				.mul(&exact, int)?
 				.add(Exact::new(Self::from(1), true).neg(), int)?
 				.try_and_then(|x| x.frac_pow(half.value, int))?;
-			sqrt.add(exact, int)?
-				.try_and_then(|x| x.ln(int))
-				.map(|x| x.value)
+			sqrt.add(exact, int)?.try_and_then(|x| x.ln(int))
 		}
 	}
 
-	pub(crate) fn atanh<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn atanh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		// Real atanh is defined for -1 < x < 1
 		// Undefined for x = 1, -1
 		if self.imag.is_zero() && self.real.between_plus_minus_one_excl(int)? {
-			Ok(Self::from(self.real.atanh(int)?))
+			Ok(self.real.atanh(int)?.apply(Self::from))
 		} else {
 			// atanh(z)=ln(sqrt(-(z-1)/(z-1)))
 			let exact = Exact::new(self, true);
@@ -540,7 +580,6 @@ impl Complex {
 				.div(exact.add(one.neg(), int)?, int)?
 				.try_and_then(|x| x.frac_pow(half.value, int))?
 				.try_and_then(|z| z.ln(int))
-				.map(|x| x.value)
 		}
 	}
 
@@ -563,6 +602,7 @@ impl Complex {
 		}
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn log<I: Interrupt>(self, base: Self, int: &I) -> FResult<Self> {
 		// log_n(z) = ln(z) / ln(n)
 		let ln = self.ln(int)?;
@@ -570,31 +610,58 @@ impl Complex {
 		Ok(ln.div(ln2, int)?.value)
 	}
 
-	pub(crate) fn log2<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn log2<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		if self.imag.is_zero() && self.real.is_pos() {
-			Ok(Self::from(self.real.log2(int)?))
+			Ok(self.real.log2(int)?.apply(Self::from))
 		} else {
-			self.log(Self::from(2), int)
+			Ok(Exact::new(self.log(Self::from(2), int)?, false))
 		}
 	}
-	pub(crate) fn log10<I: Interrupt>(self, int: &I) -> FResult<Self> {
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn log10<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		if self.imag.is_zero() && self.real.is_pos() {
-			Ok(Self::from(self.real.log10(int)?))
+			Ok(self.real.log10(int)?.apply(Self::from))
 		} else {
-			self.log(Self::from(10), int)
+			Ok(Exact::new(self.log(Self::from(10), int)?, false))
 		}
 	}
 
+	pub(crate) fn is_zero(&self) -> bool {
+		self.real.is_zero() && self.imag.is_zero()
+	}
+
 	pub(crate) fn is_definitely_one(&self) -> bool {
 		self.real.is_definitely_one() && self.imag.is_definitely_zero()
 	}
 
+	pub(crate) fn floor_div<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		Ok(Self::from(
+			self.expect_real()?.floor_div(rhs.expect_real()?, int)?,
+		))
+	}
+
 	pub(crate) fn modulo<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
 		Ok(Self::from(
 			self.expect_real()?.modulo(rhs.expect_real()?, int)?,
 		))
 	}
 
+	pub(crate) fn wrap_to_width<I: Interrupt>(
+		self,
+		bits: u64,
+		signed: bool,
+		int: &I,
+	) -> FResult<Self> {
+		Ok(Self::from(
+			self.expect_real()?.wrap_to_width(bits, signed, int)?,
+		))
+	}
+
+	pub(crate) fn bitwise_not<I: Interrupt>(self, width: u64, int: &I) -> FResult<Self> {
+		Ok(Self::from(self.expect_real()?.bitwise_not(width, int)?))
+	}
+
 	pub(crate) fn bitwise<I: Interrupt>(
 		self,
 		rhs: Self,
@@ -608,6 +675,51 @@ impl Complex {
 		)?))
 	}
 
+	pub(crate) fn prime_factors<I: Interrupt>(self, int: &I) -> FResult<Vec<(Self, usize)>> {
+		Ok(self
+			.expect_real()?
+			.prime_factors(int)?
+			.into_iter()
+			.map(|(p, k)| (p.into(), k))
+			.collect())
+	}
+
+	pub(crate) fn is_prime<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		self.clone().expect_real()?.is_prime(int)
+	}
+
+	pub(crate) fn next_prime<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self::from(self.expect_real()?.next_prime(int)?))
+	}
+
+	pub(crate) fn prev_prime<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self::from(self.expect_real()?.prev_prime(int)?))
+	}
+
+	pub(crate) fn isqrt<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self::from(self.expect_real()?.isqrt(int)?))
+	}
+
+	pub(crate) fn pow_mod<I: Interrupt>(
+		self,
+		exponent: Self,
+		modulus: Self,
+		int: &I,
+	) -> FResult<Self> {
+		Ok(Self::from(self.expect_real()?.pow_mod(
+			exponent.expect_real()?,
+			modulus.expect_real()?,
+			int,
+		)?))
+	}
+
+	pub(crate) fn mod_inverse<I: Interrupt>(self, modulus: Self, int: &I) -> FResult<Self> {
+		Ok(Self::from(
+			self.expect_real()?
+				.mod_inverse(modulus.expect_real()?, int)?,
+		))
+	}
+
 	pub(crate) fn combination<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
 		Ok(Self::from(
 			self.expect_real()?.combination(rhs.expect_real()?, int)?,
@@ -83,11 +83,11 @@ impl Dist {
 	}
 
 	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-	pub(crate) fn sample<I: Interrupt>(self, ctx: &crate::Context, int: &I) -> FResult<Self> {
+	pub(crate) fn sample<I: Interrupt>(self, ctx: &mut crate::Context, int: &I) -> FResult<Self> {
 		if self.parts.len() == 1 {
 			return Ok(self);
 		}
-		let mut random = ctx.random_u32.ok_or(FendError::RandomNumbersNotAvailable)?();
+		let mut random = ctx.next_random_u32()?;
 		let mut res = None;
 		for (k, v) in self.parts {
 			random = random.saturating_sub((v.into_f64(int)? * f64::from(u32::MAX)) as u32);
@@ -132,9 +132,14 @@ impl Dist {
 		int: &I,
 	) -> FResult<Exact<()>> {
 		if self.parts.len() == 1 {
-			let res = self.parts[0]
-				.0
-				.format(exact, style, base, use_parentheses, int)?;
+			let res = self.parts[0].0.format(
+				exact,
+				style,
+				base,
+				use_parentheses,
+				ctx.digit_format,
+				int,
+			)?;
 			write!(out, "{}", res.value)?;
 			Ok(Exact::new((), res.exact))
 		} else {
@@ -156,7 +161,7 @@ impl Dist {
 			let mut first = true;
 			for (num, _prob, prob_f64) in ordered_kvs {
 				let num = num
-					.format(exact, style, base, use_parentheses, int)?
+					.format(exact, style, base, use_parentheses, ctx.digit_format, int)?
 					.value
 					.to_string();
 				let prob_percentage = prob_f64 * 100.0;
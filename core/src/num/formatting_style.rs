@@ -2,6 +2,7 @@ use std::{fmt, io};
 
 use crate::{
 	error::FendError,
+	num::Base,
 	result::FResult,
 	serialize::{Deserialize, Serialize},
 };
@@ -29,6 +30,61 @@ pub(crate) enum FormattingStyle {
 	Exact,
 }
 
+/// Controls whether numbers formatted with a fixed number of decimal places or
+/// significant figures are padded with trailing zeros
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub(crate) struct TrailingZeros {
+	/// Pad decimals to the requested precision, e.g. `2.50` instead of `2.5`
+	pub(crate) keep: bool,
+	/// Pad integers as well, e.g. `3.00` instead of `3`
+	pub(crate) integer_decimal_point: bool,
+}
+
+/// Number of digits per group when writing binary or hexadecimal numbers,
+/// e.g. `0b1010_1100` with a group size of 4, and the separators used for
+/// decimal numbers
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub(crate) struct DigitGrouping {
+	pub(crate) binary: Option<usize>,
+	pub(crate) hex: Option<usize>,
+	pub(crate) decimal: crate::DigitSeparators,
+}
+
+impl DigitGrouping {
+	/// Returns the group size and the character used to separate groups
+	pub(crate) fn group_size(self, base: Base) -> Option<(usize, char)> {
+		match base.base_as_u8() {
+			2 => self.binary.map(|size| (size, '_')),
+			16 => self.hex.map(|size| (size, '_')),
+			10 => self
+				.decimal
+				.group_separator()
+				.map(|separator| (3, separator)),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn decimal_point(self, base: Base) -> char {
+		if base.base_as_u8() == 10 {
+			self.decimal.decimal_point()
+		} else {
+			'.'
+		}
+	}
+}
+
+/// Settings from the context that control how numbers are written
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub(crate) struct DigitFormat {
+	pub(crate) trailing_zeros: TrailingZeros,
+	pub(crate) grouping: DigitGrouping,
+	pub(crate) recurring: crate::RecurringDigitsNotation,
+	pub(crate) division: crate::DivisionSign,
+	/// Show exact non-integer results as fractions instead of decimals when
+	/// using the `Auto` style
+	pub(crate) prefer_fractions: bool,
+}
+
 impl fmt::Display for FormattingStyle {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
 		match self {
@@ -1,8 +1,9 @@
 use crate::error::{FendError, Interrupt};
 use crate::format::Format;
 use crate::num::bigrat::{BigRat, FormattedBigRat};
+use crate::num::biguint::BigUint;
 use crate::num::Exact;
-use crate::num::{Base, FormattingStyle};
+use crate::num::{Base, DigitFormat, FormattingStyle};
 use crate::result::FResult;
 use crate::serialize::{Deserialize, Serialize};
 use std::cmp::Ordering;
@@ -102,6 +103,10 @@ impl Real {
 		}
 	}
 
+	pub(crate) fn try_as_f64<I: Interrupt>(self, int: &I) -> FResult<f64> {
+		self.approximate(int)?.into_f64(int)
+	}
+
 	pub(crate) fn try_as_i64<I: Interrupt>(self, int: &I) -> FResult<i64> {
 		match self.pattern {
 			Pattern::Simple(s) => s.try_as_i64(int),
@@ -170,16 +175,87 @@ impl Real {
 		Exact::new(self, true).add(half_pi, int)?.value.sin(int)
 	}
 
-	pub(crate) fn asin<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.asin(int)?))
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn tan<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		if let Pattern::Pi(n) = &self.pattern {
+			if n < &0.into() {
+				// tan(-x) == -tan(x)
+				return Ok(-Self::tan(-self, int)?);
+			}
+			if let Ok(integer) = n.clone().mul(&4.into(), int)?.try_as_usize(int) {
+				match integer % 4 {
+					0 => return Ok(Exact::new(Self::from(0), true)),
+					1 => return Ok(Exact::new(Self::from(1), true)),
+					3 => return Ok(Exact::new(-Self::from(1), true)),
+					_ => (),
+				}
+			}
+		}
+		let num = self.clone().sin(int)?;
+		let den = self.cos(int)?;
+		num.div(&den, int)
+	}
+
+	/// Returns `asin(self) / pi` if it is rational, i.e. if `self` is
+	/// 0, ±1/2 or ±1
+	fn asin_over_pi<I: Interrupt>(&self, int: &I) -> FResult<Option<BigRat>> {
+		let Pattern::Simple(x) = &self.pattern else {
+			return Ok(None);
+		};
+		let half = BigRat::from(1).div(&2.into(), int)?;
+		let abs = if x < &0.into() { -x.clone() } else { x.clone() };
+		let res = if abs == 0.into() {
+			BigRat::from(0)
+		} else if abs == half {
+			BigRat::from(1).div(&6.into(), int)?
+		} else if abs == 1.into() {
+			half
+		} else {
+			return Ok(None);
+		};
+		Ok(Some(if x < &0.into() { -res } else { res }))
 	}
 
-	pub(crate) fn acos<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.acos(int)?))
+	pub(crate) fn asin<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		if let Some(n) = self.asin_over_pi(int)? {
+			return Ok(Exact::new(Self::from_pi_multiple(n), true));
+		}
+		Ok(Exact::new(
+			Self::from(self.approximate(int)?.asin(int)?),
+			false,
+		))
 	}
 
-	pub(crate) fn atan<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.atan(int)?))
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn acos<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		// acos(x) = pi/2 - asin(x)
+		if let Some(n) = self.asin_over_pi(int)? {
+			let half = BigRat::from(1).div(&2.into(), int)?;
+			return Ok(Exact::new(Self::from_pi_multiple(half.add(-n, int)?), true));
+		}
+		Ok(Exact::new(
+			Self::from(self.approximate(int)?.acos(int)?),
+			false,
+		))
+	}
+
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn atan<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		if let Pattern::Simple(x) = &self.pattern {
+			if x == &0.into() {
+				return Ok(Exact::new(Self::from(0), true));
+			}
+			let quarter = BigRat::from(1).div(&4.into(), int)?;
+			if x == &1.into() {
+				return Ok(Exact::new(Self::from_pi_multiple(quarter), true));
+			} else if x == &(-BigRat::from(1)) {
+				return Ok(Exact::new(Self::from_pi_multiple(-quarter), true));
+			}
+		}
+		Ok(Exact::new(
+			Self::from(self.approximate(int)?.atan(int)?),
+			false,
+		))
 	}
 
 	pub(crate) fn atan2<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
@@ -188,28 +264,32 @@ impl Real {
 		))
 	}
 
-	pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.sinh(int)?))
+	pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		Ok(self.approximate(int)?.sinh(int)?.apply(Self::from))
 	}
 
-	pub(crate) fn cosh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.cosh(int)?))
+	pub(crate) fn cosh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		Ok(self.approximate(int)?.cosh(int)?.apply(Self::from))
 	}
 
-	pub(crate) fn tanh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.tanh(int)?))
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn tanh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		Ok(self.approximate(int)?.tanh(int)?.apply(Self::from))
 	}
 
-	pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.asinh(int)?))
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		Ok(self.approximate(int)?.asinh(int)?.apply(Self::from))
 	}
 
-	pub(crate) fn acosh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.acosh(int)?))
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn acosh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		Ok(self.approximate(int)?.acosh(int)?.apply(Self::from))
 	}
 
-	pub(crate) fn atanh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.atanh(int)?))
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn atanh<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		Ok(self.approximate(int)?.atanh(int)?.apply(Self::from))
 	}
 
 	// For all logs: value must be greater than 0
@@ -217,12 +297,14 @@ impl Real {
 		Ok(self.approximate(int)?.ln(int)?.apply(Self::from))
 	}
 
-	pub(crate) fn log2<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.log2(int)?))
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn log2<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		Ok(self.approximate(int)?.log2(int)?.apply(Self::from))
 	}
 
-	pub(crate) fn log10<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		Ok(Self::from(self.approximate(int)?.log10(int)?))
+	#[cfg(feature = "transcendental")]
+	pub(crate) fn log10<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
+		Ok(self.approximate(int)?.log10(int)?.apply(Self::from))
 	}
 
 	pub(crate) fn factorial<I: Interrupt>(self, int: &I) -> FResult<Self> {
@@ -247,8 +329,15 @@ impl Real {
 		mut style: FormattingStyle,
 		imag: bool,
 		use_parens_if_fraction: bool,
+		digits: DigitFormat,
 		int: &I,
 	) -> FResult<Exact<Formatted>> {
+		if style == FormattingStyle::Auto && digits.prefer_fractions {
+			if let Pattern::Simple(_) = self.pattern {
+				style = FormattingStyle::ImproperFraction;
+			}
+		}
+
 		let mut pi = false;
 		if style == FormattingStyle::Exact && !self.is_zero() {
 			if let Pattern::Pi(_) = self.pattern {
@@ -286,6 +375,7 @@ impl Real {
 				style,
 				term,
 				use_parens_if_fraction,
+				digit_format: digits,
 			},
 			int,
 		)?;
@@ -298,6 +388,49 @@ impl Real {
 		))
 	}
 
+	/// Rounds to the number of decimal places or significant figures given
+	/// by `style`. The result is exact if rounding didn't change the number.
+	pub(crate) fn round_to_style<I: Interrupt>(
+		self,
+		style: FormattingStyle,
+		base: Base,
+		int: &I,
+	) -> FResult<Exact<Self>> {
+		if let Pattern::Simple(s) = &self.pattern {
+			let rounded = s.clone().round_to_style(style, base, int)?;
+			let exact = &rounded == s;
+			return Ok(Exact::new(Self::from(rounded), exact));
+		}
+		let rounded = self.approximate(int)?.round_to_style(style, base, int)?;
+		Ok(Exact::new(Self::from(rounded), false))
+	}
+
+	/// Splits the number into a mantissa `m` with `1 <= abs(m) < 10` and an
+	/// exponent `e` such that `self == m * 10^e`. Returns `None` for zero.
+	pub(crate) fn to_scientific<I: Interrupt>(&self, int: &I) -> FResult<Option<(Self, i64)>> {
+		if self.is_zero() {
+			return Ok(None);
+		}
+		let exponent = self.clone().approximate(int)?.decimal_exponent(int)?;
+		let scale = BigRat::from(BigUint::pow(
+			&10.into(),
+			&exponent.unsigned_abs().into(),
+			int,
+		)?);
+		let scale_rat = |rat: &BigRat| {
+			if exponent >= 0 {
+				rat.clone().div(&scale, int)
+			} else {
+				rat.clone().mul(&scale, int)
+			}
+		};
+		let mantissa = match &self.pattern {
+			Pattern::Simple(rat) => Pattern::Simple(scale_rat(rat)?),
+			Pattern::Pi(rat) => Pattern::Pi(scale_rat(rat)?),
+		};
+		Ok(Some((Self { pattern: mantissa }, exponent)))
+	}
+
 	pub(crate) fn exp<I: Interrupt>(self, int: &I) -> FResult<Exact<Self>> {
 		Ok(self.approximate(int)?.exp(int)?.apply(Self::from))
 	}
@@ -356,6 +489,16 @@ impl Real {
 		}
 	}
 
+	/// Returns `n * pi`
+	fn from_pi_multiple(n: BigRat) -> Self {
+		if n == 0.into() {
+			return Self::from(0);
+		}
+		Self {
+			pattern: Pattern::Pi(n),
+		}
+	}
+
 	pub(crate) fn is_zero(&self) -> bool {
 		match &self.pattern {
 			Pattern::Simple(a) | Pattern::Pi(a) => a.is_definitely_zero() || a == &0.into(),
@@ -380,6 +523,7 @@ impl Real {
 			&& self.compare(&1.into(), int)? != Ordering::Greater)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn between_plus_minus_one_excl<I: Interrupt>(&self, int: &I) -> FResult<bool> {
 		// -1 < x < 1
 		Ok(Self::from(1).neg().compare(self, int)? == Ordering::Less
@@ -406,6 +550,13 @@ impl Real {
 		}
 	}
 
+	pub(crate) fn floor_div<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		Ok(Self::from(
+			self.expect_rational()?
+				.floor_div(&rhs.expect_rational()?, int)?,
+		))
+	}
+
 	pub(crate) fn modulo<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
 		Ok(Self::from(
 			self.expect_rational()?
@@ -413,6 +564,21 @@ impl Real {
 		))
 	}
 
+	pub(crate) fn wrap_to_width<I: Interrupt>(
+		self,
+		bits: u64,
+		signed: bool,
+		int: &I,
+	) -> FResult<Self> {
+		Ok(Self::from(
+			self.expect_rational()?.wrap_to_width(bits, signed, int)?,
+		))
+	}
+
+	pub(crate) fn bitwise_not<I: Interrupt>(self, width: u64, int: &I) -> FResult<Self> {
+		Ok(Self::from(self.expect_rational()?.bitwise_not(width, int)?))
+	}
+
 	pub(crate) fn bitwise<I: Interrupt>(
 		self,
 		rhs: Self,
@@ -426,6 +592,51 @@ impl Real {
 		)?))
 	}
 
+	pub(crate) fn prime_factors<I: Interrupt>(self, int: &I) -> FResult<Vec<(Self, usize)>> {
+		Ok(self
+			.expect_rational()?
+			.prime_factors(int)?
+			.into_iter()
+			.map(|(p, k)| (p.into(), k))
+			.collect())
+	}
+
+	pub(crate) fn is_prime<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		self.clone().expect_rational()?.is_prime(int)
+	}
+
+	pub(crate) fn next_prime<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self::from(self.expect_rational()?.next_prime(int)?))
+	}
+
+	pub(crate) fn prev_prime<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self::from(self.expect_rational()?.prev_prime(int)?))
+	}
+
+	pub(crate) fn isqrt<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self::from(self.expect_rational()?.isqrt(int)?))
+	}
+
+	pub(crate) fn pow_mod<I: Interrupt>(
+		self,
+		exponent: Self,
+		modulus: Self,
+		int: &I,
+	) -> FResult<Self> {
+		Ok(Self::from(self.expect_rational()?.pow_mod(
+			exponent.expect_rational()?,
+			modulus.expect_rational()?,
+			int,
+		)?))
+	}
+
+	pub(crate) fn mod_inverse<I: Interrupt>(self, modulus: Self, int: &I) -> FResult<Self> {
+		Ok(Self::from(
+			self.expect_rational()?
+				.mod_inverse(modulus.expect_rational()?, int)?,
+		))
+	}
+
 	pub(crate) fn combination<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
 		Ok(Self::from(
 			self.expect_rational()?
@@ -454,12 +665,9 @@ impl Exact<Real> {
 				(Pattern::Simple(a), Pattern::Simple(b)) => {
 					Self::new(a.add(b, int)?.into(), args_exact)
 				}
-				(Pattern::Pi(a), Pattern::Pi(b)) => Self::new(
-					Real {
-						pattern: Pattern::Pi(a.add(b, int)?),
-					},
-					args_exact,
-				),
+				(Pattern::Pi(a), Pattern::Pi(b)) => {
+					Self::new(Real::from_pi_multiple(a.add(b, int)?), args_exact)
+				}
 				_ => {
 					let a = self.value.approximate(int)?;
 					let b = rhs.value.approximate(int)?;
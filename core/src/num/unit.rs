@@ -2,13 +2,13 @@ use crate::ast::{BitwiseBop, Bop};
 use crate::error::{FendError, Interrupt};
 use crate::num::complex::{Complex, UseParentheses};
 use crate::num::dist::Dist;
-use crate::num::{Base, FormattingStyle};
+use crate::num::{Base, DigitFormat, FormattingStyle};
 use crate::result::FResult;
 use crate::scope::Scope;
 use crate::serialize::{Deserialize, Serialize};
 use crate::units::{lookup_default_unit, query_unit_static};
 use crate::{ast, ident::Ident};
-use crate::{Attrs, Span, SpanKind};
+use crate::{Attrs, Span, SpanKind, UnitExponentNotation};
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
@@ -89,6 +89,10 @@ impl Value {
 		self.into_unitless_complex(int)?.try_as_usize(int)
 	}
 
+	pub(crate) fn try_as_i64<I: Interrupt>(self, int: &I) -> FResult<i64> {
+		self.into_unitless_complex(int)?.try_as_i64(int)
+	}
+
 	pub(crate) fn try_as_usize_unit<I: Interrupt>(self, int: &I) -> FResult<usize> {
 		if !self.exact {
 			return Err(FendError::InexactNumberToInt);
@@ -96,6 +100,18 @@ impl Value {
 		self.value.one_point()?.try_as_usize(int)
 	}
 
+	/// Approximates a unitless real number as a float, e.g. for statistical
+	/// functions that have no exact form
+	pub(crate) fn try_as_f64<I: Interrupt>(self, int: &I) -> FResult<f64> {
+		self.into_unitless_complex(int)?.try_as_f64(int)
+	}
+
+	pub(crate) fn from_f64<I: Interrupt>(f: f64, int: &I) -> FResult<Self> {
+		let mut result = Self::new(Real::from(BigRat::from_f64(f, int)?), vec![]);
+		result.exact = false;
+		Ok(result)
+	}
+
 	pub(crate) fn create_unit_value_from_value<I: Interrupt>(
 		value: &Self,
 		prefix: Cow<'static, str>,
@@ -183,7 +199,23 @@ impl Value {
 		}
 	}
 
+	/// Returns `1` with the same unit as this value, e.g. `1 mile` for
+	/// `5 miles`
+	pub(crate) fn unit_one(&self) -> Self {
+		Self {
+			value: 1.into(),
+			unit: self.unit.clone(),
+			exact: true,
+			base: self.base,
+			format: self.format,
+			simplifiable: false,
+		}
+	}
+
 	pub(crate) fn add<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		if self.unit.is_absolute_temperature(int)? && rhs.unit.is_absolute_temperature(int)? {
+			return Err(FendError::AddingAbsoluteTemperatures);
+		}
 		let scale_factor = Unit::compute_scale_factor(&rhs.unit, &self.unit, int)?;
 		let scaled = Exact::new(rhs.value, rhs.exact)
 			.mul(&scale_factor.scale_1.apply(Dist::from), int)?
@@ -240,6 +272,33 @@ impl Value {
 		})
 	}
 
+	/// Splits the number into several units, e.g. `200 min` into `3 h` and
+	/// `20 min`. All parts except for the last one are whole numbers.
+	pub(crate) fn convert_to_mixed<I: Interrupt>(
+		self,
+		units: Vec<Self>,
+		int: &I,
+	) -> FResult<Vec<Self>> {
+		let negative = self.real_sign() == Some(Ordering::Less);
+		let mut remaining = self.abs(int)?;
+		let mut parts = Vec::with_capacity(units.len());
+		let num_units = units.len();
+		for (i, unit) in units.into_iter().enumerate() {
+			let part = remaining.clone().convert_to(unit, int)?;
+			if i + 1 == num_units {
+				parts.push(part);
+				break;
+			}
+			let part = part.floor(int)?;
+			remaining = remaining.sub(part.clone(), int)?;
+			parts.push(part);
+		}
+		if negative {
+			parts = parts.into_iter().map(|part| -part).collect();
+		}
+		Ok(parts)
+	}
+
 	pub(crate) fn sub<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
 		let scale_factor = Unit::compute_scale_factor(&rhs.unit, &self.unit, int)?;
 		let scaled = Exact::new(rhs.value, rhs.exact)
@@ -256,6 +315,42 @@ impl Value {
 		})
 	}
 
+	/// Returns whether this value is within `tolerance` of `other`. The
+	/// tolerance is relative if it is dimensionless but has a unit like `%`
+	/// or `ppm`, and absolute otherwise. If no tolerance is given, a relative
+	/// tolerance of 1e-9 is used.
+	pub(crate) fn approx_eq<I: Interrupt>(
+		self,
+		other: Self,
+		tolerance: Option<Self>,
+		int: &I,
+	) -> FResult<bool> {
+		let difference = self.clone().sub(other.clone(), int)?.abs(int)?;
+		let tolerance = match tolerance {
+			Some(tolerance)
+				if tolerance.unit.components.is_empty() || !tolerance.is_unitless(int)? =>
+			{
+				tolerance
+			}
+			relative => {
+				let relative = match relative {
+					Some(relative) => relative,
+					None => Self::from(1).div(Self::from(1_000_000_000), int)?,
+				};
+				let (a, b) = (self.abs(int)?, other.abs(int)?);
+				let larger = if a.compare(&b, int)? == Some(Ordering::Less) {
+					b
+				} else {
+					a
+				};
+				larger.mul(relative, int)?
+			}
+		};
+		// this fails if the tolerance has incompatible units
+		let slack = tolerance.sub(difference, int)?;
+		Ok(slack.value.one_point()?.compare(&0.into(), int)? != Some(Ordering::Less))
+	}
+
 	pub(crate) fn div<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
 		let mut components = self.unit.components.clone();
 		for rhs_component in rhs.unit.components {
@@ -276,7 +371,30 @@ impl Value {
 		})
 	}
 
-	fn modulo<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+	pub(crate) fn floor_div<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		let (base, format, simplifiable) = (self.base, self.format, self.simplifiable);
+		// dividing first allows compatible units, e.g. `7 m // 2 m`
+		let quotient = self.div(rhs, int)?;
+		Ok(Self {
+			unit: Unit::unitless(),
+			exact: quotient.exact,
+			base,
+			format,
+			simplifiable,
+			value: Dist::from(
+				quotient
+					.into_unitless_complex(int)?
+					.floor_div(1.into(), int)?,
+			),
+		})
+	}
+
+	pub(crate) fn modulo<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		if !self.is_unitless(int)? || !rhs.is_unitless(int)? {
+			// e.g. `7 m mod 2 m` is `7 m - 2 m * (7 m // 2 m)`
+			let quotient = self.clone().floor_div(rhs.clone(), int)?;
+			return self.sub(rhs.mul(quotient, int)?, int);
+		}
 		Ok(Self {
 			unit: Unit::unitless(),
 			exact: self.exact && rhs.exact,
@@ -305,6 +423,135 @@ impl Value {
 		})
 	}
 
+	pub(crate) fn prime_factors<I: Interrupt>(self, int: &I) -> FResult<Vec<(Self, usize)>> {
+		let (base, format, simplifiable) = (self.base, self.format, self.simplifiable);
+		Ok(self
+			.into_unitless_complex(int)?
+			.prime_factors(int)?
+			.into_iter()
+			.map(|(p, k)| {
+				let p = Self {
+					value: Dist::from(p),
+					unit: Unit::unitless(),
+					exact: true,
+					base,
+					format,
+					simplifiable,
+				};
+				(p, k)
+			})
+			.collect())
+	}
+
+	pub(crate) fn is_prime<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		self.clone().into_unitless_complex(int)?.is_prime(int)
+	}
+
+	pub(crate) fn next_prime<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self {
+			unit: Unit::unitless(),
+			exact: self.exact,
+			base: self.base,
+			format: self.format,
+			simplifiable: self.simplifiable,
+			value: Dist::from(self.into_unitless_complex(int)?.next_prime(int)?),
+		})
+	}
+
+	pub(crate) fn prev_prime<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self {
+			unit: Unit::unitless(),
+			exact: self.exact,
+			base: self.base,
+			format: self.format,
+			simplifiable: self.simplifiable,
+			value: Dist::from(self.into_unitless_complex(int)?.prev_prime(int)?),
+		})
+	}
+
+	pub(crate) fn isqrt<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Ok(Self {
+			unit: Unit::unitless(),
+			exact: self.exact,
+			base: self.base,
+			format: self.format,
+			simplifiable: self.simplifiable,
+			value: Dist::from(self.into_unitless_complex(int)?.isqrt(int)?),
+		})
+	}
+
+	pub(crate) fn pow_mod<I: Interrupt>(
+		self,
+		exponent: Self,
+		modulus: Self,
+		int: &I,
+	) -> FResult<Self> {
+		Ok(Self {
+			unit: Unit::unitless(),
+			exact: self.exact && exponent.exact && modulus.exact,
+			base: self.base,
+			format: self.format,
+			simplifiable: self.simplifiable,
+			value: Dist::from(self.into_unitless_complex(int)?.pow_mod(
+				exponent.into_unitless_complex(int)?,
+				modulus.into_unitless_complex(int)?,
+				int,
+			)?),
+		})
+	}
+
+	pub(crate) fn mod_inverse<I: Interrupt>(self, modulus: Self, int: &I) -> FResult<Self> {
+		Ok(Self {
+			unit: Unit::unitless(),
+			exact: self.exact && modulus.exact,
+			base: self.base,
+			format: self.format,
+			simplifiable: self.simplifiable,
+			value: Dist::from(
+				self.into_unitless_complex(int)?
+					.mod_inverse(modulus.into_unitless_complex(int)?, int)?,
+			),
+		})
+	}
+
+	/// Wraps an integer into the range of a fixed-width integer type,
+	/// e.g. `wrap(256, 8)` is 0 and `wrap_signed(255, 8)` is -1
+	pub(crate) fn wrap_to_width<I: Interrupt>(
+		self,
+		width: Self,
+		signed: bool,
+		int: &I,
+	) -> FResult<Self> {
+		let width = width.try_as_usize(int)? as u64;
+		if width == 0 {
+			return Err(FendError::WidthMustBePositive);
+		}
+		Ok(Self {
+			unit: Unit::unitless(),
+			exact: self.exact,
+			base: self.base,
+			format: self.format,
+			simplifiable: self.simplifiable,
+			value: Dist::from(
+				self.into_unitless_complex(int)?
+					.wrap_to_width(width, signed, int)?,
+			),
+		})
+	}
+
+	/// Flips the lowest `width` bits, e.g. `not(5, 8)` is 250
+	pub(crate) fn bitwise_not<I: Interrupt>(self, width: Self, int: &I) -> FResult<Self> {
+		let width = width.try_as_usize(int)? as u64;
+		Ok(Self {
+			unit: Unit::unitless(),
+			exact: self.exact,
+			base: self.base,
+			format: self.format,
+			simplifiable: self.simplifiable,
+			value: Dist::from(self.into_unitless_complex(int)?.bitwise_not(width, int)?),
+		})
+	}
+
 	pub(crate) fn combination<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
 		Ok(Self {
 			unit: Unit::unitless(),
@@ -350,11 +597,14 @@ impl Value {
 			Bop::Minus => self.sub(rhs, int),
 			Bop::Mul => self.mul(rhs, int),
 			Bop::Div => self.div(rhs, int),
+			Bop::FloorDiv => self.floor_div(rhs, int),
 			Bop::Mod => self.modulo(rhs, int),
 			Bop::Pow => self.pow(rhs, int),
 			Bop::Bitwise(bitwise_bop) => self.bitwise(rhs, bitwise_bop, int),
 			Bop::Combination => self.combination(rhs, int),
 			Bop::Permutation => self.permutation(rhs, int),
+			Bop::Dot | Bop::Cross => Err(FendError::ExpectedAVector("number")),
+			Bop::PlusMinus | Bop::Range => Err(FendError::InvalidOperandsForIntervalArithmetic),
 		}
 	}
 
@@ -391,11 +641,13 @@ impl Value {
 		let new_unit = Unit {
 			components: new_components,
 		};
+		// x^0 is exactly 1, even if x is approximate
+		let zero_exponent = rhs_exact && rhs.is_zero();
 		let value = self.value.one_point()?.pow(rhs, int)?;
 		Ok(Self {
 			value: value.value.into(),
 			unit: new_unit,
-			exact: self.exact && rhs_exact && exact_res && value.exact,
+			exact: zero_exponent || (self.exact && rhs_exact && exact_res && value.exact),
 			base: self.base,
 			format: self.format,
 			simplifiable: self.simplifiable,
@@ -458,6 +710,15 @@ impl Value {
 		}
 	}
 
+	/// Parses a non-negative integer from its digits in the given base
+	pub(crate) fn from_digits<I: Interrupt>(digits: &str, base: Base, int: &I) -> FResult<Self> {
+		let n = BigUint::from_str_radix(digits, base.base_as_u8(), int)?;
+		Ok(Self {
+			value: Dist::from(Real::from(BigRat::from(n))),
+			..Self::zero_with_base(base)
+		})
+	}
+
 	pub(crate) fn is_zero<I: Interrupt>(&self, int: &I) -> FResult<bool> {
 		self.value.equals_int(0, int)
 	}
@@ -499,27 +760,7 @@ impl Value {
 		})
 	}
 
-	fn apply_fn<I: Interrupt>(
-		mut self,
-		f: impl FnOnce(Complex, &I) -> FResult<Complex>,
-		require_unitless: bool,
-		int: &I,
-	) -> FResult<Self> {
-		self = self.remove_unit_scaling(int)?;
-		if require_unitless && !self.is_unitless(int)? {
-			return Err(FendError::ExpectedAUnitlessNumber);
-		}
-		Ok(Self {
-			value: f(self.value.one_point()?, int)?.into(),
-			unit: self.unit,
-			exact: false,
-			base: self.base,
-			format: self.format,
-			simplifiable: self.simplifiable,
-		})
-	}
-
-	pub(crate) fn sample<I: Interrupt>(self, ctx: &crate::Context, int: &I) -> FResult<Self> {
+	pub(crate) fn sample<I: Interrupt>(self, ctx: &mut crate::Context, int: &I) -> FResult<Self> {
 		Ok(Self {
 			value: self.value.sample(ctx, int)?,
 			..self
@@ -674,6 +915,7 @@ impl Value {
 		}
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn tan<I: Interrupt>(
 		self,
 		scope: Option<Arc<Scope>>,
@@ -693,51 +935,62 @@ impl Value {
 	}
 
 	pub(crate) fn asin<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::asin, false, int)
+		self.apply_fn_exact(Complex::asin, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn acos<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::acos, false, int)
+		self.apply_fn_exact(Complex::acos, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn atan<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::atan, false, int)
+		self.apply_fn_exact(Complex::atan, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn sinh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::sinh, false, int)
+		self.apply_fn_exact(Complex::sinh, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn cosh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::cosh, false, int)
+		self.apply_fn_exact(Complex::cosh, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn tanh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::tanh, false, int)
+		self.apply_fn_exact(Complex::tanh, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn asinh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::asinh, false, int)
+		self.apply_fn_exact(Complex::asinh, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn acosh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::acosh, false, int)
+		self.apply_fn_exact(Complex::acosh, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn atanh<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::atanh, false, int)
+		self.apply_fn_exact(Complex::atanh, false, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn ln<I: Interrupt>(self, int: &I) -> FResult<Self> {
 		self.apply_fn_exact(Complex::ln, true, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn log2<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::log2, true, int)
+		self.apply_fn_exact(Complex::log2, true, int)
 	}
 
+	#[cfg(feature = "transcendental")]
 	pub(crate) fn log10<I: Interrupt>(self, int: &I) -> FResult<Self> {
-		self.apply_fn(Complex::log10, true, int)
+		self.apply_fn_exact(Complex::log10, true, int)
 	}
 
 	pub(crate) fn format<I: Interrupt>(
@@ -745,14 +998,22 @@ impl Value {
 		ctx: &crate::Context,
 		int: &I,
 	) -> FResult<FormattedValue> {
+		if ctx.accounting_negatives && self.is_negative_amount(int)? {
+			let mut formatted = (-self.clone()).format(ctx, int)?;
+			formatted.negative_in_parens = true;
+			return Ok(formatted);
+		}
 		let use_parentheses = if self.unit.components.is_empty() {
 			UseParentheses::No
 		} else {
 			UseParentheses::IfComplex
 		};
+		let scientific = self.scientific_notation(ctx, int)?;
+		let value = scientific
+			.as_ref()
+			.map_or(&self.value, |(mantissa, _)| mantissa);
 		let mut formatted_value = String::new();
-		let mut exact = self
-			.value
+		let mut exact = value
 			.format(
 				self.exact,
 				self.format,
@@ -769,16 +1030,99 @@ impl Value {
 			self.base,
 			self.format,
 			true,
+			UnitStyle::from_context(ctx),
 			int,
 		)?;
 		exact = exact && unit_string.exact;
 		Ok(FormattedValue {
 			number: formatted_value,
+			exponent: scientific.map(|(_, exponent)| exponent),
 			exact,
 			unit_str: unit_string.value,
+			exact_form: None,
+			negative_in_parens: false,
+		})
+	}
+
+	/// Whether this is a negative amount of money, e.g. `-$5`
+	/// The sign of this number, ignoring its unit, or `None` if it isn't a
+	/// real number
+	pub(crate) fn real_sign(&self) -> Option<Ordering> {
+		let value = self.value.one_point_ref().ok()?;
+		if !value.imag().is_zero() {
+			return None;
+		}
+		let real = value.real();
+		Some(if real.is_neg() {
+			Ordering::Less
+		} else if real.is_zero() {
+			Ordering::Equal
+		} else {
+			Ordering::Greater
 		})
 	}
 
+	fn is_negative_amount<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		let Ok(value) = self.value.one_point_ref() else {
+			return Ok(false);
+		};
+		if !value.imag().is_zero() || !value.real().is_neg() {
+			return Ok(false);
+		}
+		self.unit.is_currency(int)
+	}
+
+	/// Returns the value formatted exactly, e.g. `1/3` or `0.5π`, or `None`
+	/// if the value is approximate
+	pub(crate) fn exact_form<I: Interrupt>(
+		&self,
+		ctx: &crate::Context,
+		int: &I,
+	) -> FResult<Option<String>> {
+		if !self.exact || self.value.one_point_ref().is_err() {
+			return Ok(None);
+		}
+		let value = Self {
+			format: FormattingStyle::Exact,
+			..self.clone()
+		};
+		let formatted = value.format(ctx, int)?;
+		Ok(formatted.exact.then(|| formatted.to_string()))
+	}
+
+	/// Returns the mantissa and exponent to use if this value should be
+	/// shown in scientific notation
+	fn scientific_notation<I: Interrupt>(
+		&self,
+		ctx: &crate::Context,
+		int: &I,
+	) -> FResult<Option<(Dist, i64)>> {
+		if ctx.scientific_notation == crate::ScientificNotation::default()
+			|| self.base.base_as_u8() != 10
+			|| !matches!(
+				self.format,
+				FormattingStyle::Auto
+					| FormattingStyle::ExactFloat
+					| FormattingStyle::DecimalPlaces(_)
+					| FormattingStyle::SignificantFigures(_)
+			) {
+			return Ok(None);
+		}
+		let Ok(value) = self.value.one_point_ref() else {
+			return Ok(None);
+		};
+		if !value.imag().is_zero() {
+			return Ok(None);
+		}
+		let Some((mantissa, exponent)) = value.real().to_scientific(int)? else {
+			return Ok(None);
+		};
+		if !ctx.scientific_notation.applies_to(exponent) {
+			return Ok(None);
+		}
+		Ok(Some((Dist::from(mantissa), exponent)))
+	}
+
 	pub(crate) fn mul<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
 		let components = [self.unit.components, rhs.unit.components].concat();
 		let value =
@@ -879,7 +1223,7 @@ impl Value {
 					}
 					Err(FendError::Interrupted) => return Err(FendError::Interrupted),
 					Err(_) => (),
-				};
+				}
 			}
 			res_components.push(comp.clone());
 		}
@@ -905,7 +1249,8 @@ impl Value {
 			simplifiable: self.simplifiable,
 		};
 
-		if result.unit.components.len() > 1
+		if ctx.derived_units
+			&& result.unit.components.len() > 1
 			&& !result
 				.unit
 				.components
@@ -920,7 +1265,8 @@ impl Value {
 				.collect::<Result<Vec<String>, _>>()
 			{
 				base_units.sort();
-				if let Some(new_unit) = lookup_default_unit(&base_units.join(" ")) {
+				if let Some(new_unit) = lookup_default_unit(&base_units.join(" "), ctx.unit_system)
+				{
 					let rhs = query_unit_static(new_unit, attrs, ctx, int)?.expect_num()?;
 					return result.convert_to(rhs, int);
 				}
@@ -933,6 +1279,154 @@ impl Value {
 	pub(crate) fn unit_equal_to<I: Interrupt>(&self, rhs: &str, int: &I) -> FResult<bool> {
 		self.unit.equal_to(rhs, int)
 	}
+
+	/// Returns the value as a rational number, or `None` if it is
+	/// approximate, complex, irrational or a probability distribution
+	pub(crate) fn exact_rational<I: Interrupt>(
+		&self,
+		int: &I,
+	) -> FResult<Option<crate::ExactRational>> {
+		if !self.exact {
+			return Ok(None);
+		}
+		let Ok(value) = self.value.one_point_ref() else {
+			return Ok(None);
+		};
+		complex_to_rational(value, int)
+	}
+
+	/// Returns the value converted to base units, along with whether it is
+	/// exact and the base units themselves. Temperatures like `1 °C` are
+	/// converted to kelvin. This returns `None` in the same cases as
+	/// `exact_rational`, except that approximate values are allowed.
+	pub(crate) fn in_base_units<I: Interrupt>(
+		&self,
+		int: &I,
+	) -> FResult<Option<(bool, crate::ExactRational, Vec<crate::UnitComponent>)>> {
+		let Ok(value) = self.value.one_point_ref() else {
+			return Ok(None);
+		};
+		let (_, scale, offset) = self.unit.to_base_units(int)?;
+		let scaled = Exact::new(value.clone(), self.exact)
+			.mul(&scale, int)?
+			.add(offset, int)?;
+		let Some(rational) = complex_to_rational(&scaled.value, int)? else {
+			return Ok(None);
+		};
+		Ok(Some((
+			scaled.exact,
+			rational,
+			self.base_unit_components(int)?,
+		)))
+	}
+
+	/// Whether this value is a temperature like `5 °C` that is measured
+	/// from a point other than absolute zero
+	pub(crate) fn is_absolute_temperature<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		self.unit.is_absolute_temperature(int)
+	}
+
+	/// Returns the units of this value as written, e.g. `km` and `h^-1`
+	pub(crate) fn unit_components<I: Interrupt>(
+		&self,
+		int: &I,
+	) -> FResult<Vec<crate::UnitComponent>> {
+		let mut result = vec![];
+		for component in &self.unit.components {
+			let (prefix, name) = component.unit.prefix_and_name(false);
+			result.push(crate::UnitComponent {
+				name: format!("{prefix}{name}"),
+				exponent: format_unit_exponent(&component.exponent, int)?,
+			});
+		}
+		Ok(result)
+	}
+
+	/// Returns the prefix and singular name of the unit of this value, if it
+	/// consists of a single unit like `km` (but not `km^2` or `km/h`)
+	pub(crate) fn single_unit<I: Interrupt>(&self, int: &I) -> FResult<Option<(&str, &str)>> {
+		let [component] = self.unit.components.as_slice() else {
+			return Ok(None);
+		};
+		if component.exponent.compare(&1.into(), int)? != Some(Ordering::Equal) {
+			return Ok(None);
+		}
+		Ok(Some(component.unit.prefix_and_name(false)))
+	}
+
+	/// Approximates the absolute value of this number, ignoring its unit
+	pub(crate) fn magnitude_as_f64<I: Interrupt>(&self, int: &I) -> FResult<f64> {
+		Ok(self.value.one_point_ref()?.clone().try_as_f64(int)?.abs())
+	}
+
+	/// Returns the singular and plural names of the units of this value,
+	/// together with their exponents
+	pub(crate) fn unit_names<I: Interrupt>(
+		&self,
+		int: &I,
+	) -> FResult<Vec<crate::render::UnitName>> {
+		let mut result = vec![];
+		for component in &self.unit.components {
+			let (prefix, singular) = component.unit.prefix_and_name(false);
+			let (_, plural) = component.unit.prefix_and_name(true);
+			result.push(crate::render::UnitName {
+				singular: format!("{prefix}{singular}"),
+				plural: format!("{prefix}{plural}"),
+				exponent: format_unit_exponent(&component.exponent, int)?,
+			});
+		}
+		Ok(result)
+	}
+
+	/// Returns the base units this value is made up of, sorted by name.
+	/// Base units whose exponents cancel out are not included.
+	pub(crate) fn base_unit_components<I: Interrupt>(
+		&self,
+		int: &I,
+	) -> FResult<Vec<crate::UnitComponent>> {
+		let (hashmap, _scale, _offset) = self.unit.to_base_units(int)?;
+		let mut result = vec![];
+		for (base_unit, exponent) in hashmap {
+			result.push(crate::UnitComponent {
+				name: base_unit.name().to_string(),
+				exponent: format_unit_exponent(&exponent, int)?,
+			});
+		}
+		result.sort_by(|a, b| a.name.cmp(&b.name));
+		Ok(result)
+	}
+}
+
+fn complex_to_rational<I: Interrupt>(
+	value: &Complex,
+	int: &I,
+) -> FResult<Option<crate::ExactRational>> {
+	if !value.imag().is_zero() {
+		return Ok(None);
+	}
+	let Ok(rational) = value.real().expect_rational() else {
+		return Ok(None);
+	};
+	let (negative, numerator, denominator) = rational.to_decimal_parts(int)?;
+	Ok(Some(crate::ExactRational {
+		negative,
+		numerator,
+		denominator,
+	}))
+}
+
+fn format_unit_exponent<I: Interrupt>(exponent: &Complex, int: &I) -> FResult<String> {
+	Ok(exponent
+		.format(
+			true,
+			FormattingStyle::Exact,
+			Base::default(),
+			UseParentheses::No,
+			DigitFormat::default(),
+			int,
+		)?
+		.value
+		.to_string())
 }
 
 impl Neg for Value {
@@ -981,37 +1475,86 @@ impl fmt::Debug for Value {
 pub(crate) struct FormattedValue {
 	exact: bool,
 	number: String,
+	// decimal exponent if the number is shown in scientific notation
+	exponent: Option<i64>,
 	unit_str: String,
+	// exact or symbolic form shown instead of `approx.`, e.g. `1/3`
+	exact_form: Option<String>,
+	// accounting style for negative amounts, e.g. `($5)` instead of `-$5`
+	negative_in_parens: bool,
 }
 
 impl FormattedValue {
+	pub(crate) fn is_exact(&self) -> bool {
+		self.exact
+	}
+
+	/// Returns the formatted number (without units), and the exponent if
+	/// it is shown in scientific notation
+	pub(crate) fn number(&self) -> (Cow<'_, str>, Option<i64>) {
+		let number = if self.negative_in_parens {
+			Cow::Owned(format!("-{}", self.number))
+		} else {
+			Cow::Borrowed(self.number.as_str())
+		};
+		(number, self.exponent)
+	}
+
+	/// Show `exact_form ≈` instead of `approx.` in front of this value
+	pub(crate) fn set_exact_form(&mut self, exact_form: String) {
+		self.exact_form = Some(exact_form);
+	}
+
+	fn number_string(&self) -> String {
+		match self.exponent {
+			Some(exponent) => format!("{}e{exponent}", self.number),
+			None => self.number.clone(),
+		}
+	}
+
 	pub(crate) fn spans(self, spans: &mut Vec<Span>, attrs: Attrs) {
 		if !self.exact && attrs.show_approx && !attrs.plain_number {
 			spans.push(Span {
-				string: "approx. ".to_string(),
+				string: self.exact_form.as_ref().map_or_else(
+					|| "approx. ".to_string(),
+					|exact_form| format!("{exact_form} \u{2248} "),
+				),
 				kind: SpanKind::Ident,
 			});
 		}
+		let number = self.number_string();
+		let mut amount = vec![];
 		if ["$", "\u{a3}", "\u{a5}"].contains(&self.unit_str.as_str()) && !attrs.plain_number {
-			spans.push(Span {
+			amount.push(Span {
 				string: self.unit_str,
 				kind: SpanKind::Ident,
 			});
-			spans.push(Span {
-				string: self.number,
+			amount.push(Span {
+				string: number,
 				kind: SpanKind::Number,
 			});
-			return;
+		} else {
+			amount.push(Span {
+				string: number,
+				kind: SpanKind::Number,
+			});
+			if !attrs.plain_number {
+				amount.push(Span {
+					string: self.unit_str,
+					kind: SpanKind::Ident,
+				});
+			}
 		}
-		spans.push(Span {
-			string: self.number.to_string(),
-			kind: SpanKind::Number,
-		});
-		if !attrs.plain_number {
+		if self.negative_in_parens {
 			spans.push(Span {
-				string: self.unit_str,
-				kind: SpanKind::Ident,
+				string: format!(
+					"({})",
+					amount.into_iter().map(|s| s.string).collect::<String>()
+				),
+				kind: SpanKind::NegativeAmount,
 			});
+		} else {
+			spans.extend(amount);
 		}
 	}
 }
@@ -1019,13 +1562,38 @@ impl FormattedValue {
 impl fmt::Display for FormattedValue {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		if !self.exact {
-			write!(f, "approx. ")?;
+			match &self.exact_form {
+				Some(exact_form) => write!(f, "{exact_form} \u{2248} ")?,
+				None => write!(f, "approx. ")?,
+			}
+		}
+		if self.negative_in_parens {
+			write!(f, "({}{})", self.number_string(), self.unit_str)?;
+		} else {
+			write!(f, "{}{}", self.number_string(), self.unit_str)?;
 		}
-		write!(f, "{}{}", self.number, self.unit_str)?;
 		Ok(())
 	}
 }
 
+/// How units are written, taken from the context
+#[derive(Clone, Copy, Default)]
+struct UnitStyle {
+	exponents: UnitExponentNotation,
+	multiplication: crate::MultiplicationSign,
+	division: crate::DivisionSign,
+}
+
+impl UnitStyle {
+	fn from_context(ctx: &crate::Context) -> Self {
+		Self {
+			exponents: ctx.unit_exponent_notation,
+			multiplication: ctx.multiplication_sign,
+			division: ctx.digit_format.division,
+		}
+	}
+}
+
 // TODO: equality comparisons should not depend on order
 #[derive(Clone)]
 struct Unit {
@@ -1042,6 +1610,30 @@ struct ScaleFactor {
 }
 
 impl Unit {
+	/// Whether this unit is a currency, e.g. `USD` or `$`
+	fn is_currency<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		if self.components.is_empty() {
+			return Ok(false);
+		}
+		let (hashmap, _) = self.to_hashmap_and_scale(int)?;
+		Ok(hashmap.len() == 1
+			&& match hashmap.get(&BaseUnit::new_static("BASE_CURRENCY")) {
+				None => false,
+				Some(exponent) => exponent.compare(&1.into(), int)? == Some(Ordering::Equal),
+			})
+	}
+
+	/// Whether this unit is a temperature scale that doesn't start at absolute
+	/// zero, e.g. `°C` or `°F`. Values in these units can be converted to
+	/// kelvin, but adding them together is meaningless.
+	fn is_absolute_temperature<I: Interrupt>(&self, int: &I) -> FResult<bool> {
+		if self.components.is_empty() {
+			return Ok(false);
+		}
+		let (_, _, offset) = self.to_base_units(int)?;
+		Ok(offset.value.compare(&0.into(), int)? != Some(Ordering::Equal))
+	}
+
 	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
 		self.components.len().serialize(write)?;
 		for c in &self.components {
@@ -1082,6 +1674,14 @@ impl Unit {
 		Ok((hashmap, Exact::new(scale, exact)))
 	}
 
+	/// Like `to_hashmap_and_scale`, but temperature scales like `°C` and `°F`
+	/// are replaced by kelvin, with the offset needed to convert them
+	fn to_base_units<I: Interrupt>(&self, int: &I) -> FResult<HashmapScaleOffset> {
+		let (hashmap, scale) = self.to_hashmap_and_scale(int)?;
+		let (hashmap, adjustment, offset) = Self::reduce_hashmap(hashmap, int)?;
+		Ok((hashmap, scale.mul(&adjustment, int)?, offset))
+	}
+
 	fn reduce_hashmap<I: Interrupt>(
 		hashmap: HashMap<BaseUnit, Complex>,
 		int: &I,
@@ -1152,6 +1752,7 @@ impl Unit {
 			Base::default(),
 			FormattingStyle::Auto,
 			false,
+			UnitStyle::default(),
 			int,
 		)?
 		.value)
@@ -1163,15 +1764,13 @@ impl Unit {
 		into: &Self,
 		int: &I,
 	) -> FResult<ScaleFactor> {
-		let (hash_a, scale_a) = from.to_hashmap_and_scale(int)?;
-		let (hash_b, scale_b) = into.to_hashmap_and_scale(int)?;
-		let (hash_a, adj_a, offset_a) = Self::reduce_hashmap(hash_a, int)?;
-		let (hash_b, adj_b, offset_b) = Self::reduce_hashmap(hash_b, int)?;
+		let (hash_a, scale_a, offset_a) = from.to_base_units(int)?;
+		let (hash_b, scale_b, offset_b) = into.to_base_units(int)?;
 		if compare_hashmaps(&hash_a, &hash_b, int)? {
 			Ok(ScaleFactor {
-				scale_1: scale_a.mul(&adj_a, int)?,
+				scale_1: scale_a,
 				offset: offset_a.add(-offset_b, int)?,
-				scale_2: scale_b.mul(&adj_b, int)?,
+				scale_2: scale_b,
 			})
 		} else {
 			let from_formatted = from
@@ -1181,6 +1780,7 @@ impl Unit {
 					Base::default(),
 					FormattingStyle::Auto,
 					false,
+					UnitStyle::default(),
 					int,
 				)?
 				.value;
@@ -1191,6 +1791,7 @@ impl Unit {
 					Base::default(),
 					FormattingStyle::Auto,
 					false,
+					UnitStyle::default(),
 					int,
 				)?
 				.value;
@@ -1207,6 +1808,7 @@ impl Unit {
 		Self { components: vec![] }
 	}
 
+	#[allow(clippy::too_many_arguments)]
 	fn format<I: Interrupt>(
 		&self,
 		unitless: &str,
@@ -1214,6 +1816,7 @@ impl Unit {
 		base: Base,
 		format: FormattingStyle,
 		consider_printing_space: bool,
+		style: UnitStyle,
 		int: &I,
 	) -> FResult<Exact<String>> {
 		let mut unit_string = String::new();
@@ -1235,8 +1838,17 @@ impl Unit {
 				positive_components.push(unit_exponent);
 			}
 		}
-		let invert_negative_component =
-			!positive_components.is_empty() && negative_components.len() == 1;
+		let invert_negative_components = match style.exponents {
+			UnitExponentNotation::Mixed => {
+				!positive_components.is_empty() && negative_components.len() == 1
+			}
+			UnitExponentNotation::Slash => true,
+			UnitExponentNotation::NegativeExponents | UnitExponentNotation::Superscripts => false,
+		};
+		// e.g. `kg / (m s^2)`
+		let parenthesise_inverted = invert_negative_components && negative_components.len() > 1;
+		let num_components = positive_components.len() + negative_components.len();
+		let superscript = style.exponents == UnitExponentNotation::Superscripts;
 		let mut merged_components = vec![];
 		let pluralised_idx = if positive_components.is_empty() {
 			usize::MAX
@@ -1247,27 +1859,42 @@ impl Unit {
 			merged_components.push((pos_comp, false));
 		}
 		for neg_comp in negative_components {
-			merged_components.push((neg_comp, invert_negative_component));
+			merged_components.push((neg_comp, invert_negative_components));
 		}
 		let last_component_plural = !value_is_one;
+		let mut first_inverted = true;
 		for (i, (unit_exponent, invert)) in merged_components.into_iter().enumerate() {
-			if !first || (consider_printing_space && unit_exponent.unit.print_with_space()) {
+			let print_space =
+				!first || (consider_printing_space && unit_exponent.unit.print_with_space());
+			if invert && first_inverted {
+				if print_space {
+					unit_string.push(' ');
+				}
+				unit_string.push(style.division.symbol());
 				unit_string.push(' ');
-			}
-			first = false;
-			if invert {
-				unit_string.push('/');
+				if parenthesise_inverted {
+					unit_string.push('(');
+				}
+				first_inverted = false;
+			} else if !first {
+				unit_string.push_str(style.multiplication.separator());
+			} else if print_space {
 				unit_string.push(' ');
 			}
+			first = false;
 			let plural = last_component_plural && i == pluralised_idx;
 			let exp_format = if format == FormattingStyle::Auto {
 				FormattingStyle::Exact
 			} else {
 				format
 			};
-			let formatted_exp = unit_exponent.format(base, exp_format, plural, invert, int)?;
+			let formatted_exp =
+				unit_exponent.format(base, exp_format, plural, invert, superscript, int)?;
 			unit_string.push_str(formatted_exp.value.to_string().as_str());
 			exact = exact && formatted_exp.exact;
+			if parenthesise_inverted && i == num_components - 1 {
+				unit_string.push(')');
+			}
 		}
 		Ok(Exact::new(unit_string, true))
 	}
@@ -3,7 +3,7 @@ use std::{collections::HashMap, fmt, io};
 
 use crate::interrupt::test_int;
 use crate::num::complex::{self, Complex, UseParentheses};
-use crate::num::{Base, Exact, FormattingStyle};
+use crate::num::{Base, DigitFormat, Exact, FormattingStyle};
 use crate::result::FResult;
 use crate::Interrupt;
 
@@ -86,6 +86,7 @@ impl UnitExponent {
 		format: FormattingStyle,
 		plural: bool,
 		invert_exp: bool,
+		superscript: bool,
 		int: &I,
 	) -> FResult<Exact<FormattedExponent<'_>>> {
 		let (prefix, name) = self.unit.prefix_and_name(plural);
@@ -97,8 +98,14 @@ impl UnitExponent {
 		let (exact, exponent) = if exp.compare(&1.into(), int)? == Some(Ordering::Equal) {
 			(true, None)
 		} else {
-			let formatted =
-				exp.format(true, format, base, UseParentheses::IfComplexOrFraction, int)?;
+			let formatted = exp.format(
+				true,
+				format,
+				base,
+				UseParentheses::IfComplexOrFraction,
+				DigitFormat::default(),
+				int,
+			)?;
 			(formatted.exact, Some(formatted.value))
 		};
 		Ok(Exact::new(
@@ -106,6 +113,7 @@ impl UnitExponent {
 				prefix,
 				name,
 				number: exponent,
+				superscript,
 			},
 			exact,
 		))
@@ -127,13 +135,46 @@ pub(crate) struct FormattedExponent<'a> {
 	prefix: &'a str,
 	name: &'a str,
 	number: Option<complex::Formatted>,
+	superscript: bool,
 }
 
-impl<'a> fmt::Display for FormattedExponent<'a> {
+fn to_superscript(ch: char) -> Option<char> {
+	Some(match ch {
+		'0' => '⁰',
+		'1' => '¹',
+		'2' => '²',
+		'3' => '³',
+		'4' => '⁴',
+		'5' => '⁵',
+		'6' => '⁶',
+		'7' => '⁷',
+		'8' => '⁸',
+		'9' => '⁹',
+		'-' => '⁻',
+		_ => return None,
+	})
+}
+
+impl fmt::Display for FormattedExponent<'_> {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(f, "{}{}", self.prefix, self.name)?;
 		if let Some(number) = &self.number {
-			write!(f, "^{number}")?;
+			let number = number.to_string();
+			// fractional exponents can't be written as superscripts
+			let superscript = self
+				.superscript
+				.then(|| {
+					number
+						.chars()
+						.map(to_superscript)
+						.collect::<Option<String>>()
+				})
+				.flatten();
+			if let Some(superscript) = superscript {
+				write!(f, "{superscript}")?;
+			} else {
+				write!(f, "^{number}")?;
+			}
 		}
 		Ok(())
 	}
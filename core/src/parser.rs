@@ -1,6 +1,8 @@
 use crate::ast::{Bop, Expr};
+use crate::ident::Ident;
 use crate::lexer::{Symbol, Token};
 use crate::value::Value;
+use crate::ImplicitMultiplication;
 use std::fmt;
 
 #[derive(Debug)]
@@ -18,6 +20,8 @@ pub(crate) enum ParseError {
 	ExpectedIdentifierInAssignment,
 	ExpectedDotInLambda,
 	InvalidMixedFraction,
+	ExpectedTwoArguments(Symbol),
+	InvalidFormulaSyntax,
 }
 
 impl fmt::Display for ParseError {
@@ -44,6 +48,11 @@ impl fmt::Display for ParseError {
 				write!(f, "missing '.' in lambda (expected e.g. \\x.x)")
 			}
 			Self::InvalidMixedFraction => write!(f, "invalid mixed fraction"),
+			Self::ExpectedTwoArguments(s) => write!(f, "expected two arguments for '{s}'"),
+			Self::InvalidFormulaSyntax => write!(
+				f,
+				"expected e.g. `solve ohms_law for R given V = 12 V, I = 0.5 A`"
+			),
 		}
 	}
 }
@@ -119,6 +128,47 @@ fn parse_parens(input: &[Token]) -> ParseResult<'_> {
 	Ok((Expr::Parens(Box::new(inner)), input))
 }
 
+// e.g. `[1, 2, 3]`
+fn parse_vector(input: &[Token]) -> ParseResult<'_> {
+	let ((), mut input) = parse_fixed_symbol(input, Symbol::OpenBracket)?;
+	let mut components = vec![];
+	loop {
+		let (component, remaining) = parse_function(input)?;
+		components.push(component);
+		if let Ok(((), remaining)) = parse_fixed_symbol(remaining, Symbol::Comma) {
+			input = remaining;
+		} else {
+			let ((), remaining) = parse_fixed_symbol(remaining, Symbol::CloseBracket)?;
+			return Ok((Expr::Vector(components), remaining));
+		}
+	}
+}
+
+// e.g. `(10, 0.5, 7)` in `binompmf(10, 0.5, 7)`
+fn parse_arguments(input: &[Token]) -> ParseResult<'_, Vec<Expr>> {
+	let ((), mut input) = parse_fixed_symbol(input, Symbol::OpenParens)?;
+	let mut args = vec![];
+	loop {
+		let (mut arg, mut remaining) = parse_function(input)?;
+		// an equation to be solved, e.g. in `solve(2x + 3 = 11, x)`
+		if let Ok(((), rhs_input)) = parse_fixed_symbol(remaining, Symbol::Equals) {
+			let (rhs, rhs_remaining) = parse_function(rhs_input)?;
+			arg = Expr::Equation(Box::new(arg), Box::new(rhs));
+			remaining = rhs_remaining;
+		}
+		args.push(arg);
+		if let Ok(((), remaining)) = parse_fixed_symbol(remaining, Symbol::Comma) {
+			input = remaining;
+		} else if args.len() > 1 {
+			let ((), remaining) = parse_fixed_symbol(remaining, Symbol::CloseParens)?;
+			return Ok((args, remaining));
+		} else {
+			// a single argument is handled by `parse_parens`
+			return Err(ParseError::FoundInvalidTokenWhileExpecting(Symbol::Comma));
+		}
+	}
+}
+
 fn parse_backslash_lambda(input: &[Token]) -> ParseResult<'_> {
 	let ((), input) = parse_fixed_symbol(input, Symbol::Backslash)?;
 	let (Expr::Ident(ident), input) = parse_ident(input)? else {
@@ -138,9 +188,25 @@ fn parse_parens_or_literal(input: &[Token]) -> ParseResult<'_> {
 		Token::Ident(_) => parse_ident(input),
 		Token::StringLiteral(s) => Ok((Expr::Literal(Value::String(s)), remaining)),
 		Token::Symbol(Symbol::OpenParens) => parse_parens(input),
+		Token::Symbol(Symbol::OpenBracket) => parse_vector(input),
 		Token::Symbol(Symbol::Backslash) => parse_backslash_lambda(input),
+		// e.g. `nCr(5, 2)`, which is the same as `5 nCr 2`
+		Token::Symbol(symbol @ (Symbol::Combination | Symbol::Permutation)) => {
+			let (args, remaining) = parse_arguments(remaining)?;
+			let [n, k] = <[Expr; 2]>::try_from(args)
+				.map_err(|_| ParseError::ExpectedTwoArguments(symbol))?;
+			let bop = if symbol == Symbol::Combination {
+				Bop::Combination
+			} else {
+				Bop::Permutation
+			};
+			Ok((Expr::Bop(bop, Box::new(n), Box::new(k)), remaining))
+		}
 		Token::Symbol(s) => Err(ParseError::UnexpectedSymbol(s)),
 		Token::Date(d) => Ok((Expr::Literal(Value::Date(d)), remaining)),
+		Token::TimeOfDay(t) => Ok((Expr::Literal(Value::TimeOfDay(t)), remaining)),
+		Token::TimeZone(z) => Ok((Expr::Literal(Value::TimeZone(z)), remaining)),
+		Token::ZonedDateTime(t) => Ok((Expr::Literal(Value::ZonedDateTime(t)), remaining)),
 	}
 }
 
@@ -169,6 +235,12 @@ fn parse_power(input: &[Token], allow_unary: bool) -> ParseResult<'_> {
 			let (result, remaining) = parse_power(remaining, true)?;
 			return Ok((Expr::UnaryDiv(Box::new(result)), remaining));
 		}
+		// `//` is lexed as floor division, but e.g. `//x` means `/(/x)`
+		if let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::FloorDiv) {
+			let (result, remaining) = parse_power(remaining, true)?;
+			let result = Expr::UnaryDiv(Box::new(Expr::UnaryDiv(Box::new(result))));
+			return Ok((result, remaining));
+		}
 	}
 	let (mut result, mut input) = parse_factorial(input)?;
 	if let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::Pow) {
@@ -180,6 +252,22 @@ fn parse_power(input: &[Token], allow_unary: bool) -> ParseResult<'_> {
 }
 
 fn parse_apply_cont<'a>(input: &'a [Token], lhs: &Expr) -> ParseResult<'a> {
+	// `f(a, b)` is equivalent to `f a b`
+	if let (false, Ok((args, remaining))) = (
+		matches!(lhs, Expr::Literal(Value::Num(_))),
+		parse_arguments(input),
+	) {
+		// bitwise not takes its arguments together, because `not x` on its
+		// own is boolean negation
+		if matches!(lhs, Expr::Ident(ident) if ident.as_str() == "not") && args.len() == 2 {
+			let result = Expr::Apply(Box::new(lhs.clone()), Box::new(Expr::Vector(args)));
+			return Ok((result, remaining));
+		}
+		let result = args.into_iter().fold(lhs.clone(), |f, arg| {
+			Expr::Apply(Box::new(f), Box::new(Expr::Parens(Box::new(arg))))
+		});
+		return Ok((result, remaining));
+	}
 	let (rhs, input) = parse_power(input, false)?;
 	Ok((
 		match (lhs, &rhs) {
@@ -200,7 +288,7 @@ fn parse_apply_cont<'a>(input: &'a [Token], lhs: &Expr) -> ParseResult<'a> {
 				}
 				Expr::Apply(Box::new(lhs.clone()), Box::new(rhs))
 			}
-			// support e.g. '$5', '£3' or '¥10'
+			// support e.g. '$5', '£3', '¥10' or '€2'
 			(Expr::Ident(i), Expr::Literal(Value::Num(_))) if i.is_prefix_unit() => {
 				Expr::Apply(Box::new(lhs.clone()), Box::new(rhs))
 			}
@@ -278,6 +366,12 @@ fn parse_division_cont(input: &[Token]) -> ParseResult<'_> {
 	Ok((b, input))
 }
 
+fn parse_floor_division_cont(input: &[Token]) -> ParseResult<'_> {
+	let ((), input) = parse_fixed_symbol(input, Symbol::FloorDiv)?;
+	let (b, input) = parse_power(input, true)?;
+	Ok((b, input))
+}
+
 fn parse_modulo_cont(input: &[Token]) -> ParseResult<'_> {
 	let ((), input) = parse_fixed_symbol(input, Symbol::Mod)?;
 	let (b, input) = parse_power(input, true)?;
@@ -305,6 +399,17 @@ fn parse_modulo2_cont(input: &[Token]) -> ParseResult<'_> {
 	Ok((b, input))
 }
 
+fn parse_vector_product_cont(input: &[Token]) -> ParseResult<'_, (Bop, Expr)> {
+	let (token, input) = parse_token(input)?;
+	let bop = match token {
+		Token::Symbol(Symbol::DotProduct) => Bop::Dot,
+		Token::Symbol(Symbol::CrossProduct) => Bop::Cross,
+		_ => return Err(ParseError::UnexpectedInput),
+	};
+	let (b, input) = parse_power(input, true)?;
+	Ok(((bop, b), input))
+}
+
 fn parse_multiplicative(input: &[Token]) -> ParseResult<'_> {
 	let (mut res, mut input) = parse_power(input, true)?;
 	loop {
@@ -314,12 +419,18 @@ fn parse_multiplicative(input: &[Token]) -> ParseResult<'_> {
 		} else if let Ok((term, remaining)) = parse_division_cont(input) {
 			res = Expr::Bop(Bop::Div, Box::new(res.clone()), Box::new(term));
 			input = remaining;
+		} else if let Ok((term, remaining)) = parse_floor_division_cont(input) {
+			res = Expr::Bop(Bop::FloorDiv, Box::new(res.clone()), Box::new(term));
+			input = remaining;
 		} else if let Ok((term, remaining)) = parse_modulo_cont(input) {
 			res = Expr::Bop(Bop::Mod, Box::new(res.clone()), Box::new(term));
 			input = remaining;
 		} else if let Ok((term, remaining)) = parse_modulo2_cont(input) {
 			res = Expr::Bop(Bop::Mod, Box::new(res.clone()), Box::new(term));
 			input = remaining;
+		} else if let Ok(((bop, term), remaining)) = parse_vector_product_cont(input) {
+			res = Expr::Bop(bop, Box::new(res.clone()), Box::new(term));
+			input = remaining;
 		} else if let Ok((new_res, remaining)) = parse_mixed_fraction(input, &res) {
 			res = new_res;
 			input = remaining;
@@ -346,7 +457,7 @@ fn parse_implicit_addition(input: &[Token]) -> ParseResult<'_> {
 				Expr::Bop(Bop::ImplicitPlus, Box::new(res), Box::new(rhs)),
 				remaining,
 			));
-		};
+		}
 	}
 	Ok((res, input))
 }
@@ -369,6 +480,13 @@ fn parse_to_cont(input: &[Token]) -> ParseResult<'_> {
 	Ok((b, input))
 }
 
+// `5 ± 0.1` or `10..12`
+fn parse_interval_cont(input: &[Token], symbol: Symbol) -> ParseResult<'_> {
+	let ((), input) = parse_fixed_symbol(input, symbol)?;
+	let (b, input) = parse_implicit_addition(input)?;
+	Ok((b, input))
+}
+
 fn parse_additive(input: &[Token]) -> ParseResult<'_> {
 	let (mut res, mut input) = parse_implicit_addition(input)?;
 	loop {
@@ -381,6 +499,12 @@ fn parse_additive(input: &[Token]) -> ParseResult<'_> {
 		} else if let Ok((term, remaining)) = parse_to_cont(input) {
 			res = Expr::As(Box::new(res), Box::new(term));
 			input = remaining;
+		} else if let Ok((term, remaining)) = parse_interval_cont(input, Symbol::PlusMinus) {
+			res = Expr::Bop(Bop::PlusMinus, Box::new(res), Box::new(term));
+			input = remaining;
+		} else if let Ok((term, remaining)) = parse_interval_cont(input, Symbol::Range) {
+			res = Expr::Bop(Bop::Range, Box::new(res), Box::new(term));
+			input = remaining;
 		} else {
 			break;
 		}
@@ -477,6 +601,13 @@ fn parse_permutation(input: &[Token]) -> ParseResult<'_> {
 }
 
 fn parse_function(input: &[Token]) -> ParseResult<'_> {
+	if let [Token::Ident(keyword), remaining @ ..] = input {
+		if keyword.as_str() == "solve" {
+			if let Some(for_idx) = find_keyword(remaining, "for") {
+				return parse_solve_formula(remaining, for_idx);
+			}
+		}
+	}
 	let (lhs, input) = parse_permutation(input)?;
 	if let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::Fn) {
 		if let Expr::Ident(s) = lhs {
@@ -502,12 +633,36 @@ fn parse_equality(input: &[Token]) -> ParseResult<'_> {
 			Expr::Equality(false, Box::new(lhs), Box::new(rhs)),
 			remaining,
 		))
+	} else if let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::ApproxEquals) {
+		let (rhs, remaining) = parse_function(remaining)?;
+		Ok((
+			Expr::ApproxEquality(Box::new(lhs), Box::new(rhs)),
+			remaining,
+		))
 	} else {
 		Ok((lhs, input))
 	}
 }
 
 fn parse_assignment(input: &[Token]) -> ParseResult<'_> {
+	if let [Token::Ident(keyword), Token::Ident(name), Token::Symbol(Symbol::Equals), remaining @ ..] =
+		input
+	{
+		if keyword.as_str() == "unit" {
+			let (definition, remaining) = parse_assignment(remaining)?;
+			return Ok((
+				Expr::DefineUnit(name.clone(), Box::new(definition)),
+				remaining,
+			));
+		}
+	}
+	if let [Token::Ident(keyword), Token::Ident(name), Token::Symbol(Symbol::Fn), remaining @ ..] =
+		input
+	{
+		if keyword.as_str() == "formula" {
+			return parse_formula_definition(name, remaining);
+		}
+	}
 	let (lhs, input) = parse_equality(input)?;
 	if let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::Equals) {
 		if let Expr::Ident(s) = lhs {
@@ -519,6 +674,108 @@ fn parse_assignment(input: &[Token]) -> ParseResult<'_> {
 	Ok((lhs, input))
 }
 
+/// Returns the index of the identifier `keyword` in the current statement,
+/// ignoring any inside parentheses or brackets
+fn find_keyword(input: &[Token], keyword: &str) -> Option<usize> {
+	let mut depth = 0_usize;
+	for (i, token) in input.iter().enumerate() {
+		match token {
+			Token::Symbol(Symbol::OpenParens | Symbol::OpenBracket) => depth += 1,
+			Token::Symbol(Symbol::CloseParens | Symbol::CloseBracket) => {
+				// the end of the parentheses that the statement is in
+				depth = depth.checked_sub(1)?;
+			}
+			Token::Symbol(Symbol::Semicolon) if depth == 0 => return None,
+			Token::Ident(ident) if depth == 0 && ident.as_str() == keyword => return Some(i),
+			_ => (),
+		}
+	}
+	None
+}
+
+/// Parses an equation like `V = I R` in a formula
+fn parse_equation(input: &[Token]) -> ParseResult<'_> {
+	let (lhs, input) = parse_function(input)?;
+	let ((), input) =
+		parse_fixed_symbol(input, Symbol::Equals).map_err(|_| ParseError::InvalidFormulaSyntax)?;
+	let (rhs, input) = parse_function(input)?;
+	Ok((Expr::Equation(Box::new(lhs), Box::new(rhs)), input))
+}
+
+/// Parses an equation that makes up all of `input`, e.g. the part before
+/// `for` in `solve y = 2x for x`
+fn parse_whole_equation(input: &[Token]) -> Result<Expr, ParseError> {
+	let (equation, remaining) = parse_equation(input)?;
+	if !remaining.is_empty() {
+		return Err(ParseError::InvalidFormulaSyntax);
+	}
+	Ok(equation)
+}
+
+/// Parses a list like `V = 12 V, I = 0.5 A` after `given`, or
+/// `V in volts, I in amperes` after `where`
+fn parse_formula_list(
+	mut input: &[Token],
+	separator: Symbol,
+) -> ParseResult<'_, Vec<(Ident, Expr)>> {
+	let mut list = vec![];
+	loop {
+		let (Token::Ident(name), remaining) = parse_token(input)? else {
+			return Err(ParseError::InvalidFormulaSyntax);
+		};
+		let ((), remaining) = parse_fixed_symbol(remaining, separator)
+			.map_err(|_| ParseError::InvalidFormulaSyntax)?;
+		let (value, remaining) = parse_function(remaining)?;
+		list.push((name, value));
+		match parse_fixed_symbol(remaining, Symbol::Comma) {
+			Ok(((), remaining)) => input = remaining,
+			Err(_) => return Ok((list, remaining)),
+		}
+	}
+}
+
+/// Parses e.g. `area: A = w h where A in m^2, w in m, h in m` after the
+/// `formula` keyword
+fn parse_formula_definition<'a>(name: &Ident, input: &'a [Token]) -> ParseResult<'a> {
+	let (equation, units, remaining) = if let Some(idx) = find_keyword(input, "where") {
+		let equation = parse_whole_equation(&input[..idx])?;
+		let (units, remaining) = parse_formula_list(&input[idx + 1..], Symbol::UnitConversion)?;
+		(equation, units, remaining)
+	} else {
+		let (equation, remaining) = parse_equation(input)?;
+		(equation, vec![], remaining)
+	};
+	Ok((
+		Expr::DefineFormula(name.clone(), Box::new(equation), units),
+		remaining,
+	))
+}
+
+/// Parses e.g. `ohms_law for R given V = 12 V, I = 0.5 A` or
+/// `y = 2x + 3 for x given y = 11` after the `solve` keyword
+fn parse_solve_formula(input: &[Token], for_idx: usize) -> ParseResult<'_> {
+	let formula = match &input[..for_idx] {
+		[Token::Ident(name)] => Expr::Ident(name.clone()),
+		equation => parse_whole_equation(equation)?,
+	};
+	let (Token::Ident(var), remaining) = parse_token(&input[for_idx + 1..])? else {
+		return Err(ParseError::InvalidFormulaSyntax);
+	};
+	let (givens, remaining) = match remaining {
+		[Token::Ident(given), remaining @ ..] if given.as_str() == "given" => {
+			parse_formula_list(remaining, Symbol::Equals)?
+		}
+		_ => (vec![], remaining),
+	};
+	if givens.iter().any(|(name, _)| *name == var) {
+		return Err(ParseError::InvalidFormulaSyntax);
+	}
+	Ok((
+		Expr::SolveFormula(Box::new(formula), var, givens),
+		remaining,
+	))
+}
+
 fn parse_statements(mut input: &[Token]) -> ParseResult<'_> {
 	while let Ok(((), remaining)) = parse_fixed_symbol(input, Symbol::Semicolon) {
 		input = remaining;
@@ -543,10 +800,78 @@ pub(crate) fn parse_expression(input: &[Token]) -> ParseResult<'_> {
 	parse_statements(input)
 }
 
-pub(crate) fn parse_tokens(input: &[Token]) -> Result<Expr, ParseError> {
-	let (res, remaining) = parse_expression(input)?;
-	if !remaining.is_empty() {
-		return Err(ParseError::UnexpectedInput);
+/// Applies `apply` (i.e. implicit multiplication) to `lhs` and `rhs`, moving
+/// it into the divisor if `lhs` is a division
+fn into_divisor(apply: fn(Box<Expr>, Box<Expr>) -> Expr, lhs: Expr, rhs: Box<Expr>) -> Expr {
+	match lhs {
+		Expr::Bop(bop @ (Bop::Div | Bop::FloorDiv), a, b) => {
+			Expr::Bop(bop, a, Box::new(into_divisor(apply, *b, rhs)))
+		}
+		lhs => apply(Box::new(lhs), rhs),
+	}
+}
+
+/// Moves implicit multiplication that follows a division into the divisor,
+/// e.g. `(1/2)x` becomes `1/(2x)`
+fn group_implicit_multiplication(expr: Expr) -> Expr {
+	let group = |expr: Box<Expr>| Box::new(group_implicit_multiplication(*expr));
+	let group_list = |list: Vec<(Ident, Expr)>| {
+		list.into_iter()
+			.map(|(name, expr)| (name, group_implicit_multiplication(expr)))
+			.collect()
+	};
+	match expr {
+		Expr::Literal(_) | Expr::Ident(_) => expr,
+		Expr::Parens(x) => Expr::Parens(group(x)),
+		Expr::UnaryMinus(x) => Expr::UnaryMinus(group(x)),
+		Expr::UnaryPlus(x) => Expr::UnaryPlus(group(x)),
+		Expr::UnaryDiv(x) => Expr::UnaryDiv(group(x)),
+		Expr::Factorial(x) => Expr::Factorial(group(x)),
+		Expr::Bop(bop, a, b) => Expr::Bop(bop, group(a), group(b)),
+		Expr::Apply(a, b) => into_divisor(Expr::Apply, group_implicit_multiplication(*a), group(b)),
+		Expr::ApplyFunctionCall(a, b) => into_divisor(
+			Expr::ApplyFunctionCall,
+			group_implicit_multiplication(*a),
+			group(b),
+		),
+		Expr::ApplyMul(a, b) => {
+			into_divisor(Expr::ApplyMul, group_implicit_multiplication(*a), group(b))
+		}
+		Expr::As(a, b) => Expr::As(group(a), group(b)),
+		Expr::Fn(ident, x) => Expr::Fn(ident, group(x)),
+		Expr::Of(ident, x) => Expr::Of(ident, group(x)),
+		Expr::Assign(ident, x) => Expr::Assign(ident, group(x)),
+		Expr::DefineUnit(ident, x) => Expr::DefineUnit(ident, group(x)),
+		Expr::Equality(is_equals, a, b) => Expr::Equality(is_equals, group(a), group(b)),
+		Expr::ApproxEquality(a, b) => Expr::ApproxEquality(group(a), group(b)),
+		Expr::Equation(a, b) => Expr::Equation(group(a), group(b)),
+		Expr::DefineFormula(name, equation, units) => {
+			Expr::DefineFormula(name, group(equation), group_list(units))
+		}
+		Expr::SolveFormula(formula, var, givens) => {
+			Expr::SolveFormula(group(formula), var, group_list(givens))
+		}
+		Expr::Statements(a, b) => Expr::Statements(group(a), group(b)),
+		Expr::Vector(v) => Expr::Vector(v.into_iter().map(group_implicit_multiplication).collect()),
 	}
-	Ok(res)
+}
+
+/// Parses the given tokens. If there is unexpected input after the end of the
+/// expression, the index of the first unexpected token is returned together
+/// with the error.
+pub(crate) fn parse_tokens(
+	input: &[Token],
+	implicit_multiplication: ImplicitMultiplication,
+) -> Result<Expr, (ParseError, Option<usize>)> {
+	let (res, remaining) = parse_expression(input).map_err(|e| (e, None))?;
+	if !remaining.is_empty() {
+		return Err((
+			ParseError::UnexpectedInput,
+			Some(input.len() - remaining.len()),
+		));
+	}
+	Ok(match implicit_multiplication {
+		ImplicitMultiplication::LeftToRight => res,
+		ImplicitMultiplication::BeforeDivision => group_implicit_multiplication(res),
+	})
 }
@@ -0,0 +1,441 @@
+use crate::error::{FendError, Interrupt};
+use crate::interrupt::test_int;
+use crate::num::{out_of_range, Number, Range, RangeBound};
+use crate::result::FResult;
+use crate::value::built_in_function::BuiltInFunction;
+use crate::value::Value;
+use std::cmp;
+use std::f64::consts::{FRAC_2_SQRT_PI, PI, SQRT_2};
+
+// Only the binomial distribution can be calculated exactly. The other
+// distributions need functions like `erf` that fend can only approximate, so
+// they are evaluated using floats.
+
+fn check_probability<I: Interrupt>(p: Number, open: bool, int: &I) -> FResult<Number> {
+	let f = p.clone().try_as_f64(int)?;
+	let valid = if open {
+		f > 0.0 && f < 1.0
+	} else {
+		(0.0..=1.0).contains(&f)
+	};
+	if valid {
+		Ok(p)
+	} else if open {
+		Err(out_of_range(f, Range::open(0, 1)))
+	} else {
+		Err(out_of_range(
+			f,
+			Range {
+				start: RangeBound::Closed(0),
+				end: RangeBound::Closed(1),
+			},
+		))
+	}
+}
+
+fn check_positive<I: Interrupt>(n: Number, allow_zero: bool, int: &I) -> FResult<f64> {
+	let f = n.try_as_f64(int)?;
+	if f > 0.0 || (allow_zero && f == 0.0) {
+		Ok(f)
+	} else {
+		Err(out_of_range(
+			f,
+			Range {
+				start: if allow_zero {
+					RangeBound::Closed(0)
+				} else {
+					RangeBound::Open(0)
+				},
+				end: RangeBound::None,
+			},
+		))
+	}
+}
+
+/// Checks that e.g. the number of events is a non-negative integer
+fn expect_count<I: Interrupt>(n: Number, int: &I) -> FResult<f64> {
+	n.clone().try_as_usize(int)?;
+	n.try_as_f64(int)
+}
+
+/// The complementary error function `1 - erf(x)`
+fn erfc(x: f64) -> f64 {
+	if x < 0.0 {
+		return 2.0 - erfc(-x);
+	}
+	if x < 3.0 {
+		// erf(x) = 2/sqrt(pi) * e^(-x^2) * sum(2^n x^(2n+1) / (1 * 3 * ... * (2n+1))),
+		// which only has positive terms
+		let mut term = x;
+		let mut sum = x;
+		let mut n = 0.0;
+		while term > sum * f64::EPSILON {
+			n += 1.0;
+			term *= 2.0 * x * x / (2.0 * n + 1.0);
+			sum += term;
+		}
+		return 1.0 - FRAC_2_SQRT_PI * (-x * x).exp() * sum;
+	}
+	// continued fraction: x + (1/2) / (x + 1 / (x + (3/2) / (x + ...)))
+	let mut fraction = x;
+	for n in (1..=100).rev() {
+		fraction = x + f64::from(n) / 2.0 / fraction;
+	}
+	FRAC_2_SQRT_PI / 2.0 * (-x * x).exp() / fraction
+}
+
+/// The natural logarithm of the gamma function, using the Lanczos
+/// approximation
+fn ln_gamma(x: f64) -> f64 {
+	const COEFFICIENTS: [f64; 9] = [
+		0.999_999_999_999_809_9,
+		676.520_368_121_885_1,
+		-1_259.139_216_722_402_8,
+		771.323_428_777_653_1,
+		-176.615_029_162_140_6,
+		12.507_343_278_686_905,
+		-0.138_571_095_265_720_12,
+		9.984_369_578_019_572e-6,
+		1.505_632_735_149_311_6e-7,
+	];
+	if x < 0.5 {
+		// reflection formula
+		return (PI / (PI * x).sin().abs()).ln() - ln_gamma(1.0 - x);
+	}
+	let x = x - 1.0;
+	let mut sum = COEFFICIENTS[0];
+	for (c, i) in COEFFICIENTS[1..].iter().zip(1..) {
+		sum += c / (x + f64::from(i));
+	}
+	let t = x + 7.5;
+	0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+fn gamma_f64(x: f64) -> f64 {
+	if x < 0.5 {
+		// reflection formula
+		return PI / ((PI * x).sin() * gamma_f64(1.0 - x));
+	}
+	ln_gamma(x).exp()
+}
+
+/// The gamma function, which extends the factorial to non-integers:
+/// `gamma(n) = (n - 1)!`. Results for positive integers are exact.
+pub(crate) fn gamma<I: Interrupt>(x: Number, int: &I) -> FResult<Number> {
+	match x.clone().try_as_usize(int) {
+		Ok(0) => return Err(FendError::GammaOfNonPositiveInteger),
+		Ok(_) => return x.sub(1.into(), int)?.factorial(int),
+		Err(FendError::Interrupted) => return Err(FendError::Interrupted),
+		Err(_) => (),
+	}
+	let f = x.try_as_f64(int)?;
+	if f < 0.0 && f.fract() == 0.0 {
+		return Err(FendError::GammaOfNonPositiveInteger);
+	}
+	let result = gamma_f64(f);
+	if !result.is_finite() {
+		return Err(FendError::ValueTooLarge);
+	}
+	Number::from_f64(result, int)
+}
+
+/// Evaluates a continued fraction `1 / (1 + a1 / (1 + a2 / ...))` using
+/// Lentz's method, given a function that returns `a_n`
+fn continued_fraction<I: Interrupt>(a: impl Fn(f64) -> f64, int: &I) -> FResult<f64> {
+	const TINY: f64 = 1e-300;
+	let mut c = 1.0;
+	let mut d = 0.0;
+	let mut result = 1.0;
+	for n in 1..1000 {
+		test_int(int)?;
+		let a_n = a(f64::from(n));
+		d = 1.0 + a_n * d;
+		if d.abs() < TINY {
+			d = TINY;
+		}
+		c = 1.0 + a_n / c;
+		if c.abs() < TINY {
+			c = TINY;
+		}
+		d = 1.0 / d;
+		let delta = c * d;
+		result *= delta;
+		if (delta - 1.0).abs() < f64::EPSILON {
+			break;
+		}
+	}
+	Ok(1.0 / result)
+}
+
+/// The regularized incomplete beta function `I_x(a, b)`
+fn incomplete_beta<I: Interrupt>(a: f64, b: f64, x: f64, int: &I) -> FResult<f64> {
+	if x <= 0.0 {
+		return Ok(0.0);
+	}
+	if x >= 1.0 {
+		return Ok(1.0);
+	}
+	// the continued fraction converges quickly for x < (a + 1) / (a + b + 2),
+	// otherwise use I_x(a, b) = 1 - I_(1-x)(b, a)
+	if x > (a + 1.0) / (a + b + 2.0) {
+		return Ok(1.0 - incomplete_beta(b, a, 1.0 - x, int)?);
+	}
+	let front =
+		(ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp() / a;
+	let fraction = continued_fraction(
+		|n| {
+			let m = (n / 2.0).floor();
+			if n % 2.0 == 0.0 {
+				m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m))
+			} else {
+				-(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0))
+			}
+		},
+		int,
+	)?;
+	Ok(front * fraction)
+}
+
+/// The regularized upper incomplete gamma function `Q(a, x)`
+fn incomplete_gamma_upper<I: Interrupt>(a: f64, x: f64, int: &I) -> FResult<f64> {
+	if x <= 0.0 {
+		return Ok(1.0);
+	}
+	let front = (a * x.ln() - x - ln_gamma(a)).exp();
+	if x < a + 1.0 {
+		// series for the lower incomplete gamma function
+		let mut term = 1.0 / a;
+		let mut sum = term;
+		let mut n = a;
+		while term > sum * f64::EPSILON {
+			test_int(int)?;
+			n += 1.0;
+			term *= x / n;
+			sum += term;
+		}
+		return Ok(1.0 - front * sum);
+	}
+	// Q(a, x) = e^-x x^a / Γ(a) / (x + 1 - a - 1(1 - a) / (x + 3 - a - ...))
+	let b = |n: f64| x + 2.0 * n + 1.0 - a;
+	let fraction = continued_fraction(|n| -n * (n - a) / (b(n - 1.0) * b(n)), int)?;
+	Ok(front * fraction / b(0.0))
+}
+
+fn student_t_cdf<I: Interrupt>(t: f64, nu: f64, int: &I) -> FResult<f64> {
+	let tail = incomplete_beta(nu / 2.0, 0.5, nu / (nu + t * t), int)? / 2.0;
+	Ok(if t >= 0.0 { 1.0 - tail } else { tail })
+}
+
+fn normal_cdf(x: f64) -> f64 {
+	erfc(-x / SQRT_2) / 2.0
+}
+
+fn normal_pdf(x: f64) -> f64 {
+	(-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+/// The probability density of the standard normal distribution
+fn normpdf<I: Interrupt>(x: Number, int: &I) -> FResult<Number> {
+	Number::from_f64(normal_pdf(x.try_as_f64(int)?), int)
+}
+
+/// The cumulative distribution function of the standard normal distribution
+fn normcdf<I: Interrupt>(x: Number, int: &I) -> FResult<Number> {
+	Number::from_f64(normal_cdf(x.try_as_f64(int)?), int)
+}
+
+/// The quantile function of the standard normal distribution, i.e. the
+/// inverse of `normcdf`
+fn invnorm<I: Interrupt>(p: Number, int: &I) -> FResult<Number> {
+	// based on https://web.archive.org/web/20151030215612/http://home.online.no/~pjacklam/notes/invnorm/
+	const A: [f64; 6] = [
+		-3.969_683_028_665_376e1,
+		2.209_460_984_245_205e2,
+		-2.759_285_104_469_687e2,
+		1.383_577_518_672_69e2,
+		-3.066_479_806_614_716e1,
+		2.506_628_277_459_239,
+	];
+	const B: [f64; 5] = [
+		-5.447_609_879_822_406e1,
+		1.615_858_368_580_409e2,
+		-1.556_989_798_598_866e2,
+		6.680_131_188_771_972e1,
+		-1.328_068_155_288_572e1,
+	];
+	const C: [f64; 6] = [
+		-7.784_894_002_430_293e-3,
+		-3.223_964_580_411_365e-1,
+		-2.400_758_277_161_838,
+		-2.549_732_539_343_734,
+		4.374_664_141_464_968,
+		2.938_163_982_698_783,
+	];
+	const D: [f64; 4] = [
+		7.784_695_709_041_462e-3,
+		3.224_671_290_700_398e-1,
+		2.445_134_137_142_996,
+		3.754_408_661_907_416,
+	];
+	let polynomial =
+		|coefficients: &[f64], x: f64| coefficients.iter().fold(0.0, |acc, c| acc * x + c);
+	let tail = |q: f64| {
+		let q = (-2.0 * q.ln()).sqrt();
+		polynomial(&C, q) / (polynomial(&D, q) * q + 1.0)
+	};
+	let p = check_probability(p, true, int)?.try_as_f64(int)?;
+	let mut x = if p < 0.024_25 {
+		tail(p)
+	} else if p > 1.0 - 0.024_25 {
+		-tail(1.0 - p)
+	} else {
+		let q = p - 0.5;
+		let r = q * q;
+		polynomial(&A, r) * q / (polynomial(&B, r) * r + 1.0)
+	};
+	// refine the result using Halley's method
+	for _ in 0..2 {
+		let error = normal_cdf(x) - p;
+		let u = error / normal_pdf(x);
+		x -= u / (1.0 + x * u / 2.0);
+	}
+	Number::from_f64(x, int)
+}
+
+fn binomial_probability<I: Interrupt>(n: usize, p: &Number, k: usize, int: &I) -> FResult<Number> {
+	if k > n {
+		return Ok(0.into());
+	}
+	let q = Number::from(1).sub(p.clone(), int)?;
+	Number::from(n as u64)
+		.combination(Number::from(k as u64), int)?
+		.mul(p.clone().pow(Number::from(k as u64), int)?, int)?
+		.mul(q.pow(Number::from((n - k) as u64), int)?, int)
+}
+
+/// The probability of exactly `k` successes in `n` trials with probability
+/// `p`, calculated exactly
+fn binompmf<I: Interrupt>(n: Number, p: Number, k: Number, int: &I) -> FResult<Number> {
+	let p = check_probability(p, false, int)?;
+	binomial_probability(n.try_as_usize(int)?, &p, k.try_as_usize(int)?, int)
+}
+
+/// The probability of at most `k` successes in `n` trials with probability
+/// `p`, calculated exactly
+fn binomcdf<I: Interrupt>(n: Number, p: Number, k: Number, int: &I) -> FResult<Number> {
+	let p = check_probability(p, false, int)?;
+	let n = n.try_as_usize(int)?;
+	let k = cmp::min(k.try_as_usize(int)?, n);
+	let mut result = Number::from(0);
+	for i in 0..=k {
+		test_int(int)?;
+		result = result.add(binomial_probability(n, &p, i, int)?, int)?;
+	}
+	Ok(result)
+}
+
+/// The probability of exactly `k` events when `lambda` are expected
+fn poissonpmf<I: Interrupt>(lambda: Number, k: Number, int: &I) -> FResult<Number> {
+	let lambda = check_positive(lambda, true, int)?;
+	let k = expect_count(k, int)?;
+	if lambda == 0.0 {
+		return Ok(Number::from(u64::from(k == 0.0)));
+	}
+	Number::from_f64((k * lambda.ln() - lambda - ln_gamma(k + 1.0)).exp(), int)
+}
+
+/// The probability of at most `k` events when `lambda` are expected
+fn poissoncdf<I: Interrupt>(lambda: Number, k: Number, int: &I) -> FResult<Number> {
+	let lambda = check_positive(lambda, true, int)?;
+	let k = expect_count(k, int)?;
+	Number::from_f64(incomplete_gamma_upper(k + 1.0, lambda, int)?, int)
+}
+
+/// The probability density of Student's t-distribution with `nu` degrees of
+/// freedom
+fn tpdf<I: Interrupt>(t: Number, nu: Number, int: &I) -> FResult<Number> {
+	let t = t.try_as_f64(int)?;
+	let nu = check_positive(nu, false, int)?;
+	let half_nu = nu / 2.0;
+	let ln_density =
+		ln_gamma(half_nu + 0.5) - ln_gamma(half_nu) - (half_nu + 0.5) * (t * t / nu).ln_1p();
+	Number::from_f64(ln_density.exp() / (nu * PI).sqrt(), int)
+}
+
+/// The cumulative distribution function of Student's t-distribution with
+/// `nu` degrees of freedom
+fn tcdf<I: Interrupt>(t: Number, nu: Number, int: &I) -> FResult<Number> {
+	let t = t.try_as_f64(int)?;
+	let nu = check_positive(nu, false, int)?;
+	Number::from_f64(student_t_cdf(t, nu, int)?, int)
+}
+
+/// The quantile function of Student's t-distribution, i.e. the inverse of
+/// `tcdf`
+fn invt<I: Interrupt>(p: Number, nu: Number, int: &I) -> FResult<Number> {
+	let p = check_probability(p, true, int)?.try_as_f64(int)?;
+	let nu = check_positive(nu, false, int)?;
+	// find an interval containing the result, then use bisection
+	let (mut low, mut high) = (-1.0, 1.0);
+	while student_t_cdf(low, nu, int)? > p {
+		low *= 2.0;
+	}
+	while student_t_cdf(high, nu, int)? < p {
+		high *= 2.0;
+	}
+	loop {
+		test_int(int)?;
+		let mid = f64::midpoint(low, high);
+		if mid <= low || mid >= high {
+			return Number::from_f64(mid, int);
+		}
+		if student_t_cdf(mid, nu, int)? < p {
+			low = mid;
+		} else {
+			high = mid;
+		}
+	}
+}
+
+/// Evaluates a probability distribution function. Functions with several
+/// parameters receive them as a vector.
+pub(crate) fn evaluate<I: Interrupt>(
+	func: BuiltInFunction,
+	arg: Value,
+	int: &I,
+) -> FResult<Number> {
+	match func {
+		BuiltInFunction::NormPdf => normpdf(arg.expect_num()?, int),
+		BuiltInFunction::NormCdf => normcdf(arg.expect_num()?, int),
+		BuiltInFunction::InvNorm => invnorm(arg.expect_num()?, int),
+		BuiltInFunction::BinomPmf => {
+			let [n, p, k] = arg.expect_vector()?.into_array()?;
+			binompmf(n, p, k, int)
+		}
+		BuiltInFunction::BinomCdf => {
+			let [n, p, k] = arg.expect_vector()?.into_array()?;
+			binomcdf(n, p, k, int)
+		}
+		BuiltInFunction::PoissonPmf => {
+			let [lambda, k] = arg.expect_vector()?.into_array()?;
+			poissonpmf(lambda, k, int)
+		}
+		BuiltInFunction::PoissonCdf => {
+			let [lambda, k] = arg.expect_vector()?.into_array()?;
+			poissoncdf(lambda, k, int)
+		}
+		BuiltInFunction::TPdf => {
+			let [t, nu] = arg.expect_vector()?.into_array()?;
+			tpdf(t, nu, int)
+		}
+		BuiltInFunction::TCdf => {
+			let [t, nu] = arg.expect_vector()?.into_array()?;
+			tcdf(t, nu, int)
+		}
+		BuiltInFunction::InvT => {
+			let [p, nu] = arg.expect_vector()?.into_array()?;
+			invt(p, nu, int)
+		}
+		_ => Err(FendError::ExpectedAVector(arg.type_name())),
+	}
+}
@@ -0,0 +1,281 @@
+use std::fmt::Write;
+
+use crate::error::Interrupt;
+use crate::eval::Attrs;
+use crate::result::FResult;
+use crate::value::Value;
+
+/// A unit as shown in a result, e.g. `km` with exponent `2`
+pub(crate) struct UnitName {
+	pub(crate) singular: String,
+	pub(crate) plural: String,
+	pub(crate) exponent: String,
+}
+
+/// The result rendered in every format other than plain text
+#[derive(PartialEq, Eq, Debug, Default)]
+pub(crate) struct Renderings {
+	pub(crate) latex: String,
+	pub(crate) json: String,
+	pub(crate) spoken: String,
+}
+
+/// A number split into the parts that are rendered differently in
+/// each format
+struct NumberParts {
+	approx: bool,
+	number: String,
+	exponent: Option<i64>,
+	units: Vec<UnitName>,
+}
+
+pub(crate) fn render<I: Interrupt>(
+	value: &Value,
+	plain: &str,
+	attrs: Attrs,
+	ctx: &mut crate::Context,
+	int: &I,
+) -> FResult<Renderings> {
+	let number = match value {
+		Value::Num(n) => {
+			let n = n.clone().simplify(attrs, ctx, int)?;
+			let formatted = n.format(ctx, int)?;
+			let (number, exponent) = formatted.number();
+			// probability distributions are shown as multi-line graphs
+			if number.contains('\n') {
+				None
+			} else {
+				Some(NumberParts {
+					approx: !formatted.is_exact() && attrs.show_approx,
+					number: number.to_string(),
+					exponent,
+					units: n.unit_names(int)?,
+				})
+			}
+		}
+		_ => None,
+	};
+	Ok(Renderings {
+		latex: number
+			.as_ref()
+			.map_or_else(|| latex_text(plain), latex_number),
+		json: json(value.type_name(), plain, number.as_ref()),
+		spoken: number
+			.as_ref()
+			.map_or_else(|| plain.to_string(), spoken_number),
+	})
+}
+
+fn latex_escape(s: &str) -> String {
+	let mut result = String::new();
+	for ch in s.chars() {
+		match ch {
+			'\\' => result.push_str("\\textbackslash{}"),
+			'%' | '$' | '#' | '_' | '&' | '{' | '}' => {
+				result.push('\\');
+				result.push(ch);
+			}
+			'^' => result.push_str("\\^{}"),
+			'~' => result.push_str("\\~{}"),
+			_ => result.push(ch),
+		}
+	}
+	result
+}
+
+fn latex_text(plain: &str) -> String {
+	format!("\\text{{{}}}", latex_escape(plain))
+}
+
+// converts overlined recurring digits back to parentheses, e.g. `0.1(6)`
+fn recurring_in_parens(number: &str) -> String {
+	let Some(start) = number.find('\u{305}') else {
+		return number.to_string();
+	};
+	// the overline follows the first recurring digit
+	let start = number[..start]
+		.char_indices()
+		.next_back()
+		.map_or(0, |(idx, _)| idx);
+	let recurring: String = number[start..]
+		.chars()
+		.filter(|&ch| ch != '\u{305}')
+		.collect();
+	format!("{}({recurring})", &number[..start])
+}
+
+// e.g. `1 2/3` becomes `1\frac{2}{3}` and `0.(3)` becomes `0.\overline{3}`
+fn latex_mantissa(number: &str) -> String {
+	let number = recurring_in_parens(number).replace('\u{3c0}', "\\pi ");
+	// leave complex numbers as they are
+	if number.contains(" + ") || number.contains(" - ") {
+		return number;
+	}
+	if let Some((whole, den)) = number.rsplit_once(['/', '\u{2215}']) {
+		let (whole, num) = whole.rsplit_once(' ').unwrap_or(("", whole));
+		let (sign, num) = num.strip_prefix('-').map_or(("", num), |n| ("-", n));
+		return format!("{whole}{sign}\\frac{{{}}}{{{}}}", num.trim(), den.trim());
+	}
+	if let Some((non_recurring, recurring)) = number.split_once('(') {
+		let recurring = recurring.trim_end_matches(')');
+		return format!("{non_recurring}\\overline{{{recurring}}}");
+	}
+	number
+}
+
+fn latex_unit(unit: &UnitName, exponent: &str) -> String {
+	let mut result = format!("\\mathrm{{{}}}", latex_escape(&unit.singular));
+	if exponent != "1" {
+		write!(result, "^{{{exponent}}}").unwrap();
+	}
+	result
+}
+
+fn latex_number(parts: &NumberParts) -> String {
+	let mut result = String::new();
+	if parts.approx {
+		result.push_str("\\approx ");
+	}
+	result.push_str(&latex_mantissa(&parts.number));
+	if let Some(exponent) = parts.exponent {
+		write!(result, " \\times 10^{{{exponent}}}").unwrap();
+	}
+	let mut numerator = vec![];
+	let mut denominator = vec![];
+	for unit in &parts.units {
+		if let Some(exponent) = unit.exponent.strip_prefix('-') {
+			denominator.push(latex_unit(unit, exponent));
+		} else {
+			numerator.push(latex_unit(unit, &unit.exponent));
+		}
+	}
+	if denominator.is_empty() {
+		if !numerator.is_empty() {
+			write!(result, "\\,{}", numerator.join("\\,")).unwrap();
+		}
+	} else {
+		let numerator = if numerator.is_empty() {
+			"1".to_string()
+		} else {
+			numerator.join("\\,")
+		};
+		write!(
+			result,
+			"\\,\\frac{{{numerator}}}{{{}}}",
+			denominator.join("\\,")
+		)
+		.unwrap();
+	}
+	result
+}
+
+fn json(type_name: &str, plain: &str, number: Option<&NumberParts>) -> String {
+	let mut result = String::from("{\"type\":\"");
+	crate::json::escape_string(type_name, &mut result);
+	result.push_str("\",\"plain\":\"");
+	crate::json::escape_string(plain, &mut result);
+	result.push('"');
+	if let Some(number) = number {
+		write!(result, ",\"approx\":{},\"number\":\"", number.approx).unwrap();
+		crate::json::escape_string(&number.number, &mut result);
+		result.push('"');
+		if let Some(exponent) = number.exponent {
+			write!(result, ",\"exponent\":{exponent}").unwrap();
+		}
+		result.push_str(",\"units\":[");
+		for (i, unit) in number.units.iter().enumerate() {
+			if i > 0 {
+				result.push(',');
+			}
+			result.push_str("{\"name\":\"");
+			crate::json::escape_string(&unit.singular, &mut result);
+			result.push_str("\",\"exponent\":\"");
+			crate::json::escape_string(&unit.exponent, &mut result);
+			result.push_str("\"}");
+		}
+		result.push(']');
+	}
+	result.push('}');
+	result
+}
+
+/// Adds the conversion steps to a JSON rendering as a `steps` array
+pub(crate) fn add_json_steps(json: &mut String, steps: &[String]) {
+	if steps.is_empty() || !json.ends_with('}') {
+		return;
+	}
+	json.pop();
+	json.push_str(",\"steps\":[");
+	for (i, step) in steps.iter().enumerate() {
+		if i > 0 {
+			json.push(',');
+		}
+		json.push('"');
+		crate::json::escape_string(step, json);
+		json.push('"');
+	}
+	json.push_str("]}");
+}
+
+fn spoken_exponent(exponent: &str) -> String {
+	match exponent {
+		"1" => String::new(),
+		"2" => " squared".to_string(),
+		"3" => " cubed".to_string(),
+		_ => format!(" to the power of {exponent}"),
+	}
+}
+
+fn spoken_mantissa(number: &str) -> String {
+	let mut number = recurring_in_parens(number).replace('\u{3c0}', " pi");
+	if let Some(positive) = number.strip_prefix('-') {
+		number = format!("minus {positive}");
+	}
+	if let Some((non_recurring, recurring)) = number.split_once('(') {
+		number = format!(
+			"{non_recurring}{} recurring",
+			recurring.trim_end_matches(')')
+		);
+	}
+	number.replace(['/', '\u{2215}'], " over ")
+}
+
+fn spoken_number(parts: &NumberParts) -> String {
+	let mut result = String::new();
+	if parts.approx {
+		result.push_str("approximately ");
+	}
+	result.push_str(&spoken_mantissa(&parts.number));
+	if let Some(exponent) = parts.exponent {
+		write!(result, " times 10 to the power of {exponent}").unwrap();
+	}
+	// like in plain results, only the last unit with a positive exponent
+	// is pluralised
+	let last_positive = parts
+		.units
+		.iter()
+		.rposition(|unit| !unit.exponent.starts_with('-'));
+	let plural = parts.number != "1" || parts.exponent.is_some();
+	for (i, unit) in parts.units.iter().enumerate() {
+		if !unit.exponent.starts_with('-') {
+			let name = if plural && Some(i) == last_positive {
+				&unit.plural
+			} else {
+				&unit.singular
+			};
+			write!(result, " {name}{}", spoken_exponent(&unit.exponent)).unwrap();
+		}
+	}
+	for unit in &parts.units {
+		if let Some(exponent) = unit.exponent.strip_prefix('-') {
+			write!(
+				result,
+				" per {}{}",
+				unit.singular,
+				spoken_exponent(exponent)
+			)
+			.unwrap();
+		}
+	}
+	result
+}
@@ -16,7 +16,7 @@ impl ScopeValue {
 	pub(crate) fn compare<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
 		let Self::LazyVariable(a1, a2) = self;
 		let Self::LazyVariable(b1, b2) = other;
-		Ok(a1.compare(b1, int)? && compare_option_arc_scope(a2, b2, int)?)
+		Ok(a1.compare(b1, int)? && compare_option_arc_scope(a2.as_ref(), b2.as_ref(), int)?)
 	}
 
 	fn eval<I: Interrupt>(
@@ -64,12 +64,12 @@ impl ScopeValue {
 pub(crate) struct Scope {
 	ident: Ident,
 	value: ScopeValue,
-	inner: Option<Arc<Scope>>,
+	inner: Option<Arc<Self>>,
 }
 
 pub(crate) fn compare_option_arc_scope<I: Interrupt>(
-	a: &Option<Arc<Scope>>,
-	b: &Option<Arc<Scope>>,
+	a: Option<&Arc<Scope>>,
+	b: Option<&Arc<Scope>>,
 	int: &I,
 ) -> FResult<bool> {
 	Ok(match (a, b) {
@@ -83,7 +83,7 @@ impl Scope {
 	pub(crate) fn compare<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
 		Ok(self.ident == other.ident
 			&& self.value.compare(&other.value, int)?
-			&& compare_option_arc_scope(&self.inner, &other.inner, int)?)
+			&& compare_option_arc_scope(self.inner.as_ref(), other.inner.as_ref(), int)?)
 	}
 
 	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
@@ -130,6 +130,17 @@ impl Scope {
 		Self::with_scope_value(name, ScopeValue::LazyVariable(expr, scope), inner)
 	}
 
+	/// Returns the unevaluated expression that `ident` is bound to, together
+	/// with the scope it needs to be evaluated in
+	pub(crate) fn get_expr(&self, ident: &Ident) -> Option<(&Expr, Option<&Arc<Self>>)> {
+		if self.ident.as_str() == ident.as_str() {
+			let ScopeValue::LazyVariable(expr, scope) = &self.value;
+			Some((expr, scope.as_ref()))
+		} else {
+			self.inner.as_ref()?.get_expr(ident)
+		}
+	}
+
 	pub(crate) fn get<I: Interrupt>(
 		&self,
 		ident: &Ident,
@@ -34,7 +34,7 @@ macro_rules! impl_serde {
 	};
 }
 
-impl_serde!(u8 i32 u64);
+impl_serde!(u8 i32 u32 u64);
 
 impl Serialize for usize {
 	fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
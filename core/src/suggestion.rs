@@ -0,0 +1,127 @@
+use crate::completion::FUNCTIONS;
+
+/// Returns the number of insertions, deletions, substitutions and swaps of
+/// adjacent characters needed to turn `a` into `b`, ignoring case
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+	let a: Vec<char> = a.chars().flat_map(char::to_lowercase).collect();
+	let b: Vec<char> = b.chars().flat_map(char::to_lowercase).collect();
+	// rows for the previous two characters of `a`
+	let mut before_prev: Vec<usize> = vec![0; b.len() + 1];
+	let mut prev: Vec<usize> = (0..=b.len()).collect();
+	for i in 1..=a.len() {
+		let mut current = vec![i; b.len() + 1];
+		for j in 1..=b.len() {
+			let cost = usize::from(a[i - 1] != b[j - 1]);
+			current[j] = (prev[j] + 1)
+				.min(current[j - 1] + 1)
+				.min(prev[j - 1] + cost);
+			if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+				current[j] = current[j].min(before_prev[j - 2] + 1);
+			}
+		}
+		before_prev = prev;
+		prev = current;
+	}
+	prev[b.len()]
+}
+
+/// The largest edit distance that is still considered a typo. Names with
+/// fewer than four characters are too likely to be close to something
+/// unrelated, so they never get a suggestion.
+fn max_typo_distance(ident: &str) -> usize {
+	(ident.chars().count() / 4).min(3)
+}
+
+/// Keeps track of the closest name seen so far
+pub(crate) struct Suggestion {
+	ident: String,
+	max_distance: usize,
+	best: Option<(usize, String)>,
+}
+
+impl Suggestion {
+	pub(crate) fn new(ident: &str) -> Self {
+		Self {
+			ident: ident.to_string(),
+			max_distance: max_typo_distance(ident),
+			best: None,
+		}
+	}
+
+	pub(crate) fn ident(&self) -> &str {
+		&self.ident
+	}
+
+	fn update(&mut self, distance: usize, name: impl FnOnce() -> String) {
+		if distance == 0 || distance > self.max_distance {
+			return;
+		}
+		if self.best.as_ref().is_none_or(|(best, _)| distance < *best) {
+			self.best = Some((distance, name()));
+		}
+	}
+
+	pub(crate) fn consider(&mut self, name: &str) {
+		if name.is_empty() {
+			return;
+		}
+		let distance = edit_distance(&self.ident, name);
+		self.update(distance, || name.to_string());
+	}
+
+	/// Considers `prefix` followed by `name`, where `remaining` is the part
+	/// of the identifier after `prefix`, e.g. `kilo` and `meters` for
+	/// `kilometrs`
+	pub(crate) fn consider_prefixed(&mut self, remaining: &str, prefix: &str, name: &str) {
+		if name.is_empty() {
+			return;
+		}
+		let distance = edit_distance(remaining, name);
+		self.update(distance, || format!("{prefix}{name}"));
+	}
+
+	fn into_name(self) -> Option<String> {
+		self.best.map(|(_, name)| name)
+	}
+}
+
+/// Returns a known variable, function or unit whose name is similar to
+/// `ident`, e.g. `kilometers` for `kilometrs`. Keywords like `to` are not
+/// suggested, since they can't be used in place of an identifier.
+pub(crate) fn suggest_identifier(ident: &str, context: &crate::Context) -> Option<String> {
+	let mut suggestion = Suggestion::new(ident);
+	if suggestion.max_distance == 0 {
+		return None;
+	}
+	let mut variables: Vec<&String> = context.variables.keys().collect();
+	variables.sort();
+	for name in variables {
+		if name != "_" {
+			suggestion.consider(name);
+		}
+	}
+	for name in FUNCTIONS {
+		suggestion.consider(name);
+	}
+	for (singular, plural, _) in &context.custom_units {
+		suggestion.consider(singular);
+		suggestion.consider(plural);
+	}
+	crate::units::suggest_unit(&mut suggestion);
+	suggestion.into_name()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::edit_distance;
+
+	#[test]
+	fn test_edit_distance() {
+		assert_eq!(edit_distance("meters", "meters"), 0);
+		assert_eq!(edit_distance("metrs", "meters"), 1);
+		assert_eq!(edit_distance("Meters", "meters"), 0);
+		assert_eq!(edit_distance("mteers", "meters"), 1);
+		assert_eq!(edit_distance("", "abc"), 3);
+		assert_eq!(edit_distance("kitten", "sitting"), 3);
+	}
+}
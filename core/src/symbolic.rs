@@ -0,0 +1,895 @@
+use crate::ast::{Bop, Expr};
+use crate::error::{FendError, Interrupt};
+use crate::interrupt::test_int;
+use crate::num::Number;
+use crate::result::FResult;
+use crate::scope::Scope;
+use crate::serialize::{Deserialize, Serialize};
+use crate::value::built_in_function::BuiltInFunction;
+use crate::value::Value;
+use crate::{Attrs, Span, SpanKind};
+use std::sync::Arc;
+use std::{cmp, io, mem};
+
+/// Powers of a sum like `(x + 1)^n` are only expanded up to this exponent
+const MAX_EXPANDED_EXPONENT: usize = 64;
+
+/// A polynomial-like expression in unbound variables, e.g. `2x^2 + 3x y + 1`.
+/// Like terms are always combined, and terms with a coefficient of zero are
+/// removed.
+#[derive(Clone, Debug)]
+pub(crate) struct Expression {
+	terms: Vec<Term>,
+}
+
+/// A coefficient multiplied by powers of atoms, e.g. `3x y^2`. Factors are
+/// sorted and never have an exponent of zero.
+#[derive(Clone, Debug)]
+struct Term {
+	coefficient: Number,
+	factors: Vec<(Atom, Number)>,
+}
+
+/// Something that can't be simplified any further, which terms are made of
+#[derive(Clone, Debug)]
+enum Atom {
+	/// An unbound variable like `x`
+	Variable(String),
+	/// The square root of a square-free integer, e.g. `sqrt(2)`, which keeps
+	/// roots like `1 + sqrt(2)` exact. Its exponent is always less than 2.
+	Sqrt(u64),
+	/// An elementary function applied to an expression, e.g. `sin(2x)`. The
+	/// terms of the argument are sorted, so that equal arguments compare
+	/// equal.
+	Function(BuiltInFunction, Box<Expression>),
+}
+
+impl Atom {
+	/// Atoms are sorted by kind, then by name, radicand or function and
+	/// argument
+	fn sort_key(&self) -> (u8, &str, u64, Vec<u8>) {
+		match self {
+			Self::Variable(name) => (0, name, 0, vec![]),
+			Self::Sqrt(radicand) => (1, "", *radicand, vec![]),
+			Self::Function(function, argument) => {
+				let mut bytes = vec![];
+				// serializing into memory can't fail
+				mem::drop(argument.serialize(&mut bytes));
+				(2, function.as_str(), 0, bytes)
+			}
+		}
+	}
+
+	fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
+		match self {
+			Self::Variable(name) => {
+				0u8.serialize(write)?;
+				name.as_str().serialize(write)?;
+			}
+			Self::Sqrt(radicand) => {
+				1u8.serialize(write)?;
+				radicand.serialize(write)?;
+			}
+			Self::Function(function, argument) => {
+				2u8.serialize(write)?;
+				function.serialize(write)?;
+				argument.serialize(write)?;
+			}
+		}
+		Ok(())
+	}
+
+	fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		Ok(match u8::deserialize(read)? {
+			0 => Self::Variable(String::deserialize(read)?),
+			1 => Self::Sqrt(u64::deserialize(read)?),
+			2 => Self::Function(
+				BuiltInFunction::deserialize(read)?,
+				Box::new(Expression::deserialize(read)?),
+			),
+			_ => return Err(FendError::DeserializationError),
+		})
+	}
+
+	/// Returns the derivative of `f(u)` with respect to `u`, e.g. `cos(u)`
+	/// for `sin(u)`
+	fn outer_derivative<I: Interrupt>(
+		function: BuiltInFunction,
+		argument: &Expression,
+		int: &I,
+	) -> FResult<Expression> {
+		let apply =
+			|function| Expression::atom(Self::Function(function, Box::new(argument.clone())));
+		let reciprocal = || Expression::constant(Number::from(1)).div(argument.clone(), int);
+		Ok(match function {
+			BuiltInFunction::Sin => apply(BuiltInFunction::Cos),
+			BuiltInFunction::Cos => apply(BuiltInFunction::Sin).neg(),
+			BuiltInFunction::Tan => apply(BuiltInFunction::Cos).pow(&-Number::from(2), int)?,
+			BuiltInFunction::Sinh => apply(BuiltInFunction::Cosh),
+			BuiltInFunction::Cosh => apply(BuiltInFunction::Sinh),
+			BuiltInFunction::Tanh => apply(BuiltInFunction::Cosh).pow(&-Number::from(2), int)?,
+			BuiltInFunction::Exp => apply(BuiltInFunction::Exp),
+			BuiltInFunction::Ln => reciprocal()?,
+			_ => return Err(FendError::UnsupportedSymbolicFunction(function.as_str())),
+		})
+	}
+
+	/// Returns the derivative with respect to `var`, or `None` if this atom
+	/// doesn't depend on `var`
+	fn derivative<I: Interrupt>(&self, var: &str, int: &I) -> FResult<Option<Expression>> {
+		Ok(match self {
+			Self::Variable(name) if name == var => Some(Expression::constant(Number::from(1))),
+			Self::Variable(_) | Self::Sqrt(_) => None,
+			// chain rule: `f(u)' = f'(u) u'`
+			Self::Function(function, argument) => {
+				let inner = argument.derivative(var, int)?;
+				if inner.terms.is_empty() {
+					None
+				} else {
+					Some(Self::outer_derivative(*function, argument, int)?.mul(&inner, int)?)
+				}
+			}
+		})
+	}
+
+	fn format<I: Interrupt>(
+		&self,
+		spans: &mut Vec<Span>,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<()> {
+		match self {
+			Self::Variable(name) => spans.push(Span {
+				string: name.clone(),
+				kind: SpanKind::Ident,
+			}),
+			Self::Sqrt(radicand) => {
+				spans.push(Span {
+					string: "sqrt".to_string(),
+					kind: SpanKind::BuiltInFunction,
+				});
+				spans.push(Span {
+					string: format!("({radicand})"),
+					kind: SpanKind::Number,
+				});
+			}
+			Self::Function(function, argument) => {
+				spans.push(Span {
+					string: function.as_str().to_string(),
+					kind: SpanKind::BuiltInFunction,
+				});
+				spans.push(Span::from_string("(".to_string()));
+				argument.format(spans, attrs, ctx, int)?;
+				spans.push(Span::from_string(")".to_string()));
+			}
+		}
+		Ok(())
+	}
+}
+
+impl PartialEq for Atom {
+	fn eq(&self, other: &Self) -> bool {
+		self.sort_key() == other.sort_key()
+	}
+}
+
+impl Eq for Atom {}
+
+impl PartialOrd for Atom {
+	fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Atom {
+	fn cmp(&self, other: &Self) -> cmp::Ordering {
+		self.sort_key().cmp(&other.sort_key())
+	}
+}
+
+/// Whether an identifier that is not otherwise defined should be treated as
+/// a variable, e.g. `x` or `y`. Longer names are still reported as unknown
+/// identifiers, since they are much more likely to be typos.
+pub(crate) fn is_variable_name(ident: &str, context: &crate::Context) -> bool {
+	if !context.symbolic_variables {
+		return false;
+	}
+	let mut chars = ident.chars();
+	chars.next().is_some_and(|ch| ch.is_ascii_lowercase()) && chars.next().is_none()
+}
+
+/// Calls `f` with unbound variables kept symbolic, e.g. for the argument
+/// of `expand`
+pub(crate) fn with_variables<T>(
+	context: &mut crate::Context,
+	f: impl FnOnce(&mut crate::Context) -> FResult<T>,
+) -> FResult<T> {
+	let enabled = mem::replace(&mut context.symbolic_variables, true);
+	let result = f(context);
+	context.symbolic_variables = enabled;
+	result
+}
+
+pub(crate) fn variable(name: &str) -> Value {
+	Value::Symbolic(Expression {
+		terms: vec![Term {
+			coefficient: Number::from(1),
+			factors: vec![(Atom::Variable(name.to_string()), Number::from(1))],
+		}],
+	})
+}
+
+/// Splits `n` into `s^2 r`, where `r` is square-free, returning `(s, r)`.
+/// Returns `None` if `n` has a large prime factor that would take too long
+/// to find.
+fn square_free_part<I: Interrupt>(mut n: u64, int: &I) -> FResult<Option<(u64, u64)>> {
+	const MAX_DIVISOR: u64 = 1 << 20;
+	let (mut square, mut radicand) = (1, 1);
+	let mut divisor = 2;
+	while divisor * divisor <= n {
+		test_int(int)?;
+		if divisor > MAX_DIVISOR {
+			return Ok(None);
+		}
+		while n.is_multiple_of(divisor * divisor) {
+			n /= divisor * divisor;
+			square *= divisor;
+		}
+		if n.is_multiple_of(divisor) {
+			n /= divisor;
+			radicand *= divisor;
+		}
+		divisor += 1;
+	}
+	Ok(Some((square, radicand * n)))
+}
+
+/// Returns the exact square root of a non-negative unitless rational number,
+/// e.g. `3/2` for `9/4` and `2 sqrt(3)` for `12`, or `None` if it can't be
+/// represented exactly
+pub(crate) fn exact_sqrt<I: Interrupt>(n: &Number, int: &I) -> FResult<Option<Value>> {
+	if !n.is_unitless(int)? {
+		return Ok(None);
+	}
+	let Some(rational) = n.exact_rational(int)? else {
+		return Ok(None);
+	};
+	if rational.is_negative() {
+		return Ok(None);
+	}
+	// `sqrt(a/b)` is `sqrt(a b)/b`
+	let (Ok(numerator), Ok(denominator)) = (
+		rational.numerator().parse::<u64>(),
+		rational.denominator().parse::<u64>(),
+	) else {
+		return Ok(None);
+	};
+	let Some(product) = numerator.checked_mul(denominator) else {
+		return Ok(None);
+	};
+	let Some((square, radicand)) = square_free_part(product, int)? else {
+		return Ok(None);
+	};
+	let coefficient = Number::from(square).div(Number::from(denominator), int)?;
+	let factors = if radicand == 1 {
+		vec![]
+	} else {
+		vec![(Atom::Sqrt(radicand), Number::from(1))]
+	};
+	Ok(Some(
+		Expression {
+			terms: vec![Term {
+				coefficient,
+				factors,
+			}],
+		}
+		.into_value(),
+	))
+}
+
+fn numbers_equal<I: Interrupt>(a: &Number, b: &Number, int: &I) -> FResult<bool> {
+	Ok(a.compare(b, int)? == Some(cmp::Ordering::Equal))
+}
+
+/// Whether `n` is negative or a negative imaginary number like `-2i`, so that
+/// it can be subtracted instead of added
+fn is_negative<I: Interrupt>(n: &Number, int: &I) -> FResult<bool> {
+	if n.real_sign().is_none() && n.clone().real()?.is_zero(int)? {
+		return Ok(n.clone().imag()?.real_sign() == Some(cmp::Ordering::Less));
+	}
+	Ok(n.compare(&Number::from(0), int)? == Some(cmp::Ordering::Less))
+}
+
+impl Term {
+	fn constant(coefficient: Number) -> Self {
+		Self {
+			coefficient,
+			factors: vec![],
+		}
+	}
+
+	fn is_like<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
+		if self.factors.len() != other.factors.len() {
+			return Ok(false);
+		}
+		for ((a_atom, a_exp), (b_atom, b_exp)) in self.factors.iter().zip(&other.factors) {
+			if a_atom != b_atom || !numbers_equal(a_exp, b_exp, int)? {
+				return Ok(false);
+			}
+		}
+		Ok(true)
+	}
+
+	fn mul<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<Self> {
+		let mut factors = self.factors.clone();
+		for (atom, exponent) in &other.factors {
+			match factors.binary_search_by(|(a, _)| a.cmp(atom)) {
+				Ok(idx) => {
+					let sum = factors[idx].1.clone().add(exponent.clone(), int)?;
+					if sum.is_zero(int)? {
+						factors.remove(idx);
+					} else {
+						factors[idx].1 = sum;
+					}
+				}
+				Err(idx) => factors.insert(idx, (atom.clone(), exponent.clone())),
+			}
+		}
+		Self {
+			coefficient: self
+				.coefficient
+				.clone()
+				.mul(other.coefficient.clone(), int)?,
+			factors,
+		}
+		.simplify_square_roots(int)
+	}
+
+	/// Moves squares of square roots into the coefficient, e.g. `sqrt(2)^3`
+	/// becomes `2 sqrt(2)`, and combines the remaining square roots, e.g.
+	/// `sqrt(2) sqrt(6)` becomes `2 sqrt(3)`
+	fn simplify_square_roots<I: Interrupt>(mut self, int: &I) -> FResult<Self> {
+		let mut product: u64 = 1;
+		let mut factors = Vec::with_capacity(self.factors.len());
+		for (atom, exponent) in self.factors {
+			let Atom::Sqrt(radicand) = atom else {
+				factors.push((atom, exponent));
+				continue;
+			};
+			let Ok(exponent_int) = exponent.clone().try_as_i64(int) else {
+				factors.push((atom, exponent));
+				continue;
+			};
+			// `sqrt(r)^n` is `r^floor(n/2) sqrt(r)^(n mod 2)`
+			let half = exponent_int.div_euclid(2);
+			let power = Number::from(radicand).pow(Number::from(half.unsigned_abs()), int)?;
+			self.coefficient = if half < 0 {
+				self.coefficient.div(power, int)?
+			} else {
+				self.coefficient.mul(power, int)?
+			};
+			if exponent_int.rem_euclid(2) == 1 {
+				match product.checked_mul(radicand) {
+					Some(p) => product = p,
+					None => factors.push((atom, Number::from(1))),
+				}
+			}
+		}
+		if product > 1 {
+			match square_free_part(product, int)? {
+				Some((square, radicand)) => {
+					self.coefficient = self.coefficient.mul(Number::from(square), int)?;
+					if radicand > 1 {
+						factors.push((Atom::Sqrt(radicand), Number::from(1)));
+					}
+				}
+				None => factors.push((Atom::Sqrt(product), Number::from(1))),
+			}
+		}
+		factors.sort_by(|(a, _), (b, _)| a.cmp(b));
+		self.factors = factors;
+		Ok(self)
+	}
+
+	fn pow<I: Interrupt>(self, exponent: &Number, int: &I) -> FResult<Self> {
+		let mut factors = vec![];
+		for (atom, exp) in self.factors {
+			let exp = exp.mul(exponent.clone(), int)?;
+			if !exp.is_zero(int)? {
+				factors.push((atom, exp));
+			}
+		}
+		Self {
+			coefficient: self.coefficient.pow(exponent.clone(), int)?,
+			factors,
+		}
+		.simplify_square_roots(int)
+	}
+
+	fn inverse<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		Self {
+			coefficient: Number::from(1).div(self.coefficient, int)?,
+			factors: self
+				.factors
+				.into_iter()
+				.map(|(atom, exp)| (atom, -exp))
+				.collect(),
+		}
+		.simplify_square_roots(int)
+	}
+
+	/// The sum of the exponents of all variables, used to show higher powers
+	/// first
+	fn degree<I: Interrupt>(&self, int: &I) -> f64 {
+		self.factors
+			.iter()
+			.filter(|(atom, _)| matches!(atom, Atom::Variable(_)))
+			.map(|(_, exp)| exp.clone().try_as_f64(int).unwrap_or_default())
+			.sum()
+	}
+
+	/// Differentiates this term with the product rule, e.g. `x^2 sin(x)`
+	/// becomes `2x sin(x) + x^2 cos(x)`
+	fn derivative<I: Interrupt>(&self, var: &str, int: &I) -> FResult<Expression> {
+		let mut result = Expression { terms: vec![] };
+		for (i, (atom, exponent)) in self.factors.iter().enumerate() {
+			let Some(inner) = atom.derivative(var, int)? else {
+				continue;
+			};
+			// power rule: `(u^n)' = n u^(n - 1) u'`
+			let mut factors = self.factors.clone();
+			let lowered = exponent.clone().sub(Number::from(1), int)?;
+			if lowered.is_zero(int)? {
+				factors.remove(i);
+			} else {
+				factors[i].1 = lowered;
+			}
+			let term = Self {
+				coefficient: self.coefficient.clone().mul(exponent.clone(), int)?,
+				factors,
+			};
+			let product = Expression { terms: vec![term] }.mul(&inner, int)?;
+			result = result.add(product, int)?;
+		}
+		Ok(result)
+	}
+
+	fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
+		self.coefficient.serialize(write)?;
+		self.factors.len().serialize(write)?;
+		for (atom, exp) in &self.factors {
+			atom.serialize(write)?;
+			exp.serialize(write)?;
+		}
+		Ok(())
+	}
+
+	fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		let coefficient = Number::deserialize(read)?;
+		let len = usize::deserialize(read)?;
+		let mut factors = Vec::with_capacity(len);
+		for _ in 0..len {
+			factors.push((Atom::deserialize(read)?, Number::deserialize(read)?));
+		}
+		Ok(Self {
+			coefficient,
+			factors,
+		})
+	}
+
+	fn format<I: Interrupt>(
+		&self,
+		is_first: bool,
+		spans: &mut Vec<Span>,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<bool> {
+		let negative = is_negative(&self.coefficient, int)?;
+		let sign = match (is_first, negative) {
+			(true, false) => "",
+			(true, true) => "-",
+			(false, false) => " + ",
+			(false, true) => " - ",
+		};
+		if !sign.is_empty() {
+			spans.push(Span {
+				string: sign.to_string(),
+				kind: SpanKind::Operator,
+			});
+		}
+		let coefficient = if negative {
+			-self.coefficient.clone()
+		} else {
+			self.coefficient.clone()
+		};
+		let mut exact = true;
+		if self.factors.is_empty() || !coefficient.is_unitless_one(int)? {
+			let formatted = coefficient.simplify(attrs, ctx, int)?.format(ctx, int)?;
+			exact = formatted.is_exact();
+			let mut coefficient_spans = vec![];
+			formatted.spans(
+				&mut coefficient_spans,
+				Attrs {
+					show_approx: false,
+					..attrs
+				},
+			);
+			let plain_number = coefficient_spans.iter().all(|span| {
+				span.string
+					.chars()
+					.all(|ch| ch.is_ascii_digit() || ch == '.')
+			});
+			spans.extend(coefficient_spans);
+			// e.g. `2x`, but `2 sqrt(3)`
+			let needs_space = match self.factors.first() {
+				Some((Atom::Variable(_), _)) => !plain_number,
+				Some((Atom::Sqrt(_) | Atom::Function(_, _), _)) => true,
+				None => false,
+			};
+			if needs_space {
+				spans.push(Span::from_string(" ".to_string()));
+			}
+		}
+		for (i, (atom, exp)) in self.factors.iter().enumerate() {
+			if i > 0 {
+				spans.push(Span::from_string(" ".to_string()));
+			}
+			atom.format(spans, attrs, ctx, int)?;
+			if exp.is_unitless_one(int)? {
+				continue;
+			}
+			let exp = exp.clone().simplify(attrs, ctx, int)?.format(ctx, int)?;
+			exact = exact && exp.is_exact();
+			let exp = exp.to_string();
+			spans.push(Span {
+				string: "^".to_string(),
+				kind: SpanKind::Operator,
+			});
+			let string = if exp.chars().all(|ch| ch.is_ascii_digit()) {
+				exp
+			} else {
+				format!("({exp})")
+			};
+			spans.push(Span {
+				string,
+				kind: SpanKind::Number,
+			});
+		}
+		Ok(exact)
+	}
+}
+
+impl Expression {
+	fn constant(coefficient: Number) -> Self {
+		Self {
+			terms: vec![Term::constant(coefficient)],
+		}
+	}
+
+	fn atom(atom: Atom) -> Self {
+		Self {
+			terms: vec![Term {
+				coefficient: Number::from(1),
+				factors: vec![(atom, Number::from(1))],
+			}],
+		}
+	}
+
+	fn derivative<I: Interrupt>(&self, var: &str, int: &I) -> FResult<Self> {
+		let mut result = Self { terms: vec![] };
+		for term in &self.terms {
+			result = result.add(term.derivative(var, int)?, int)?;
+		}
+		Ok(result)
+	}
+
+	fn from_value<I: Interrupt>(value: Value, int: &I) -> FResult<Self> {
+		Ok(match value {
+			Value::Symbolic(e) => e,
+			Value::Num(n) if n.is_zero(int)? => Self { terms: vec![] },
+			Value::Num(n) => Self {
+				terms: vec![Term::constant(*n)],
+			},
+			_ => return Err(FendError::InvalidOperandsForSymbolicArithmetic),
+		})
+	}
+
+	/// Turns expressions without any variables back into plain numbers
+	fn into_value(self) -> Value {
+		match self.terms.as_slice() {
+			[] => Value::Num(Box::new(Number::from(0))),
+			[term] if term.factors.is_empty() => {
+				Value::Num(Box::new(self.terms.into_iter().next().unwrap().coefficient))
+			}
+			_ => Value::Symbolic(self),
+		}
+	}
+
+	fn as_constant(&self) -> Option<&Number> {
+		match self.terms.as_slice() {
+			[term] if term.factors.is_empty() => Some(&term.coefficient),
+			_ => None,
+		}
+	}
+
+	fn add_term<I: Interrupt>(&mut self, term: Term, int: &I) -> FResult<()> {
+		for i in 0..self.terms.len() {
+			if self.terms[i].is_like(&term, int)? {
+				let sum = self.terms[i]
+					.coefficient
+					.clone()
+					.add(term.coefficient, int)?;
+				if sum.is_zero(int)? {
+					self.terms.remove(i);
+				} else {
+					self.terms[i].coefficient = sum;
+				}
+				return Ok(());
+			}
+		}
+		if !term.coefficient.is_zero(int)? {
+			self.terms.push(term);
+		}
+		Ok(())
+	}
+
+	fn add<I: Interrupt>(mut self, other: Self, int: &I) -> FResult<Self> {
+		for term in other.terms {
+			self.add_term(term, int)?;
+		}
+		Ok(self)
+	}
+
+	fn neg(self) -> Self {
+		Self {
+			terms: self
+				.terms
+				.into_iter()
+				.map(|term| Term {
+					coefficient: -term.coefficient,
+					factors: term.factors,
+				})
+				.collect(),
+		}
+	}
+
+	fn mul<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<Self> {
+		let mut result = Self { terms: vec![] };
+		for a in &self.terms {
+			for b in &other.terms {
+				result.add_term(a.mul(b, int)?, int)?;
+			}
+		}
+		Ok(result)
+	}
+
+	fn div<I: Interrupt>(self, other: Self, int: &I) -> FResult<Self> {
+		let mut terms = other.terms.into_iter();
+		match (terms.next(), terms.next()) {
+			(None, _) => Err(FendError::DivideByZero),
+			(Some(divisor), None) => self.mul(
+				&Self {
+					terms: vec![divisor.inverse(int)?],
+				},
+				int,
+			),
+			(Some(_), Some(_)) => Err(FendError::CannotDivideBySymbolicSum),
+		}
+	}
+
+	fn pow<I: Interrupt>(self, exponent: &Number, int: &I) -> FResult<Self> {
+		if !exponent.is_unitless(int)? {
+			return Err(FendError::ExpectedAUnitlessNumber);
+		}
+		if self.terms.len() == 1 {
+			let term = self.terms.into_iter().next().unwrap();
+			return Ok(Self {
+				terms: vec![term.pow(exponent, int)?],
+			});
+		}
+		let n = exponent
+			.clone()
+			.try_as_usize(int)
+			.map_err(|_| FendError::InvalidOperandsForSymbolicArithmetic)?;
+		if n > MAX_EXPANDED_EXPONENT {
+			return Err(FendError::ExponentTooLarge);
+		}
+		let mut result = Self {
+			terms: vec![Term::constant(Number::from(1))],
+		};
+		for _ in 0..n {
+			result = result.mul(&self, int)?;
+		}
+		Ok(result)
+	}
+
+	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
+		self.terms.len().serialize(write)?;
+		for term in &self.terms {
+			term.serialize(write)?;
+		}
+		Ok(())
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		let len = usize::deserialize(read)?;
+		let mut terms = Vec::with_capacity(len);
+		for _ in 0..len {
+			terms.push(Term::deserialize(read)?);
+		}
+		Ok(Self { terms })
+	}
+
+	/// Two expressions are equal if they have the same terms, in any order
+	pub(crate) fn compare<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
+		if self.terms.len() != other.terms.len() {
+			return Ok(false);
+		}
+		for a in &self.terms {
+			let mut found = false;
+			for b in &other.terms {
+				if a.is_like(b, int)? && numbers_equal(&a.coefficient, &b.coefficient, int)? {
+					found = true;
+					break;
+				}
+			}
+			if !found {
+				return Ok(false);
+			}
+		}
+		Ok(true)
+	}
+
+	pub(crate) fn format<I: Interrupt>(
+		&self,
+		spans: &mut Vec<Span>,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<()> {
+		let mut terms = Vec::with_capacity(self.terms.len());
+		for term in &self.terms {
+			terms.push((term.degree(int), term));
+		}
+		// highest degree first, e.g. `x^2 + 2x + 1`
+		terms.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+		let mut term_spans = vec![];
+		let mut exact = true;
+		for (i, (_, term)) in terms.into_iter().enumerate() {
+			exact &= term.format(i == 0, &mut term_spans, attrs, ctx, int)?;
+		}
+		if !exact && attrs.show_approx && !attrs.plain_number {
+			spans.push(Span {
+				string: "approx. ".to_string(),
+				kind: SpanKind::Ident,
+			});
+		}
+		spans.extend(term_spans);
+		Ok(())
+	}
+}
+
+/// Evaluates arithmetic where at least one operand is a symbolic expression
+pub(crate) fn evaluate_bop<I: Interrupt>(
+	bop: Bop,
+	lhs: Value,
+	rhs: Value,
+	scope: Option<Arc<Scope>>,
+	int: &I,
+) -> FResult<Value> {
+	Ok(match (bop, lhs, rhs) {
+		// e.g. `x * sqrt 2` is parsed as `(x * sqrt) 2`
+		(bop, f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _)), v) => {
+			f.wrap_with_expr(|f| Expr::Bop(bop, f, Box::new(Expr::Literal(v))), scope)?
+		}
+		(bop, v, f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _))) => {
+			f.wrap_with_expr(|f| Expr::Bop(bop, Box::new(Expr::Literal(v)), f), scope)?
+		}
+		(bop, lhs, rhs) => {
+			let (lhs, rhs) = (
+				Expression::from_value(lhs, int)?,
+				Expression::from_value(rhs, int)?,
+			);
+			let result = match bop {
+				Bop::Plus => lhs.add(rhs, int)?,
+				Bop::Minus => lhs.add(rhs.neg(), int)?,
+				Bop::Mul => lhs.mul(&rhs, int)?,
+				Bop::Div => lhs.div(rhs, int)?,
+				Bop::Pow => match rhs.as_constant() {
+					Some(exponent) => lhs.pow(exponent, int)?,
+					// `x^0` has no terms left
+					None if rhs.terms.is_empty() => Expression {
+						terms: vec![Term::constant(Number::from(1))],
+					},
+					None => return Err(FendError::InvalidOperandsForSymbolicArithmetic),
+				},
+				_ => return Err(FendError::InvalidOperandsForSymbolicArithmetic),
+			};
+			result.into_value()
+		}
+	})
+}
+
+/// Returns the coefficients of `value` as a polynomial in `var`, starting
+/// with the constant term, or `None` if it contains other variables or
+/// powers of `var` that aren't non-negative integers. Missing terms are
+/// `None` rather than zero, because zero can't be added to a number with
+/// units.
+pub(crate) fn polynomial_coefficients<I: Interrupt>(
+	value: Value,
+	var: &str,
+	int: &I,
+) -> FResult<Option<Vec<Option<Number>>>> {
+	let mut coefficients = vec![];
+	for term in Expression::from_value(value, int)?.terms {
+		let degree = match term.factors.as_slice() {
+			[] => 0,
+			[(Atom::Variable(name), exp)] if name == var => match exp.clone().try_as_usize(int) {
+				Ok(degree) => degree,
+				Err(FendError::Interrupted) => return Err(FendError::Interrupted),
+				Err(_) => return Ok(None),
+			},
+			_ => return Ok(None),
+		};
+		if coefficients.len() <= degree {
+			coefficients.resize(degree + 1, None);
+		}
+		coefficients[degree] = Some(term.coefficient);
+	}
+	Ok(Some(coefficients))
+}
+
+/// Expands products and powers of sums, e.g. `expand((x + 1)^2)` is
+/// `x^2 + 2x + 1`. Expressions are always stored as a sum of terms, so this
+/// only needs to check that the argument is a number or an expression.
+pub(crate) fn expand<I: Interrupt>(value: Value, int: &I) -> FResult<Value> {
+	Ok(Expression::from_value(value, int)?.into_value())
+}
+
+/// Differentiates `value` with respect to `var` using the power, product
+/// and chain rules, e.g. `diff(x^3 + 2x, x)` is `3x^2 + 2` and
+/// `diff(sin(x^2), x)` is `2x cos(x^2)`
+pub(crate) fn differentiate<I: Interrupt>(value: Value, var: &str, int: &I) -> FResult<Value> {
+	Ok(Expression::from_value(value, int)?
+		.derivative(var, int)?
+		.into_value())
+}
+
+/// Keeps an elementary function like `sin` unevaluated when it's applied
+/// to an expression, so that it can be differentiated
+pub(crate) fn apply_function<I: Interrupt>(
+	function: BuiltInFunction,
+	mut argument: Expression,
+	int: &I,
+) -> FResult<Value> {
+	if !matches!(
+		function,
+		BuiltInFunction::Sin
+			| BuiltInFunction::Cos
+			| BuiltInFunction::Tan
+			| BuiltInFunction::Sinh
+			| BuiltInFunction::Cosh
+			| BuiltInFunction::Tanh
+			| BuiltInFunction::Exp
+			| BuiltInFunction::Ln
+	) {
+		return Err(FendError::UnsupportedSymbolicFunction(function.as_str()));
+	}
+	test_int(int)?;
+	argument.terms.sort_by_cached_key(|term| {
+		let mut bytes = vec![];
+		// serializing into memory can't fail
+		mem::drop(term.serialize(&mut bytes));
+		bytes
+	});
+	Ok(Value::Symbolic(Expression::atom(Atom::Function(
+		function,
+		Box::new(argument),
+	))))
+}
+
+pub(crate) fn negate<I: Interrupt>(value: Value, int: &I) -> FResult<Value> {
+	Ok(Expression::from_value(value, int)?.neg().into_value())
+}
@@ -19,6 +19,12 @@ pub(crate) enum PrefixRule {
 	LongPrefix,
 	ShortPrefixAllowed,
 	ShortPrefix,
+	// binary prefixes like `kibi` and `Ki` can only be combined with
+	// information units like `byte` and `B`
+	LongAndBinaryPrefixAllowed,
+	LongBinaryPrefix,
+	ShortAndBinaryPrefixAllowed,
+	ShortBinaryPrefix,
 }
 
 #[derive(Debug)]
@@ -30,6 +36,68 @@ pub(crate) struct UnitDef {
 	value: Value,
 }
 
+/// Splits a unit definition into its prefix rule (e.g. `l@`) and the
+/// remaining definition
+fn parse_prefix_rule(definition: &str) -> (PrefixRule, &str) {
+	let mut definition = definition.trim();
+	if definition == "$CURRENCY" {
+		return (PrefixRule::LongPrefixAllowed, definition);
+	}
+	let mut rule = PrefixRule::NoPrefixesAllowed;
+	if let Some(remaining) = definition.strip_prefix("l@") {
+		definition = remaining;
+		rule = PrefixRule::LongPrefixAllowed;
+	}
+	if let Some(remaining) = definition.strip_prefix("lp@") {
+		definition = remaining;
+		rule = PrefixRule::LongPrefix;
+	}
+	if let Some(remaining) = definition.strip_prefix("s@") {
+		definition = remaining;
+		rule = PrefixRule::ShortPrefixAllowed;
+	}
+	if let Some(remaining) = definition.strip_prefix("sp@") {
+		definition = remaining;
+		rule = PrefixRule::ShortPrefix;
+	}
+	if let Some(remaining) = definition.strip_prefix("lb@") {
+		definition = remaining;
+		rule = PrefixRule::LongAndBinaryPrefixAllowed;
+	}
+	if let Some(remaining) = definition.strip_prefix("lbp@") {
+		definition = remaining;
+		rule = PrefixRule::LongBinaryPrefix;
+	}
+	if let Some(remaining) = definition.strip_prefix("sb@") {
+		definition = remaining;
+		rule = PrefixRule::ShortAndBinaryPrefixAllowed;
+	}
+	if let Some(remaining) = definition.strip_prefix("sbp@") {
+		definition = remaining;
+		rule = PrefixRule::ShortBinaryPrefix;
+	}
+	(rule, definition)
+}
+
+const fn can_combine_prefix(prefix: PrefixRule, unit: PrefixRule) -> bool {
+	matches!(
+		(prefix, unit),
+		(
+			PrefixRule::LongPrefix,
+			PrefixRule::LongPrefixAllowed | PrefixRule::LongAndBinaryPrefixAllowed
+		) | (
+			PrefixRule::ShortPrefix,
+			PrefixRule::ShortPrefixAllowed | PrefixRule::ShortAndBinaryPrefixAllowed
+		) | (
+			PrefixRule::LongBinaryPrefix,
+			PrefixRule::LongAndBinaryPrefixAllowed
+		) | (
+			PrefixRule::ShortBinaryPrefix,
+			PrefixRule::ShortAndBinaryPrefixAllowed
+		)
+	)
+}
+
 fn expr_unit<I: Interrupt>(
 	unit_def: (Cow<'static, str>, Cow<'static, str>, Cow<'static, str>),
 	attrs: Attrs,
@@ -37,8 +105,11 @@ fn expr_unit<I: Interrupt>(
 	int: &I,
 ) -> FResult<UnitDef> {
 	let (singular, plural, definition) = unit_def;
-	let mut definition = definition.trim();
+	let definition = definition.trim();
 	if definition == "$CURRENCY" {
+		if cfg!(not(feature = "currency")) {
+			return Err(FendError::FeatureDisabled("currency"));
+		}
 		let Some(exchange_rate_fn) = &context.get_exchange_rate else {
 			return Err(FendError::NoExchangeRatesAvailable);
 		};
@@ -67,23 +138,7 @@ fn expr_unit<I: Interrupt>(
 			value: Value::Num(Box::new(value)),
 		});
 	}
-	let mut rule = PrefixRule::NoPrefixesAllowed;
-	if let Some(remaining) = definition.strip_prefix("l@") {
-		definition = remaining;
-		rule = PrefixRule::LongPrefixAllowed;
-	}
-	if let Some(remaining) = definition.strip_prefix("lp@") {
-		definition = remaining;
-		rule = PrefixRule::LongPrefix;
-	}
-	if let Some(remaining) = definition.strip_prefix("s@") {
-		definition = remaining;
-		rule = PrefixRule::ShortPrefixAllowed;
-	}
-	if let Some(remaining) = definition.strip_prefix("sp@") {
-		definition = remaining;
-		rule = PrefixRule::ShortPrefix;
-	}
+	let (rule, definition) = parse_prefix_rule(definition);
 	if definition == "!" {
 		return Ok(UnitDef {
 			value: Value::Num(Box::new(Number::new_base_unit(
@@ -100,7 +155,7 @@ fn expr_unit<I: Interrupt>(
 		.strip_prefix('=')
 		.map_or((false, definition), |remaining| (true, remaining));
 	// long prefixes like `hecto` are always treated as aliases
-	let alias = alias || rule == PrefixRule::LongPrefix;
+	let alias = alias || matches!(rule, PrefixRule::LongPrefix | PrefixRule::LongBinaryPrefix);
 	let mut num = evaluate_to_value(definition, None, attrs, context, int)?.expect_num()?;
 
 	// There are three cases to consider:
@@ -209,18 +264,14 @@ fn query_unit_case_sensitive<I: Interrupt>(
 			Ok(a) => a,
 		};
 		match query_unit_internal(remaining_ident, false, case_sensitive, false, context) {
-			Err(FendError::IdentifierNotFound(_)) => continue,
+			Err(FendError::IdentifierNotFound(_)) => (),
 			Err(e) => return Err(e),
 			Ok(b) => {
 				let (a, b) = (
 					expr_unit(a, attrs, context, int)?,
 					expr_unit(b, attrs, context, int)?,
 				);
-				if (a.prefix_rule == PrefixRule::LongPrefix
-					&& b.prefix_rule == PrefixRule::LongPrefixAllowed)
-					|| (a.prefix_rule == PrefixRule::ShortPrefix
-						&& b.prefix_rule == PrefixRule::ShortPrefixAllowed)
-				{
+				if can_combine_prefix(a.prefix_rule, b.prefix_rule) {
 					// now construct a new unit!
 					return construct_prefixed_unit(a, b, int);
 				}
@@ -240,17 +291,22 @@ fn query_unit_internal(
 	context: &crate::Context,
 ) -> FResult<(Cow<'static, str>, Cow<'static, str>, Cow<'static, str>)> {
 	if !short_prefixes {
+		for (name, definition) in &context.user_units {
+			if ident == name || (!case_sensitive && name.eq_ignore_ascii_case(ident)) {
+				return Ok((
+					name.clone().into(),
+					name.clone().into(),
+					definition.clone().into(),
+				));
+			}
+		}
 		for (s, p, d) in &context.custom_units {
 			let p = if p.is_empty() { s } else { p };
 			if (ident == s || ident == p)
 				|| (!case_sensitive
 					&& (s.eq_ignore_ascii_case(ident) || p.eq_ignore_ascii_case(ident)))
 			{
-				return Ok((
-					s.to_string().into(),
-					p.to_string().into(),
-					d.to_string().into(),
-				));
+				return Ok((s.clone().into(), p.clone().into(), d.clone().into()));
 			}
 		}
 	}
@@ -276,8 +332,162 @@ fn query_unit_internal(
 	}
 }
 
+/// SI prefixes that can be chosen by `to si`, from largest to smallest, as
+/// (power of ten, short prefix, long prefix)
+const AUTO_PREFIXES: &[(i32, &str, &str)] = &[
+	(24, "Y", "yotta"),
+	(21, "Z", "zetta"),
+	(18, "E", "exa"),
+	(15, "P", "peta"),
+	(12, "T", "tera"),
+	(9, "G", "giga"),
+	(6, "M", "mega"),
+	(3, "k", "kilo"),
+	(0, "", ""),
+	(-2, "c", "centi"),
+	(-3, "m", "milli"),
+	(-6, "\u{b5}", "micro"),
+	(-9, "n", "nano"),
+	(-12, "p", "pico"),
+	(-15, "f", "femto"),
+	(-18, "a", "atto"),
+	(-21, "z", "zepto"),
+	(-24, "y", "yocto"),
+];
+
+/// Looks up `name` with the given prefix, making sure that it isn't parsed
+/// as a different unit (e.g. `cd` is a candela, not a centiday)
+fn query_prefixed_unit<I: Interrupt>(
+	prefix: &str,
+	name: &str,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Option<Number>> {
+	let unit = match query_unit_static(&format!("{prefix}{name}"), attrs, context, int) {
+		Ok(Value::Num(unit)) => unit,
+		Ok(_) | Err(FendError::IdentifierNotFound(_)) => return Ok(None),
+		Err(e) => return Err(e),
+	};
+	Ok((unit.single_unit(int)? == Some((prefix, name))).then_some(*unit))
+}
+
+/// Converts `num` to the SI prefix that makes it most readable, e.g.
+/// `0.0000012 s` becomes `1.2 µs`. If `engineering` is set, only prefixes
+/// that are powers of 1000 are used (so `0.05 m` becomes `50 mm` rather
+/// than `5 cm`). Numbers whose unit can't be prefixed are returned unchanged.
+pub(crate) fn with_best_prefix<I: Interrupt>(
+	num: Number,
+	engineering: bool,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<Number> {
+	let Some((_, name)) = num.single_unit(int)? else {
+		return Ok(num);
+	};
+	let name = name.to_string();
+	let Some(unprefixed) = query_prefixed_unit("", &name, attrs, context, int)? else {
+		return Ok(num);
+	};
+	let magnitude = match num
+		.clone()
+		.convert_to(unprefixed, int)?
+		.magnitude_as_f64(int)
+	{
+		Ok(magnitude) if magnitude.is_normal() => magnitude,
+		Ok(_) | Err(FendError::ExpectedARealNumber) => return Ok(num),
+		Err(e) => return Err(e),
+	};
+	let mut best = None;
+	for &(power, short, long) in AUTO_PREFIXES {
+		if engineering && power % 3 != 0 {
+			continue;
+		}
+		let unit = match query_prefixed_unit(short, &name, attrs, context, int)? {
+			Some(unit) => unit,
+			None => match query_prefixed_unit(long, &name, attrs, context, int)? {
+				Some(unit) => unit,
+				None => continue,
+			},
+		};
+		// allow for rounding errors, so that e.g. `0.001 m` becomes `1 mm`
+		let fits = magnitude * (1.0 + 1e-9) >= 10_f64.powi(power);
+		best = Some(unit);
+		if fits {
+			break;
+		}
+	}
+	match best {
+		Some(unit) => num.convert_to(unit, int),
+		None => Ok(num),
+	}
+}
+
+/// Returns whether `ident` refers to a built-in unit, possibly with a prefix.
+/// Unlike `query_unit`, this only looks at the unit definitions without
+/// evaluating them, which makes it cheap enough for syntax highlighting.
+pub(crate) fn is_builtin_unit(ident: &str) -> bool {
+	if ident.is_empty() {
+		return false;
+	}
+	for case_sensitive in [true, false] {
+		if builtin::query_unit(ident, false, case_sensitive).is_some() {
+			return true;
+		}
+		for (split_idx, _) in ident.char_indices().skip(1) {
+			let (prefix, remaining_ident) = ident.split_at(split_idx);
+			let (Some(a), Some(b)) = (
+				builtin::query_unit(prefix, true, case_sensitive),
+				builtin::query_unit(remaining_ident, false, case_sensitive),
+			) else {
+				continue;
+			};
+			if can_combine_prefix(parse_prefix_rule(&a.2).0, parse_prefix_rule(&b.2).0) {
+				return true;
+			}
+		}
+	}
+	false
+}
+
+/// Considers the names of all built-in units as suggestions, including
+/// names with a prefix like `kilo`. Prefixes on their own are not suggested.
+pub(crate) fn suggest_unit(suggestion: &mut crate::suggestion::Suggestion) {
+	let mut prefixes = vec![];
+	for group in builtin::ALL_UNIT_DEFS {
+		for (singular, plural, definition, _) in *group {
+			match parse_prefix_rule(definition).0 {
+				rule @ (PrefixRule::LongPrefix | PrefixRule::LongBinaryPrefix) => {
+					prefixes.push((*singular, rule));
+				}
+				PrefixRule::ShortPrefix | PrefixRule::ShortBinaryPrefix => (),
+				_ if builtin::is_short_prefix(singular) => (),
+				_ => {
+					suggestion.consider(singular);
+					suggestion.consider(plural);
+				}
+			}
+		}
+	}
+	let ident = suggestion.ident().to_string();
+	for (prefix, prefix_rule) in prefixes {
+		let Some(remaining) = ident.strip_prefix(prefix) else {
+			continue;
+		};
+		for group in builtin::ALL_UNIT_DEFS {
+			for (singular, plural, definition, _) in *group {
+				if can_combine_prefix(prefix_rule, parse_prefix_rule(definition).0) {
+					suggestion.consider_prefixed(remaining, prefix, singular);
+					suggestion.consider_prefixed(remaining, prefix, plural);
+				}
+			}
+		}
+	}
+}
+
 pub(crate) fn get_completions_for_prefix(prefix: &str) -> Vec<crate::Completion> {
-	use crate::Completion;
+	use crate::{Completion, CompletionKind};
 
 	let mut result = vec![];
 
@@ -286,15 +496,42 @@ pub(crate) fn get_completions_for_prefix(prefix: &str) -> Vec<crate::Completion>
 			result.push(Completion {
 				display: name.to_string(),
 				insert: name.split_at(prefix.len()).1.to_string(),
+				kind: CompletionKind::Unit,
 			});
 		}
 	};
 
+	let mut long_prefixes = vec![];
 	for group in builtin::ALL_UNIT_DEFS {
-		for (s, _, _, _) in *group {
+		for (s, _, definition, _) in *group {
 			// only add singular name, since plurals
 			// unnecessarily clutter autocompletions
 			add(s);
+			if let rule @ (PrefixRule::LongPrefix | PrefixRule::LongBinaryPrefix) =
+				parse_prefix_rule(definition).0
+			{
+				long_prefixes.push((*s, rule));
+			}
+		}
+	}
+
+	// units with a prefix like `kilometer` are only suggested once the
+	// prefix has been typed, since there are far too many combinations
+	for (long_prefix, prefix_rule) in long_prefixes {
+		let Some(remaining) = prefix.strip_prefix(long_prefix) else {
+			continue;
+		};
+		if remaining.is_empty() {
+			continue;
+		}
+		for group in builtin::ALL_UNIT_DEFS {
+			for (s, _, definition, _) in *group {
+				if s.starts_with(remaining)
+					&& can_combine_prefix(prefix_rule, parse_prefix_rule(definition).0)
+				{
+					add(&format!("{long_prefix}{s}"));
+				}
+			}
 		}
 	}
 
@@ -1,3 +1,4 @@
+use crate::UnitSystem;
 use std::borrow::Cow;
 
 #[derive(Eq, PartialEq, PartialOrd, Ord, Clone, Copy)]
@@ -50,13 +51,13 @@ const TEMPERATURE_SCALES: &[UnitTuple] = &[
 ];
 
 const BITS_AND_BYTES: &[UnitTuple] = &[
-	("bit", "bits", "l@!", ""),
-	("bps", "", "s@bits/second", ""),
-	("byte", "bytes", "l@8 bits", ""),
-	("b", "", "s@bit", ""),
-	("B", "", "s@byte", ""),
-	("octet", "octets", "l@8 bits", ""),
-	("nibble", "nibbles", "l@4 bits", ""),
+	("bit", "bits", "lb@!", ""),
+	("bps", "", "sb@bits/second", ""),
+	("byte", "bytes", "lb@8 bits", ""),
+	("b", "", "sb@bit", ""),
+	("B", "", "sb@byte", ""),
+	("octet", "octets", "lb@8 bits", ""),
+	("nibble", "nibbles", "lb@4 bits", ""),
 ];
 
 const STANDARD_PREFIXES: &[UnitTuple] = &[
@@ -92,6 +93,7 @@ const STANDARD_PREFIXES: &[UnitTuple] = &[
 	("T", "", "=1,000,000,000,000", ""),
 ];
 
+#[cfg(feature = "units")]
 const NON_STANDARD_PREFIXES: &[UnitTuple] = &[
 	("quarter", "", "lp@1/4", ""),
 	("semi", "", "lp@0.5", ""),
@@ -104,20 +106,21 @@ const NON_STANDARD_PREFIXES: &[UnitTuple] = &[
 ];
 
 const BINARY_PREFIXES: &[UnitTuple] = &[
-	("kibi", "", "lp@2^10", ""),
-	("mebi", "", "lp@2^20", ""),
-	("gibi", "", "lp@2^30", ""),
-	("tebi", "", "lp@2^40", ""),
-	("pebi", "", "lp@2^50", ""),
-	("exbi", "", "lp@2^60", ""),
-	("zebi", "", "lp@2^70", ""),
-	("yobi", "", "lp@2^80", ""),
+	("kibi", "", "lbp@2^10", ""),
+	("mebi", "", "lbp@2^20", ""),
+	("gibi", "", "lbp@2^30", ""),
+	("tebi", "", "lbp@2^40", ""),
+	("pebi", "", "lbp@2^50", ""),
+	("exbi", "", "lbp@2^60", ""),
+	("zebi", "", "lbp@2^70", ""),
+	("yobi", "", "lbp@2^80", ""),
 	("Ki", "", "=2^10", ""),
 	("Mi", "", "=2^20", ""),
 	("Gi", "", "=2^30", ""),
 	("Ti", "", "=2^40", ""),
 ];
 
+#[cfg(feature = "units")]
 const NUMBER_WORDS: &[UnitTuple] = &[
 	("tithe", "", "=1/10", ""),
 	("one", "", "=1", ""),
@@ -320,6 +323,8 @@ const COMMON_SI_DERIVED_UNITS: &[UnitTuple] = &[
 	("V", "", "s@volt", ""),
 	("Ah", "", "s@ampere hour", ""),
 	("ohm", "ohms", "l@V/A", "electrical resistance"),
+	("\u{3a9}", "", "s@ohm", ""),  // U+03A9 (uppercase omega)
+	("\u{2126}", "", "s@ohm", ""), // U+2126 (ohm sign)
 	("siemens", "", "l@A/V", "electrical conductance"),
 	("S", "", "s@siemens", ""),
 	("farad", "", "l@coulomb/V", "capacitance"),
@@ -457,6 +462,7 @@ const RATIOS: &[UnitTuple] = &[
 	("basispoint", "", "0.01 %", ""),
 ];
 
+#[cfg(feature = "units")]
 const COMMON_PHYSICAL_UNITS: &[UnitTuple] = &[
 	("electron_volt", "electron_volts", "l@electron_charge V", ""),
 	("eV", "", "s@electron_volt", ""),
@@ -476,6 +482,11 @@ const COMMON_PHYSICAL_UNITS: &[UnitTuple] = &[
 	),
 	("au", "", "astronomical_unit", ""),
 	("AU", "", "astronomical_unit", ""),
+	("solar_mass", "solar_masses", "1.98847e30 kg", ""),
+	("solar_radius", "solar_radii", "695700 km", ""),
+	("solar_luminosity", "solar_luminosities", "3.828e26 W", ""),
+	("earth_mass", "earth_masses", "5.9722e24 kg", ""),
+	("jupiter_mass", "jupiter_masses", "1.89813e27 kg", ""),
 	("barn", "", "l@1e-28 m^2", ""),
 	("shed", "", "l@1e-24 barn", ""),
 	("cc", "", "cm^3", ""),
@@ -515,6 +526,7 @@ const COMMON_PHYSICAL_UNITS: &[UnitTuple] = &[
 	("light", "", "c", ""),
 ];
 
+#[cfg(feature = "units")]
 const CGS_UNITS: &[UnitTuple] = &[
 	("gal", "gals", "cm/s^2", "acceleration"),
 	("dyne", "dynes", "g*gal", "force"),
@@ -544,6 +556,7 @@ const CGS_UNITS: &[UnitTuple] = &[
 	("ph", "", "phot", ""),
 ];
 
+#[cfg(feature = "units")]
 const IMPERIAL_UNITS: &[UnitTuple] = &[
 	("inch", "inches", "2.54 cm", ""),
 	("mil", "mils", "1/1000 inch", ""),
@@ -601,6 +614,7 @@ const IMPERIAL_UNITS: &[UnitTuple] = &[
 	("U", "", "rack_unit", ""),
 ];
 
+#[cfg(feature = "units")]
 const LIQUID_UNITS: &[UnitTuple] = &[
 	("gallon", "gallons", "231 inch^3", ""),
 	("gal", "", "gallon", ""),
@@ -620,6 +634,7 @@ const LIQUID_UNITS: &[UnitTuple] = &[
 	("tsp", "", "teaspoon", ""),
 ];
 
+#[cfg(feature = "units")]
 const AVOIRDUPOIS_WEIGHT: &[UnitTuple] = &[
 	("pound", "pounds", "0.45359237 kg", ""),
 	("lb", "lbs", "pound", ""),
@@ -636,6 +651,7 @@ const AVOIRDUPOIS_WEIGHT: &[UnitTuple] = &[
 	("st", "", "stone", ""),
 ];
 
+#[cfg(feature = "units")]
 const TROY_WEIGHT: &[UnitTuple] = &[
 	("troy_pound", "troy_pounds", "5760 grains", ""),
 	("troy_ounce", "troy_ounces", "1/12 troy_pound", ""),
@@ -644,6 +660,7 @@ const TROY_WEIGHT: &[UnitTuple] = &[
 	("dwt", "", "pennyweight", ""),
 ];
 
+#[cfg(feature = "units")]
 const OTHER_WEIGHTS: &[UnitTuple] = &[
 	("metric_grain", "metric_grains", "50 mg", ""),
 	("carat", "carats", "0.2 grams", ""),
@@ -653,6 +670,7 @@ const OTHER_WEIGHTS: &[UnitTuple] = &[
 	("t", "", "tonne", ""),
 ];
 
+#[cfg(feature = "units")]
 const IMPERIAL_ABBREVIATIONS: &[UnitTuple] = &[
 	("yd", "", "yard", ""),
 	("ch", "", "chain", ""),
@@ -680,6 +698,7 @@ const IMPERIAL_ABBREVIATIONS: &[UnitTuple] = &[
 	("ftn", "ftns", "fortnight", ""),
 ];
 
+#[cfg(feature = "units")]
 const NAUTICAL_UNITS: &[UnitTuple] = &[
 	("fathom", "fathoms", "6 ft", ""),
 	("nautical_mile", "nautical_miles", "1852 m", ""),
@@ -691,6 +710,7 @@ const NAUTICAL_UNITS: &[UnitTuple] = &[
 	("NM", "", "nautical_mile", ""),
 ];
 
+#[cfg(feature = "currency")]
 const CURRENCIES: &[UnitTuple] = &[
 	("BASE_CURRENCY", "BASE_CURRENCY", "!", ""),
 	("dollar", "dollars", "USD", ""),
@@ -708,6 +728,7 @@ const CURRENCIES: &[UnitTuple] = &[
 	("zl", "zl", "PLN", ""),
 ];
 
+#[cfg(feature = "units")]
 const HISTORICAL_UNITS: &[UnitTuple] = &[
 	("shaku", "shaku", "0.303 m", ""),
 	("tsubo", "tsubo", "3.306 m^2", ""),
@@ -716,6 +737,7 @@ const HISTORICAL_UNITS: &[UnitTuple] = &[
 ];
 
 // from https://en.wikipedia.org/wiki/ISO_4217
+#[cfg(feature = "currency")]
 const CURRENCY_IDENTIFIERS: &[&str] = &[
 	"AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT",
 	"BGN", "BHD", "BIF", "BMD", "BND", "BOB", "BOV", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD",
@@ -739,8 +761,10 @@ pub(crate) const ALL_UNIT_DEFS: &[&[UnitTuple]] = &[
 	TEMPERATURE_SCALES,
 	BITS_AND_BYTES,
 	STANDARD_PREFIXES,
+	#[cfg(feature = "units")]
 	NON_STANDARD_PREFIXES,
 	BINARY_PREFIXES,
+	#[cfg(feature = "units")]
 	NUMBER_WORDS,
 	CONSTANTS,
 	ANGLES,
@@ -748,28 +772,39 @@ pub(crate) const ALL_UNIT_DEFS: &[&[UnitTuple]] = &[
 	COMMON_SI_DERIVED_UNITS,
 	TIME_UNITS,
 	RATIOS,
+	#[cfg(feature = "units")]
 	COMMON_PHYSICAL_UNITS,
+	#[cfg(feature = "units")]
 	IMPERIAL_UNITS,
+	#[cfg(feature = "units")]
 	LIQUID_UNITS,
+	#[cfg(feature = "units")]
 	AVOIRDUPOIS_WEIGHT,
+	#[cfg(feature = "units")]
 	TROY_WEIGHT,
+	#[cfg(feature = "units")]
 	OTHER_WEIGHTS,
+	#[cfg(feature = "units")]
 	IMPERIAL_ABBREVIATIONS,
+	#[cfg(feature = "units")]
 	NAUTICAL_UNITS,
+	#[cfg(feature = "currency")]
 	CURRENCIES,
+	#[cfg(feature = "units")]
 	CGS_UNITS,
+	#[cfg(feature = "units")]
 	HISTORICAL_UNITS,
 ];
 
 const SHORT_PREFIXES: &[(&str, &str)] = &[
-	("Ki", "sp@kibi"),
-	("Mi", "sp@mebi"),
-	("Gi", "sp@gibi"),
-	("Ti", "sp@tebi"),
-	("Pi", "sp@pebi"),
-	("Ei", "sp@exbi"),
-	("Zi", "sp@zebi"),
-	("Yi", "sp@yobi"),
+	("Ki", "sbp@kibi"),
+	("Mi", "sbp@mebi"),
+	("Gi", "sbp@gibi"),
+	("Ti", "sbp@tebi"),
+	("Pi", "sbp@pebi"),
+	("Ei", "sbp@exbi"),
+	("Zi", "sbp@zebi"),
+	("Yi", "sbp@yobi"),
 	("Y", "sp@yotta"),
 	("Z", "sp@zetta"),
 	("E", "sp@exa"),
@@ -794,6 +829,12 @@ const SHORT_PREFIXES: &[(&str, &str)] = &[
 	("y", "sp@yocto"),
 ];
 
+/// Whether `name` is a short prefix like `k` or `Gi`. Some of these can
+/// also be used on their own, e.g. `2 Gi` is `2 * 2^30`.
+pub(crate) fn is_short_prefix(name: &str) -> bool {
+	SHORT_PREFIXES.iter().any(|(prefix, _)| *prefix == name)
+}
+
 #[allow(clippy::too_many_lines)]
 pub(crate) fn query_unit(
 	ident: &str,
@@ -807,6 +848,7 @@ pub(crate) fn query_unit(
 			}
 		}
 	}
+	#[cfg(feature = "currency")]
 	if let Ok(idx) = CURRENCY_IDENTIFIERS.binary_search(
 		&if case_sensitive {
 			ident.to_string()
@@ -866,7 +908,25 @@ const DEFAULT_UNITS: &[(&str, &str)] = &[
 	("liter", "meter^3"),
 ];
 
-pub(crate) fn lookup_default_unit(base_units: &str) -> Option<&str> {
+const IMPERIAL_DEFAULT_UNITS: &[(&str, &str)] = &[
+	("foot", "meter^1"),
+	("sqft", "meter^2"),
+	("gallon", "meter^3"),
+	("pound", "kilogram^1"),
+	("lbf", "kilogram^1 meter^1 second^-2"),
+	("psi", "kilogram^1 meter^-1 second^-2"),
+	("btu", "kilogram^1 meter^2 second^-2"),
+];
+
+pub(crate) fn lookup_default_unit(base_units: &str, unit_system: UnitSystem) -> Option<&str> {
+	if unit_system == UnitSystem::Imperial {
+		if let Some((unit_name, _)) = IMPERIAL_DEFAULT_UNITS
+			.iter()
+			.find(|(_, base)| *base == base_units)
+		{
+			return Some(unit_name);
+		}
+	}
 	if let Some((unit_name, _)) = DEFAULT_UNITS.iter().find(|(_, base)| *base == base_units) {
 		return Some(unit_name);
 	}
@@ -882,10 +942,11 @@ pub(crate) fn lookup_default_unit(base_units: &str) -> Option<&str> {
 /// used for implicit unit addition, e.g. 5'5 -> 5'5"
 pub(crate) const IMPLICIT_UNIT_MAP: &[(&str, &str)] = &[("'", "\""), ("foot", "inches")];
 
-#[cfg(test)]
+#[cfg(all(test, any(feature = "transcendental", feature = "currency")))]
 mod tests {
 	use super::*;
 
+	#[cfg(feature = "transcendental")]
 	fn test_str(s: &str, ctx: &mut crate::Context) {
 		if s.is_empty() || s == "'" || s == "\"" {
 			return;
@@ -894,6 +955,7 @@ mod tests {
 		crate::evaluate(s, ctx).unwrap();
 	}
 
+	#[cfg(feature = "transcendental")]
 	fn test_group(group: &[UnitTuple]) {
 		let mut ctx = crate::Context::new();
 		ctx.set_exchange_rate_handler_v1(crate::test_utils::dummy_currency_handler);
@@ -904,6 +966,7 @@ mod tests {
 	}
 
 	#[test]
+	#[cfg(feature = "transcendental")]
 	fn test_all_units() {
 		for &group in ALL_UNIT_DEFS {
 			test_group(group);
@@ -911,6 +974,7 @@ mod tests {
 	}
 
 	#[test]
+	#[cfg(feature = "currency")]
 	fn currencies_sorted() {
 		let currencies = CURRENCY_IDENTIFIERS.to_vec();
 		let mut sorted = currencies.clone();
@@ -919,6 +983,7 @@ mod tests {
 	}
 
 	#[test]
+	#[cfg(feature = "currency")]
 	fn lowercase_currency() {
 		assert!(query_unit("usd", true, true).is_none());
 		assert!(query_unit("usd", true, false).is_some());
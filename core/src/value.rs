@@ -1,10 +1,14 @@
 use crate::ast::Bop;
-use crate::date::{Date, DayOfWeek, Month};
+use crate::date::{Date, DayOfWeek, Month, TimeOfDay, TimeZone, ZonedDateTime};
 use crate::error::{FendError, Interrupt};
+use crate::interval::Interval;
+use crate::matrix::Matrix;
 use crate::num::{Base, FormattingStyle, Number};
 use crate::result::FResult;
 use crate::scope::{compare_option_arc_scope, Scope};
 use crate::serialize::{Deserialize, Serialize};
+use crate::symbolic::Expression;
+use crate::vector::{compare_quantities, CoordinateSystem, Quaternion, Vector};
 use crate::{ast::Expr, ident::Ident};
 use crate::{date, Attrs, Span, SpanKind};
 use std::borrow::Cow;
@@ -16,6 +20,9 @@ use std::{
 
 pub(crate) mod built_in_function;
 
+/// Incremented whenever the canonical value representation changes
+const CANONICAL_VERSION: u8 = 1;
+
 use built_in_function::BuiltInFunction;
 
 #[derive(Clone)]
@@ -28,13 +35,27 @@ pub(crate) enum Value {
 	Base(Base),
 	// user-defined function with a named parameter
 	Fn(Ident, Box<Expr>, Option<Arc<Scope>>),
-	Object(Vec<(Cow<'static, str>, Box<Value>)>),
+	Object(Vec<(Cow<'static, str>, Box<Self>)>),
 	String(Cow<'static, str>),
 	Bool(bool),
 	Unit, // unit value `()`
 	Month(date::Month),
 	DayOfWeek(date::DayOfWeek),
 	Date(date::Date),
+	TimeOfDay(date::TimeOfDay),
+	TimeZone(date::TimeZone),
+	ZonedDateTime(date::ZonedDateTime),
+	Vector(Vector),
+	Quaternion(Box<Quaternion>),
+	Matrix(Box<Matrix>),
+	// expression in unbound variables, e.g. `2x + 1`
+	Symbolic(Expression),
+	Interval(Box<Interval>),
+	// a number split into several units, e.g. `3 h 20 min`
+	MixedUnits(Vec<Number>),
+	// values that can't be stored in a vector because they aren't all
+	// numbers, e.g. the roots `[1 - sqrt(2), 1 + sqrt(2)]`
+	List(Vec<Self>),
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -62,9 +83,9 @@ impl Value {
 			(Self::Format(a), Self::Format(b)) => c(a == b),
 			(Self::Dp, Self::Dp) | (Self::Sf, Self::Sf) | (Self::Unit, Self::Unit) => c(true),
 			(Self::Base(a), Self::Base(b)) => c(a == b),
-			(Self::Fn(a1, a2, a3), Self::Fn(b1, b2, b3)) => {
-				c(a1 == b1 && a2.compare(b2, int)? && compare_option_arc_scope(a3, b3, int)?)
-			}
+			(Self::Fn(a1, a2, a3), Self::Fn(b1, b2, b3)) => c(a1 == b1
+				&& a2.compare(b2, int)?
+				&& compare_option_arc_scope(a3.as_ref(), b3.as_ref(), int)?),
 			(Self::Object(a), Self::Object(b)) => {
 				if a.len() != b.len() {
 					return Ok(None);
@@ -85,10 +106,41 @@ impl Value {
 			(Self::Month(a), Self::Month(b)) => c(a == b),
 			(Self::DayOfWeek(a), Self::DayOfWeek(b)) => c(a == b),
 			(Self::Date(a), Self::Date(b)) => c(a == b),
+			(Self::TimeOfDay(a), Self::TimeOfDay(b)) => c(a == b),
+			(Self::TimeZone(a), Self::TimeZone(b)) => c(a == b),
+			(Self::ZonedDateTime(a), Self::ZonedDateTime(b)) => c(a == b),
+			(Self::MixedUnits(a), Self::MixedUnits(b)) => {
+				if a.len() != b.len() {
+					return Ok(None);
+				}
+				for (a, b) in a.iter().zip(b) {
+					if a.compare(b, int)? != Some(cmp::Ordering::Equal) {
+						return Ok(None);
+					}
+				}
+				Some(cmp::Ordering::Equal)
+			}
+			(Self::List(a), Self::List(b)) => {
+				if a.len() != b.len() {
+					return Ok(None);
+				}
+				for (a, b) in a.iter().zip(b) {
+					if a.compare(b, int)? != Some(cmp::Ordering::Equal) {
+						return Ok(None);
+					}
+				}
+				Some(cmp::Ordering::Equal)
+			}
+			(Self::Vector(a), Self::Vector(b)) => c(a.compare(b, int)?),
+			(Self::Quaternion(a), Self::Quaternion(b)) => c(a.compare(b, int)?),
+			(Self::Matrix(a), Self::Matrix(b)) => c(a.compare(b, int)?),
+			(Self::Symbolic(a), Self::Symbolic(b)) => c(a.compare(b, int)?),
+			(Self::Interval(a), Self::Interval(b)) => c(a.compare(b, int)?),
 			_ => None,
 		})
 	}
 
+	#[allow(clippy::too_many_lines)]
 	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
 		match self {
 			Self::Num(n) => {
@@ -150,6 +202,52 @@ impl Value {
 				13u8.serialize(write)?;
 				d.serialize(write)?;
 			}
+			Self::Vector(v) => {
+				14u8.serialize(write)?;
+				v.serialize(write)?;
+			}
+			Self::Quaternion(q) => {
+				15u8.serialize(write)?;
+				q.serialize(write)?;
+			}
+			Self::Symbolic(e) => {
+				16u8.serialize(write)?;
+				e.serialize(write)?;
+			}
+			Self::Interval(i) => {
+				17u8.serialize(write)?;
+				i.serialize(write)?;
+			}
+			Self::TimeOfDay(t) => {
+				18u8.serialize(write)?;
+				t.serialize(write)?;
+			}
+			Self::MixedUnits(parts) => {
+				19u8.serialize(write)?;
+				parts.len().serialize(write)?;
+				for part in parts {
+					part.serialize(write)?;
+				}
+			}
+			Self::TimeZone(z) => {
+				20u8.serialize(write)?;
+				z.serialize(write)?;
+			}
+			Self::ZonedDateTime(t) => {
+				21u8.serialize(write)?;
+				t.serialize(write)?;
+			}
+			Self::Matrix(m) => {
+				22u8.serialize(write)?;
+				m.serialize(write)?;
+			}
+			Self::List(values) => {
+				23u8.serialize(write)?;
+				values.len().serialize(write)?;
+				for value in values {
+					value.serialize(write)?;
+				}
+			}
 		}
 		Ok(())
 	}
@@ -188,6 +286,30 @@ impl Value {
 			11 => Self::Month(Month::deserialize(read)?),
 			12 => Self::DayOfWeek(DayOfWeek::deserialize(read)?),
 			13 => Self::Date(Date::deserialize(read)?),
+			14 => Self::Vector(Vector::deserialize(read)?),
+			15 => Self::Quaternion(Box::new(Quaternion::deserialize(read)?)),
+			16 => Self::Symbolic(Expression::deserialize(read)?),
+			17 => Self::Interval(Box::new(Interval::deserialize(read)?)),
+			18 => Self::TimeOfDay(TimeOfDay::deserialize(read)?),
+			19 => Self::MixedUnits({
+				let len = usize::deserialize(read)?;
+				let mut parts = Vec::with_capacity(len);
+				for _ in 0..len {
+					parts.push(Number::deserialize(read)?);
+				}
+				parts
+			}),
+			20 => Self::TimeZone(TimeZone::deserialize(read)?),
+			21 => Self::ZonedDateTime(ZonedDateTime::deserialize(read)?),
+			22 => Self::Matrix(Box::new(Matrix::deserialize(read)?)),
+			23 => Self::List({
+				let len = usize::deserialize(read)?;
+				let mut values = Vec::with_capacity(len);
+				for _ in 0..len {
+					values.push(Self::deserialize(read)?);
+				}
+				values
+			}),
 			_ => return Err(FendError::DeserializationError),
 		})
 	}
@@ -207,6 +329,15 @@ impl Value {
 			Self::Month(_) => "month",
 			Self::DayOfWeek(_) => "day of week",
 			Self::Date(_) => "date",
+			Self::TimeOfDay(_) | Self::ZonedDateTime(_) => "time",
+			Self::TimeZone(_) => "time zone",
+			Self::MixedUnits(_) => "mixed units",
+			Self::Vector(_) => "vector",
+			Self::List(_) => "list",
+			Self::Quaternion(_) => "quaternion",
+			Self::Matrix(_) => "matrix",
+			Self::Symbolic(_) => "symbolic expression",
+			Self::Interval(_) => "interval",
 		}
 	}
 
@@ -225,10 +356,221 @@ impl Value {
 		}
 	}
 
+	/// Applies `f` to a number, to both endpoints of an interval, or to each
+	/// entry of a vector or matrix
+	pub(crate) fn map_numbers<I: Interrupt>(
+		self,
+		f: impl Fn(Number) -> FResult<Number>,
+		int: &I,
+	) -> FResult<Self> {
+		match self {
+			Self::Interval(i) => Ok(Self::Interval(Box::new(i.map(f, int)?))),
+			Self::Vector(v) => Ok(Self::Vector(v.map(f)?)),
+			Self::Matrix(m) => Ok(Self::Matrix(Box::new(m.map(f)?))),
+			other => Ok(Self::Num(Box::new(f(other.expect_num()?)?))),
+		}
+	}
+
+	pub(crate) fn expect_vector(self) -> FResult<Vector> {
+		match self {
+			Self::Vector(v) => v.expect_cartesian(),
+			_ => Err(FendError::ExpectedAVector(self.type_name())),
+		}
+	}
+
+	fn expect_matrix(self) -> FResult<Matrix> {
+		match self {
+			Self::Matrix(m) => Ok(*m),
+			_ => Err(FendError::ExpectedAMatrix(self.type_name())),
+		}
+	}
+
+	/// Accepts a vector, or a range of integers like `1..10`
+	fn expect_list<I: Interrupt>(self, int: &I) -> FResult<Vector> {
+		match self {
+			Self::Interval(i) => i.into_list(int),
+			other => other.expect_vector(),
+		}
+	}
+
+	fn expect_quaternion<I: Interrupt>(self, int: &I) -> FResult<Quaternion> {
+		match self {
+			Self::Quaternion(q) => Ok(*q),
+			Self::Vector(v) => Quaternion::from_vector(v.expect_cartesian()?, int),
+			_ => Err(FendError::ExpectedAVector(self.type_name())),
+		}
+	}
+
+	pub(crate) fn is_vector_or_quaternion(&self) -> bool {
+		matches!(self, Self::Vector(_) | Self::Quaternion(_))
+	}
+
+	pub(crate) fn is_matrix(&self) -> bool {
+		matches!(self, Self::Matrix(_))
+	}
+
+	pub(crate) fn is_symbolic(&self) -> bool {
+		matches!(self, Self::Symbolic(_))
+	}
+
+	pub(crate) fn is_interval(&self) -> bool {
+		matches!(self, Self::Interval(_))
+	}
+
 	pub(crate) fn is_unit(&self) -> bool {
 		matches!(self, Self::Unit)
 	}
 
+	pub(crate) fn result_info<I: Interrupt>(
+		&self,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<crate::ResultInfo> {
+		let canonical = self.canonical(int)?;
+		let table = self.to_table(attrs, ctx, int)?;
+		Ok(match self {
+			Self::Num(n) => crate::ResultInfo {
+				is_boolean: false,
+				rational: n.exact_rational(int)?,
+				units: n.unit_components(int)?,
+				base_units: n.base_unit_components(int)?,
+				canonical,
+				table,
+				..crate::ResultInfo::default()
+			},
+			Self::Bool(_) => crate::ResultInfo {
+				is_boolean: true,
+				canonical,
+				..crate::ResultInfo::default()
+			},
+			_ => crate::ResultInfo {
+				canonical,
+				table,
+				..crate::ResultInfo::default()
+			},
+		})
+	}
+
+	/// Returns this value as a table if it consists of rows of values:
+	/// - matrices have one row per matrix row, with numbered columns
+	/// - objects whose properties are lists of the same length have one
+	///   column per property
+	/// - other objects have one row per property
+	///
+	/// Lists like `[1, 2, 3]` aren't tables since they only have a single
+	/// column. Values that span multiple lines can't be shown in a table.
+	fn to_table<I: Interrupt>(
+		&self,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<Option<crate::Table>> {
+		let mut format_cell = |value: Self| value.format_to_plain_string(0, attrs, ctx, int);
+		let (headers, rows) = match self {
+			Self::Matrix(m) => {
+				let columns = m.rows()[0].len();
+				let headers = std::iter::once("#".to_string())
+					.chain((1..=columns).map(|i| i.to_string()))
+					.collect();
+				let mut rows = vec![];
+				for (i, row) in m.rows().iter().enumerate() {
+					let mut cells = vec![(i + 1).to_string()];
+					for n in row {
+						cells.push(format_cell(Self::Num(Box::new(n.clone())))?);
+					}
+					rows.push(cells);
+				}
+				(headers, rows)
+			}
+			Self::Object(kv) => {
+				if let Some(columns) = Self::object_columns(kv) {
+					let headers = kv.iter().map(|(k, _)| k.to_string()).collect();
+					let mut rows = vec![vec![]; columns[0].len()];
+					for column in columns {
+						for (row, n) in rows.iter_mut().zip(column) {
+							row.push(format_cell(Self::Num(Box::new(n)))?);
+						}
+					}
+					(headers, rows)
+				} else {
+					let mut rows = vec![];
+					for (k, v) in kv {
+						rows.push(vec![k.to_string(), format_cell((**v).clone())?]);
+					}
+					(vec!["name".to_string(), "value".to_string()], rows)
+				}
+			}
+			_ => return Ok(None),
+		};
+		if rows.iter().flatten().any(|cell| cell.contains('\n')) {
+			return Ok(None);
+		}
+		Ok(Some(crate::Table { headers, rows }))
+	}
+
+	/// Returns the properties of an object as columns if they are all lists
+	/// of the same length, e.g. `{ x: [1, 2], y: [3, 4] }`
+	fn object_columns(kv: &[(Cow<'static, str>, Box<Self>)]) -> Option<Vec<Vec<Number>>> {
+		let mut columns: Vec<Vec<Number>> = vec![];
+		for (_, v) in kv {
+			let Self::Vector(v) = &**v else {
+				return None;
+			};
+			if !v.is_cartesian() {
+				return None;
+			}
+			let column = v.clone().into_cartesian_components().ok()?;
+			if columns
+				.first()
+				.is_some_and(|first| first.len() != column.len())
+			{
+				return None;
+			}
+			columns.push(column);
+		}
+		if columns.is_empty() {
+			return None;
+		}
+		Some(columns)
+	}
+
+	/// Returns the canonical text representation of this value, as described
+	/// in `FendResult::canonical_value`. Only numbers, strings, booleans and
+	/// `()` can be represented.
+	fn canonical<I: Interrupt>(&self, int: &I) -> FResult<Option<String>> {
+		let mut result = format!("fend{CANONICAL_VERSION};");
+		match self {
+			Self::Num(n) => {
+				let Some((exact, rational, base_units)) = n.in_base_units(int)? else {
+					return Ok(None);
+				};
+				write!(
+					result,
+					"number;{};{}{}/{};",
+					if exact { "exact" } else { "approx" },
+					if rational.is_negative() { "-" } else { "" },
+					rational.numerator(),
+					rational.denominator(),
+				)?;
+				for (i, unit) in base_units.iter().enumerate() {
+					if i > 0 {
+						result.push(' ');
+					}
+					write!(result, "{}^{}", unit.name(), unit.exponent())?;
+				}
+			}
+			Self::Bool(b) => write!(result, "bool;{b}")?,
+			Self::String(s) => {
+				result.push_str("string;");
+				crate::json::escape_string(s, &mut result);
+			}
+			Self::Unit => result.push_str("unit"),
+			_ => return Ok(None),
+		}
+		Ok(Some(result))
+	}
+
 	pub(crate) fn handle_num(
 		self,
 		eval_fn: impl FnOnce(Number) -> FResult<Number>,
@@ -243,6 +585,19 @@ impl Value {
 		})
 	}
 
+	/// Applies `lazy_fn` to the result of this function
+	pub(crate) fn wrap_with_expr(
+		self,
+		lazy_fn: impl FnOnce(Box<Expr>) -> Expr,
+		scope: Option<Arc<Scope>>,
+	) -> FResult<Self> {
+		Ok(match self {
+			Self::BuiltInFunction(f) => f.wrap_with_expr(lazy_fn, scope),
+			Self::Fn(param, expr, scope) => Self::Fn(param, Box::new(lazy_fn(expr)), scope),
+			_ => return Err(FendError::ExpectedANumber),
+		})
+	}
+
 	pub(crate) fn handle_two_nums<F1: FnOnce(Box<Expr>) -> Expr, F2: FnOnce(Box<Expr>) -> Expr>(
 		self,
 		rhs: Self,
@@ -295,6 +650,30 @@ impl Value {
 						self_.format_to_plain_string(0, attrs, context, int)?,
 					));
 				}
+				if other.is_matrix() {
+					return crate::matrix::evaluate_bop(Bop::Mul, Self::Num(n), other, scope, int);
+				}
+				if other.is_vector_or_quaternion() {
+					return crate::vector::evaluate_bop(Bop::Mul, Self::Num(n), other, scope, int);
+				}
+				if other.is_interval() {
+					return crate::interval::evaluate_bop(
+						Bop::Mul,
+						Self::Num(n),
+						other,
+						scope,
+						int,
+					);
+				}
+				if other.is_symbolic() {
+					return crate::symbolic::evaluate_bop(
+						Bop::Mul,
+						Self::Num(n),
+						other,
+						scope,
+						int,
+					);
+				}
 				let n2 = n.clone();
 				other.handle_num(
 					|x| n.mul(x, int),
@@ -302,8 +681,48 @@ impl Value {
 					scope,
 				)?
 			}
-			Self::BuiltInFunction(func) => {
-				Self::apply_built_in_function(func, other, scope, attrs, context, int)?
+			Self::BuiltInFunction(func) => match (func, other) {
+				(BuiltInFunction::LinReg | BuiltInFunction::Correlation, Expr::Vector(args)) => {
+					Self::apply_regression(func, args, scope.as_ref(), attrs, context, int)?
+				}
+				(BuiltInFunction::Npv, Expr::Vector(args)) => {
+					let [rate, cashflows] = <[Expr; 2]>::try_from(args)
+						.map_err(|v| FendError::VectorDimensionMismatch(v.len(), 2))?;
+					let mut eval =
+						|arg| crate::ast::evaluate(arg, scope.clone(), attrs, context, int);
+					let rate = eval(rate)?.expect_num()?;
+					let cashflows = eval(cashflows)?.expect_vector()?;
+					Self::Num(Box::new(crate::finance::npv(rate, cashflows, int)?))
+				}
+				(BuiltInFunction::Solve, Expr::Vector(args)) => {
+					Self::apply_solve(args, scope.as_ref(), attrs, context, int)?
+				}
+				(BuiltInFunction::Diff, Expr::Vector(args)) => {
+					Self::apply_diff(args, scope.as_ref(), attrs, context, int)?
+				}
+				(BuiltInFunction::Map | BuiltInFunction::Filter, Expr::Vector(args)) => {
+					Self::apply_list_function(func, args, scope.as_ref(), attrs, context, int)?
+				}
+				(
+					BuiltInFunction::MemoryAdd
+					| BuiltInFunction::MemorySubtract
+					| BuiltInFunction::Store
+					| BuiltInFunction::Recall,
+					arg,
+				) => Self::apply_memory_function(func, arg, scope, attrs, context, int)?,
+				(func, other) => {
+					Self::apply_built_in_function(func, other, scope, attrs, context, int)?
+				}
+			},
+			// implicit multiplication, e.g. `x y`
+			Self::Symbolic(e) if apply_mul_handling == ApplyMulHandling::Both => {
+				let other = crate::ast::evaluate(other, scope.clone(), attrs, context, int)?;
+				crate::symbolic::evaluate_bop(Bop::Mul, Self::Symbolic(e), other, scope, int)?
+			}
+			// e.g. `(5 ± 0.1) m`
+			Self::Interval(i) if apply_mul_handling == ApplyMulHandling::Both => {
+				let other = crate::ast::evaluate(other, scope.clone(), attrs, context, int)?;
+				crate::interval::evaluate_bop(Bop::Mul, Self::Interval(i), other, scope, int)?
 			}
 			Self::Fn(param, expr, custom_scope) => {
 				let new_scope = Scope::with_variable(param, other, scope, custom_scope);
@@ -313,6 +732,9 @@ impl Value {
 		})
 	}
 
+	#[allow(clippy::too_many_lines)]
+	// `scope` is only needed by the trigonometric functions
+	#[cfg_attr(not(feature = "transcendental"), allow(clippy::needless_pass_by_value))]
 	fn apply_built_in_function<I: Interrupt>(
 		func: BuiltInFunction,
 		arg: Expr,
@@ -321,25 +743,82 @@ impl Value {
 		context: &mut crate::Context,
 		int: &I,
 	) -> FResult<Self> {
-		let arg = crate::ast::evaluate(arg, scope.clone(), attrs, context, int)?;
+		let arg = if matches!(func, BuiltInFunction::Expand) {
+			// unbound variables are kept symbolic, e.g. in `expand((x + 1)^2)`
+			crate::symbolic::with_variables(context, |context| {
+				crate::ast::evaluate(arg, scope.clone(), attrs, context, int)
+			})?
+		} else {
+			crate::ast::evaluate(arg, scope.clone(), attrs, context, int)?
+		};
+		// e.g. `sin(x)` in `diff(sin(x), x)`
+		if let (Self::Symbolic(e), false) = (&arg, func == BuiltInFunction::Expand) {
+			return crate::symbolic::apply_function(func, e.clone(), int);
+		}
+		if let Some(res) =
+			Self::apply_vector_function(func, arg.clone(), scope.as_ref(), attrs, context, int)?
+		{
+			return Ok(res);
+		}
 		Ok(Self::Num(Box::new(match func {
 			BuiltInFunction::Approximately => arg.expect_num()?.make_approximate(),
 			BuiltInFunction::Abs => arg.expect_num()?.abs(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Sin => arg.expect_num()?.sin(scope, attrs, context, int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Cos => arg.expect_num()?.cos(scope, attrs, context, int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Tan => arg.expect_num()?.tan(scope, attrs, context, int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Asin => arg.expect_num()?.asin(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Acos => arg.expect_num()?.acos(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Atan => arg.expect_num()?.atan(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Sinh => arg.expect_num()?.sinh(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Cosh => arg.expect_num()?.cosh(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Tanh => arg.expect_num()?.tanh(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Asinh => arg.expect_num()?.asinh(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Acosh => arg.expect_num()?.acosh(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Atanh => arg.expect_num()?.atanh(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Ln => arg.expect_num()?.ln(int)?,
+			#[cfg(feature = "transcendental")]
+			BuiltInFunction::Exp => {
+				let power = Expr::Bop(
+					Bop::Pow,
+					Box::new(Expr::Ident(Ident::new_str("e"))),
+					Box::new(Expr::Literal(arg)),
+				);
+				return crate::ast::evaluate(power, scope, attrs, context, int);
+			}
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Log2 => arg.expect_num()?.log2(int)?,
+			#[cfg(feature = "transcendental")]
 			BuiltInFunction::Log10 => arg.expect_num()?.log10(int)?,
+			#[cfg(not(feature = "transcendental"))]
+			BuiltInFunction::Sin
+			| BuiltInFunction::Cos
+			| BuiltInFunction::Tan
+			| BuiltInFunction::Asin
+			| BuiltInFunction::Acos
+			| BuiltInFunction::Atan
+			| BuiltInFunction::Sinh
+			| BuiltInFunction::Cosh
+			| BuiltInFunction::Tanh
+			| BuiltInFunction::Asinh
+			| BuiltInFunction::Acosh
+			| BuiltInFunction::Atanh
+			| BuiltInFunction::Ln
+			| BuiltInFunction::Exp
+			| BuiltInFunction::Log2
+			| BuiltInFunction::Log10 => return Err(FendError::FeatureDisabled("transcendental")),
 			BuiltInFunction::Base => {
 				let n: u8 = arg
 					.expect_num()?
@@ -359,9 +838,454 @@ impl Value {
 			BuiltInFunction::Ceil => arg.expect_num()?.ceil(int)?,
 			BuiltInFunction::Round => arg.expect_num()?.round(int)?,
 			BuiltInFunction::Fibonacci => arg.expect_num()?.fibonacci(int)?,
+			BuiltInFunction::Gamma => crate::probability::gamma(arg.expect_num()?, int)?,
+			BuiltInFunction::IsPrime => return Ok(Self::Bool(arg.expect_num()?.is_prime(int)?)),
+			BuiltInFunction::NextPrime => arg.expect_num()?.next_prime(int)?,
+			BuiltInFunction::PrevPrime => arg.expect_num()?.prev_prime(int)?,
+			BuiltInFunction::Factor => {
+				let factors = arg.expect_num()?.prime_factors(int)?;
+				if factors.is_empty() {
+					return Ok(Self::String("1".into()));
+				}
+				let mut result = String::new();
+				for (i, (prime, count)) in factors.into_iter().enumerate() {
+					if i > 0 {
+						result.push_str(" * ");
+					}
+					write!(result, "{}", prime.format(context, int)?)?;
+					if count > 1 {
+						write!(result, "^{count}")?;
+					}
+				}
+				return Ok(Self::String(result.into()));
+			}
+			BuiltInFunction::ApproxEq => {
+				let [a, b, tolerance] = arg.expect_vector()?.into_array()?;
+				return Ok(Self::Bool(a.approx_eq(b, Some(tolerance), int)?));
+			}
+			BuiltInFunction::DivMod => {
+				let [a, b] = arg.expect_vector()?.into_array()?;
+				let quotient = a.clone().floor_div(b.clone(), int)?;
+				let remainder = a.modulo(b, int)?;
+				return Ok(Self::Vector(Vector::new(vec![quotient, remainder])));
+			}
+			BuiltInFunction::Root => {
+				let [x, n] = arg.expect_vector()?.into_array()?;
+				x.pow(Number::from(1).div(n, int)?, int)?
+			}
+			BuiltInFunction::Isqrt => arg.expect_num()?.isqrt(int)?,
+			BuiltInFunction::Wrap | BuiltInFunction::WrapSigned => {
+				let [x, bits] = arg.expect_vector()?.into_array()?;
+				x.wrap_to_width(bits, func == BuiltInFunction::WrapSigned, int)?
+			}
+			BuiltInFunction::PowMod => {
+				let [base, exponent, modulus] = arg.expect_vector()?.into_array()?;
+				base.pow_mod(exponent, modulus, int)?
+			}
+			BuiltInFunction::ModInv => {
+				let [a, modulus] = arg.expect_vector()?.into_array()?;
+				a.mod_inverse(modulus, int)?
+			}
+			BuiltInFunction::Clamp => {
+				let [x, lower, upper] = arg.expect_vector()?.into_array()?;
+				if compare_quantities(&lower, &upper, int)? == cmp::Ordering::Greater {
+					return Err(FendError::ClampBoundsReversed);
+				}
+				if compare_quantities(&x, &lower, int)? == cmp::Ordering::Less {
+					lower
+				} else if compare_quantities(&x, &upper, int)? == cmp::Ordering::Greater {
+					upper
+				} else {
+					x
+				}
+			}
+			BuiltInFunction::FromJdn | BuiltInFunction::FromMjd => {
+				let modified = func == BuiltInFunction::FromMjd;
+				return Ok(Self::Date(Date::from_day_number(
+					arg.expect_num()?,
+					modified,
+					int,
+				)?));
+			}
+			BuiltInFunction::Norm
+			| BuiltInFunction::Normalize
+			| BuiltInFunction::Polar
+			| BuiltInFunction::Spherical
+			| BuiltInFunction::Cylindrical
+			| BuiltInFunction::Quaternion
+			| BuiltInFunction::FromEuler
+			| BuiltInFunction::ToEuler
+			| BuiltInFunction::ToAxisAngle
+			| BuiltInFunction::LinReg
+			| BuiltInFunction::Correlation
+			| BuiltInFunction::Npv
+			| BuiltInFunction::Min
+			| BuiltInFunction::Max
+			| BuiltInFunction::Sum
+			| BuiltInFunction::Sort
+			| BuiltInFunction::Product
+			| BuiltInFunction::Median
+			| BuiltInFunction::Mode
+			| BuiltInFunction::Variance
+			| BuiltInFunction::StdDev
+			| BuiltInFunction::Map
+			| BuiltInFunction::Filter
+			| BuiltInFunction::Solve
+			| BuiltInFunction::Diff => return Err(FendError::ExpectedAVector("number")),
+			BuiltInFunction::MemoryAdd
+			| BuiltInFunction::MemorySubtract
+			| BuiltInFunction::Store
+			| BuiltInFunction::Recall => unreachable!("handled by `apply_memory_function`"),
+			BuiltInFunction::Transpose
+			| BuiltInFunction::Determinant
+			| BuiltInFunction::Inverse => unreachable!("handled by `apply_vector_function`"),
+			BuiltInFunction::NormPdf
+			| BuiltInFunction::NormCdf
+			| BuiltInFunction::InvNorm
+			| BuiltInFunction::BinomPmf
+			| BuiltInFunction::BinomCdf
+			| BuiltInFunction::PoissonPmf
+			| BuiltInFunction::PoissonCdf
+			| BuiltInFunction::TPdf
+			| BuiltInFunction::TCdf
+			| BuiltInFunction::InvT => crate::probability::evaluate(func, arg, int)?,
+			BuiltInFunction::Expand => return crate::symbolic::expand(arg, int),
+			BuiltInFunction::Amortize => return crate::finance::evaluate_schedule(arg, int),
+			BuiltInFunction::Compound | BuiltInFunction::Pmt => {
+				crate::finance::evaluate(func, arg, int)?
+			}
 		})))
 	}
 
+	/// Applies `linreg` or `correlation` to the data vectors `xs` and `ys`
+	fn apply_regression<I: Interrupt>(
+		func: BuiltInFunction,
+		args: Vec<Expr>,
+		scope: Option<&Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Self> {
+		let mut vectors = vec![];
+		for arg in args {
+			let arg = crate::ast::evaluate(arg, scope.cloned(), attrs, context, int)?;
+			vectors.push(arg.expect_vector()?);
+		}
+		let [xs, ys] = <[Vector; 2]>::try_from(vectors)
+			.map_err(|v| FendError::VectorDimensionMismatch(v.len(), 2))?;
+		if func == BuiltInFunction::Correlation {
+			return Ok(Self::Num(Box::new(xs.correlation(&ys, int)?)));
+		}
+		let (gradient, intercept, r_squared) = xs.linear_regression(&ys, int)?;
+		Ok(Self::Object(vec![
+			("slope".into(), Box::new(Self::Num(Box::new(gradient)))),
+			("intercept".into(), Box::new(Self::Num(Box::new(intercept)))),
+			("r_squared".into(), Box::new(Self::Num(Box::new(r_squared)))),
+		]))
+	}
+
+	/// Applies `solve(A, b)` to a matrix `A` and a vector `b`
+	fn apply_solve<I: Interrupt>(
+		args: Vec<Expr>,
+		scope: Option<&Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Self> {
+		let [a, b] = <[Expr; 2]>::try_from(args)
+			.map_err(|v| FendError::VectorDimensionMismatch(v.len(), 2))?;
+		// e.g. `solve(2x + 3 = 11, x)`
+		if let (Expr::Equation(lhs, rhs), equation_scope) = unevaluated_arg(a.clone(), scope) {
+			let Expr::Ident(var) = unevaluated_arg(b, scope).0 else {
+				return Err(FendError::ExpectedAVariableToSolveFor);
+			};
+			return crate::formula::solve_for_roots(
+				*lhs,
+				*rhs,
+				var.as_str(),
+				equation_scope,
+				attrs,
+				context,
+				int,
+			);
+		}
+		let a = crate::ast::evaluate(a, scope.cloned(), attrs, context, int)?.expect_matrix()?;
+		let b = crate::ast::evaluate(b, scope.cloned(), attrs, context, int)?.expect_vector()?;
+		Ok(Self::Vector(a.solve(b, int)?))
+	}
+
+	/// Applies `diff(f, x)`. `x` is kept symbolic while evaluating `f`, even
+	/// if it is also defined as a variable or unit (like `t` for tonnes).
+	fn apply_diff<I: Interrupt>(
+		args: Vec<Expr>,
+		scope: Option<&Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Self> {
+		let [f, var] = <[Expr; 2]>::try_from(args)
+			.map_err(|v| FendError::VectorDimensionMismatch(v.len(), 2))?;
+		let Expr::Ident(var) = unevaluated_arg(var, scope).0 else {
+			return Err(FendError::ExpectedAVariable);
+		};
+		let (f, f_scope) = unevaluated_arg(f, scope);
+		let f_scope = Scope::with_variable(
+			var.clone(),
+			Expr::Literal(crate::symbolic::variable(var.as_str())),
+			None,
+			f_scope,
+		);
+		let f = crate::symbolic::with_variables(context, |context| {
+			crate::ast::evaluate(f, Some(Arc::new(f_scope)), attrs, context, int)
+		})?;
+		crate::symbolic::differentiate(f, var.as_str(), int)
+	}
+
+	/// Applies `map(f, list)` or `filter(f, list)`, where `list` is a vector
+	/// or a range of integers
+	fn apply_list_function<I: Interrupt>(
+		func: BuiltInFunction,
+		args: Vec<Expr>,
+		scope: Option<&Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Self> {
+		let [f, list] = <[Expr; 2]>::try_from(args)
+			.map_err(|v| FendError::VectorDimensionMismatch(v.len(), 2))?;
+		let f = crate::ast::evaluate(f, scope.cloned(), attrs, context, int)?;
+		let list = crate::ast::evaluate(list, scope.cloned(), attrs, context, int)?;
+		let mut result = vec![];
+		for x in list.expect_list(int)?.into_cartesian_components()? {
+			let y = f.clone().apply(
+				Expr::Literal(Self::Num(Box::new(x.clone()))),
+				ApplyMulHandling::OnlyApply,
+				scope.cloned(),
+				attrs,
+				context,
+				int,
+			)?;
+			if func == BuiltInFunction::Map {
+				result.push(y.expect_num()?);
+			} else if y.as_bool()? {
+				result.push(x);
+			}
+		}
+		Ok(Self::Vector(Vector::new(result)))
+	}
+
+	/// Applies functions that use the calculator memory registers: `mplus x`
+	/// and `mminus x` change the unnamed register, and `store(name, x)` and
+	/// `recall(name)` use named registers
+	fn apply_memory_function<I: Interrupt>(
+		func: BuiltInFunction,
+		arg: Expr,
+		scope: Option<Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Self> {
+		// function parameters are resolved to the name they were called with,
+		// e.g. `2 * recall(rate)` is evaluated as `(\x.2 * recall(x)) rate`
+		let register_name = |mut arg: Expr| {
+			let mut scope = scope.clone();
+			loop {
+				arg = match arg {
+					Expr::Parens(inner) => *inner,
+					Expr::Ident(name) => match scope.as_ref().and_then(|s| s.get_expr(&name)) {
+						Some((expr @ (Expr::Ident(_) | Expr::Parens(_)), expr_scope)) => {
+							let expr = expr.clone();
+							scope = expr_scope.cloned();
+							expr
+						}
+						_ => return Ok(name),
+					},
+					_ => return Err(FendError::ExpectedARegisterName),
+				};
+			}
+		};
+		Ok(match (func, arg) {
+			// `store(name, x)` is evaluated as `(\value.store (name = value)) x`, so that
+			// the register name isn't looked up as a variable
+			(BuiltInFunction::Store, Expr::Assign(name, value)) => {
+				let value = crate::ast::evaluate(*value, scope, attrs, context, int)?;
+				context
+					.memory
+					.insert(name.as_str().to_string(), value.clone());
+				value
+			}
+			(BuiltInFunction::Store, arg) => {
+				let param = Ident::new_str("value");
+				let body = Expr::Apply(
+					Box::new(Expr::Literal(Self::BuiltInFunction(BuiltInFunction::Store))),
+					Box::new(Expr::Assign(
+						register_name(arg)?,
+						Box::new(Expr::Ident(param.clone())),
+					)),
+				);
+				Self::Fn(param, Box::new(body), scope)
+			}
+			(BuiltInFunction::Recall, arg) => {
+				let name = register_name(arg)?;
+				context
+					.memory
+					.get(name.as_str())
+					.cloned()
+					.ok_or_else(|| FendError::EmptyMemoryRegister(name.to_string()))?
+			}
+			(func, arg) => {
+				let mut x = crate::ast::evaluate(arg, scope, attrs, context, int)?.expect_num()?;
+				if func == BuiltInFunction::MemorySubtract {
+					x = -x;
+				}
+				let total = match context.memory.get("") {
+					Some(memory) => memory.clone().expect_num()?.add(x, int)?,
+					None => x,
+				};
+				let total = Self::Num(Box::new(total));
+				context.memory.insert(String::new(), total.clone());
+				total
+			}
+		})
+	}
+
+	/// Applies functions that take or return vectors and quaternions. Returns
+	/// `None` for other functions, or if e.g. `conjugate` is applied to a
+	/// number.
+	#[allow(clippy::too_many_lines)]
+	fn apply_vector_function<I: Interrupt>(
+		func: BuiltInFunction,
+		arg: Self,
+		scope: Option<&Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Option<Self>> {
+		let radians = |n: Number| Self::Num(Box::new(n));
+		// ranges like `1..10` can be used as lists, e.g. `sum(1..10)`
+		let arg = match (func, arg) {
+			(
+				BuiltInFunction::Sum
+				| BuiltInFunction::Sort
+				| BuiltInFunction::Product
+				| BuiltInFunction::Mean
+				| BuiltInFunction::Median
+				| BuiltInFunction::Mode
+				| BuiltInFunction::Variance
+				| BuiltInFunction::StdDev,
+				arg @ Self::Interval(_),
+			) => Self::Vector(arg.expect_list(int)?),
+			(_, arg) => arg,
+		};
+		Ok(Some(match (func, arg) {
+			(BuiltInFunction::Norm, Self::Vector(v)) => {
+				Self::Num(Box::new(v.expect_cartesian()?.norm(int)?))
+			}
+			(BuiltInFunction::Norm, Self::Quaternion(q)) => Self::Num(Box::new(q.norm(int)?)),
+			(BuiltInFunction::Normalize, Self::Vector(v)) => {
+				Self::Vector(v.expect_cartesian()?.normalize(int)?)
+			}
+			(BuiltInFunction::Normalize, Self::Quaternion(q)) => {
+				Self::Quaternion(Box::new(q.normalize(int)?))
+			}
+			(BuiltInFunction::Conjugate, Self::Quaternion(q)) => {
+				Self::Quaternion(Box::new(q.conjugate()))
+			}
+			(BuiltInFunction::Polar, Self::Vector(v)) => {
+				Self::Vector(v.with_system(CoordinateSystem::Polar)?)
+			}
+			(BuiltInFunction::Spherical, Self::Vector(v)) => {
+				Self::Vector(v.with_system(CoordinateSystem::Spherical)?)
+			}
+			(BuiltInFunction::Cylindrical, Self::Vector(v)) => {
+				Self::Vector(v.with_system(CoordinateSystem::Cylindrical)?)
+			}
+			(BuiltInFunction::Min, Self::Vector(v)) => Self::Num(Box::new(
+				v.expect_cartesian()?.extremum(cmp::Ordering::Less, int)?,
+			)),
+			(BuiltInFunction::Max, Self::Vector(v)) => Self::Num(Box::new(
+				v.expect_cartesian()?
+					.extremum(cmp::Ordering::Greater, int)?,
+			)),
+			(BuiltInFunction::Sum, Self::Vector(v)) => {
+				Self::Num(Box::new(v.expect_cartesian()?.sum(int)?))
+			}
+			(BuiltInFunction::Sort, Self::Vector(v)) => {
+				Self::Vector(v.expect_cartesian()?.sorted(int)?)
+			}
+			(BuiltInFunction::Product, Self::Vector(v)) => {
+				Self::Num(Box::new(v.expect_cartesian()?.product(int)?))
+			}
+			(BuiltInFunction::Mean, Self::Vector(v)) => {
+				Self::Num(Box::new(v.expect_cartesian()?.mean(int)?))
+			}
+			(BuiltInFunction::Median, Self::Vector(v)) => {
+				Self::Num(Box::new(v.expect_cartesian()?.median(int)?))
+			}
+			(BuiltInFunction::Mode, Self::Vector(v)) => {
+				Self::Num(Box::new(v.expect_cartesian()?.mode(int)?))
+			}
+			(BuiltInFunction::Variance, Self::Vector(v)) => {
+				Self::Num(Box::new(v.expect_cartesian()?.variance(int)?))
+			}
+			(BuiltInFunction::StdDev, Self::Vector(v)) => {
+				Self::Num(Box::new(v.expect_cartesian()?.stddev(int)?))
+			}
+			(
+				BuiltInFunction::Transpose
+				| BuiltInFunction::Determinant
+				| BuiltInFunction::Inverse,
+				arg,
+			) => Self::apply_matrix_function(func, arg, int)?,
+			(BuiltInFunction::Not, Self::Vector(v)) => {
+				let [x, width] = v.into_array()?;
+				Self::Num(Box::new(x.bitwise_not(width, int)?))
+			}
+			(BuiltInFunction::Quaternion, arg) => {
+				Self::Quaternion(Box::new(arg.expect_quaternion(int)?))
+			}
+			(BuiltInFunction::FromEuler, arg) => Self::Quaternion(Box::new(
+				Quaternion::from_euler(arg.expect_vector()?, scope, attrs, context, int)?,
+			)),
+			(BuiltInFunction::ToEuler, arg) => {
+				let [roll, pitch, yaw] = arg.expect_quaternion(int)?.into_euler(int)?;
+				Self::Object(vec![
+					("roll".into(), Box::new(radians(roll))),
+					("pitch".into(), Box::new(radians(pitch))),
+					("yaw".into(), Box::new(radians(yaw))),
+				])
+			}
+			(BuiltInFunction::ToAxisAngle, arg) => {
+				let (axis, angle) = arg.expect_quaternion(int)?.into_axis_angle(int)?;
+				Self::Object(vec![
+					("axis".into(), Box::new(Self::Vector(axis))),
+					("angle".into(), Box::new(radians(angle))),
+				])
+			}
+			_ => return Ok(None),
+		}))
+	}
+
+	/// Applies `transpose`, `det` or `inverse`
+	fn apply_matrix_function<I: Interrupt>(
+		func: BuiltInFunction,
+		arg: Self,
+		int: &I,
+	) -> FResult<Self> {
+		Ok(match (func, arg) {
+			(BuiltInFunction::Transpose, Self::Matrix(m)) => Self::Matrix(Box::new(m.transpose())),
+			// a vector is transposed into a column
+			(BuiltInFunction::Transpose, Self::Vector(v)) => Self::Matrix(Box::new(
+				Matrix::from_rows(vec![v.expect_cartesian()?])?.transpose(),
+			)),
+			(BuiltInFunction::Determinant, Self::Matrix(m)) => {
+				Self::Num(Box::new(m.determinant(int)?))
+			}
+			(BuiltInFunction::Inverse, Self::Matrix(m)) => Self::Matrix(Box::new(m.inverse(int)?)),
+			(_, arg) => return Err(FendError::ExpectedAMatrix(arg.type_name())),
+		})
+	}
+
 	pub(crate) fn format_to_plain_string<I: Interrupt>(
 		&self,
 		indent: usize,
@@ -378,6 +1302,7 @@ impl Value {
 		Ok(res)
 	}
 
+	#[allow(clippy::too_many_lines)]
 	pub(crate) fn format<I: Interrupt>(
 		&self,
 		indent: usize,
@@ -482,11 +1407,60 @@ impl Value {
 				string: d.to_string(),
 				kind: crate::SpanKind::Date,
 			}),
+			Self::TimeOfDay(t) => spans.push(crate::Span {
+				string: t.to_string(),
+				kind: crate::SpanKind::Date,
+			}),
+			Self::TimeZone(z) => spans.push(crate::Span {
+				string: z.to_string(),
+				kind: crate::SpanKind::Date,
+			}),
+			Self::ZonedDateTime(t) => spans.push(crate::Span {
+				string: t.to_string(),
+				kind: crate::SpanKind::Date,
+			}),
+			Self::MixedUnits(parts) => {
+				let formatted = parts
+					.iter()
+					.map(|part| part.format(ctx, int))
+					.collect::<FResult<Vec<_>>>()?;
+				if formatted.iter().any(|part| !part.is_exact()) && attrs.show_approx {
+					spans.push(Span {
+						string: "approx. ".to_string(),
+						kind: SpanKind::Ident,
+					});
+				}
+				let attrs = Attrs {
+					show_approx: false,
+					..attrs
+				};
+				for (i, part) in formatted.into_iter().enumerate() {
+					if i > 0 {
+						spans.push(Span::from_string(" ".to_string()));
+					}
+					part.spans(spans, attrs);
+				}
+			}
+			Self::List(values) => {
+				spans.push(Span::from_string("[".to_string()));
+				for (i, value) in values.iter().enumerate() {
+					if i > 0 {
+						spans.push(Span::from_string(", ".to_string()));
+					}
+					value.format(indent, spans, attrs, ctx, int)?;
+				}
+				spans.push(Span::from_string("]".to_string()));
+			}
+			Self::Vector(v) => v.format(spans, attrs, ctx, int)?,
+			Self::Quaternion(q) => q.format(spans, attrs, ctx, int)?,
+			Self::Matrix(m) => m.format(spans, attrs, ctx, int)?,
+			Self::Symbolic(e) => e.format(spans, attrs, ctx, int)?,
+			Self::Interval(i) => i.format(spans, attrs, ctx, int)?,
 		}
 		Ok(())
 	}
 
-	pub(crate) fn get_object_member(self, key: &Ident) -> FResult<Self> {
+	pub(crate) fn get_object_member<I: Interrupt>(self, key: &Ident, int: &I) -> FResult<Self> {
 		match self {
 			Self::Object(kv) => {
 				for (k, v) in kv {
@@ -497,11 +1471,32 @@ impl Value {
 				Err(FendError::CouldNotFindKeyInObject)
 			}
 			Self::Date(d) => d.get_object_member(key),
+			Self::Vector(v) => v.get_object_member(key),
+			Self::Quaternion(q) => q.get_object_member(key),
+			Self::Interval(i) => i.get_object_member(key, int),
 			_ => Err(FendError::ExpectedAnObject),
 		}
 	}
 }
 
+/// Built-in functions like `diff` have their parameters bound to the
+/// unevaluated arguments, so this looks them up to find e.g. the name of the
+/// variable in `diff(x^2, x)`, together with the scope to evaluate them in.
+/// Parentheses around the argument are removed.
+fn unevaluated_arg(arg: Expr, scope: Option<&Arc<Scope>>) -> (Expr, Option<Arc<Scope>>) {
+	let (mut arg, arg_scope) = match &arg {
+		Expr::Ident(name) => match scope.and_then(|s| s.get_expr(name)) {
+			Some((expr, expr_scope)) => (expr.clone(), expr_scope.cloned()),
+			None => (arg, scope.cloned()),
+		},
+		_ => (arg, scope.cloned()),
+	};
+	while let Expr::Parens(inner) = arg {
+		arg = *inner;
+	}
+	(arg, arg_scope)
+}
+
 impl fmt::Debug for Value {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
@@ -531,6 +1526,24 @@ impl fmt::Debug for Value {
 			Self::Month(m) => write!(f, "{m}"),
 			Self::DayOfWeek(d) => write!(f, "{d}"),
 			Self::Date(d) => write!(f, "{d:?}"),
+			Self::TimeOfDay(t) => write!(f, "{t:?}"),
+			Self::TimeZone(z) => write!(f, "{z:?}"),
+			Self::ZonedDateTime(t) => write!(f, "{t:?}"),
+			Self::MixedUnits(parts) => {
+				for (i, part) in parts.iter().enumerate() {
+					if i > 0 {
+						write!(f, " ")?;
+					}
+					write!(f, "{part:?}")?;
+				}
+				Ok(())
+			}
+			Self::List(values) => write!(f, "{values:?}"),
+			Self::Vector(v) => write!(f, "{v:?}"),
+			Self::Quaternion(q) => write!(f, "{q:?}"),
+			Self::Matrix(m) => write!(f, "{m:?}"),
+			Self::Symbolic(e) => write!(f, "{e:?}"),
+			Self::Interval(i) => write!(f, "{i:?}"),
 		}
 	}
 }
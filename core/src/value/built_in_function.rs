@@ -27,6 +27,7 @@ pub(crate) enum BuiltInFunction {
 	Acosh,
 	Atanh,
 	Ln,
+	Exp,
 	Log2,
 	Log10,
 	Base,
@@ -41,6 +42,68 @@ pub(crate) enum BuiltInFunction {
 	Ceil,
 	Round,
 	Fibonacci,
+	Gamma,
+	Factor,
+	IsPrime,
+	NextPrime,
+	PrevPrime,
+	Norm,
+	Normalize,
+	Polar,
+	Spherical,
+	Cylindrical,
+	Quaternion,
+	FromEuler,
+	ToEuler,
+	ToAxisAngle,
+	NormPdf,
+	NormCdf,
+	InvNorm,
+	BinomPmf,
+	BinomCdf,
+	PoissonPmf,
+	PoissonCdf,
+	TPdf,
+	TCdf,
+	InvT,
+	LinReg,
+	Correlation,
+	MemoryAdd,
+	MemorySubtract,
+	Store,
+	Recall,
+	FromJdn,
+	FromMjd,
+	ApproxEq,
+	Min,
+	Max,
+	Clamp,
+	Sum,
+	Sort,
+	DivMod,
+	PowMod,
+	Wrap,
+	WrapSigned,
+	Root,
+	Isqrt,
+	ModInv,
+	Compound,
+	Pmt,
+	Amortize,
+	Npv,
+	Product,
+	Median,
+	Mode,
+	Variance,
+	StdDev,
+	Map,
+	Filter,
+	Transpose,
+	Determinant,
+	Inverse,
+	Solve,
+	Expand,
+	Diff,
 }
 
 impl BuiltInFunction {
@@ -94,6 +157,7 @@ impl BuiltInFunction {
 			Self::Acosh => "acosh",
 			Self::Atanh => "atanh",
 			Self::Ln => "ln",
+			Self::Exp => "exp",
 			Self::Log2 => "log2",
 			Self::Log10 => "log10",
 			Self::Base => "base",
@@ -108,6 +172,68 @@ impl BuiltInFunction {
 			Self::Ceil => "ceil",
 			Self::Round => "round",
 			Self::Fibonacci => "fibonacci",
+			Self::Gamma => "gamma",
+			Self::Factor => "factor",
+			Self::IsPrime => "isprime",
+			Self::NextPrime => "nextprime",
+			Self::PrevPrime => "prevprime",
+			Self::Norm => "norm",
+			Self::Normalize => "normalize",
+			Self::Polar => "polar",
+			Self::Spherical => "spherical",
+			Self::Cylindrical => "cylindrical",
+			Self::Quaternion => "quaternion",
+			Self::FromEuler => "from_euler",
+			Self::ToEuler => "to_euler",
+			Self::ToAxisAngle => "to_axis_angle",
+			Self::NormPdf => "normpdf",
+			Self::NormCdf => "normcdf",
+			Self::InvNorm => "invnorm",
+			Self::BinomPmf => "binompmf",
+			Self::BinomCdf => "binomcdf",
+			Self::PoissonPmf => "poissonpmf",
+			Self::PoissonCdf => "poissoncdf",
+			Self::TPdf => "tpdf",
+			Self::TCdf => "tcdf",
+			Self::InvT => "invt",
+			Self::LinReg => "linreg",
+			Self::Correlation => "correlation",
+			Self::MemoryAdd => "mplus",
+			Self::MemorySubtract => "mminus",
+			Self::Store => "store",
+			Self::Recall => "recall",
+			Self::FromJdn => "from_jdn",
+			Self::FromMjd => "from_mjd",
+			Self::ApproxEq => "approx_eq",
+			Self::Min => "min",
+			Self::Max => "max",
+			Self::Clamp => "clamp",
+			Self::Sum => "sum",
+			Self::Sort => "sort",
+			Self::DivMod => "divmod",
+			Self::PowMod => "powmod",
+			Self::Wrap => "wrap",
+			Self::WrapSigned => "wrap_signed",
+			Self::Root => "root",
+			Self::Isqrt => "isqrt",
+			Self::ModInv => "modinv",
+			Self::Compound => "compound",
+			Self::Pmt => "pmt",
+			Self::Amortize => "amortize",
+			Self::Npv => "npv",
+			Self::Product => "product",
+			Self::Median => "median",
+			Self::Mode => "mode",
+			Self::Variance => "variance",
+			Self::StdDev => "stddev",
+			Self::Map => "map",
+			Self::Filter => "filter",
+			Self::Transpose => "transpose",
+			Self::Determinant => "det",
+			Self::Inverse => "inverse",
+			Self::Solve => "solve",
+			Self::Expand => "expand",
+			Self::Diff => "diff",
 		}
 	}
 
@@ -128,6 +254,7 @@ impl BuiltInFunction {
 			"acosh" => Self::Acosh,
 			"atanh" => Self::Atanh,
 			"ln" => Self::Ln,
+			"exp" => Self::Exp,
 			"log2" => Self::Log2,
 			"log10" => Self::Log10,
 			"base" => Self::Base,
@@ -137,6 +264,68 @@ impl BuiltInFunction {
 			"real" => Self::Real,
 			"imag" => Self::Imag,
 			"fibonacci" => Self::Fibonacci,
+			"gamma" => Self::Gamma,
+			"factor" => Self::Factor,
+			"isprime" => Self::IsPrime,
+			"nextprime" => Self::NextPrime,
+			"prevprime" => Self::PrevPrime,
+			"norm" => Self::Norm,
+			"normalize" => Self::Normalize,
+			"polar" => Self::Polar,
+			"spherical" => Self::Spherical,
+			"cylindrical" => Self::Cylindrical,
+			"quaternion" => Self::Quaternion,
+			"from_euler" => Self::FromEuler,
+			"to_euler" => Self::ToEuler,
+			"to_axis_angle" => Self::ToAxisAngle,
+			"normpdf" => Self::NormPdf,
+			"normcdf" => Self::NormCdf,
+			"invnorm" => Self::InvNorm,
+			"binompmf" => Self::BinomPmf,
+			"binomcdf" => Self::BinomCdf,
+			"poissonpmf" => Self::PoissonPmf,
+			"poissoncdf" => Self::PoissonCdf,
+			"tpdf" => Self::TPdf,
+			"tcdf" => Self::TCdf,
+			"invt" => Self::InvT,
+			"linreg" => Self::LinReg,
+			"correlation" => Self::Correlation,
+			"mplus" => Self::MemoryAdd,
+			"mminus" => Self::MemorySubtract,
+			"store" => Self::Store,
+			"recall" => Self::Recall,
+			"from_jdn" => Self::FromJdn,
+			"from_mjd" => Self::FromMjd,
+			"approx_eq" => Self::ApproxEq,
+			"min" => Self::Min,
+			"max" => Self::Max,
+			"clamp" => Self::Clamp,
+			"sum" => Self::Sum,
+			"sort" => Self::Sort,
+			"divmod" => Self::DivMod,
+			"powmod" => Self::PowMod,
+			"wrap" => Self::Wrap,
+			"wrap_signed" => Self::WrapSigned,
+			"root" => Self::Root,
+			"isqrt" => Self::Isqrt,
+			"modinv" => Self::ModInv,
+			"compound" => Self::Compound,
+			"pmt" => Self::Pmt,
+			"amortize" => Self::Amortize,
+			"npv" => Self::Npv,
+			"product" => Self::Product,
+			"median" => Self::Median,
+			"mode" => Self::Mode,
+			"variance" => Self::Variance,
+			"stddev" => Self::StdDev,
+			"map" => Self::Map,
+			"filter" => Self::Filter,
+			"transpose" => Self::Transpose,
+			"det" => Self::Determinant,
+			"inverse" => Self::Inverse,
+			"solve" => Self::Solve,
+			"expand" => Self::Expand,
+			"diff" => Self::Diff,
 			_ => return Err(FendError::DeserializationError),
 		})
 	}
@@ -0,0 +1,984 @@
+use crate::ast::{Bop, Expr};
+use crate::error::{FendError, Interrupt};
+use crate::ident::Ident;
+use crate::num::Number;
+use crate::result::FResult;
+use crate::scope::Scope;
+use crate::serialize::{Deserialize, Serialize};
+use crate::value::Value;
+use crate::{Attrs, Span, SpanKind};
+use std::sync::Arc;
+use std::{cmp, io};
+
+/// A vector like `[1, 2, 3]`. Components may have units, as long as they are
+/// compatible whenever they are added together.
+#[derive(Clone, Debug)]
+pub(crate) struct Vector {
+	components: Vec<Number>,
+	system: CoordinateSystem,
+}
+
+/// How the components of a vector are interpreted. Angles are in radians
+/// unless they have a unit like degrees.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CoordinateSystem {
+	/// `[x, y]` or `[x, y, z]`
+	Cartesian,
+	/// `[r, theta]`
+	Polar,
+	/// `[r, theta, phi]`, where theta is measured from the z axis and phi
+	/// from the x axis
+	Spherical,
+	/// `[rho, phi, z]`
+	Cylindrical,
+}
+
+/// A quaternion `w + xi + yj + zk`, mostly used to represent rotations
+#[derive(Clone, Debug)]
+pub(crate) struct Quaternion {
+	w: Number,
+	x: Number,
+	y: Number,
+	z: Number,
+}
+
+pub(crate) fn serialize_numbers<'a>(
+	numbers: impl ExactSizeIterator<Item = &'a Number>,
+	write: &mut impl io::Write,
+) -> FResult<()> {
+	numbers.len().serialize(write)?;
+	for n in numbers {
+		n.serialize(write)?;
+	}
+	Ok(())
+}
+
+pub(crate) fn deserialize_numbers(read: &mut impl io::Read) -> FResult<Vec<Number>> {
+	let len = usize::deserialize(read)?;
+	let mut result = Vec::with_capacity(len);
+	for _ in 0..len {
+		result.push(Number::deserialize(read)?);
+	}
+	Ok(result)
+}
+
+pub(crate) fn compare_numbers<'a, I: Interrupt>(
+	a: impl ExactSizeIterator<Item = &'a Number>,
+	b: impl ExactSizeIterator<Item = &'a Number>,
+	int: &I,
+) -> FResult<bool> {
+	if a.len() != b.len() {
+		return Ok(false);
+	}
+	for (a, b) in a.zip(b) {
+		if a.compare(b, int)? != Some(cmp::Ordering::Equal) {
+			return Ok(false);
+		}
+	}
+	Ok(true)
+}
+
+pub(crate) fn format_numbers<'a, I: Interrupt>(
+	numbers: impl Iterator<Item = &'a Number>,
+	spans: &mut Vec<Span>,
+	attrs: Attrs,
+	ctx: &mut crate::Context,
+	int: &I,
+) -> FResult<()> {
+	spans.push(Span::from_string("[".to_string()));
+	for (i, n) in numbers.enumerate() {
+		if i > 0 {
+			spans.push(Span::from_string(", ".to_string()));
+		}
+		n.clone()
+			.simplify(attrs, ctx, int)?
+			.format(ctx, int)?
+			.spans(spans, attrs);
+	}
+	spans.push(Span::from_string("]".to_string()));
+	Ok(())
+}
+
+/// Computes e.g. `a1 * b1 - a2 * b2 + ...`, where `false` marks a term that
+/// is subtracted
+pub(crate) fn sum_of_products<I: Interrupt>(
+	terms: &[(bool, &Number, &Number)],
+	int: &I,
+) -> FResult<Number> {
+	let mut result: Option<Number> = None;
+	for &(add, a, b) in terms {
+		let product = a.clone().mul(b.clone(), int)?;
+		result = Some(match result {
+			None if add => product,
+			None => -product,
+			Some(sum) if add => sum.add(product, int)?,
+			Some(sum) => sum.sub(product, int)?,
+		});
+	}
+	result.ok_or(FendError::ExpectedANumber)
+}
+
+struct RegressionSums {
+	n: Number,
+	sum_x: Number,
+	sum_y: Number,
+	covariance: Number,
+	variance_x: Number,
+	variance_y: Number,
+}
+
+/// Compares two numbers whose units may differ, e.g. `10 ft` and `3 m`
+pub(crate) fn compare_quantities<I: Interrupt>(
+	a: &Number,
+	b: &Number,
+	int: &I,
+) -> FResult<cmp::Ordering> {
+	// `compare` treats incompatible units as unordered, but e.g. comparing a
+	// length with a mass should be an error
+	a.clone().sub(b.clone(), int)?;
+	a.compare(b, int)?.ok_or(FendError::ExpectedARealNumber)
+}
+
+fn sqrt<I: Interrupt>(n: Number, int: &I) -> FResult<Number> {
+	n.pow(Number::from(1).div(2.into(), int)?, int)
+}
+
+/// The angle between the positive x axis and the point `(x, y)`
+fn atan2<I: Interrupt>(y: Number, x: Number, int: &I) -> FResult<Number> {
+	// `arg` only works on unitless numbers, so e.g. metres need to be removed
+	let unit = x.unit_one();
+	let x = x.div(unit.clone(), int)?;
+	let y = y.div(unit, int)?;
+	x.add(y.mul(Number::i(), int)?, int)?.arg(int)
+}
+
+fn hypot<I: Interrupt>(x: &Number, y: &Number, int: &I) -> FResult<Number> {
+	sqrt(sum_of_products(&[(true, x, x), (true, y, y)], int)?, int)
+}
+
+fn sin_cos<I: Interrupt>(
+	angle: Number,
+	scope: Option<&Arc<Scope>>,
+	attrs: Attrs,
+	context: &mut crate::Context,
+	int: &I,
+) -> FResult<(Number, Number)> {
+	Ok((
+		angle.clone().sin(scope.cloned(), attrs, context, int)?,
+		angle.cos(scope.cloned(), attrs, context, int)?,
+	))
+}
+
+impl CoordinateSystem {
+	pub(crate) fn from_name(name: &str) -> Option<Self> {
+		Some(match name {
+			"cartesian" => Self::Cartesian,
+			"polar" => Self::Polar,
+			"spherical" => Self::Spherical,
+			"cylindrical" => Self::Cylindrical,
+			_ => return None,
+		})
+	}
+
+	fn as_str(self) -> &'static str {
+		match self {
+			Self::Cartesian => "cartesian",
+			Self::Polar => "polar",
+			Self::Spherical => "spherical",
+			Self::Cylindrical => "cylindrical",
+		}
+	}
+
+	fn component_names(self, len: usize) -> &'static [&'static str] {
+		match self {
+			Self::Cartesian if len <= 3 => &["x", "y", "z"],
+			Self::Cartesian => &[],
+			Self::Polar => &["r", "theta"],
+			Self::Spherical => &["r", "theta", "phi"],
+			Self::Cylindrical => &["rho", "phi", "z"],
+		}
+	}
+
+	/// The number of components, or `None` if any number is allowed
+	fn dimensions(self) -> Option<usize> {
+		match self {
+			Self::Cartesian => None,
+			Self::Polar => Some(2),
+			Self::Spherical | Self::Cylindrical => Some(3),
+		}
+	}
+
+	fn serialize(self, write: &mut impl io::Write) -> FResult<()> {
+		self.as_str().serialize(write)
+	}
+
+	fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		Self::from_name(String::deserialize(read)?.as_str()).ok_or(FendError::DeserializationError)
+	}
+}
+
+impl Vector {
+	pub(crate) fn new(components: Vec<Number>) -> Self {
+		Self {
+			components,
+			system: CoordinateSystem::Cartesian,
+		}
+	}
+
+	/// Interprets the components of a cartesian vector like `[2, 90°]` using
+	/// a different coordinate system, without converting them
+	pub(crate) fn with_system(self, system: CoordinateSystem) -> FResult<Self> {
+		let vector = self.expect_cartesian()?;
+		vector.check_system_dimensions(system)?;
+		Ok(Self { system, ..vector })
+	}
+
+	pub(crate) fn is_cartesian(&self) -> bool {
+		self.system == CoordinateSystem::Cartesian
+	}
+
+	pub(crate) fn expect_cartesian(self) -> FResult<Self> {
+		if self.is_cartesian() {
+			Ok(self)
+		} else {
+			Err(FendError::ExpectedCartesianVector(self.system.as_str()))
+		}
+	}
+
+	fn check_system_dimensions(&self, system: CoordinateSystem) -> FResult<()> {
+		match system.dimensions() {
+			Some(dimensions) if dimensions != self.components.len() => {
+				Err(FendError::CoordinateDimensionMismatch(
+					system.as_str(),
+					dimensions,
+					self.components.len(),
+				))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
+		self.system.serialize(write)?;
+		serialize_numbers(self.components.iter(), write)
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		let system = CoordinateSystem::deserialize(read)?;
+		Ok(Self {
+			components: deserialize_numbers(read)?,
+			system,
+		})
+	}
+
+	pub(crate) fn compare<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
+		Ok(self.system == other.system
+			&& compare_numbers(self.components.iter(), other.components.iter(), int)?)
+	}
+
+	pub(crate) fn format<I: Interrupt>(
+		&self,
+		spans: &mut Vec<Span>,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<()> {
+		if !self.is_cartesian() {
+			spans.push(Span {
+				string: self.system.as_str().to_string(),
+				kind: SpanKind::BuiltInFunction,
+			});
+			spans.push(Span::from_string(" ".to_string()));
+		}
+		format_numbers(self.components.iter(), spans, attrs, ctx, int)
+	}
+
+	pub(crate) fn get_object_member(self, key: &Ident) -> FResult<Value> {
+		let names = self.system.component_names(self.components.len());
+		let idx = names
+			.iter()
+			.position(|&name| name == key.as_str())
+			.ok_or_else(|| FendError::CouldNotFindKey(key.to_string()))?;
+		self.components
+			.into_iter()
+			.nth(idx)
+			.map(|n| Value::Num(Box::new(n)))
+			.ok_or_else(|| FendError::CouldNotFindKey(key.to_string()))
+	}
+
+	/// Converts this vector into the given coordinate system, e.g.
+	/// `[1, 1] to polar`
+	pub(crate) fn convert_to<I: Interrupt>(
+		self,
+		system: CoordinateSystem,
+		scope: Option<&Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Self> {
+		if self.system == system {
+			return Ok(self);
+		}
+		let cartesian = self.into_cartesian(scope, attrs, context, int)?;
+		cartesian.check_system_dimensions(system)?;
+		let components = match system {
+			CoordinateSystem::Cartesian => return Ok(cartesian),
+			CoordinateSystem::Polar => {
+				let [x, y] = cartesian.into_components(system)?;
+				vec![hypot(&x, &y, int)?, atan2(y, x, int)?]
+			}
+			CoordinateSystem::Spherical => {
+				let [x, y, z] = cartesian.into_components(system)?;
+				let rho = hypot(&x, &y, int)?;
+				vec![
+					hypot(&rho, &z, int)?,
+					atan2(rho, z, int)?,
+					atan2(y, x, int)?,
+				]
+			}
+			CoordinateSystem::Cylindrical => {
+				let [x, y, z] = cartesian.into_components(system)?;
+				vec![hypot(&x, &y, int)?, atan2(y, x, int)?, z]
+			}
+		};
+		Ok(Self { components, system })
+	}
+
+	fn into_cartesian<I: Interrupt>(
+		self,
+		scope: Option<&Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Self> {
+		let system = self.system;
+		Ok(Self::new(match system {
+			CoordinateSystem::Cartesian => return Ok(self),
+			CoordinateSystem::Polar => {
+				let [r, theta] = self.into_components(system)?;
+				let (sin, cos) = sin_cos(theta, scope, attrs, context, int)?;
+				vec![r.clone().mul(cos, int)?, r.mul(sin, int)?]
+			}
+			CoordinateSystem::Spherical => {
+				let [r, theta, phi] = self.into_components(system)?;
+				let (sin_theta, cos_theta) = sin_cos(theta, scope, attrs, context, int)?;
+				let (sin_phi, cos_phi) = sin_cos(phi, scope, attrs, context, int)?;
+				let rho = r.clone().mul(sin_theta, int)?;
+				vec![
+					rho.clone().mul(cos_phi, int)?,
+					rho.mul(sin_phi, int)?,
+					r.mul(cos_theta, int)?,
+				]
+			}
+			CoordinateSystem::Cylindrical => {
+				let [rho, phi, z] = self.into_components(system)?;
+				let (sin, cos) = sin_cos(phi, scope, attrs, context, int)?;
+				vec![rho.clone().mul(cos, int)?, rho.mul(sin, int)?, z]
+			}
+		}))
+	}
+
+	fn check_dimensions(&self, other: &Self) -> FResult<()> {
+		if self.components.len() == other.components.len() {
+			Ok(())
+		} else {
+			Err(FendError::VectorDimensionMismatch(
+				self.components.len(),
+				other.components.len(),
+			))
+		}
+	}
+
+	/// Returns the components of a vector with exactly `N` components
+	pub(crate) fn into_array<const N: usize>(self) -> FResult<[Number; N]> {
+		let len = self.components.len();
+		self.components
+			.try_into()
+			.map_err(|_| FendError::VectorDimensionMismatch(len, N))
+	}
+
+	/// Returns the components, which are expected to be valid coordinates
+	/// in the given system
+	fn into_components<const N: usize>(self, system: CoordinateSystem) -> FResult<[Number; N]> {
+		let len = self.components.len();
+		self.components
+			.try_into()
+			.map_err(|_| FendError::CoordinateDimensionMismatch(system.as_str(), N, len))
+	}
+
+	fn into_3d(self) -> FResult<[Number; 3]> {
+		self.components
+			.try_into()
+			.map_err(|_| FendError::ExpectedA3dVector)
+	}
+
+	/// Applies `f` to each component, keeping the coordinate system
+	pub(crate) fn map(self, f: impl Fn(Number) -> FResult<Number>) -> FResult<Self> {
+		Ok(Self {
+			components: self.components.into_iter().map(f).collect::<FResult<_>>()?,
+			system: self.system,
+		})
+	}
+
+	fn zip_with(self, rhs: Self, f: impl Fn(Number, Number) -> FResult<Number>) -> FResult<Self> {
+		self.check_dimensions(&rhs)?;
+		Ok(Self::new(
+			self.components
+				.into_iter()
+				.zip(rhs.components)
+				.map(|(a, b)| f(a, b))
+				.collect::<FResult<_>>()?,
+		))
+	}
+
+	fn neg(self) -> Self {
+		Self::new(self.components.into_iter().map(|n| -n).collect())
+	}
+
+	pub(crate) fn dot<I: Interrupt>(&self, rhs: &Self, int: &I) -> FResult<Number> {
+		self.check_dimensions(rhs)?;
+		let terms: Vec<_> = self
+			.components
+			.iter()
+			.zip(&rhs.components)
+			.map(|(a, b)| (true, a, b))
+			.collect();
+		sum_of_products(&terms, int)
+	}
+
+	/// The components of the vector in cartesian coordinates
+	pub(crate) fn into_cartesian_components(self) -> FResult<Vec<Number>> {
+		Ok(self.expect_cartesian()?.components)
+	}
+
+	pub(crate) fn sum<I: Interrupt>(&self, int: &I) -> FResult<Number> {
+		let mut components = self.components.iter().cloned();
+		let first = components.next().ok_or(FendError::ExpectedANumber)?;
+		components.try_fold(first, |sum, c| sum.add(c, int))
+	}
+
+	/// Returns the smallest (`Less`) or largest (`Greater`) component in its
+	/// original unit, e.g. `max([3 m, 10 ft])` is `10 ft`
+	pub(crate) fn extremum<I: Interrupt>(
+		self,
+		ordering: cmp::Ordering,
+		int: &I,
+	) -> FResult<Number> {
+		let mut components = self.components.into_iter();
+		let mut result = components.next().ok_or(FendError::ExpectedANumber)?;
+		for c in components {
+			if compare_quantities(&c, &result, int)? == ordering {
+				result = c;
+			}
+		}
+		Ok(result)
+	}
+
+	/// Sorts the components in ascending order, keeping their units
+	pub(crate) fn sorted<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		let mut components = self.components;
+		let mut error = None;
+		components.sort_by(|a, b| {
+			compare_quantities(a, b, int).unwrap_or_else(|e| {
+				error.get_or_insert(e);
+				cmp::Ordering::Equal
+			})
+		});
+		match error {
+			Some(e) => Err(e),
+			None => Ok(Self::new(components)),
+		}
+	}
+
+	pub(crate) fn product<I: Interrupt>(&self, int: &I) -> FResult<Number> {
+		let mut components = self.components.iter().cloned();
+		let first = components.next().ok_or(FendError::ExpectedANumber)?;
+		components.try_fold(first, |product, c| product.mul(c, int))
+	}
+
+	/// The arithmetic mean of the components
+	pub(crate) fn mean<I: Interrupt>(&self, int: &I) -> FResult<Number> {
+		let len = self.components.len() as u64;
+		self.sum(int)?.div(Number::from(len), int)
+	}
+
+	/// The middle component after sorting, or the mean of the two middle
+	/// components if there is an even number of them
+	pub(crate) fn median<I: Interrupt>(self, int: &I) -> FResult<Number> {
+		let components = self.sorted(int)?.components;
+		let len = components.len();
+		if len == 0 {
+			return Err(FendError::ExpectedANumber);
+		}
+		let upper = components[len / 2].clone();
+		if len % 2 == 1 {
+			return Ok(upper);
+		}
+		upper
+			.add(components[len / 2 - 1].clone(), int)?
+			.div(2.into(), int)
+	}
+
+	/// The most common component. If several are equally common, the
+	/// smallest one is returned.
+	pub(crate) fn mode<I: Interrupt>(self, int: &I) -> FResult<Number> {
+		let components = self.sorted(int)?.components;
+		let mut result = None;
+		let (mut best_count, mut count, mut run_start) = (0, 0, 0);
+		for (i, c) in components.iter().enumerate() {
+			let same_as_previous =
+				i > 0 && compare_quantities(c, &components[i - 1], int)? == cmp::Ordering::Equal;
+			if !same_as_previous {
+				(count, run_start) = (0, i);
+			}
+			count += 1;
+			if count > best_count {
+				best_count = count;
+				result = Some(&components[run_start]);
+			}
+		}
+		result.cloned().ok_or(FendError::ExpectedANumber)
+	}
+
+	/// The sample variance, i.e. the sum of squared deviations from the mean
+	/// divided by `n - 1`
+	pub(crate) fn variance<I: Interrupt>(&self, int: &I) -> FResult<Number> {
+		let len = self.components.len();
+		if len < 2 {
+			return Err(FendError::TooFewDataPoints(len));
+		}
+		let mean = self.mean(int)?;
+		let squares = self
+			.components
+			.iter()
+			.map(|c| {
+				let deviation = c.clone().sub(mean.clone(), int)?;
+				deviation.clone().mul(deviation, int)
+			})
+			.collect::<FResult<Vec<_>>>()?;
+		Self::new(squares)
+			.sum(int)?
+			.div(Number::from(len as u64 - 1), int)
+	}
+
+	/// The sample standard deviation
+	pub(crate) fn stddev<I: Interrupt>(&self, int: &I) -> FResult<Number> {
+		sqrt(self.variance(int)?, int)
+	}
+
+	/// Collects the sums needed to fit a line through the points `(x, y)`
+	fn regression_sums<I: Interrupt>(&self, ys: &Self, int: &I) -> FResult<RegressionSums> {
+		self.check_dimensions(ys)?;
+		let len = self.components.len();
+		if len < 2 {
+			return Err(FendError::TooFewDataPoints(len));
+		}
+		let n = Number::from(len as u64);
+		let sum_x = self.sum(int)?;
+		let sum_y = ys.sum(int)?;
+		// n Σab - Σa Σb, which is n² times the covariance of a and b
+		let scaled_covariance = |a: &Self, sum_a: &Number, b: &Self, sum_b: &Number| {
+			n.clone()
+				.mul(a.dot(b, int)?, int)?
+				.sub(sum_a.clone().mul(sum_b.clone(), int)?, int)
+		};
+		Ok(RegressionSums {
+			covariance: scaled_covariance(self, &sum_x, ys, &sum_y)?,
+			variance_x: scaled_covariance(self, &sum_x, self, &sum_x)?,
+			variance_y: scaled_covariance(ys, &sum_y, ys, &sum_y)?,
+			n,
+			sum_x,
+			sum_y,
+		})
+	}
+
+	/// Fits the least-squares line `y = slope * x + intercept`, returning
+	/// the slope, the intercept and the coefficient of determination r²
+	pub(crate) fn linear_regression<I: Interrupt>(
+		&self,
+		ys: &Self,
+		int: &I,
+	) -> FResult<(Number, Number, Number)> {
+		let sums = self.regression_sums(ys, int)?;
+		let slope = sums.covariance.clone().div(sums.variance_x.clone(), int)?;
+		let intercept = sums
+			.sum_y
+			.sub(slope.clone().mul(sums.sum_x, int)?, int)?
+			.div(sums.n, int)?;
+		let r_squared = sums
+			.covariance
+			.clone()
+			.mul(sums.covariance, int)?
+			.div(sums.variance_x.mul(sums.variance_y, int)?, int)?;
+		Ok((slope, intercept, r_squared))
+	}
+
+	/// Pearson's correlation coefficient
+	pub(crate) fn correlation<I: Interrupt>(&self, ys: &Self, int: &I) -> FResult<Number> {
+		let sums = self.regression_sums(ys, int)?;
+		sums.covariance
+			.div(sqrt(sums.variance_x.mul(sums.variance_y, int)?, int)?, int)
+	}
+
+	pub(crate) fn cross<I: Interrupt>(self, rhs: Self, int: &I) -> FResult<Self> {
+		let [ax, ay, az] = self.into_3d()?;
+		let [bx, by, bz] = rhs.into_3d()?;
+		Ok(Self::new(vec![
+			sum_of_products(&[(true, &ay, &bz), (false, &az, &by)], int)?,
+			sum_of_products(&[(true, &az, &bx), (false, &ax, &bz)], int)?,
+			sum_of_products(&[(true, &ax, &by), (false, &ay, &bx)], int)?,
+		]))
+	}
+
+	pub(crate) fn norm<I: Interrupt>(self, int: &I) -> FResult<Number> {
+		sqrt(self.dot(&self, int)?, int)
+	}
+
+	pub(crate) fn normalize<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		let norm = self.clone().norm(int)?;
+		self.map(|n| n.div(norm.clone(), int))
+	}
+}
+
+impl Quaternion {
+	/// Creates a quaternion from the vector `[w, x, y, z]`, or a pure
+	/// quaternion from the vector `[x, y, z]`
+	pub(crate) fn from_vector<I: Interrupt>(vector: Vector, int: &I) -> FResult<Self> {
+		let len = vector.components.len();
+		if len == 3 {
+			let [x, y, z] = vector.into_3d()?;
+			let w = Number::from(0).mul(x.unit_one(), int)?;
+			return Ok(Self { w, x, y, z });
+		}
+		let [w, x, y, z]: [Number; 4] = vector
+			.components
+			.try_into()
+			.map_err(|_| FendError::InvalidQuaternionComponents(len))?;
+		Ok(Self { w, x, y, z })
+	}
+
+	/// Creates a rotation from the vector `[roll, pitch, yaw]`, with the
+	/// rotations applied around the x, y and z axes, in that order
+	pub(crate) fn from_euler<I: Interrupt>(
+		angles: Vector,
+		scope: Option<&Arc<Scope>>,
+		attrs: Attrs,
+		context: &mut crate::Context,
+		int: &I,
+	) -> FResult<Self> {
+		let mut sines = vec![];
+		let mut cosines = vec![];
+		for angle in angles.into_3d()? {
+			let half = angle.div(2.into(), int)?;
+			sines.push(half.clone().sin(scope.cloned(), attrs, context, int)?);
+			cosines.push(half.cos(scope.cloned(), attrs, context, int)?);
+		}
+		let (sr, sp, sy) = (&sines[0], &sines[1], &sines[2]);
+		let (cr, cp, cy) = (&cosines[0], &cosines[1], &cosines[2]);
+		let cos_r_cos_p = cr.clone().mul(cp.clone(), int)?;
+		let sin_r_sin_p = sr.clone().mul(sp.clone(), int)?;
+		let sin_r_cos_p = sr.clone().mul(cp.clone(), int)?;
+		let cos_r_sin_p = cr.clone().mul(sp.clone(), int)?;
+		Ok(Self {
+			w: sum_of_products(&[(true, &cos_r_cos_p, cy), (true, &sin_r_sin_p, sy)], int)?,
+			x: sum_of_products(&[(true, &sin_r_cos_p, cy), (false, &cos_r_sin_p, sy)], int)?,
+			y: sum_of_products(&[(true, &cos_r_sin_p, cy), (true, &sin_r_cos_p, sy)], int)?,
+			z: sum_of_products(&[(true, &cos_r_cos_p, sy), (false, &sin_r_sin_p, cy)], int)?,
+		})
+	}
+
+	pub(crate) fn serialize(&self, write: &mut impl io::Write) -> FResult<()> {
+		serialize_numbers(self.components().into_iter(), write)
+	}
+
+	pub(crate) fn deserialize(read: &mut impl io::Read) -> FResult<Self> {
+		let [w, x, y, z]: [Number; 4] = deserialize_numbers(read)?
+			.try_into()
+			.map_err(|_| FendError::DeserializationError)?;
+		Ok(Self { w, x, y, z })
+	}
+
+	fn components(&self) -> [&Number; 4] {
+		[&self.w, &self.x, &self.y, &self.z]
+	}
+
+	pub(crate) fn compare<I: Interrupt>(&self, other: &Self, int: &I) -> FResult<bool> {
+		compare_numbers(
+			self.components().into_iter(),
+			other.components().into_iter(),
+			int,
+		)
+	}
+
+	pub(crate) fn format<I: Interrupt>(
+		&self,
+		spans: &mut Vec<Span>,
+		attrs: Attrs,
+		ctx: &mut crate::Context,
+		int: &I,
+	) -> FResult<()> {
+		spans.push(Span {
+			string: "quaternion".to_string(),
+			kind: SpanKind::BuiltInFunction,
+		});
+		spans.push(Span::from_string(" ".to_string()));
+		format_numbers(self.components().into_iter(), spans, attrs, ctx, int)
+	}
+
+	pub(crate) fn get_object_member(self, key: &Ident) -> FResult<Value> {
+		let n = match key.as_str() {
+			"w" => self.w,
+			"x" => self.x,
+			"y" => self.y,
+			"z" => self.z,
+			_ => return Err(FendError::CouldNotFindKey(key.to_string())),
+		};
+		Ok(Value::Num(Box::new(n)))
+	}
+
+	fn map(self, f: impl Fn(Number) -> FResult<Number>) -> FResult<Self> {
+		Ok(Self {
+			w: f(self.w)?,
+			x: f(self.x)?,
+			y: f(self.y)?,
+			z: f(self.z)?,
+		})
+	}
+
+	fn zip_with(self, rhs: Self, f: impl Fn(Number, Number) -> FResult<Number>) -> FResult<Self> {
+		Ok(Self {
+			w: f(self.w, rhs.w)?,
+			x: f(self.x, rhs.x)?,
+			y: f(self.y, rhs.y)?,
+			z: f(self.z, rhs.z)?,
+		})
+	}
+
+	fn neg(self) -> Self {
+		Self {
+			w: -self.w,
+			x: -self.x,
+			y: -self.y,
+			z: -self.z,
+		}
+	}
+
+	pub(crate) fn conjugate(self) -> Self {
+		Self {
+			w: self.w,
+			x: -self.x,
+			y: -self.y,
+			z: -self.z,
+		}
+	}
+
+	/// The Hamilton product, which combines two rotations
+	fn mul<I: Interrupt>(&self, rhs: &Self, int: &I) -> FResult<Self> {
+		let (a, b) = (self, rhs);
+		Ok(Self {
+			w: sum_of_products(
+				&[
+					(true, &a.w, &b.w),
+					(false, &a.x, &b.x),
+					(false, &a.y, &b.y),
+					(false, &a.z, &b.z),
+				],
+				int,
+			)?,
+			x: sum_of_products(
+				&[
+					(true, &a.w, &b.x),
+					(true, &a.x, &b.w),
+					(true, &a.y, &b.z),
+					(false, &a.z, &b.y),
+				],
+				int,
+			)?,
+			y: sum_of_products(
+				&[
+					(true, &a.w, &b.y),
+					(false, &a.x, &b.z),
+					(true, &a.y, &b.w),
+					(true, &a.z, &b.x),
+				],
+				int,
+			)?,
+			z: sum_of_products(
+				&[
+					(true, &a.w, &b.z),
+					(true, &a.x, &b.y),
+					(false, &a.y, &b.x),
+					(true, &a.z, &b.w),
+				],
+				int,
+			)?,
+		})
+	}
+
+	fn norm_squared<I: Interrupt>(&self, int: &I) -> FResult<Number> {
+		sum_of_products(&self.components().map(|n| (true, n, n)), int)
+	}
+
+	pub(crate) fn norm<I: Interrupt>(self, int: &I) -> FResult<Number> {
+		sqrt(self.norm_squared(int)?, int)
+	}
+
+	pub(crate) fn normalize<I: Interrupt>(self, int: &I) -> FResult<Self> {
+		let norm = self.clone().norm(int)?;
+		self.map(|n| n.div(norm.clone(), int))
+	}
+
+	/// Rotates a 3D vector, i.e. computes `q v q^-1`
+	fn rotate<I: Interrupt>(self, vector: Vector, int: &I) -> FResult<Vector> {
+		let vector = Self::from_vector(Vector::new(vector.into_3d()?.into()), int)?;
+		let norm_squared = self.norm_squared(int)?;
+		let rotated = self.mul(&vector, int)?.mul(&self.conjugate(), int)?;
+		Vector::new(vec![rotated.x, rotated.y, rotated.z]).map(|n| n.div(norm_squared.clone(), int))
+	}
+
+	/// Returns the rotation axis as a unit vector, and the angle in radians
+	pub(crate) fn into_axis_angle<I: Interrupt>(self, int: &I) -> FResult<(Vector, Number)> {
+		let axis = Vector::new(vec![self.x, self.y, self.z]);
+		let sin_half_angle = axis.clone().norm(int)?;
+		if sin_half_angle.is_zero(int)? {
+			// no rotation, so any axis works
+			return Ok((Vector::new(vec![1.into(), 0.into(), 0.into()]), 0.into()));
+		}
+		let half_angle = atan2(sin_half_angle.clone(), self.w, int)?;
+		Ok((
+			axis.map(|n| n.div(sin_half_angle.clone(), int))?,
+			half_angle.mul(2.into(), int)?,
+		))
+	}
+
+	/// Returns the roll, pitch and yaw in radians, using the same convention
+	/// as `from_euler`
+	pub(crate) fn into_euler<I: Interrupt>(self, int: &I) -> FResult<[Number; 3]> {
+		let q = self.normalize(int)?;
+		let two = || Number::from(2);
+		let roll = atan2(
+			sum_of_products(&[(true, &q.w, &q.x), (true, &q.y, &q.z)], int)?.mul(two(), int)?,
+			sum_of_products(
+				&[
+					(true, &q.w, &q.w),
+					(false, &q.x, &q.x),
+					(false, &q.y, &q.y),
+					(true, &q.z, &q.z),
+				],
+				int,
+			)?,
+			int,
+		)?;
+		let pitch = sum_of_products(&[(true, &q.w, &q.y), (false, &q.x, &q.z)], int)?
+			.mul(two(), int)?
+			.asin(int)?;
+		let yaw = atan2(
+			sum_of_products(&[(true, &q.w, &q.z), (true, &q.x, &q.y)], int)?.mul(two(), int)?,
+			sum_of_products(
+				&[
+					(true, &q.w, &q.w),
+					(true, &q.x, &q.x),
+					(false, &q.y, &q.y),
+					(false, &q.z, &q.z),
+				],
+				int,
+			)?,
+			int,
+		)?;
+		Ok([roll, pitch, yaw])
+	}
+}
+
+/// Evaluates arithmetic where at least one operand is a vector or quaternion
+pub(crate) fn evaluate_bop<I: Interrupt>(
+	bop: Bop,
+	lhs: Value,
+	rhs: Value,
+	scope: Option<Arc<Scope>>,
+	int: &I,
+) -> FResult<Value> {
+	let (lhs, rhs) = (expect_cartesian(lhs)?, expect_cartesian(rhs)?);
+	Ok(match (bop, lhs, rhs) {
+		// e.g. `[1, 2] * sqrt 2` is parsed as `([1, 2] * sqrt) 2`
+		(bop, f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _)), v) => {
+			f.wrap_with_expr(|f| Expr::Bop(bop, f, Box::new(Expr::Literal(v))), scope)?
+		}
+		(bop, v, f @ (Value::BuiltInFunction(_) | Value::Fn(_, _, _))) => {
+			f.wrap_with_expr(|f| Expr::Bop(bop, Box::new(Expr::Literal(v)), f), scope)?
+		}
+		(Bop::Plus, Value::Vector(a), Value::Vector(b)) => {
+			Value::Vector(a.zip_with(b, |a, b| a.add(b, int))?)
+		}
+		(Bop::Minus, Value::Vector(a), Value::Vector(b)) => {
+			Value::Vector(a.zip_with(b, |a, b| a.sub(b, int))?)
+		}
+		(Bop::Mul, Value::Vector(v), Value::Num(n))
+		| (Bop::Mul, Value::Num(n), Value::Vector(v)) => {
+			Value::Vector(v.map(|x| x.mul((*n).clone(), int))?)
+		}
+		(Bop::Div, Value::Vector(v), Value::Num(n)) => {
+			Value::Vector(v.map(|x| x.div((*n).clone(), int))?)
+		}
+		(Bop::Mul, Value::Vector(_), Value::Vector(_)) => {
+			return Err(FendError::CannotMultiplyVectors)
+		}
+		(Bop::Dot, Value::Vector(a), Value::Vector(b)) => Value::Num(Box::new(a.dot(&b, int)?)),
+		(Bop::Cross, Value::Vector(a), Value::Vector(b)) => Value::Vector(a.cross(b, int)?),
+		(Bop::Plus, Value::Quaternion(a), Value::Quaternion(b)) => {
+			Value::Quaternion(Box::new(a.zip_with(*b, |a, b| a.add(b, int))?))
+		}
+		(Bop::Minus, Value::Quaternion(a), Value::Quaternion(b)) => {
+			Value::Quaternion(Box::new(a.zip_with(*b, |a, b| a.sub(b, int))?))
+		}
+		(Bop::Plus, Value::Quaternion(q), Value::Num(n))
+		| (Bop::Plus, Value::Num(n), Value::Quaternion(q)) => {
+			let q = *q;
+			Value::Quaternion(Box::new(Quaternion {
+				w: q.w.add(*n, int)?,
+				..q
+			}))
+		}
+		(Bop::Minus, Value::Quaternion(q), Value::Num(n)) => {
+			let q = *q;
+			Value::Quaternion(Box::new(Quaternion {
+				w: q.w.sub(*n, int)?,
+				..q
+			}))
+		}
+		(Bop::Minus, Value::Num(n), Value::Quaternion(q)) => {
+			let q = q.neg();
+			Value::Quaternion(Box::new(Quaternion {
+				w: q.w.add(*n, int)?,
+				..q
+			}))
+		}
+		(Bop::Mul, Value::Quaternion(a), Value::Quaternion(b)) => {
+			Value::Quaternion(Box::new(a.mul(&b, int)?))
+		}
+		(Bop::Mul, Value::Quaternion(q), Value::Vector(v)) => Value::Vector(q.rotate(v, int)?),
+		(Bop::Mul, Value::Quaternion(q), Value::Num(n))
+		| (Bop::Mul, Value::Num(n), Value::Quaternion(q)) => {
+			Value::Quaternion(Box::new(q.map(|x| x.mul((*n).clone(), int))?))
+		}
+		(Bop::Div, Value::Quaternion(q), Value::Num(n)) => {
+			Value::Quaternion(Box::new(q.map(|x| x.div((*n).clone(), int))?))
+		}
+		_ => return Err(FendError::InvalidOperandsForVectorArithmetic),
+	})
+}
+
+/// Arithmetic on e.g. polar coordinates would give meaningless results
+fn expect_cartesian(value: Value) -> FResult<Value> {
+	Ok(match value {
+		Value::Vector(v) => Value::Vector(v.expect_cartesian()?),
+		value => value,
+	})
+}
+
+pub(crate) fn negate(value: Value) -> FResult<Value> {
+	Ok(match expect_cartesian(value)? {
+		Value::Vector(v) => Value::Vector(v.neg()),
+		Value::Quaternion(q) => Value::Quaternion(Box::new(q.neg())),
+		_ => return Err(FendError::ExpectedANumber),
+	})
+}
@@ -0,0 +1,42 @@
+/* C bindings for fend, see README.md */
+
+#ifndef FEND_H
+#define FEND_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Evaluation succeeded, and the result contains the output */
+#define FEND_OK 0
+/* Evaluation failed, and the result contains the error message */
+#define FEND_ERROR 1
+/* A null pointer or invalid UTF-8 was passed in, and the result is NULL */
+#define FEND_INVALID_ARGUMENT (-1)
+
+/* A calculator context, containing variables and settings */
+typedef struct FendContext FendContext;
+
+/* Creates a new context, which must be freed with `fend_context_free` */
+FendContext *fend_context_new(void);
+
+/* Frees a context created by `fend_context_new`. Passing NULL does nothing. */
+void fend_context_free(FendContext *context);
+
+/* Evaluates the null-terminated UTF-8 string `input`. Variables defined in
+ * earlier calls with the same context remain available.
+ *
+ * On success, `*result` is set to the output (which may be empty) and
+ * `FEND_OK` is returned. If evaluation fails, `*result` is
+ * set to the error message and `FEND_ERROR` is returned. The string in
+ * `*result` must be freed with `fend_free_string`. */
+int fend_evaluate(FendContext *context, const char *input, char **result);
+
+/* Frees a string returned by fend. Passing NULL does nothing. */
+void fend_free_string(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FEND_H */
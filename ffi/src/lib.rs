@@ -0,0 +1,195 @@
+//! C bindings for fend, for embedding the calculator in other applications.
+//! The corresponding declarations are in `fend.h`, which is written by hand
+//! (see the `header_declares_all_functions` test) so that building fend
+//! doesn't need `cbindgen`.
+
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![deny(elided_lifetimes_in_paths)]
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::{ptr, time};
+
+const FEND_OK: c_int = 0;
+const FEND_ERROR: c_int = 1;
+const FEND_INVALID_ARGUMENT: c_int = -1;
+
+/// A calculator context, containing variables and settings
+pub struct FendContext {
+	ctx: fend_core::Context,
+}
+
+/// Creates a new context, which must be freed with [`fend_context_free`]
+#[no_mangle]
+pub extern "C" fn fend_context_new() -> *mut FendContext {
+	let mut ctx = fend_core::Context::new();
+	// only the lower bits of the current time are needed as a seed
+	#[allow(clippy::cast_possible_truncation)]
+	let seed = time::SystemTime::now()
+		.duration_since(time::UNIX_EPOCH)
+		.map_or(0, |d| d.as_nanos() as u64);
+	ctx.set_random_seed(seed);
+	Box::into_raw(Box::new(FendContext { ctx }))
+}
+
+/// Frees a context created by [`fend_context_new`]. Passing null does
+/// nothing.
+///
+/// # Safety
+/// `context` must be null or a pointer returned by [`fend_context_new`]
+/// that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn fend_context_free(context: *mut FendContext) {
+	if !context.is_null() {
+		drop(Box::from_raw(context));
+	}
+}
+
+fn into_c_string(s: &str) -> *mut c_char {
+	// results can't contain null bytes in C
+	CString::new(s.replace('\0', "")).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Evaluates the null-terminated UTF-8 string `input`, storing the result or
+/// error message in `*result`. Returns `FEND_OK` on success, `FEND_ERROR` if
+/// evaluation failed, or `FEND_INVALID_ARGUMENT` if a pointer is null or the
+/// input isn't valid UTF-8.
+///
+/// # Safety
+/// `context` must be a valid context, `input` a null-terminated string and
+/// `result` must be valid for writes. The string stored in `*result` must be
+/// freed with [`fend_free_string`].
+#[no_mangle]
+pub unsafe extern "C" fn fend_evaluate(
+	context: *mut FendContext,
+	input: *const c_char,
+	result: *mut *mut c_char,
+) -> c_int {
+	if result.is_null() {
+		return FEND_INVALID_ARGUMENT;
+	}
+	*result = ptr::null_mut();
+	let Some(context) = context.as_mut() else {
+		return FEND_INVALID_ARGUMENT;
+	};
+	if input.is_null() {
+		return FEND_INVALID_ARGUMENT;
+	}
+	let Ok(input) = CStr::from_ptr(input).to_str() else {
+		return FEND_INVALID_ARGUMENT;
+	};
+	match fend_core::evaluate(input, &mut context.ctx) {
+		Ok(res) => {
+			let output = if res.is_unit_type() {
+				""
+			} else {
+				res.get_main_result()
+			};
+			*result = into_c_string(output);
+			FEND_OK
+		}
+		Err(msg) => {
+			*result = into_c_string(&msg);
+			FEND_ERROR
+		}
+	}
+}
+
+/// Frees a string returned by fend. Passing null does nothing.
+///
+/// # Safety
+/// `string` must be null or a string returned by fend that hasn't been freed
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn fend_free_string(string: *mut c_char) {
+	if !string.is_null() {
+		drop(CString::from_raw(string));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn eval(context: *mut FendContext, input: &str) -> (c_int, String) {
+		let input = CString::new(input).unwrap();
+		let mut result = ptr::null_mut();
+		unsafe {
+			let status = fend_evaluate(context, input.as_ptr(), ptr::addr_of_mut!(result));
+			let output = CStr::from_ptr(result).to_str().unwrap().to_string();
+			fend_free_string(result);
+			(status, output)
+		}
+	}
+
+	#[test]
+	fn evaluate_with_context() {
+		let context = fend_context_new();
+		assert_eq!(eval(context, "a = 5 km"), (FEND_OK, "5 km".to_string()));
+		assert_eq!(eval(context, ""), (FEND_OK, String::new()));
+		assert_eq!(eval(context, "a to m"), (FEND_OK, "5000 m".to_string()));
+		assert_eq!(
+			eval(context, "1/0"),
+			(FEND_ERROR, "division by zero".to_string())
+		);
+		unsafe { fend_context_free(context) };
+	}
+
+	#[test]
+	fn invalid_arguments() {
+		let mut result = ptr::null_mut();
+		let input = CString::new("1").unwrap();
+		unsafe {
+			assert_eq!(
+				fend_evaluate(ptr::null_mut(), input.as_ptr(), ptr::addr_of_mut!(result)),
+				FEND_INVALID_ARGUMENT
+			);
+			assert!(result.is_null());
+			let context = fend_context_new();
+			assert_eq!(
+				fend_evaluate(context, ptr::null(), ptr::addr_of_mut!(result)),
+				FEND_INVALID_ARGUMENT
+			);
+			let invalid = [0xff_u8, 0];
+			assert_eq!(
+				fend_evaluate(context, invalid.as_ptr().cast(), ptr::addr_of_mut!(result)),
+				FEND_INVALID_ARGUMENT
+			);
+			fend_context_free(context);
+			fend_context_free(ptr::null_mut());
+			fend_free_string(ptr::null_mut());
+		}
+	}
+
+	#[test]
+	fn header_declares_all_functions() {
+		let header = include_str!("../fend.h");
+		for decl in [
+			"FendContext *fend_context_new(void);",
+			"void fend_context_free(FendContext *context);",
+			"int fend_evaluate(FendContext *context, const char *input, char **result);",
+			"void fend_free_string(char *string);",
+			"#define FEND_OK 0",
+			"#define FEND_ERROR 1",
+			"#define FEND_INVALID_ARGUMENT (-1)",
+		] {
+			assert!(header.contains(decl), "fend.h is missing `{decl}`");
+		}
+		// every function and constant defined here must also be in the header
+		for line in include_str!("lib.rs").lines() {
+			if let Some((_, rest)) = line.split_once("extern \"C\" fn ") {
+				let name = rest.split('(').next().unwrap();
+				assert!(
+					header.contains(&format!(" {name}(")) || header.contains(&format!("*{name}(")),
+					"fend.h is missing `{name}`"
+				);
+			} else if let Some(rest) = line.strip_prefix("const FEND_") {
+				let name = rest.split(':').next().unwrap();
+				assert!(
+					header.contains(&format!("#define FEND_{name} ")),
+					"fend.h is missing `FEND_{name}`"
+				);
+			}
+		}
+	}
+}
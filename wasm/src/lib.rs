@@ -1,11 +1,12 @@
 use instant::Instant;
 use std::collections::HashMap;
 use std::fmt::Write;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
 use std::{error, fmt};
 use wasm_bindgen::prelude::*;
 
 static CURRENCY_DATA: OnceLock<HashMap<String, f64>> = OnceLock::new();
+static LOCALE: Mutex<fend_core::Locale> = Mutex::new(fend_core::Locale::English);
 
 struct TimeoutInterrupt {
 	start: Instant,
@@ -57,6 +58,18 @@ pub fn initialise_with_handlers(currency_data: js_sys::Map) {
 	});
 }
 
+/// Sets the language used for error messages, e.g. `de` or `en-US`. Returns
+/// `false` if the language is not supported, in which case the previous
+/// language is kept.
+#[wasm_bindgen(js_name = setLocale)]
+pub fn set_locale(language_tag: &str) -> bool {
+	let Some(locale) = fend_core::Locale::from_language_tag(language_tag) else {
+		return false;
+	};
+	*LOCALE.lock().unwrap() = locale;
+	true
+}
+
 fn random_u32() -> u32 {
 	let random_f64 = js_sys::Math::random();
 	(random_f64 * f64::from(u32::MAX)) as u32
@@ -87,14 +100,19 @@ fn currency_handler(currency: &str) -> Result<f64, Box<dyn error::Error + Send +
 	}
 }
 
-fn create_context() -> fend_core::Context {
-	let mut ctx = fend_core::Context::new();
+fn set_current_time(ctx: &mut fend_core::Context) {
 	let date = js_sys::Date::new_0();
 	ctx.set_current_time_v1(
 		date.get_time() as u64,
 		date.get_timezone_offset() as i64 * 60,
 	);
+}
+
+fn create_context() -> fend_core::Context {
+	let mut ctx = fend_core::Context::new();
+	set_current_time(&mut ctx);
 	ctx.set_random_u32_fn(random_u32);
+	ctx.set_locale(*LOCALE.lock().unwrap());
 	if CURRENCY_DATA.get().is_some_and(|x| !x.is_empty()) {
 		ctx.set_exchange_rate_handler_v1(currency_handler);
 	}
@@ -147,6 +165,54 @@ pub fn evaluate_fend_with_timeout_multiple(inputs: &str, timeout: u32) -> String
 	result
 }
 
+/// A calculator context that keeps variables between calls to `evaluate`
+#[wasm_bindgen]
+pub struct FendContext {
+	ctx: fend_core::Context,
+}
+
+#[wasm_bindgen]
+impl FendContext {
+	#[wasm_bindgen(constructor)]
+	pub fn new() -> Self {
+		Self {
+			ctx: create_context(),
+		}
+	}
+}
+
+impl Default for FendContext {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Evaluates `input` in the given context, so that variables defined by
+/// earlier calls can be used. Evaluation is aborted after `timeout`
+/// milliseconds.
+#[wasm_bindgen]
+pub fn evaluate(input: &str, context: &mut FendContext, timeout: u32) -> String {
+	set_current_time(&mut context.ctx);
+	let interrupt = TimeoutInterrupt::new_with_timeout(u128::from(timeout));
+	match fend_core::evaluate_with_interrupt(input, &mut context.ctx, &interrupt) {
+		Ok(res) => {
+			if res.is_unit_type() {
+				return String::new();
+			}
+			res.get_main_result().to_string()
+		}
+		Err(msg) => format!("Error: {msg}"),
+	}
+}
+
+/// Like `evaluate`, but returns the result or error as a JSON object
+#[wasm_bindgen(js_name = evaluateJson)]
+pub fn evaluate_json(input: &str, context: &mut FendContext, timeout: u32) -> String {
+	set_current_time(&mut context.ctx);
+	let interrupt = TimeoutInterrupt::new_with_timeout(u128::from(timeout));
+	fend_core::evaluate_to_json(input, &mut context.ctx, &interrupt)
+}
+
 fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
 	(0..s.len())
 		.step_by(2)
@@ -160,7 +226,7 @@ pub fn evaluate_fend_with_variables_json(input: &str, timeout: u32, variables: &
 	let mut ctx = create_context();
 	if !variables.is_empty() {
 		if let Ok(variables) = decode_hex(variables) {
-			let _ = ctx.deserialize_variables(&mut variables.as_slice());
+			let _ = ctx.deserialize(&mut variables.as_slice());
 		}
 	}
 	let interrupt = TimeoutInterrupt::new_with_timeout(u128::from(timeout));
@@ -175,8 +241,8 @@ pub fn evaluate_fend_with_variables_json(input: &str, timeout: u32, variables: &
 			};
 			let variables = {
 				let mut vars_vec = vec![];
-				// if we can't serialize variables just ignore it and return an empty string
-				let _ = ctx.serialize_variables(&mut vars_vec);
+				// if we can't serialize the state just ignore it and return an empty string
+				let _ = ctx.serialize(&mut vars_vec);
 				let mut hex = String::new();
 				for b in &vars_vec {
 					write!(hex, "{b:02x}").unwrap();
@@ -200,3 +266,36 @@ pub fn substitute_inline_fend_expressions(input: &str, timeout: u32) -> String {
 	let res = fend_core::substitute_inline_fend_expressions(input, &mut ctx, &interrupt);
 	res.to_json()
 }
+
+/// Returns completions for the word at the end of `input` as JSON, e.g.
+/// `{"position":0,"completions":[{"display":"sqrt","insert":"sqrt","kind":"function"}]}`
+#[wasm_bindgen(js_name = getCompletionsJson)]
+pub fn get_completions_json(input: &str, variables: &str) -> String {
+	let mut ctx = create_context();
+	if !variables.is_empty() {
+		if let Ok(variables) = decode_hex(variables) {
+			let _ = ctx.deserialize(&mut variables.as_slice());
+		}
+	}
+	let (position, completions) = fend_core::get_completions(input, &ctx);
+	let mut result = format!(r#"{{"position":{position},"completions":["#);
+	for (i, completion) in completions.iter().enumerate() {
+		if i > 0 {
+			result.push(',');
+		}
+		let kind = match completion.kind() {
+			fend_core::CompletionKind::Variable => "variable",
+			fend_core::CompletionKind::Function => "function",
+			fend_core::CompletionKind::Keyword => "keyword",
+			fend_core::CompletionKind::Unit => "unit",
+			_ => "other",
+		};
+		result.push_str(r#"{"display":""#);
+		fend_core::json::escape_string(completion.display(), &mut result);
+		result.push_str(r#"","insert":""#);
+		fend_core::json::escape_string(completion.insert(), &mut result);
+		write!(result, r#"","kind":"{kind}"}}"#).unwrap();
+	}
+	result.push_str("]}");
+	result
+}